@@ -1,11 +1,14 @@
+use crate::common::document_action::DocumentAction;
 #[cfg(target_os = "macos")]
 use crate::extension::built_in::window_management::actions::Action;
 use crate::extension::view_extension::serve_files_in;
 use crate::extension::{ExtensionPermission, ExtensionSettings, ViewExtensionUISettings};
+use crate::search::frecency::{OpenedSearchResult, record_opened_search_result};
 use log::debug;
 use serde::{Deserialize, Serialize};
 use serde_json::Value as Json;
 use std::collections::HashMap;
+use std::ops::Range;
 use tauri::{AppHandle, Emitter};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -53,6 +56,10 @@ pub(crate) enum OnOpened {
     WindowManagementAction { action: Action },
     /// The document is an extension.
     Extension(ExtensionOnOpened),
+    /// Paste this Clipboard History entry again.
+    ClipboardEntry { id: String },
+    /// Paste this snippet, with its placeholders filled in.
+    Snippet { id: String },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -98,6 +105,10 @@ pub(crate) enum ExtensionOnOpenedType {
         page: String,
         ui: Option<ViewExtensionUISettings>,
     },
+    /// Open the settings tab or panel.
+    Setting {
+        setting: crate::extension::setting::Setting,
+    },
 }
 
 impl OnOpened {
@@ -110,6 +121,8 @@ impl OnOpened {
                 // We don't have URL for this
                 String::from("N/A")
             }
+            Self::ClipboardEntry { .. } => String::from("N/A"),
+            Self::Snippet { .. } => String::from("N/A"),
             Self::Extension(ext_on_opened) => {
                 match &ext_on_opened.ty {
                     ExtensionOnOpenedType::Command { action } => {
@@ -135,23 +148,31 @@ impl OnOpened {
                         // We currently don't have URL for this kind of extension.
                         String::from("N/A")
                     }
+                    ExtensionOnOpenedType::Setting { .. } => String::from("N/A"),
                 }
             }
         }
     }
 }
 
+/// Perform the `on_opened` action.
+///
+/// If `opened_search_result` is Some, i.e., the document comes from a search,
+/// this open will be recorded in the usage history for frecency ranking.
 #[tauri::command]
 pub(crate) async fn open(
     tauri_app_handle: AppHandle,
     on_opened: OnOpened,
     extra_args: Option<HashMap<String, Json>>,
+    opened_search_result: Option<OpenedSearchResult>,
 ) -> Result<(), String> {
     use crate::util::open as homemade_tauri_shell_open;
     use tokio::process::Command;
     use tokio::time::Duration;
     use tokio::time::timeout;
 
+    // `tauri_app_handle` will be moved into the async closure
+    let tauri_app_handle_for_frecency = tauri_app_handle.clone();
     let on_opened_clone = on_opened.clone();
     // Put the main logic in an async closure so that we can `time::timeout()`
     // it
@@ -176,12 +197,31 @@ pub(crate) async fn open(
                     action,
                 )?;
             }
+            OnOpened::ClipboardEntry { id } => {
+                log::debug!("paste clipboard entry [{}]", id);
+
+                crate::extension::built_in::clipboard_history::paste_entry(&tauri_app_handle, &id)
+                    .await?;
+            }
+            OnOpened::Snippet { id } => {
+                log::debug!("paste snippet [{}]", id);
+
+                crate::extension::built_in::snippets::paste_snippet(
+                    &tauri_app_handle,
+                    &id,
+                    &extra_args,
+                )
+                .await?;
+            }
             OnOpened::Extension(ext_on_opened) => {
                 // Apply the settings that would affect open behavior
-                // Default to hiding the Coco window before opening for Command extensions,
-                // keep the window visible for other extension types unless explicitly configured
-                let default_hide =
-                    matches!(ext_on_opened.ty, ExtensionOnOpenedType::Command { .. });
+                // Default to hiding the Coco window before opening for Command and Setting
+                // extensions, keep the window visible for other extension types unless
+                // explicitly configured
+                let default_hide = matches!(
+                    ext_on_opened.ty,
+                    ExtensionOnOpenedType::Command { .. } | ExtensionOnOpenedType::Setting { .. }
+                );
                 let should_hide = ext_on_opened
                     .settings
                     .and_then(|s| s.hide_before_open)
@@ -299,6 +339,12 @@ pub(crate) async fn open(
                             .emit("open_view_extension", view_extension_opened)
                             .unwrap();
                     }
+                    ExtensionOnOpenedType::Setting { setting } => {
+                        log::debug!("open setting [{:?}]", setting);
+
+                        crate::extension::setting::open_setting(&tauri_app_handle, &setting)
+                            .await?;
+                    }
                 }
             }
         }
@@ -307,7 +353,16 @@ pub(crate) async fn open(
     };
 
     match timeout(Duration::from_millis(500), async_closure).await {
-        Ok(res) => res,
+        Ok(res) => {
+            if res.is_ok()
+                && let Some(opened_search_result) = opened_search_result
+            {
+                record_opened_search_result(&tauri_app_handle_for_frecency, opened_search_result)
+                    .await;
+            }
+
+            res
+        }
         Err(_timed_out) => {
             log::warn!("executing open(on_opened: [{:?}]) timed out", on_opened);
 
@@ -331,6 +386,9 @@ pub struct Document {
     pub categories: Option<Vec<String>>,
     pub rich_categories: Option<Vec<RichLabel>>,
     pub title: Option<String>,
+    /// Char ranges of `title` that match the query, for highlighting. Only
+    /// local query sources set this, see `crate::search::fuzzy`.
+    pub title_match_ranges: Option<Vec<Range<usize>>>,
     pub summary: Option<String>,
     pub lang: Option<String>,
     pub content: Option<String>,
@@ -340,6 +398,9 @@ pub struct Document {
     pub tags: Option<Vec<String>>,
     /// What will happen if we open this document.
     pub on_opened: Option<OnOpened>,
+    /// Other actions that can be performed on this document, in the order
+    /// they should be shown. See `execute_document_action()`.
+    pub actions: Option<Vec<DocumentAction>>,
     pub url: Option<String>,
     pub size: Option<i64>,
     pub metadata: Option<HashMap<String, serde_json::Value>>,
@@ -0,0 +1,378 @@
+//! Secondary actions of a [document](Document), besides opening it, e.g.,
+//! copying its URL or revealing it in the file manager.
+
+use crate::common::document::Document;
+use crate::extension::QuicklinkLink;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as Json;
+use std::collections::HashMap;
+use tauri::AppHandle;
+use tauri_plugin_clipboard_manager::ClipboardExt;
+use tauri_plugin_opener::OpenerExt;
+
+const ACTION_ID_COPY_URL: &str = "copy_url";
+const ACTION_ID_COPY_PATH: &str = "copy_path";
+const ACTION_ID_COPY_MARKDOWN_LINK: &str = "copy_markdown_link";
+const ACTION_ID_REVEAL_IN_FOLDER: &str = "reveal_in_folder";
+const ACTION_ID_OPEN_WITH: &str = "open_with";
+const ACTION_ID_DISABLE_APP: &str = "disable_app";
+const ACTION_ID_COPY_CLIPBOARD_ENTRY: &str = "copy_clipboard_entry";
+const ACTION_ID_PIN_CLIPBOARD_ENTRY: &str = "pin_clipboard_entry";
+const ACTION_ID_UNPIN_CLIPBOARD_ENTRY: &str = "unpin_clipboard_entry";
+const ACTION_ID_DELETE_CLIPBOARD_ENTRY: &str = "delete_clipboard_entry";
+const ACTION_ID_COPY_SNIPPET: &str = "copy_snippet";
+const ACTION_ID_COPY_FORMATTED_VALUE: &str = "copy_formatted_value";
+const ACTION_ID_COPY_RAW_VALUE: &str = "copy_raw_value";
+
+/// Key of `extra_args` that specifies the app to open the file with, for
+/// action `DocumentActionKind::OpenWith`.
+const EXTRA_ARG_APP: &str = "app";
+
+/// A named action that can be performed on a document.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct DocumentAction {
+    /// Unique among the actions of a document, e.g., "copy_url".
+    pub(crate) id: String,
+    /// Shown to the user, e.g., "Copy URL".
+    pub(crate) name: String,
+    /// Keyboard shortcut, e.g., "CommandOrControl+Shift+C".
+    pub(crate) shortcut: Option<String>,
+    pub(crate) kind: DocumentActionKind,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) enum DocumentActionKind {
+    /// Copy `text` to the clipboard.
+    Copy { text: String },
+    /// Copy the URL of the quicklink, concatenated with the arguments in
+    /// `extra_args`.
+    CopyQuicklinkUrl { link: QuicklinkLink },
+    /// Show the file in the file manager.
+    RevealInFolder { path: String },
+    /// Open the file with the app picked by the user, specified by
+    /// `extra_args["app"]`.
+    OpenWith { path: String },
+    /// Exclude the application from the search results.
+    DisableApp { app_path: String },
+    /// Put the Clipboard History entry back onto the clipboard.
+    CopyClipboardEntry { id: String },
+    /// Exempt the Clipboard History entry from the retention limits.
+    PinClipboardEntry { id: String },
+    /// Subject the Clipboard History entry to the retention limits again.
+    UnpinClipboardEntry { id: String },
+    /// Remove the entry from the Clipboard History.
+    DeleteClipboardEntry { id: String },
+    /// Copy the snippet, with the arguments in `extra_args` filled in.
+    CopySnippet { id: String },
+}
+
+impl DocumentAction {
+    fn new(id: &str, name: &str, shortcut: Option<&str>, kind: DocumentActionKind) -> Self {
+        Self {
+            id: id.to_string(),
+            name: name.to_string(),
+            shortcut: shortcut.map(str::to_string),
+            kind,
+        }
+    }
+
+    pub(crate) fn copy_url(url: &str) -> Self {
+        Self::new(
+            ACTION_ID_COPY_URL,
+            "Copy URL",
+            Some("CommandOrControl+Shift+C"),
+            DocumentActionKind::Copy {
+                text: url.to_string(),
+            },
+        )
+    }
+
+    pub(crate) fn copy_quicklink_url(link: QuicklinkLink) -> Self {
+        Self::new(
+            ACTION_ID_COPY_URL,
+            "Copy URL",
+            Some("CommandOrControl+Shift+C"),
+            DocumentActionKind::CopyQuicklinkUrl { link },
+        )
+    }
+
+    pub(crate) fn copy_path(path: &str) -> Self {
+        Self::new(
+            ACTION_ID_COPY_PATH,
+            "Copy Path",
+            Some("CommandOrControl+Shift+C"),
+            DocumentActionKind::Copy {
+                text: path.to_string(),
+            },
+        )
+    }
+
+    pub(crate) fn copy_markdown_link(title: &str, url: &str) -> Self {
+        Self::new(
+            ACTION_ID_COPY_MARKDOWN_LINK,
+            "Copy as Markdown Link",
+            Some("CommandOrControl+Shift+M"),
+            DocumentActionKind::Copy {
+                text: markdown_link(title, url),
+            },
+        )
+    }
+
+    pub(crate) fn reveal_in_folder(path: &str) -> Self {
+        Self::new(
+            ACTION_ID_REVEAL_IN_FOLDER,
+            "Reveal in Folder",
+            Some("CommandOrControl+Enter"),
+            DocumentActionKind::RevealInFolder {
+                path: path.to_string(),
+            },
+        )
+    }
+
+    pub(crate) fn open_with(path: &str) -> Self {
+        Self::new(
+            ACTION_ID_OPEN_WITH,
+            "Open With…",
+            Some("CommandOrControl+O"),
+            DocumentActionKind::OpenWith {
+                path: path.to_string(),
+            },
+        )
+    }
+
+    pub(crate) fn disable_app(app_path: &str) -> Self {
+        Self::new(
+            ACTION_ID_DISABLE_APP,
+            "Disable This App",
+            None,
+            DocumentActionKind::DisableApp {
+                app_path: app_path.to_string(),
+            },
+        )
+    }
+
+    pub(crate) fn copy_clipboard_entry(id: &str) -> Self {
+        Self::new(
+            ACTION_ID_COPY_CLIPBOARD_ENTRY,
+            "Copy to Clipboard",
+            Some("CommandOrControl+Shift+C"),
+            DocumentActionKind::CopyClipboardEntry { id: id.to_string() },
+        )
+    }
+
+    /// Pin the entry if `pinned` is true, unpin it otherwise.
+    pub(crate) fn pin_clipboard_entry(id: &str, pinned: bool) -> Self {
+        if pinned {
+            Self::new(
+                ACTION_ID_PIN_CLIPBOARD_ENTRY,
+                "Pin",
+                Some("CommandOrControl+Shift+P"),
+                DocumentActionKind::PinClipboardEntry { id: id.to_string() },
+            )
+        } else {
+            Self::new(
+                ACTION_ID_UNPIN_CLIPBOARD_ENTRY,
+                "Unpin",
+                Some("CommandOrControl+Shift+P"),
+                DocumentActionKind::UnpinClipboardEntry { id: id.to_string() },
+            )
+        }
+    }
+
+    pub(crate) fn delete_clipboard_entry(id: &str) -> Self {
+        Self::new(
+            ACTION_ID_DELETE_CLIPBOARD_ENTRY,
+            "Delete",
+            Some("CommandOrControl+Backspace"),
+            DocumentActionKind::DeleteClipboardEntry { id: id.to_string() },
+        )
+    }
+
+    /// Copy a calculated value as displayed, e.g., "1,234.5 mi".
+    pub(crate) fn copy_formatted_value(value: &str) -> Self {
+        Self::new(
+            ACTION_ID_COPY_FORMATTED_VALUE,
+            "Copy Result",
+            Some("CommandOrControl+Shift+C"),
+            DocumentActionKind::Copy {
+                text: value.to_string(),
+            },
+        )
+    }
+
+    /// Copy a calculated value as a bare number, e.g., "1234.5".
+    pub(crate) fn copy_raw_value(value: &str) -> Self {
+        Self::new(
+            ACTION_ID_COPY_RAW_VALUE,
+            "Copy Raw Value",
+            Some("CommandOrControl+Shift+R"),
+            DocumentActionKind::Copy {
+                text: value.to_string(),
+            },
+        )
+    }
+
+    pub(crate) fn copy_snippet(id: &str) -> Self {
+        Self::new(
+            ACTION_ID_COPY_SNIPPET,
+            "Copy to Clipboard",
+            Some("CommandOrControl+Shift+C"),
+            DocumentActionKind::CopySnippet { id: id.to_string() },
+        )
+    }
+}
+
+/// Default actions of a local file.
+pub(crate) fn file_actions(path: &str, file_name: &str) -> Vec<DocumentAction> {
+    vec![
+        DocumentAction::reveal_in_folder(path),
+        DocumentAction::open_with(path),
+        DocumentAction::copy_path(path),
+        DocumentAction::copy_markdown_link(file_name, path),
+    ]
+}
+
+/// Default actions of a document that has a URL.
+pub(crate) fn url_actions(title: Option<&str>, url: &str) -> Vec<DocumentAction> {
+    vec![
+        DocumentAction::copy_url(url),
+        DocumentAction::copy_markdown_link(title.unwrap_or(url), url),
+    ]
+}
+
+/// Return a Markdown link "[title](url)".
+fn markdown_link(title: &str, url: &str) -> String {
+    let mut escaped_title = String::with_capacity(title.len());
+    for c in title.chars() {
+        if matches!(c, '[' | ']' | '\\') {
+            escaped_title.push('\\');
+        }
+        escaped_title.push(c);
+    }
+    // Whitespace and parentheses would end the link destination
+    let escaped_url = url
+        .replace(' ', "%20")
+        .replace('(', "%28")
+        .replace(')', "%29");
+
+    format!("[{}]({})", escaped_title, escaped_url)
+}
+
+/// Perform the action `action_id` of `document`.
+///
+/// `extra_args` carries the arguments provided by the user, if the action
+/// needs any.
+#[tauri::command]
+pub(crate) async fn execute_document_action(
+    tauri_app_handle: AppHandle,
+    document: Document,
+    action_id: String,
+    extra_args: Option<HashMap<String, Json>>,
+) -> Result<(), String> {
+    let Some(action) = document
+        .actions
+        .into_iter()
+        .flatten()
+        .find(|action| action.id == action_id)
+    else {
+        return Err(format!(
+            "document [{}] has no action [{}]",
+            document.id, action_id
+        ));
+    };
+
+    log::debug!(
+        "executing action [{}] of document [{}]",
+        action.id,
+        document.id
+    );
+
+    match action.kind {
+        DocumentActionKind::Copy { text } => tauri_app_handle
+            .clipboard()
+            .write_text(text)
+            .map_err(|e| e.to_string()),
+        DocumentActionKind::CopyQuicklinkUrl { link } => tauri_app_handle
+            .clipboard()
+            .write_text(link.concatenate_url(&extra_args))
+            .map_err(|e| e.to_string()),
+        DocumentActionKind::RevealInFolder { path } => {
+            tauri_plugin_opener::reveal_item_in_dir(&path)
+                .map_err(|e| format!("failed to reveal [{}] in folder due to error [{}]", path, e))
+        }
+        DocumentActionKind::OpenWith { path } => {
+            let Some(app) = extra_args
+                .as_ref()
+                .and_then(|args| args.get(EXTRA_ARG_APP))
+                .and_then(Json::as_str)
+            else {
+                return Err(format!(
+                    "action [{}] requires argument [{}]",
+                    ACTION_ID_OPEN_WITH, EXTRA_ARG_APP
+                ));
+            };
+
+            tauri_app_handle
+                .opener()
+                .open_path(path.as_str(), Some(app))
+                .map_err(|e| {
+                    format!(
+                        "failed to open [{}] with [{}] due to error [{}]",
+                        path, app, e
+                    )
+                })
+        }
+        DocumentActionKind::DisableApp { app_path } => {
+            crate::extension::built_in::application::disable_app_search(
+                &tauri_app_handle,
+                &app_path,
+            )
+        }
+        DocumentActionKind::CopyClipboardEntry { id } => {
+            crate::extension::built_in::clipboard_history::copy_entry(&tauri_app_handle, &id)
+        }
+        DocumentActionKind::PinClipboardEntry { id } => {
+            crate::extension::built_in::clipboard_history::pin_entry(&id, true)
+        }
+        DocumentActionKind::UnpinClipboardEntry { id } => {
+            crate::extension::built_in::clipboard_history::pin_entry(&id, false)
+        }
+        DocumentActionKind::DeleteClipboardEntry { id } => {
+            crate::extension::built_in::clipboard_history::delete_entry(&id)
+        }
+        DocumentActionKind::CopySnippet { id } => {
+            crate::extension::built_in::snippets::copy_snippet(&tauri_app_handle, &id, &extra_args)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_markdown_link() {
+        assert_eq!(
+            markdown_link("Coco AI", "https://coco.rs"),
+            "[Coco AI](https://coco.rs)"
+        );
+        assert_eq!(
+            markdown_link("[draft] notes", "/tmp/my notes (1).md"),
+            "[\\[draft\\] notes](/tmp/my%20notes%20%281%29.md)"
+        );
+    }
+
+    #[test]
+    fn test_default_actions_have_unique_ids() {
+        for actions in [
+            file_actions("/tmp/a.txt", "a.txt"),
+            url_actions(None, "https://coco.rs"),
+        ] {
+            let mut ids: Vec<&str> = actions.iter().map(|action| action.id.as_str()).collect();
+            let n_actions = ids.len();
+            ids.sort();
+            ids.dedup();
+            assert_eq!(ids.len(), n_actions);
+        }
+    }
+}
@@ -40,10 +40,43 @@ pub enum SearchError {
     /// The search operation timed out.
     #[snafu(display("search operation timed out"))]
     SearchTimeout,
+    /// The search was cancelled because a newer search of the same query
+    /// session arrived.
+    #[snafu(display("search cancelled, superseded by a newer search"))]
+    Cancelled,
+    /// The query is malformed, e.g., its prefix refers to a query source that
+    /// does not exist.
+    #[snafu(display("invalid query: {}", reason))]
+    InvalidQuery { reason: String },
     #[snafu(display("an internal error occurred: '{}'", error))]
     InternalError { error: String },
 }
 
+/// Kind of a `SearchError`, without the details.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchErrorKind {
+    HttpError,
+    ResponseDecodeError,
+    SearchTimeout,
+    Cancelled,
+    InvalidQuery,
+    InternalError,
+}
+
+impl SearchError {
+    pub(crate) fn kind(&self) -> SearchErrorKind {
+        match self {
+            Self::HttpError { .. } => SearchErrorKind::HttpError,
+            Self::ResponseDecodeError { .. } => SearchErrorKind::ResponseDecodeError,
+            Self::SearchTimeout => SearchErrorKind::SearchTimeout,
+            Self::Cancelled => SearchErrorKind::Cancelled,
+            Self::InvalidQuery { .. } => SearchErrorKind::InvalidQuery,
+            Self::InternalError { .. } => SearchErrorKind::InternalError,
+        }
+    }
+}
+
 pub(crate) fn serialize_error<S, E: std::error::Error>(
     error: &E,
     serializer: S,
@@ -14,3 +14,27 @@ pub struct Health {
     pub services: Option<HashMap<String, Status>>,
     pub status: Status,
 }
+
+/// Health of a search source, see `SearchSource::health()`.
+#[derive(Debug, Clone, Serialize)]
+pub struct SourceHealth {
+    pub status: Status,
+    /// Why it is not healthy, `None` if `status` is `Status::Green`.
+    pub reason: Option<String>,
+}
+
+impl SourceHealth {
+    pub fn healthy() -> Self {
+        Self {
+            status: Status::Green,
+            reason: None,
+        }
+    }
+
+    pub fn unhealthy(status: Status, reason: impl Into<String>) -> Self {
+        Self {
+            status,
+            reason: Some(reason.into()),
+        }
+    }
+}
@@ -2,6 +2,7 @@ pub mod assistant;
 pub mod connector;
 pub mod datasource;
 pub mod document;
+pub mod document_action;
 pub mod error;
 pub mod health;
 pub mod http;
@@ -1,32 +1,79 @@
+use crate::common::error::SearchError;
 use crate::common::traits::SearchSource;
 use std::collections::HashMap;
 use std::sync::Arc;
+use tauri::AppHandle;
 use tokio::sync::RwLock;
 
-// Define a shared registry for search sources
+/// Registry of the search sources.
+///
+/// It drives the lifecycle hooks of the search sources: `SearchSource::init()`
+/// is called before registering, and `SearchSource::shutdown()` is called after
+/// removing.  The hooks are called without holding the lock so that slow hooks
+/// won't block searches.
 #[derive(Default)]
 pub struct SearchSourceRegistry {
     sources: RwLock<HashMap<String, Arc<dyn SearchSource>>>, // Store trait objects
 }
 
 impl SearchSourceRegistry {
-    pub async fn register_source<T: SearchSource + 'static>(&self, source: T) {
-        let mut sources = self.sources.write().await;
+    /// Initialize and register `source`, it replaces the registered search
+    /// source with the same ID, if any.
+    ///
+    /// The replaced search source is shut down before `source` gets
+    /// initialized, as they may share global states, e.g., hotkeys.
+    ///
+    /// If the initialization fails, `source` won't be registered, and the error
+    /// is returned.
+    pub async fn register_source<T: SearchSource + 'static>(
+        &self,
+        tauri_app_handle: &AppHandle,
+        source: T,
+    ) -> Result<(), SearchError> {
         let source_id = source.get_type().id.clone();
-        sources.insert(source_id, Arc::new(source));
+        self.remove_source(tauri_app_handle, &source_id).await;
+
+        source.init(tauri_app_handle).await?;
+
+        let opt_replaced = {
+            let mut sources = self.sources.write().await;
+            sources.insert(source_id, Arc::new(source))
+        };
+        // Another one with the same ID got registered while we were
+        // initializing this one.
+        if let Some(replaced) = opt_replaced {
+            replaced.shutdown(tauri_app_handle).await;
+        }
+
+        Ok(())
     }
 
     #[allow(dead_code)]
-    pub async fn clear(&self) {
-        let mut sources = self.sources.write().await;
-        sources.clear();
+    pub async fn clear(&self, tauri_app_handle: &AppHandle) {
+        let removed: Vec<Arc<dyn SearchSource>> = {
+            let mut sources = self.sources.write().await;
+            sources.drain().map(|(_id, source)| source).collect()
+        };
+        for source in removed {
+            source.shutdown(tauri_app_handle).await;
+        }
     }
 
     /// Remove the SearchSource specified by `id`, return a boolean indicating
     /// if it get removed or not.
-    pub async fn remove_source(&self, id: &str) -> bool {
-        let mut sources = self.sources.write().await;
-        sources.remove(id).is_some()
+    pub async fn remove_source(&self, tauri_app_handle: &AppHandle, id: &str) -> bool {
+        let opt_removed = {
+            let mut sources = self.sources.write().await;
+            sources.remove(id)
+        };
+
+        match opt_removed {
+            Some(removed) => {
+                removed.shutdown(tauri_app_handle).await;
+                true
+            }
+            None => false,
+        }
     }
 
     #[allow(dead_code)]
@@ -1,4 +1,5 @@
 use crate::common::document::Document;
+use crate::common::error::SearchErrorKind;
 use crate::common::http::get_response_body_text;
 use reqwest::Response;
 use serde::de::Deserializer;
@@ -114,6 +115,11 @@ pub struct QueryHits {
     pub source: Option<QuerySource>,
     pub score: f64,
     pub document: Document,
+    /// Other query sources that returned the same document.
+    ///
+    /// Duplicate hits from different query sources are merged into one, see
+    /// `crate::search::dedup`.
+    pub also_found_in: Vec<QuerySource>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -302,12 +308,78 @@ pub struct QueryResponse {
     pub aggregations: Option<Aggregations>,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Default, Serialize)]
 pub struct MultiSourceQueryResponse {
     pub failed: Vec<FailedRequest>,
     pub hits: Vec<QueryHits>,
     pub total_hits: usize,
     pub aggregations: Option<Aggregations>,
+    /// How every queried source performed.
+    pub diagnostics: Vec<SourceDiagnostics>,
+    /// Pass it to the next `query_coco_fusion()` call to get the next page,
+    /// `None` if there are no more hits. See `crate::search::cursor`.
+    pub next_cursor: Option<String>,
+}
+
+/// Per-source breakdown of a `query_coco_fusion()` call.
+#[derive(Debug, Clone, Serialize)]
+pub struct SourceDiagnostics {
+    pub source: QuerySource,
+    /// Time spent on this source, in milliseconds.
+    pub elapsed_ms: u64,
+    /// Number of hits returned by this source, before rank fusion.
+    pub hit_count: usize,
+    pub timed_out: bool,
+    /// Set if this source failed or timed out.
+    pub error_kind: Option<SearchErrorKind>,
+}
+
+/// Scores of a hit at different ranking stages, returned by
+/// `query_coco_fusion_debug()`.
+#[derive(Debug, Clone, Serialize)]
+pub struct HitScoreDebug {
+    pub source: QuerySource,
+    pub document_id: String,
+    pub title: Option<String>,
+    /// Score returned by the query source.
+    pub raw_score: f64,
+    /// Score after rank fusion, `None` if fusion dropped this hit.
+    pub fused_score: Option<f64>,
+    /// Score after deduplication and frecency boost, `None` if this hit is not
+    /// in the final result.
+    pub final_score: Option<f64>,
+    /// Position in the final result.
+    pub final_rank: Option<usize>,
+}
+
+/// Payload of the events emitted by `query_coco_fusion_stream()`.
+#[derive(Debug, Clone, Serialize)]
+pub struct QueryFusionStreamEvent {
+    /// The query ID passed to `query_coco_fusion_stream()`, the frontend uses
+    /// it to drop events that belong to an outdated query.
+    pub query_id: String,
+    /// Starts from 0, incremented by 1 for every event emitted for this query.
+    pub revision: u32,
+    pub payload: QueryFusionStreamPayload,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum QueryFusionStreamPayload {
+    /// Hits from a single query source, emitted as soon as it responds.
+    ///
+    /// Scores are the raw scores returned by that source, they are not
+    /// comparable across sources.
+    SourceHits {
+        source: QuerySource,
+        hits: Vec<QueryHits>,
+        total_hits: usize,
+    },
+    /// A query source failed.
+    SourceFailed { failed: FailedRequest },
+    /// The re-ranked snapshot, emitted once after all the query sources have
+    /// responded (or timed out). This is always the last event of a query.
+    Final { response: MultiSourceQueryResponse },
 }
 
 #[cfg(test)]
@@ -1,9 +1,17 @@
+use crate::common::document::Document;
 use crate::common::error::SearchError;
+use crate::common::health::SourceHealth;
 use crate::common::search::SearchQuery;
 use crate::common::search::{QueryResponse, QuerySource};
+use crate::search::preview::DocumentPreview;
 use async_trait::async_trait;
 use tauri::AppHandle;
 
+/// A search source, it should be registered to `SearchSourceRegistry` to be
+/// searched.
+///
+/// Apart from `get_type()` and `search()`, the methods are optional hooks with
+/// default implementations, `SearchSourceRegistry` drives them.
 #[async_trait]
 pub trait SearchSource: Send + Sync {
     fn get_type(&self) -> QuerySource;
@@ -13,4 +21,51 @@ pub trait SearchSource: Send + Sync {
         tauri_app_handle: AppHandle,
         query: SearchQuery,
     ) -> Result<QueryResponse, SearchError>;
+
+    /// If `search()` honours `SearchQuery::from`.
+    ///
+    /// If not, it is always searched from 0 for all the hits up to the
+    /// requested page, and the hits on the previous pages are dropped, see
+    /// `crate::search::cursor`.
+    fn supports_pagination(&self) -> bool {
+        true
+    }
+
+    /// Called when this search source is being registered, before it becomes
+    /// searchable.  If it fails, this search source won't be registered.
+    async fn init(&self, _tauri_app_handle: &AppHandle) -> Result<(), SearchError> {
+        Ok(())
+    }
+
+    /// Called after this search source is removed from the registry, or
+    /// replaced by another search source with the same ID.
+    async fn shutdown(&self, _tauri_app_handle: &AppHandle) {}
+
+    /// Report if this search source works.
+    async fn health(&self, _tauri_app_handle: &AppHandle) -> SourceHealth {
+        SourceHealth::healthy()
+    }
+
+    /// Return the rich preview of `document`, which is returned by this search
+    /// source, or `None` if there is nothing more to show than its summary.
+    ///
+    /// Previews are cached by `crate::search::preview::preview_document()`.
+    async fn preview(
+        &self,
+        _tauri_app_handle: &AppHandle,
+        _document: &Document,
+    ) -> Result<Option<DocumentPreview>, SearchError> {
+        Ok(None)
+    }
+
+    /// Return at most `size` completions of `prefix`, the partial query that
+    /// the user is typing.
+    async fn suggest(
+        &self,
+        _tauri_app_handle: &AppHandle,
+        _prefix: &str,
+        _size: usize,
+    ) -> Result<Vec<String>, SearchError> {
+        Ok(Vec::new())
+    }
 }
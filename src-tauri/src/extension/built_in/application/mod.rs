@@ -25,7 +25,7 @@ pub struct AppEntry {
     is_disabled: bool,
 }
 
-#[derive(serde::Serialize)]
+#[derive(Debug, Clone, serde::Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct AppMetadata {
     name: String,
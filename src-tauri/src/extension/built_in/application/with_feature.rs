@@ -5,11 +5,15 @@ use super::super::pizza_engine_runtime::Task;
 use super::AppMetadata;
 use crate::GLOBAL_TAURI_APP_HANDLE;
 use crate::common::document::{DataSourceReference, Document, OnOpened};
+use crate::common::document_action::DocumentAction;
 use crate::common::error::SearchError;
 use crate::common::search::{QueryResponse, QuerySource, SearchQuery};
 use crate::common::traits::SearchSource;
 use crate::extension::ExtensionType;
 use crate::extension::LOCAL_QUERY_SOURCE_TYPE;
+use crate::search::fuzzy::fuzzy_match;
+use crate::search::preview::DocumentPreview;
+use crate::search::transliteration::transliteration_keys_for_indexing;
 use crate::util::open;
 use applications::{App, AppTrait};
 use async_trait::async_trait;
@@ -45,6 +49,9 @@ const FIELD_APP_NAME_ZH: &str = "app_name_zh";
 const FIELD_APP_NAME_EN: &str = "app_name_en";
 const FIELD_ICON_PATH: &str = "icon_path";
 const FIELD_APP_ALIAS: &str = "app_alias";
+/// Pinyin transliteration keys of the Chinese app names, see
+/// `crate::search::transliteration`.
+const FIELD_APP_NAME_PINYIN: &str = "app_name_pinyin";
 const APPLICATION_SEARCH_SOURCE_ID: &str = "application";
 
 const TAURI_STORE_DISABLED_APP_LIST_AND_SEARCH_PATH: &str = "disabled_app_list_and_search_path";
@@ -54,7 +61,10 @@ const TAURI_STORE_APP_ALIAS: &str = "app_alias";
 const TAURI_STORE_KEY_SEARCH_PATH: &str = "search_path";
 const TAURI_STORE_KEY_DISABLED_APP_LIST: &str = "disabled_app_list";
 
-const INDEX_DIR: &str = "local_application_index";
+/// Bump the version when the index schema changes, which triggers a re-index.
+const INDEX_DIR: &str = "local_application_index_v2";
+/// Index directories of the previous versions, they will be removed.
+const LEGACY_INDEX_DIRS: &[&str] = &["local_application_index"];
 
 /// We use this as:
 ///
@@ -267,6 +277,10 @@ async fn index_applications_if_not_indexed(
     schema
         .add_property(FIELD_APP_ALIAS, Property::as_text(None))
         .expect("no collision could happen");
+    let field_app_name_pinyin = Property::builder(FieldType::Text).build();
+    schema
+        .add_property(FIELD_APP_NAME_PINYIN, field_app_name_pinyin)
+        .expect("no collision could happen");
     schema.freeze();
     pizza_engine_builder.set_schema(schema);
 
@@ -310,6 +324,12 @@ async fn index_applications_if_not_indexed(
                 .await
                 .map_err(|str| anyhow::anyhow!(str))?;
             let app_alias = get_app_alias(&tauri_app_handle, &app_path).unwrap_or(String::new());
+            let app_name_pinyin = [&app_name_zh, &app_name_in_system_lang]
+                .into_iter()
+                .map(|app_name| transliteration_keys_for_indexing(app_name))
+                .filter(|keys| !keys.is_empty())
+                .collect::<Vec<_>>()
+                .join(" ");
 
             // Skip if all names are empty
             if app_name_zh.is_empty()
@@ -338,6 +358,7 @@ async fn index_applications_if_not_indexed(
                 FIELD_APP_NAME_IN_SYSTEM_LANG => app_name_in_system_lang,
                 FIELD_ICON_PATH => app_icon_path,
                 FIELD_APP_ALIAS => app_alias,
+                FIELD_APP_NAME_PINYIN => app_name_pinyin,
               }
             );
 
@@ -364,6 +385,23 @@ async fn index_applications_if_not_indexed(
     })
 }
 
+/// Best-effort removal of the index directories of the previous versions,
+/// `app_data_dir` is the parent directory of them.
+async fn remove_legacy_index_dirs(app_data_dir: &Path) {
+    for legacy_index_dir in LEGACY_INDEX_DIRS {
+        let legacy_index_dir = app_data_dir.join(legacy_index_dir);
+        if legacy_index_dir.exists()
+            && let Err(e) = tokio::fs::remove_dir_all(&legacy_index_dir).await
+        {
+            warn!(
+                "failed to remove legacy application index [{}] due to error [{}]",
+                legacy_index_dir.display(),
+                e
+            );
+        }
+    }
+}
+
 /// Upon application start, index all the applications found in the `get_default_search_paths()`.
 struct IndexAllApplicationsTask {
     tauri_app_handle: AppHandle,
@@ -383,6 +421,7 @@ impl Task for IndexAllApplicationsTask {
             .path()
             .app_data_dir()
             .expect("failed to find the local dir");
+        remove_legacy_index_dirs(&app_index_dir).await;
         app_index_dir.push(INDEX_DIR);
         let app_search_source_state = task_exec_try!(
             index_applications_if_not_indexed(&self.tauri_app_handle, &app_index_dir).await,
@@ -478,7 +517,9 @@ impl Task for SearchApplicationsTask {
         // In order to be backward compatible, we still do match and prefix queries to the
         // app_name field.
         let dsl = format!(
-            "{{ \"query\": {{ \"bool\": {{ \"should\": [ {{ \"match\": {{ \"{FIELD_APP_NAME_ZH}\": {:?} }} }}, {{ \"prefix\": {{ \"{FIELD_APP_NAME_ZH}\": {:?} }} }}, {{ \"match\": {{ \"{FIELD_APP_NAME_EN}\": {:?} }} }}, {{ \"prefix\": {{ \"{FIELD_APP_NAME_EN}\": {:?} }} }}, {{ \"match\": {{ \"{FIELD_APP_NAME_IN_SYSTEM_LANG}\": {:?} }} }}, {{ \"prefix\": {{ \"{FIELD_APP_NAME_IN_SYSTEM_LANG}\": {:?} }} }}, {{ \"match\": {{ \"{FIELD_APP_NAME}\": {:?} }} }}, {{ \"prefix\": {{ \"{FIELD_APP_NAME}\": {:?} }} }} ] }} }} }}",
+            "{{ \"query\": {{ \"bool\": {{ \"should\": [ {{ \"match\": {{ \"{FIELD_APP_NAME_ZH}\": {:?} }} }}, {{ \"prefix\": {{ \"{FIELD_APP_NAME_ZH}\": {:?} }} }}, {{ \"match\": {{ \"{FIELD_APP_NAME_EN}\": {:?} }} }}, {{ \"prefix\": {{ \"{FIELD_APP_NAME_EN}\": {:?} }} }}, {{ \"match\": {{ \"{FIELD_APP_NAME_IN_SYSTEM_LANG}\": {:?} }} }}, {{ \"prefix\": {{ \"{FIELD_APP_NAME_IN_SYSTEM_LANG}\": {:?} }} }}, {{ \"match\": {{ \"{FIELD_APP_NAME}\": {:?} }} }}, {{ \"prefix\": {{ \"{FIELD_APP_NAME}\": {:?} }} }}, {{ \"match\": {{ \"{FIELD_APP_NAME_PINYIN}\": {:?} }} }}, {{ \"prefix\": {{ \"{FIELD_APP_NAME_PINYIN}\": {:?} }} }} ] }} }} }}",
+            self.query_string,
+            self.query_string,
             self.query_string,
             self.query_string,
             self.query_string,
@@ -573,8 +614,23 @@ impl Task for IndexNewApplicationsTask {
 
 pub struct ApplicationSearchSource;
 
+/// Set once the stores are prepared and the applications are indexed.
+static INDEX_AND_STORE_PREPARED: tokio::sync::OnceCell<()> = tokio::sync::OnceCell::const_new();
+
 impl ApplicationSearchSource {
+    /// Prepare the stores and index the applications, this only does the work
+    /// once.
+    ///
+    /// Apart from `init()`, it is also called on startup as the settings page
+    /// lists the indexed applications even if this extension is disabled.
     pub async fn prepare_index_and_store(app_handle: AppHandle) -> Result<(), String> {
+        INDEX_AND_STORE_PREPARED
+            .get_or_try_init(|| Self::_prepare_index_and_store(app_handle))
+            .await
+            .map(|_| ())
+    }
+
+    async fn _prepare_index_and_store(app_handle: AppHandle) -> Result<(), String> {
         app_handle
             .store(TAURI_STORE_APP_HOTKEY)
             .map_err(|e| e.to_string())?;
@@ -638,6 +694,41 @@ impl SearchSource for ApplicationSearchSource {
         }
     }
 
+    /// Pizza engine returns all the matched apps, `from` is ignored.
+    fn supports_pagination(&self) -> bool {
+        false
+    }
+
+    /// Index the applications if they are not indexed yet, and set up the app
+    /// hotkeys, which work only if the application extension is enabled, i.e.,
+    /// registered.
+    async fn init(&self, tauri_app_handle: &AppHandle) -> Result<(), SearchError> {
+        Self::prepare_index_and_store(tauri_app_handle.clone())
+            .await
+            .map_err(|error| SearchError::InternalError { error })?;
+        set_apps_hotkey(tauri_app_handle).map_err(|error| SearchError::InternalError { error })
+    }
+
+    async fn preview(
+        &self,
+        _tauri_app_handle: &AppHandle,
+        document: &Document,
+    ) -> Result<Option<DocumentPreview>, SearchError> {
+        // Document ID is the app path
+        let app_name = document.title.clone().unwrap_or_default();
+        let metadata = get_app_metadata(app_name, document.id.clone())
+            .await
+            .map_err(|error| SearchError::InternalError { error })?;
+
+        Ok(Some(DocumentPreview::Application { metadata }))
+    }
+
+    async fn shutdown(&self, tauri_app_handle: &AppHandle) {
+        if let Err(e) = unset_apps_hotkey(tauri_app_handle) {
+            error!("failed to unset app hotkeys, error [{}]", e);
+        }
+    }
+
     async fn search(
         &self,
         _tauri_app_handle: AppHandle,
@@ -665,7 +756,7 @@ impl SearchSource for ApplicationSearchSource {
                 .get()
                 .expect("global tauri app handle not initialized")
                 .clone(),
-            query_string,
+            query_string: query_string.clone(),
             callback: Some(tx),
         };
 
@@ -685,7 +776,16 @@ impl SearchSource for ApplicationSearchSource {
 
         let total_hits = search_result.total_hits;
         let source = self.get_type();
-        let hits = pizza_engine_hits_to_coco_hits(search_result.hits).await;
+        let mut hits = pizza_engine_hits_to_coco_hits(search_result.hits).await;
+
+        // Applications are matched by Pizza engine, we only need the match
+        // ranges of the app names for highlighting.
+        for (document, _score) in hits.iter_mut() {
+            if let Some(ref app_name) = document.title {
+                document.title_match_ranges =
+                    fuzzy_match(&query_string, app_name).map(|name_match| name_match.ranges);
+            }
+        }
 
         Ok(QueryResponse {
             source,
@@ -769,6 +869,11 @@ async fn pizza_engine_hits_to_coco_hits(
             title: Some(app_name),
             icon: Some(app_icon_path),
             on_opened: Some(on_opened),
+            actions: Some(vec![
+                DocumentAction::reveal_in_folder(&app_path),
+                DocumentAction::copy_path(&app_path),
+                DocumentAction::disable_app(&app_path),
+            ]),
             url: Some(url),
 
             ..Default::default()
@@ -829,7 +934,7 @@ fn app_hotkey_handler(
 }
 
 /// For all the applications, if it is enabled & has hotkey set, then set it up.
-pub(crate) fn set_apps_hotkey(tauri_app_handle: &AppHandle) -> Result<(), String> {
+fn set_apps_hotkey(tauri_app_handle: &AppHandle) -> Result<(), String> {
     let app_hotkey_store = tauri_app_handle
         .store(TAURI_STORE_APP_HOTKEY)
         .unwrap_or_else(|_| panic!("store [{}] not found/loaded", TAURI_STORE_APP_HOTKEY));
@@ -853,7 +958,7 @@ pub(crate) fn set_apps_hotkey(tauri_app_handle: &AppHandle) -> Result<(), String
 }
 
 /// For all the applications, if it is enabled & has hotkey set, then unset it.
-pub(crate) fn unset_apps_hotkey(tauri_app_handle: &AppHandle) -> Result<(), String> {
+fn unset_apps_hotkey(tauri_app_handle: &AppHandle) -> Result<(), String> {
     let app_hotkey_store = tauri_app_handle
         .store(TAURI_STORE_APP_HOTKEY)
         .unwrap_or_else(|_| panic!("store [{}] not found/loaded", TAURI_STORE_APP_HOTKEY));
@@ -1238,9 +1343,11 @@ pub async fn get_app_list(tauri_app_handle: AppHandle) -> Result<Vec<Extension>,
             r#type: ExtensionType::Application,
             action: None,
             quicklink: None,
+            setting: None,
             commands: None,
             scripts: None,
             views: None,
+            setting_entries: None,
             quicklinks: None,
             alias: Some(alias),
             hotkey,
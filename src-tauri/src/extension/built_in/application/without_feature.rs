@@ -113,16 +113,6 @@ pub async fn get_app_metadata(
     unreachable!("app list should be empty, there is no way this can be invoked")
 }
 
-pub(crate) fn set_apps_hotkey(_tauri_app_handle: &AppHandle) -> Result<(), String> {
-    // no-op
-    Ok(())
-}
-
-pub(crate) fn unset_apps_hotkey(_tauri_app_handle: &AppHandle) -> Result<(), String> {
-    // no-op
-    Ok(())
-}
-
 #[tauri::command]
 pub async fn reindex_applications(_tauri_app_handle: AppHandle) -> Result<(), String> {
     // no-op
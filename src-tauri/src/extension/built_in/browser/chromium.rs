@@ -0,0 +1,128 @@
+//! Chromium-family browsers (Chrome, Chromium, Brave, Edge, Vivaldi), they
+//! store bookmarks in a JSON file named `Bookmarks` under the profile
+//! directory.
+
+use super::{BrowserEntry, EntryKind};
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Name of the bookmarks file in a profile directory.
+pub(super) const BOOKMARKS_FILE: &str = "Bookmarks";
+
+#[derive(Debug, Deserialize)]
+struct BookmarksFile {
+    /// "bookmark_bar", "other", "synced", and possibly new roots added by
+    /// newer browser versions, parse them lazily so that an unknown root
+    /// won't break the others.
+    roots: HashMap<String, Value>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum BookmarkNode {
+    Url {
+        name: String,
+        url: String,
+    },
+    Folder {
+        name: String,
+        #[serde(default)]
+        children: Vec<BookmarkNode>,
+    },
+}
+
+/// Read the bookmarks from the `Bookmarks` file at `path`.
+pub(super) fn read_bookmarks(path: &Path) -> Result<Vec<BrowserEntry>, String> {
+    let content = std::fs::read(path)
+        .map_err(|e| format!("failed to read [{}], error [{}]", path.display(), e))?;
+    parse_bookmarks(&content)
+        .map_err(|e| format!("failed to parse [{}], error [{}]", path.display(), e))
+}
+
+fn parse_bookmarks(content: &[u8]) -> Result<Vec<BrowserEntry>, serde_json::Error> {
+    let file: BookmarksFile = serde_json::from_slice(content)?;
+
+    let mut entries = Vec::new();
+    for (root_name, root) in file.roots {
+        match serde_json::from_value::<BookmarkNode>(root) {
+            Ok(node) => collect_bookmarks(node, &mut Vec::new(), &mut entries),
+            Err(e) => log::debug!("skipping bookmark root [{}], error [{}]", root_name, e),
+        }
+    }
+
+    Ok(entries)
+}
+
+fn collect_bookmarks(node: BookmarkNode, folders: &mut Vec<String>, out: &mut Vec<BrowserEntry>) {
+    match node {
+        BookmarkNode::Url { name, url } => {
+            if !is_openable_url(&url) {
+                return;
+            }
+            out.push(BrowserEntry {
+                title: name,
+                url,
+                kind: EntryKind::Bookmark,
+                folder: (!folders.is_empty()).then(|| folders.join("/")),
+                visit_count: 0,
+            });
+        }
+        BookmarkNode::Folder { name, children } => {
+            folders.push(name);
+            for child in children {
+                collect_bookmarks(child, folders, out);
+            }
+            folders.pop();
+        }
+    }
+}
+
+/// Bookmarklets cannot be opened from Coco.
+fn is_openable_url(url: &str) -> bool {
+    !url.starts_with("javascript:")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_bookmarks() {
+        let content = br#"{
+          "checksum": "0",
+          "roots": {
+            "bookmark_bar": {
+              "type": "folder",
+              "name": "Bookmarks bar",
+              "children": [
+                { "type": "url", "name": "Wiki", "url": "https://wiki.internal/" },
+                {
+                  "type": "folder",
+                  "name": "Work",
+                  "children": [
+                    { "type": "url", "name": "CI", "url": "https://ci.internal/" },
+                    { "type": "url", "name": "Bookmarklet", "url": "javascript:alert(1)" }
+                  ]
+                }
+              ]
+            },
+            "other": { "type": "folder", "name": "Other bookmarks", "children": [] },
+            "sync_transaction_version": "1"
+          },
+          "version": 1
+        }"#;
+
+        let mut entries = parse_bookmarks(content).unwrap();
+        entries.sort_by(|a, b| a.title.cmp(&b.title));
+
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].title, "CI");
+        assert_eq!(entries[0].url, "https://ci.internal/");
+        assert_eq!(entries[0].folder.as_deref(), Some("Bookmarks bar/Work"));
+        assert_eq!(entries[1].title, "Wiki");
+        assert_eq!(entries[1].folder.as_deref(), Some("Bookmarks bar"));
+        assert!(entries.iter().all(|e| e.kind == EntryKind::Bookmark));
+    }
+}
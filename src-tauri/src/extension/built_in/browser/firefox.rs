@@ -0,0 +1,186 @@
+//! Firefox, it stores both bookmarks and history in the `places.sqlite`
+//! database under the profile directory.
+
+use super::{BrowserEntry, EntryKind};
+use rusqlite::Connection;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+/// Name of the places database in a profile directory.
+pub(super) const PLACES_FILE: &str = "places.sqlite";
+/// Write-ahead log of the places database, recent changes live here until
+/// Firefox checkpoints them.
+pub(super) const PLACES_WAL_FILE: &str = "places.sqlite-wal";
+
+/// Only the most frecent history entries are indexed, heavy users have
+/// hundreds of thousands of them.
+const HISTORY_LIMIT: usize = 10_000;
+
+/// `moz_bookmarks.type` of a bookmark (rather than a folder or separator).
+const TYPE_BOOKMARK: i64 = 1;
+
+/// Read the bookmarks and history from the places database at `path`.
+///
+/// Firefox locks the database while it is running, so we read a copy of it.
+pub(super) fn read_places(path: &Path) -> Result<Vec<BrowserEntry>, String> {
+    let copy = PlacesCopy::new(path)?;
+    let connection = Connection::open(&copy.db).map_err(|e| {
+        format!(
+            "failed to open the copy of [{}], error [{}]",
+            path.display(),
+            e
+        )
+    })?;
+
+    let mut entries = query_bookmarks(&connection).map_err(|e| {
+        format!(
+            "failed to read bookmarks of [{}], error [{}]",
+            path.display(),
+            e
+        )
+    })?;
+    entries.extend(query_history(&connection).map_err(|e| {
+        format!(
+            "failed to read history of [{}], error [{}]",
+            path.display(),
+            e
+        )
+    })?);
+
+    Ok(entries)
+}
+
+fn query_bookmarks(connection: &Connection) -> rusqlite::Result<Vec<BrowserEntry>> {
+    let mut statement = connection.prepare(
+        "SELECT b.title, p.url, folder.title
+         FROM moz_bookmarks b
+         JOIN moz_places p ON b.fk = p.id
+         LEFT JOIN moz_bookmarks folder ON b.parent = folder.id
+         WHERE b.type = ?1 AND p.url NOT LIKE 'place:%' AND p.url NOT LIKE 'javascript:%'",
+    )?;
+
+    statement
+        .query_map([TYPE_BOOKMARK], |row| {
+            let url: String = row.get(1)?;
+            Ok(BrowserEntry {
+                title: row
+                    .get::<_, Option<String>>(0)?
+                    .unwrap_or_else(|| url.clone()),
+                url,
+                kind: EntryKind::Bookmark,
+                folder: row
+                    .get::<_, Option<String>>(2)?
+                    .filter(|folder| !folder.is_empty()),
+                visit_count: 0,
+            })
+        })?
+        .collect()
+}
+
+fn query_history(connection: &Connection) -> rusqlite::Result<Vec<BrowserEntry>> {
+    let mut statement = connection.prepare(
+        "SELECT title, url, visit_count
+         FROM moz_places
+         WHERE visit_count > 0 AND hidden = 0 AND url NOT LIKE 'place:%'
+         ORDER BY frecency DESC
+         LIMIT ?1",
+    )?;
+
+    statement
+        .query_map([HISTORY_LIMIT as i64], |row| {
+            let url: String = row.get(1)?;
+            Ok(BrowserEntry {
+                title: row
+                    .get::<_, Option<String>>(0)?
+                    .unwrap_or_else(|| url.clone()),
+                url,
+                kind: EntryKind::History,
+                folder: None,
+                visit_count: row.get::<_, i64>(2)?.max(0) as u64,
+            })
+        })?
+        .collect()
+}
+
+/// A temporary copy of a places database and its write-ahead log, removed on
+/// drop.
+struct PlacesCopy {
+    /// Only accessible by the current user
+    _dir: TempDir,
+    db: PathBuf,
+}
+
+impl PlacesCopy {
+    fn new(path: &Path) -> Result<Self, String> {
+        let dir = tempfile::Builder::new()
+            .prefix("coco-firefox-places-")
+            .tempdir()
+            .map_err(|e| format!("failed to create temporary directory, error [{}]", e))?;
+        let db = dir.path().join(PLACES_FILE);
+
+        std::fs::copy(path, &db)
+            .map_err(|e| format!("failed to copy [{}], error [{}]", path.display(), e))?;
+        let wal = path.with_file_name(PLACES_WAL_FILE);
+        if wal.is_file() {
+            std::fs::copy(&wal, dir.path().join(PLACES_WAL_FILE))
+                .map_err(|e| format!("failed to copy [{}], error [{}]", wal.display(), e))?;
+        }
+
+        Ok(Self { _dir: dir, db })
+    }
+}
+
+#[cfg(test)]
+pub(super) mod tests {
+    use super::*;
+
+    /// Create a minimal places database at `path`, with a bookmark and 2
+    /// history entries.
+    pub(in super::super) fn create_places_db(path: &Path) {
+        let connection = Connection::open(path).unwrap();
+        connection
+            .execute_batch(
+                "CREATE TABLE moz_places (id INTEGER PRIMARY KEY, url TEXT, title TEXT,
+                     visit_count INTEGER DEFAULT 0, hidden INTEGER DEFAULT 0, frecency INTEGER DEFAULT -1);
+                 CREATE TABLE moz_bookmarks (id INTEGER PRIMARY KEY, type INTEGER, fk INTEGER,
+                     parent INTEGER, title TEXT);
+                 INSERT INTO moz_places VALUES (1, 'https://wiki.internal/', 'Wiki', 3, 0, 100);
+                 INSERT INTO moz_places VALUES (2, 'https://ci.internal/', 'CI', 10, 0, 200);
+                 INSERT INTO moz_places VALUES (3, 'place:sort=8', NULL, 0, 0, 0);
+                 INSERT INTO moz_places VALUES (4, 'https://hidden.internal/', 'Hidden', 1, 1, 0);
+                 INSERT INTO moz_bookmarks VALUES (1, 2, NULL, 0, '');
+                 INSERT INTO moz_bookmarks VALUES (2, 2, NULL, 1, 'toolbar');
+                 INSERT INTO moz_bookmarks VALUES (3, 1, 1, 2, 'Team Wiki');
+                 INSERT INTO moz_bookmarks VALUES (4, 1, 3, 2, 'Recent tags');",
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn test_read_places() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(PLACES_FILE);
+        create_places_db(&path);
+
+        let entries = read_places(&path).unwrap();
+        let bookmarks: Vec<_> = entries
+            .iter()
+            .filter(|e| e.kind == EntryKind::Bookmark)
+            .collect();
+        let history: Vec<_> = entries
+            .iter()
+            .filter(|e| e.kind == EntryKind::History)
+            .collect();
+
+        assert_eq!(bookmarks.len(), 1);
+        assert_eq!(bookmarks[0].title, "Team Wiki");
+        assert_eq!(bookmarks[0].url, "https://wiki.internal/");
+        assert_eq!(bookmarks[0].folder.as_deref(), Some("toolbar"));
+
+        // Sorted by frecency, hidden and "place:" entries are excluded
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].url, "https://ci.internal/");
+        assert_eq!(history[0].visit_count, 10);
+        assert_eq!(history[1].url, "https://wiki.internal/");
+    }
+}
@@ -0,0 +1,746 @@
+//! Browser Bookmarks extension, it searches the bookmarks and history of the
+//! browsers installed for the current user.
+//!
+//! Supported browsers:
+//!
+//! * Chromium-family browsers: bookmarks, see `chromium.rs`
+//! * Firefox: bookmarks and history, see `firefox.rs`
+//!
+//! All the profiles of these browsers are indexed in memory.  The profile
+//! directories are watched so that a profile gets re-read when its data files
+//! change, and new profiles get picked up.
+
+mod chromium;
+mod firefox;
+
+use super::super::LOCAL_QUERY_SOURCE_TYPE;
+use crate::common::document::{DataSourceReference, Document, OnOpened};
+use crate::common::document_action::url_actions;
+use crate::common::error::SearchError;
+use crate::common::search::{QueryResponse, QuerySource, SearchQuery};
+use crate::common::traits::SearchSource;
+use crate::search::fuzzy::{fuzzy_match, fuzzy_score};
+use async_trait::async_trait;
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+use tauri::AppHandle;
+
+pub(crate) const EXTENSION_ID: &str = "Browser Bookmarks";
+
+/// JSON file for this extension.
+pub(crate) const PLUGIN_JSON_FILE: &str = r#"
+{
+  "id": "Browser Bookmarks",
+  "name": "Browser Bookmarks",
+  "platforms": ["linux"],
+  "description": "Search the bookmarks and history of your browsers",
+  "icon": "font_file_html",
+  "type": "extension",
+  "enabled": true
+}
+"#;
+
+/// Browsers write their data files in bursts, wait for this long after the
+/// last change before re-reading them.
+const REFRESH_DEBOUNCE: Duration = Duration::from_secs(2);
+/// Firefox keeps writing while browsing, don't postpone refreshing forever.
+const REFRESH_MAX_DELAY: Duration = Duration::from_secs(30);
+/// Re-reading a Firefox profile copies its places database, which can be
+/// large, so a profile is re-read at most once in this interval.
+const FIREFOX_RELOAD_MIN_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// URL matches are less relevant than title matches.
+const URL_MATCH_WEIGHT: f64 = 0.8;
+/// History entries are less relevant than bookmarks, frequently visited ones
+/// get a bonus of up to `HISTORY_MAX_VISIT_BONUS`.
+const HISTORY_WEIGHT: f64 = 0.6;
+const HISTORY_MAX_VISIT_BONUS: f64 = 0.2;
+const HISTORY_VISIT_COUNT_CAP: u64 = 100;
+
+/// A bookmark or history entry.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct BrowserEntry {
+    title: String,
+    url: String,
+    kind: EntryKind,
+    /// Folder path of a bookmark, e.g., "Bookmarks bar/Work".
+    folder: Option<String>,
+    /// Always 0 for bookmarks.
+    visit_count: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum EntryKind {
+    Bookmark,
+    History,
+}
+
+impl EntryKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            EntryKind::Bookmark => "Bookmark",
+            EntryKind::History => "History",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BrowserFamily {
+    Chromium,
+    Firefox,
+}
+
+/// Browser name, family and data directory (relative to `$HOME`).  Every
+/// subdirectory of a data directory that contains the data file of its
+/// family is a profile.
+const BROWSERS: &[(&str, BrowserFamily, &str)] = &[
+    (
+        "Google Chrome",
+        BrowserFamily::Chromium,
+        ".config/google-chrome",
+    ),
+    ("Chromium", BrowserFamily::Chromium, ".config/chromium"),
+    (
+        "Chromium",
+        BrowserFamily::Chromium,
+        "snap/chromium/common/chromium",
+    ),
+    (
+        "Brave",
+        BrowserFamily::Chromium,
+        ".config/BraveSoftware/Brave-Browser",
+    ),
+    (
+        "Microsoft Edge",
+        BrowserFamily::Chromium,
+        ".config/microsoft-edge",
+    ),
+    ("Vivaldi", BrowserFamily::Chromium, ".config/vivaldi"),
+    ("Firefox", BrowserFamily::Firefox, ".mozilla/firefox"),
+    (
+        "Firefox",
+        BrowserFamily::Firefox,
+        "snap/firefox/common/.mozilla/firefox",
+    ),
+    (
+        "Firefox",
+        BrowserFamily::Firefox,
+        ".var/app/org.mozilla.firefox/.mozilla/firefox",
+    ),
+];
+
+#[derive(Debug, Clone, PartialEq)]
+struct Profile {
+    browser: &'static str,
+    family: BrowserFamily,
+    /// "Default", "Profile 1" for Chromium, and "default-release" for Firefox.
+    name: String,
+    dir: PathBuf,
+}
+
+impl Profile {
+    fn data_file(&self) -> PathBuf {
+        match self.family {
+            BrowserFamily::Chromium => self.dir.join(chromium::BOOKMARKS_FILE),
+            BrowserFamily::Firefox => self.dir.join(firefox::PLACES_FILE),
+        }
+    }
+
+    /// Return true if changes to the file named `file_name` in the profile
+    /// directory require re-reading this profile.
+    fn is_data_file(&self, file_name: &str) -> bool {
+        match self.family {
+            BrowserFamily::Chromium => file_name == chromium::BOOKMARKS_FILE,
+            BrowserFamily::Firefox => {
+                file_name == firefox::PLACES_FILE || file_name == firefox::PLACES_WAL_FILE
+            }
+        }
+    }
+
+    /// Minimum interval between re-reads of this profile.
+    fn reload_min_interval(&self) -> Duration {
+        match self.family {
+            BrowserFamily::Chromium => Duration::ZERO,
+            BrowserFamily::Firefox => FIREFOX_RELOAD_MIN_INTERVAL,
+        }
+    }
+
+    fn read(&self) -> Result<Vec<BrowserEntry>, String> {
+        match self.family {
+            BrowserFamily::Chromium => chromium::read_bookmarks(&self.data_file()),
+            BrowserFamily::Firefox => firefox::read_places(&self.data_file()),
+        }
+    }
+
+    fn label(&self) -> String {
+        format!("{} ({})", self.browser, self.name)
+    }
+}
+
+/// The existing data directories under `home`.
+fn browser_dirs(home: &Path) -> Vec<(&'static str, BrowserFamily, PathBuf)> {
+    BROWSERS
+        .iter()
+        .map(|(browser, family, relative_dir)| (*browser, *family, home.join(relative_dir)))
+        .filter(|(_, _, dir)| dir.is_dir())
+        .collect()
+}
+
+fn discover_profiles(home: &Path) -> Vec<Profile> {
+    let mut profiles = Vec::new();
+    for (browser, family, browser_dir) in browser_dirs(home) {
+        let Ok(read_dir) = std::fs::read_dir(&browser_dir) else {
+            continue;
+        };
+        for dir_entry in read_dir.flatten() {
+            let dir = dir_entry.path();
+            let dir_name = dir_entry.file_name().to_string_lossy().into_owned();
+            let name = match family {
+                BrowserFamily::Chromium => dir_name,
+                // Firefox profile directories are named "<random>.<profile name>"
+                BrowserFamily::Firefox => match dir_name.split_once('.') {
+                    Some((_random, name)) => name.to_string(),
+                    None => dir_name,
+                },
+            };
+            let profile = Profile {
+                browser,
+                family,
+                name,
+                dir,
+            };
+
+            if profile.data_file().is_file() {
+                profiles.push(profile);
+            }
+        }
+    }
+
+    profiles
+}
+
+/// Entries of all the profiles, keyed by profile directory.
+#[derive(Debug, Default)]
+struct BrowserIndex {
+    profiles: HashMap<PathBuf, (Profile, Vec<BrowserEntry>)>,
+}
+
+impl BrowserIndex {
+    /// Read the newly-found profiles and drop the removed ones.  Return the
+    /// newly-found profiles.
+    fn rescan(index: &RwLock<Self>, home: &Path) -> Vec<Profile> {
+        let profiles = discover_profiles(home);
+        let new_profiles: Vec<Profile> = {
+            let index = index.read().unwrap();
+            profiles
+                .iter()
+                .filter(|profile| !index.profiles.contains_key(&profile.dir))
+                .cloned()
+                .collect()
+        };
+
+        // Read the files without holding the lock
+        let new_entries: Vec<(Profile, Vec<BrowserEntry>)> = new_profiles
+            .iter()
+            .map(|profile| (profile.clone(), read_profile(profile)))
+            .collect();
+
+        let mut index = index.write().unwrap();
+        index
+            .profiles
+            .retain(|dir, _| profiles.iter().any(|profile| &profile.dir == dir));
+        for (profile, entries) in new_entries {
+            index
+                .profiles
+                .insert(profile.dir.clone(), (profile, entries));
+        }
+
+        new_profiles
+    }
+
+    /// Re-read the profile in `profile_dir`.
+    fn reload(index: &RwLock<Self>, profile_dir: &Path) {
+        let Some(profile) = index
+            .read()
+            .unwrap()
+            .profiles
+            .get(profile_dir)
+            .map(|(profile, _)| profile.clone())
+        else {
+            return;
+        };
+        let entries = read_profile(&profile);
+
+        let mut index = index.write().unwrap();
+        if let Some((_, old_entries)) = index.profiles.get_mut(profile_dir) {
+            *old_entries = entries;
+        }
+    }
+
+    fn profile_of(&self, path: &Path) -> Option<&Profile> {
+        let file_name = path.file_name()?.to_str()?;
+        let (profile, _) = self.profiles.get(path.parent()?)?;
+        profile.is_data_file(file_name).then_some(profile)
+    }
+
+    /// Match `query_string` against all the entries.  Every URL appears at
+    /// most once, with its best score.
+    fn search(&self, query_string: &str, base_score: f64) -> Vec<(Document, f64)> {
+        let mut best_by_url: HashMap<&str, (Document, f64)> = HashMap::new();
+
+        for (profile, entries) in self.profiles.values() {
+            for entry in entries {
+                let opt_title_match = fuzzy_match(query_string, &entry.title);
+                let title_score = opt_title_match.as_ref().map_or(0.0, |m| m.score);
+                let url_score = fuzzy_score(query_string, strip_scheme(&entry.url))
+                    .map_or(0.0, |score| score * URL_MATCH_WEIGHT);
+                let match_score = title_score.max(url_score);
+                if match_score <= 0.0 {
+                    continue;
+                }
+
+                let score = base_score * match_score * entry_weight(entry);
+                if best_by_url
+                    .get(entry.url.as_str())
+                    .is_some_and(|(_, best_score)| *best_score >= score)
+                {
+                    continue;
+                }
+
+                let title_match_ranges = opt_title_match.map(|title_match| title_match.ranges);
+                best_by_url.insert(
+                    &entry.url,
+                    (entry_to_document(profile, entry, title_match_ranges), score),
+                );
+            }
+        }
+
+        let mut hits: Vec<(Document, f64)> = best_by_url.into_values().collect();
+        hits.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+        hits
+    }
+}
+
+fn read_profile(profile: &Profile) -> Vec<BrowserEntry> {
+    match profile.read() {
+        Ok(entries) => {
+            log::debug!(
+                "loaded [{}] entries from browser profile [{}]",
+                entries.len(),
+                profile.label()
+            );
+            entries
+        }
+        Err(e) => {
+            log::warn!(
+                "failed to read browser profile [{}], {}",
+                profile.label(),
+                e
+            );
+            Vec::new()
+        }
+    }
+}
+
+fn strip_scheme(url: &str) -> &str {
+    url.split_once("://").map_or(url, |(_scheme, rest)| rest)
+}
+
+fn entry_weight(entry: &BrowserEntry) -> f64 {
+    match entry.kind {
+        EntryKind::Bookmark => 1.0,
+        EntryKind::History => {
+            let visits = entry.visit_count.min(HISTORY_VISIT_COUNT_CAP) as f64;
+            HISTORY_WEIGHT + HISTORY_MAX_VISIT_BONUS * visits / HISTORY_VISIT_COUNT_CAP as f64
+        }
+    }
+}
+
+fn entry_to_document(
+    profile: &Profile,
+    entry: &BrowserEntry,
+    title_match_ranges: Option<Vec<std::ops::Range<usize>>>,
+) -> Document {
+    Document {
+        id: entry.url.clone(),
+        title: Some(entry.title.clone()),
+        title_match_ranges,
+        summary: Some(entry.url.clone()),
+        category: Some(entry.kind.as_str().into()),
+        subcategory: Some(profile.label()),
+        categories: entry
+            .folder
+            .as_ref()
+            .map(|folder| folder.split('/').map(String::from).collect()),
+        icon: Some("font_file_html".into()),
+        on_opened: Some(OnOpened::Document {
+            url: entry.url.clone(),
+        }),
+        actions: Some(url_actions(Some(&entry.title), &entry.url)),
+        url: Some(entry.url.clone()),
+        source: Some(DataSourceReference {
+            r#type: Some(entry.kind.as_str().into()),
+            name: Some(EXTENSION_ID.into()),
+            id: Some(EXTENSION_ID.into()),
+            icon: None,
+        }),
+        ..Default::default()
+    }
+}
+
+/// Search source of the Browser Bookmarks extension.
+pub(crate) struct BrowserSearchSource {
+    base_score: f64,
+    index: Arc<RwLock<BrowserIndex>>,
+    /// Set in `init()` and dropped in `shutdown()`, which stops the refresh
+    /// thread.
+    watcher: Arc<Mutex<Option<RecommendedWatcher>>>,
+    /// Set in `shutdown()`, so that the watcher won't be set if `init()` is
+    /// still loading the profiles in background.
+    shut_down: Arc<AtomicBool>,
+}
+
+impl BrowserSearchSource {
+    pub(crate) fn new(base_score: f64) -> Self {
+        Self {
+            base_score,
+            index: Arc::default(),
+            watcher: Arc::default(),
+            shut_down: Arc::default(),
+        }
+    }
+}
+
+/// Watch the browser data directories (for new profiles) and the profile
+/// directories.  Data directories that don't exist yet won't be watched, so
+/// browsers installed later get picked up after restarting Coco.
+fn start_watching(
+    home: PathBuf,
+    index: Arc<RwLock<BrowserIndex>>,
+    watcher_slot: Arc<Mutex<Option<RecommendedWatcher>>>,
+    shut_down: &AtomicBool,
+) -> notify::Result<()> {
+    let (tx, rx) = std::sync::mpsc::channel();
+    let mut watcher = notify::recommended_watcher(move |res| {
+        // The refresh thread has exited
+        let _ = tx.send(res);
+    })?;
+
+    for (_, _, browser_dir) in browser_dirs(&home) {
+        watcher.watch(&browser_dir, RecursiveMode::NonRecursive)?;
+    }
+    for profile_dir in index.read().unwrap().profiles.keys() {
+        watcher.watch(profile_dir, RecursiveMode::NonRecursive)?;
+    }
+    {
+        let mut watcher_slot = watcher_slot.lock().unwrap();
+        if shut_down.load(Ordering::Acquire) {
+            return Ok(());
+        }
+        *watcher_slot = Some(watcher);
+    }
+
+    std::thread::Builder::new()
+        .name("browser-bookmarks-refresh".into())
+        .spawn(move || refresh_loop(&home, &index, &watcher_slot, rx))
+        .expect("failed to spawn thread");
+
+    Ok(())
+}
+
+/// Re-read the profiles as their files change, but not more often than their
+/// [`Profile::reload_min_interval()`].  It returns when the watcher is
+/// dropped.
+fn refresh_loop(
+    home: &Path,
+    index: &RwLock<BrowserIndex>,
+    watcher_slot: &Mutex<Option<RecommendedWatcher>>,
+    rx: Receiver<notify::Result<notify::Event>>,
+) {
+    let browser_dirs: HashSet<PathBuf> = browser_dirs(home)
+        .into_iter()
+        .map(|(_, _, dir)| dir)
+        .collect();
+    let mut last_reloads: HashMap<PathBuf, Instant> = HashMap::new();
+    // Changed profiles that were re-read too recently, with the time they
+    // can be re-read
+    let mut deferred_profiles: HashMap<PathBuf, Instant> = HashMap::new();
+
+    loop {
+        let opt_first = match deferred_profiles.values().min() {
+            None => match rx.recv() {
+                Ok(event) => Some(event),
+                Err(_) => return,
+            },
+            Some(due) => match rx.recv_timeout(due.saturating_duration_since(Instant::now())) {
+                Ok(event) => Some(event),
+                Err(RecvTimeoutError::Timeout) => None,
+                Err(RecvTimeoutError::Disconnected) => return,
+            },
+        };
+        let mut events = Vec::from_iter(opt_first);
+        let burst_start = Instant::now();
+        while !events.is_empty() && burst_start.elapsed() < REFRESH_MAX_DELAY {
+            match rx.recv_timeout(REFRESH_DEBOUNCE) {
+                Ok(event) => events.push(event),
+                Err(RecvTimeoutError::Timeout) => break,
+                Err(RecvTimeoutError::Disconnected) => return,
+            }
+        }
+
+        let mut rescan = false;
+        {
+            let index = index.read().unwrap();
+            for event in events {
+                let event = match event {
+                    Ok(event) => event,
+                    Err(e) => {
+                        log::warn!("browser profile watcher error [{}]", e);
+                        continue;
+                    }
+                };
+                for path in event.paths {
+                    if path
+                        .parent()
+                        .is_some_and(|parent| browser_dirs.contains(parent))
+                    {
+                        rescan = true;
+                    } else if let Some(profile) = index.profile_of(&path) {
+                        let due = last_reloads
+                            .get(&profile.dir)
+                            .map_or_else(Instant::now, |last| {
+                                *last + profile.reload_min_interval()
+                            });
+                        deferred_profiles.entry(profile.dir.clone()).or_insert(due);
+                    }
+                }
+            }
+        }
+
+        let now = Instant::now();
+        let due_profiles: Vec<PathBuf> = deferred_profiles
+            .iter()
+            .filter(|(_, due)| **due <= now)
+            .map(|(profile_dir, _)| profile_dir.clone())
+            .collect();
+        for profile_dir in due_profiles {
+            deferred_profiles.remove(&profile_dir);
+            BrowserIndex::reload(index, &profile_dir);
+            last_reloads.insert(profile_dir, Instant::now());
+        }
+        if rescan {
+            let new_profiles = BrowserIndex::rescan(index, home);
+            let mut opt_watcher = watcher_slot.lock().unwrap();
+            let Some(watcher) = opt_watcher.as_mut() else {
+                return;
+            };
+            for profile in new_profiles {
+                if let Err(e) = watcher.watch(&profile.dir, RecursiveMode::NonRecursive) {
+                    log::warn!(
+                        "failed to watch browser profile [{}], error [{}]",
+                        profile.label(),
+                        e
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl SearchSource for BrowserSearchSource {
+    fn get_type(&self) -> QuerySource {
+        QuerySource {
+            r#type: LOCAL_QUERY_SOURCE_TYPE.into(),
+            name: hostname::get()
+                .unwrap_or(EXTENSION_ID.into())
+                .to_string_lossy()
+                .into(),
+            id: EXTENSION_ID.into(),
+        }
+    }
+
+    /// Load all the profiles and start watching them in background, so that
+    /// it won't block the registration, the bookmarks and history become
+    /// searchable as they get loaded.
+    async fn init(&self, _tauri_app_handle: &AppHandle) -> Result<(), SearchError> {
+        let home = dirs::home_dir().ok_or_else(|| SearchError::InternalError {
+            error: "home directory not found".into(),
+        })?;
+        let index = Arc::clone(&self.index);
+        let watcher_slot = Arc::clone(&self.watcher);
+        let shut_down = Arc::clone(&self.shut_down);
+
+        tokio::task::spawn_blocking(move || {
+            BrowserIndex::rescan(&index, &home);
+            if let Err(e) = start_watching(home, index, watcher_slot, &shut_down) {
+                // Still searchable, just won't refresh
+                log::warn!("failed to watch browser profiles, error [{}]", e);
+            }
+        });
+
+        Ok(())
+    }
+
+    async fn shutdown(&self, _tauri_app_handle: &AppHandle) {
+        let mut watcher_slot = self.watcher.lock().unwrap();
+        self.shut_down.store(true, Ordering::Release);
+        watcher_slot.take();
+    }
+
+    async fn search(
+        &self,
+        _tauri_app_handle: AppHandle,
+        query: SearchQuery,
+    ) -> Result<QueryResponse, SearchError> {
+        let query_string = query
+            .query_strings
+            .get("query")
+            .map(|query_string| query_string.trim())
+            .unwrap_or_default();
+        if query_string.is_empty() {
+            return Ok(QueryResponse {
+                source: self.get_type(),
+                hits: Vec::new(),
+                total_hits: 0,
+                aggregations: None,
+            });
+        }
+
+        let hits = self
+            .index
+            .read()
+            .unwrap()
+            .search(query_string, self.base_score);
+        let total_hits = hits.len();
+        let hits = hits
+            .into_iter()
+            .skip(query.from as usize)
+            .take(query.size as usize)
+            .collect();
+
+        Ok(QueryResponse {
+            source: self.get_type(),
+            hits,
+            total_hits,
+            aggregations: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHROME_BOOKMARKS: &str = r#"{
+      "roots": {
+        "bookmark_bar": {
+          "type": "folder",
+          "name": "Bookmarks bar",
+          "children": [
+            { "type": "url", "name": "Grafana", "url": "https://grafana.internal/" },
+            { "type": "url", "name": "Wiki", "url": "https://wiki.internal/" }
+          ]
+        }
+      }
+    }"#;
+
+    fn create_profiles(home: &Path) {
+        let chrome_profile = home.join(".config/google-chrome/Profile 1");
+        std::fs::create_dir_all(&chrome_profile).unwrap();
+        std::fs::write(
+            chrome_profile.join(chromium::BOOKMARKS_FILE),
+            CHROME_BOOKMARKS,
+        )
+        .unwrap();
+        // Not a profile
+        std::fs::create_dir_all(home.join(".config/google-chrome/Crashpad")).unwrap();
+
+        let firefox_profile = home.join(".mozilla/firefox/abcd1234.default-release");
+        std::fs::create_dir_all(&firefox_profile).unwrap();
+        firefox::tests::create_places_db(&firefox_profile.join(firefox::PLACES_FILE));
+    }
+
+    #[test]
+    fn test_discover_profiles() {
+        let home = tempfile::tempdir().unwrap();
+        create_profiles(home.path());
+
+        let mut profiles = discover_profiles(home.path());
+        profiles.sort_by(|a, b| a.browser.cmp(b.browser));
+
+        assert_eq!(profiles.len(), 2);
+        assert_eq!(profiles[0].label(), "Firefox (default-release)");
+        assert_eq!(profiles[0].family, BrowserFamily::Firefox);
+        assert_eq!(profiles[1].label(), "Google Chrome (Profile 1)");
+        assert_eq!(profiles[1].family, BrowserFamily::Chromium);
+    }
+
+    #[test]
+    fn test_search_dedups_urls_and_prefers_bookmarks() {
+        let home = tempfile::tempdir().unwrap();
+        create_profiles(home.path());
+        let index = RwLock::new(BrowserIndex::default());
+        BrowserIndex::rescan(&index, home.path());
+
+        let index = index.read().unwrap();
+        // Bookmarked in both browsers, and visited in Firefox, the exact title
+        // match of the Chrome bookmark wins
+        let hits = index.search("wiki", 1.0);
+        assert_eq!(hits.len(), 1);
+        let (document, _) = &hits[0];
+        assert_eq!(document.url.as_deref(), Some("https://wiki.internal/"));
+        assert_eq!(document.category.as_deref(), Some("Bookmark"));
+        assert!(matches!(
+            document.on_opened,
+            Some(OnOpened::Document { ref url }) if url == "https://wiki.internal/"
+        ));
+
+        // Only in the Firefox history
+        let hits = index.search("ci.internal", 1.0);
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].0.category.as_deref(), Some("History"));
+    }
+
+    #[test]
+    fn test_rescan_and_reload() {
+        let home = tempfile::tempdir().unwrap();
+        create_profiles(home.path());
+        let index = RwLock::new(BrowserIndex::default());
+        assert_eq!(BrowserIndex::rescan(&index, home.path()).len(), 2);
+        // Nothing new
+        assert!(BrowserIndex::rescan(&index, home.path()).is_empty());
+
+        let chrome_profile = home.path().join(".config/google-chrome/Profile 1");
+        let bookmarks_file = chrome_profile.join(chromium::BOOKMARKS_FILE);
+        assert!(index.read().unwrap().profile_of(&bookmarks_file).is_some());
+        assert!(
+            index
+                .read()
+                .unwrap()
+                .profile_of(&chrome_profile.join("Preferences"))
+                .is_none()
+        );
+
+        std::fs::write(
+            &bookmarks_file,
+            CHROME_BOOKMARKS
+                .replace("Grafana", "Dashboards")
+                .replace("grafana", "dashboards"),
+        )
+        .unwrap();
+        BrowserIndex::reload(&index, &chrome_profile);
+        assert!(index.read().unwrap().search("grafana", 1.0).is_empty());
+        assert_eq!(index.read().unwrap().search("dashboards", 1.0).len(), 1);
+
+        // Removed profiles are dropped
+        std::fs::remove_dir_all(&chrome_profile).unwrap();
+        BrowserIndex::rescan(&index, home.path());
+        assert!(index.read().unwrap().search("dashboards", 1.0).is_empty());
+    }
+}
@@ -0,0 +1,416 @@
+//! Natural conversion queries, e.g., "10 km in miles", "72f to c",
+//! "0xff in binary", "3 hours 20 min in seconds" and "100 usd in eur".
+//!
+//! A query is a quantity, a separator ("in", "to", "as", "into", "=" or
+//! "->"), and a target.  The quantity is one or more number-unit pairs that
+//! get summed, or an integer if the target is a number base.
+
+use super::currency::CurrencyRates;
+use super::units::find_unit;
+
+const SEPARATORS: &[&str] = &["in", "to", "as", "into", "=", "->", "=>"];
+/// Characters that can prefix an amount of money, e.g., "$10".
+const CURRENCY_SYMBOLS: &[char] = &['$', '€', '£', '¥', '₩', '₹'];
+/// Raw values are rounded to this many significant digits, to hide floating
+/// point noise such as 0.30000000000000004.
+const RAW_SIGNIFICANT_DIGITS: i32 = 10;
+const CURRENCY_DECIMALS: usize = 2;
+
+#[derive(Debug, PartialEq)]
+pub(crate) struct Conversion {
+    /// For display, e.g., "6.213712 mi".
+    pub(crate) formatted: String,
+    /// The bare number, e.g., "6.213711922".
+    pub(crate) raw: String,
+    /// What is converted, e.g., "Length".
+    pub(crate) kind_en: &'static str,
+    pub(crate) kind_zh: &'static str,
+    /// For currency conversions, when the used rates were published, Unix
+    /// timestamp in seconds.
+    pub(crate) rates_updated: Option<i64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum NumberBase {
+    Binary,
+    Octal,
+    Decimal,
+    Hexadecimal,
+}
+
+impl NumberBase {
+    fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "bin" | "binary" => Some(Self::Binary),
+            "oct" | "octal" => Some(Self::Octal),
+            "dec" | "decimal" => Some(Self::Decimal),
+            "hex" | "hexadecimal" => Some(Self::Hexadecimal),
+            _ => None,
+        }
+    }
+
+    fn format(&self, value: i128) -> (String, String) {
+        let sign = if value < 0 { "-" } else { "" };
+        let abs = value.unsigned_abs();
+        let (prefix, digits) = match self {
+            Self::Binary => ("0b", format!("{:b}", abs)),
+            Self::Octal => ("0o", format!("{:o}", abs)),
+            Self::Decimal => ("", abs.to_string()),
+            Self::Hexadecimal => ("0x", format!("{:x}", abs)),
+        };
+
+        (
+            format!("{}{}{}", sign, prefix, digits),
+            format!("{}{}", sign, digits),
+        )
+    }
+
+    fn names(&self) -> (&'static str, &'static str) {
+        match self {
+            Self::Binary => ("Binary", "二进制"),
+            Self::Octal => ("Octal", "八进制"),
+            Self::Decimal => ("Decimal", "十进制"),
+            Self::Hexadecimal => ("Hexadecimal", "十六进制"),
+        }
+    }
+}
+
+/// Try to interpret `query` as a conversion, return `None` if it is not one.
+pub(crate) fn convert(query: &str, rates: &CurrencyRates) -> Option<Conversion> {
+    let (source, target) = split_query(query)?;
+
+    if let Some(base) = NumberBase::from_name(&target) {
+        let value = parse_integer(&source)?;
+        let (formatted, raw) = base.format(value);
+        let (kind_en, kind_zh) = base.names();
+        return Some(Conversion {
+            formatted,
+            raw,
+            kind_en,
+            kind_zh,
+            rates_updated: None,
+        });
+    }
+
+    let terms = parse_terms(&source)?;
+
+    if let Some(target_unit) = find_unit(&target) {
+        let mut sum = 0.0;
+        for (value, unit_name) in terms.iter() {
+            let unit = find_unit(unit_name)?;
+            if unit.dimension != target_unit.dimension || (terms.len() > 1 && unit.is_affine()) {
+                return None;
+            }
+            sum += unit.to_base(*value);
+        }
+        let raw = format_raw(target_unit.convert_from_base(sum))?;
+
+        return Some(Conversion {
+            formatted: format!("{} {}", group_thousands(&raw), target_unit.symbol),
+            raw,
+            kind_en: target_unit.dimension.name_en(),
+            kind_zh: target_unit.dimension.name_zh(),
+            rates_updated: None,
+        });
+    }
+
+    let to = rates.find_currency(&target)?;
+    let [(amount, ref from)] = terms[..] else {
+        return None;
+    };
+    let from = rates.find_currency(from)?;
+    let converted = rates.convert(amount, &from, &to)?;
+    let formatted = format!("{:.*}", CURRENCY_DECIMALS, converted);
+
+    Some(Conversion {
+        formatted: format!("{} {}", group_thousands(&formatted), to),
+        raw: format_raw(converted)?,
+        kind_en: "Currency",
+        kind_zh: "货币",
+        rates_updated: Some(rates.updated),
+    })
+}
+
+/// Split `query` at the last separator, into the quantity and the target.
+fn split_query(query: &str) -> Option<(String, String)> {
+    let tokens: Vec<&str> = query.split_whitespace().collect();
+    let separator_idx = tokens
+        .iter()
+        .rposition(|token| SEPARATORS.contains(&token.to_ascii_lowercase().as_str()))?;
+    let source = tokens[..separator_idx].join(" ");
+    let target = tokens[separator_idx + 1..].join(" ");
+    if source.is_empty() || target.is_empty() {
+        return None;
+    }
+
+    Some((source, target))
+}
+
+/// Parse an integer that is optionally prefixed by "0x", "0o" or "0b".
+fn parse_integer(str: &str) -> Option<i128> {
+    let str = str.trim();
+    let (negative, unsigned) = match str.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, str),
+    };
+    let lowercase = unsigned.to_ascii_lowercase();
+    let (radix, digits) = if let Some(digits) = lowercase.strip_prefix("0x") {
+        (16, digits)
+    } else if let Some(digits) = lowercase.strip_prefix("0o") {
+        (8, digits)
+    } else if let Some(digits) = lowercase.strip_prefix("0b") {
+        (2, digits)
+    } else {
+        (10, lowercase.as_str())
+    };
+    // `from_str_radix()` accepts a sign, which we have handled
+    if digits.is_empty() || !digits.chars().all(|c| c.is_digit(radix)) {
+        return None;
+    }
+
+    let value = i128::from_str_radix(digits, radix).ok()?;
+    Some(if negative { -value } else { value })
+}
+
+/// Parse a number at the start of `chars`, return it and the number of chars
+/// consumed.
+fn parse_number(chars: &[char]) -> Option<(f64, usize)> {
+    let mut end = 0;
+    if matches!(chars.first(), Some('-' | '+')) {
+        end += 1;
+    }
+
+    // Integers with a radix prefix
+    if chars.get(end) == Some(&'0') {
+        let radix = match chars.get(end + 1) {
+            Some('x' | 'X') => Some(16),
+            Some('o' | 'O') => Some(8),
+            Some('b' | 'B') => Some(2),
+            _ => None,
+        };
+        if let Some(radix) = radix
+            && chars.get(end + 2).is_some_and(|c| c.is_digit(radix))
+        {
+            let digits_end = end
+                + 2
+                + chars[end + 2..]
+                    .iter()
+                    .take_while(|c| c.is_digit(radix))
+                    .count();
+            let literal: String = chars[..digits_end].iter().collect();
+            return Some((parse_integer(&literal)? as f64, digits_end));
+        }
+    }
+
+    let digits_start = end;
+    end += chars[end..]
+        .iter()
+        .take_while(|c| c.is_ascii_digit() || **c == '.')
+        .count();
+    let literal: String = chars[..end].iter().collect();
+    if end == digits_start {
+        return None;
+    }
+
+    Some((literal.parse().ok()?, end))
+}
+
+/// Parse a quantity like "3 hours 20 min" into number-unit pairs, the unit
+/// can be empty.
+fn parse_terms(source: &str) -> Option<Vec<(f64, String)>> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut terms = Vec::new();
+    let mut pos = 0;
+
+    loop {
+        while chars.get(pos).is_some_and(|c| c.is_whitespace()) {
+            pos += 1;
+        }
+        if pos == chars.len() {
+            break;
+        }
+
+        let opt_symbol = chars
+            .get(pos)
+            .filter(|c| CURRENCY_SYMBOLS.contains(c))
+            .copied();
+        if opt_symbol.is_some() {
+            pos += 1;
+        }
+
+        let (value, len) = parse_number(&chars[pos..])?;
+        pos += len;
+
+        // The unit ends where the next number starts, digits right after
+        // letters are part of the unit, e.g., "m2".
+        let unit_start = pos;
+        while let Some(c) = chars.get(pos) {
+            let starts_number = (c.is_ascii_digit() && !chars[pos - 1].is_alphabetic())
+                || (matches!(c, '-' | '+') && chars.get(pos + 1).is_some_and(char::is_ascii_digit));
+            if starts_number {
+                break;
+            }
+            pos += 1;
+        }
+        let unit: String = chars[unit_start..pos].iter().collect();
+        let unit = unit.trim().to_string();
+
+        let unit = match opt_symbol {
+            Some(symbol) if unit.is_empty() => symbol.to_string(),
+            Some(_) => return None,
+            None => unit,
+        };
+        terms.push((value, unit));
+    }
+
+    (!terms.is_empty()).then_some(terms)
+}
+
+/// Round `value` to `RAW_SIGNIFICANT_DIGITS` and format it without trailing
+/// zeros.  Return `None` if it is not finite.
+fn format_raw(value: f64) -> Option<String> {
+    if !value.is_finite() {
+        return None;
+    }
+    if value == 0.0 {
+        return Some("0".into());
+    }
+
+    let magnitude = value.abs().log10().floor() as i32 + 1;
+    if !(-5..=15).contains(&magnitude) {
+        return Some(format!(
+            "{:.*e}",
+            (RAW_SIGNIFICANT_DIGITS - 1) as usize,
+            value
+        ));
+    }
+    let decimals = (RAW_SIGNIFICANT_DIGITS - magnitude).max(0) as usize;
+    let formatted = format!("{:.*}", decimals, value);
+    let formatted = if formatted.contains('.') {
+        formatted.trim_end_matches('0').trim_end_matches('.')
+    } else {
+        formatted.as_str()
+    };
+
+    Some(formatted.to_string())
+}
+
+/// Insert commas into the integer part of a formatted number.
+fn group_thousands(number: &str) -> String {
+    if number.contains('e') {
+        return number.to_string();
+    }
+    let (sign, unsigned) = match number.strip_prefix('-') {
+        Some(rest) => ("-", rest),
+        None => ("", number),
+    };
+    let (integer, fraction) = match unsigned.split_once('.') {
+        Some((integer, fraction)) => (integer, Some(fraction)),
+        None => (unsigned, None),
+    };
+
+    let mut grouped = String::new();
+    for (idx, c) in integer.chars().enumerate() {
+        if idx > 0 && (integer.len() - idx) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(c);
+    }
+
+    match fraction {
+        Some(fraction) => format!("{}{}.{}", sign, grouped, fraction),
+        None => format!("{}{}", sign, grouped),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn rates() -> CurrencyRates {
+        CurrencyRates {
+            base: "USD".into(),
+            updated: 0,
+            rates: HashMap::from([("USD".into(), 1.0), ("EUR".into(), 0.5)]),
+        }
+    }
+
+    fn convert_formatted(query: &str) -> Option<String> {
+        convert(query, &rates()).map(|conversion| conversion.formatted)
+    }
+
+    #[test]
+    fn test_unit_conversions() {
+        let conversion = convert("10 km in miles", &rates()).unwrap();
+        assert_eq!(conversion.formatted, "6.213711922 mi");
+        assert_eq!(conversion.raw, "6.213711922");
+        assert_eq!(conversion.kind_en, "Length");
+
+        assert_eq!(convert_formatted("72f to c").unwrap(), "22.22222222 °C");
+        assert_eq!(convert_formatted("5 GiB in MB").unwrap(), "5,368.70912 MB");
+        assert_eq!(convert_formatted("1 kg as lb").unwrap(), "2.204622622 lb");
+        assert_eq!(
+            convert_formatted("100 km/h in m/s").unwrap(),
+            "27.77777778 m/s"
+        );
+    }
+
+    #[test]
+    fn test_summed_quantities() {
+        assert_eq!(
+            convert_formatted("3 hours 20 min in seconds").unwrap(),
+            "12,000 s"
+        );
+        assert_eq!(convert_formatted("5ft 11in to cm").unwrap(), "180.34 cm");
+        // Temperatures cannot be summed
+        assert!(convert_formatted("10c 5c in f").is_none());
+        // Different dimensions
+        assert!(convert_formatted("3 hours 20 km in seconds").is_none());
+    }
+
+    #[test]
+    fn test_base_conversions() {
+        let conversion = convert("0xff in binary", &rates()).unwrap();
+        assert_eq!(conversion.formatted, "0b11111111");
+        assert_eq!(conversion.raw, "11111111");
+        assert_eq!(convert_formatted("255 to hex").unwrap(), "0xff");
+        assert_eq!(convert_formatted("-0b1010 in dec").unwrap(), "-10");
+        assert_eq!(convert_formatted("0o17 in decimal").unwrap(), "15");
+        assert!(convert_formatted("1.5 in hex").is_none());
+    }
+
+    #[test]
+    fn test_currency_conversions() {
+        let conversion = convert("1000 usd in eur", &rates()).unwrap();
+        assert_eq!(conversion.formatted, "500.00 EUR");
+        assert_eq!(conversion.raw, "500");
+        assert_eq!(conversion.rates_updated, Some(rates().updated));
+        assert_eq!(convert_formatted("$3 to EUR").unwrap(), "1.50 EUR");
+        assert_eq!(convert_formatted("3 EUR to usd").unwrap(), "6.00 USD");
+        assert!(convert_formatted("3 EUR to JPY").is_none());
+    }
+
+    #[test]
+    fn test_not_conversions() {
+        assert!(convert_formatted("1+2").is_none());
+        assert!(convert_formatted("10 km").is_none());
+        assert!(convert_formatted("how to cook").is_none());
+        assert!(convert_formatted("10 furlongs in km").is_none());
+    }
+
+    #[test]
+    fn test_format_raw() {
+        assert_eq!(format_raw(0.1 + 0.2).unwrap(), "0.3");
+        assert_eq!(format_raw(1e20).unwrap(), "1.000000000e20");
+        assert_eq!(format_raw(-42.0).unwrap(), "-42");
+        assert!(format_raw(f64::NAN).is_none());
+    }
+
+    #[test]
+    fn test_group_thousands() {
+        assert_eq!(group_thousands("1234567.891"), "1,234,567.891");
+        assert_eq!(group_thousands("-1000"), "-1,000");
+        assert_eq!(group_thousands("999"), "999");
+    }
+}
@@ -0,0 +1,369 @@
+//! Currency exchange rates used by currency conversions.
+//!
+//! The rates are cached in a file under the app data directory so that
+//! currency conversion works offline.  Until the first refresh, a bundled
+//! rate table is used.  Refreshing fetches the rates from a Coco server,
+//! endpoint `GET /calculator/currency_rates`, which should respond with a
+//! JSON serialized [`CurrencyRates`].
+//!
+//! When a currency conversion uses stale rates, they are refreshed in the
+//! background from the enabled and available Coco servers.  Servers that
+//! don't have the endpoint are not asked again, and failed refreshes are
+//! retried less and less often.  The date of the rates is shown in the
+//! result, so users know how fresh they are.
+
+use crate::server::http_client::HttpClient;
+use crate::server::servers::get_all_servers;
+use http::StatusCode;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::{LazyLock, Mutex, RwLock};
+use tauri::{AppHandle, Manager};
+
+const CACHE_DIR: &str = "calculator";
+const CACHE_FILE: &str = "currency_rates.json";
+const COCO_SERVER_RATES_PATH: &str = "/calculator/currency_rates";
+/// Rates published longer ago than this are refreshed when they are used.
+/// Rates are usually not published on weekends, so this is more than 2 days.
+const STALE_AFTER_SECS: i64 = 3 * 24 * 60 * 60;
+/// Refreshing is attempted at most once within this period.
+const REFRESH_RETRY_INTERVAL_SECS: i64 = 60 * 60;
+/// After consecutive failures, the retry interval doubles up to this.
+const MAX_REFRESH_RETRY_INTERVAL_SECS: i64 = 24 * 60 * 60;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub(crate) struct CurrencyRates {
+    /// ISO 4217 code of the currency the rates are relative to.
+    pub(crate) base: String,
+    /// When the rates were published, Unix timestamp in seconds.
+    pub(crate) updated: i64,
+    /// Units of each currency, keyed by its ISO 4217 code, per 1 unit of the
+    /// base currency.
+    pub(crate) rates: HashMap<String, f64>,
+}
+
+/// Approximate rates bundled with Coco, relative to USD.
+const BUNDLED_RATES: &[(&str, f64)] = &[
+    ("USD", 1.0),
+    ("EUR", 0.92),
+    ("GBP", 0.79),
+    ("CNY", 7.24),
+    ("JPY", 151.0),
+    ("HKD", 7.82),
+    ("TWD", 32.0),
+    ("KRW", 1350.0),
+    ("SGD", 1.35),
+    ("INR", 83.3),
+    ("AUD", 1.52),
+    ("CAD", 1.36),
+    ("CHF", 0.9),
+    ("SEK", 10.6),
+    ("NOK", 10.7),
+    ("DKK", 6.88),
+    ("NZD", 1.66),
+    ("RUB", 92.0),
+    ("BRL", 5.05),
+    ("MXN", 16.8),
+    ("THB", 36.5),
+    ("MYR", 4.74),
+];
+/// 2024-04-01
+const BUNDLED_RATES_UPDATED: i64 = 1_711_929_600;
+
+/// Currency symbols that users can type instead of the codes.
+const SYMBOLS: &[(&str, &str)] = &[
+    ("$", "USD"),
+    ("€", "EUR"),
+    ("£", "GBP"),
+    ("¥", "CNY"),
+    ("₩", "KRW"),
+    ("₹", "INR"),
+];
+
+static RATES: LazyLock<RwLock<CurrencyRates>> =
+    LazyLock::new(|| RwLock::new(CurrencyRates::bundled()));
+
+/// IDs of the Coco servers that don't have the rates endpoint.
+static SERVERS_WITHOUT_RATES: LazyLock<Mutex<HashSet<String>>> = LazyLock::new(Mutex::default);
+
+static REFRESH_STATE: Mutex<RefreshState> = Mutex::new(RefreshState {
+    next_attempt: i64::MIN,
+    n_failures: 0,
+    running: false,
+});
+
+/// State of the background refresh, see `refresh_if_stale()`.
+struct RefreshState {
+    /// Unix timestamp in seconds, refreshing is not attempted before it.
+    next_attempt: i64,
+    /// Number of consecutive failed attempts.
+    n_failures: u32,
+    running: bool,
+}
+
+/// Return how long to wait before retrying after `n_failures` consecutive
+/// failures.
+fn retry_interval_secs(n_failures: u32) -> i64 {
+    let factor = 1_i64 << n_failures.saturating_sub(1).min(16);
+    REFRESH_RETRY_INTERVAL_SECS
+        .saturating_mul(factor)
+        .min(MAX_REFRESH_RETRY_INTERVAL_SECS)
+}
+
+impl CurrencyRates {
+    fn bundled() -> Self {
+        Self {
+            base: "USD".into(),
+            updated: BUNDLED_RATES_UPDATED,
+            rates: BUNDLED_RATES
+                .iter()
+                .map(|(code, rate)| (code.to_string(), *rate))
+                .collect(),
+        }
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.rates.get(&self.base) != Some(&1.0) {
+            return Err(format!(
+                "rate of the base currency [{}] should be 1",
+                self.base
+            ));
+        }
+        if let Some((code, rate)) = self
+            .rates
+            .iter()
+            .find(|(_code, rate)| !rate.is_finite() || **rate <= 0.0)
+        {
+            return Err(format!("currency [{}] has invalid rate [{}]", code, rate));
+        }
+
+        Ok(())
+    }
+
+    fn is_stale(&self, now: i64) -> bool {
+        now.saturating_sub(self.updated) > STALE_AFTER_SECS
+    }
+
+    /// Return the ISO 4217 code of the currency named `name`, which can be a
+    /// code in any case, or a symbol.
+    pub(crate) fn find_currency(&self, name: &str) -> Option<String> {
+        let name = name.trim();
+        if let Some((_symbol, code)) = SYMBOLS.iter().find(|(symbol, _code)| *symbol == name) {
+            return Some(code.to_string());
+        }
+
+        let code = name.to_ascii_uppercase();
+        self.rates.contains_key(&code).then_some(code)
+    }
+
+    /// Convert `amount` of currency `from` to currency `to`, both should be
+    /// ISO 4217 codes in this table.
+    pub(crate) fn convert(&self, amount: f64, from: &str, to: &str) -> Option<f64> {
+        let from_rate = self.rates.get(from)?;
+        let to_rate = self.rates.get(to)?;
+        Some(amount / from_rate * to_rate)
+    }
+}
+
+/// Return a snapshot of the current rates.
+pub(crate) fn current_rates() -> CurrencyRates {
+    RATES.read().unwrap().clone()
+}
+
+fn cache_file(tauri_app_handle: &AppHandle) -> PathBuf {
+    tauri_app_handle
+        .path()
+        .app_data_dir()
+        .expect("app data directory not found")
+        .join(CACHE_DIR)
+        .join(CACHE_FILE)
+}
+
+/// Load the cached rates, if any.
+pub(crate) fn load_cache(tauri_app_handle: &AppHandle) {
+    let cache_file = cache_file(tauri_app_handle);
+    if !cache_file.is_file() {
+        return;
+    }
+
+    let result = std::fs::read(&cache_file)
+        .map_err(|e| e.to_string())
+        .and_then(|content| {
+            serde_json::from_slice::<CurrencyRates>(&content).map_err(|e| e.to_string())
+        })
+        .and_then(|rates| rates.validate().map(|()| rates));
+    match result {
+        Ok(rates) => *RATES.write().unwrap() = rates,
+        Err(e) => log::warn!(
+            "ignoring invalid currency rates cache [{}], error [{}]",
+            cache_file.display(),
+            e
+        ),
+    }
+}
+
+fn save_cache(tauri_app_handle: &AppHandle, rates: &CurrencyRates) -> Result<(), String> {
+    let cache_file = cache_file(tauri_app_handle);
+    let cache_dir = cache_file.parent().expect("cache file has a parent");
+    std::fs::create_dir_all(cache_dir)
+        .map_err(|e| format!("failed to create [{}], error [{}]", cache_dir.display(), e))?;
+    let content = serde_json::to_vec(rates).expect("rates should be serializable");
+    std::fs::write(&cache_file, content)
+        .map_err(|e| format!("failed to write [{}], error [{}]", cache_file.display(), e))
+}
+
+#[tauri::command]
+pub(crate) async fn get_currency_rates() -> CurrencyRates {
+    current_rates()
+}
+
+/// Fetch the latest rates from the Coco server `server_id`, and cache them.
+#[tauri::command]
+pub(crate) async fn refresh_currency_rates(
+    tauri_app_handle: AppHandle,
+    server_id: String,
+) -> Result<CurrencyRates, String> {
+    let response = HttpClient::get(&server_id, COCO_SERVER_RATES_PATH, None)
+        .await
+        .map_err(|e| e.to_string())?;
+    if matches!(
+        response.status(),
+        StatusCode::NOT_FOUND | StatusCode::METHOD_NOT_ALLOWED | StatusCode::NOT_IMPLEMENTED
+    ) {
+        SERVERS_WITHOUT_RATES
+            .lock()
+            .unwrap()
+            .insert(server_id.clone());
+    }
+    if !response.status().is_success() {
+        return Err(format!(
+            "failed to fetch currency rates from server [{}], status [{}]",
+            server_id,
+            response.status()
+        ));
+    }
+    let rates = response
+        .json::<CurrencyRates>()
+        .await
+        .map_err(|e| e.to_string())?;
+    rates.validate()?;
+
+    save_cache(&tauri_app_handle, &rates)?;
+    *RATES.write().unwrap() = rates.clone();
+
+    Ok(rates)
+}
+
+/// Refresh the rates in the background if they are stale, from the first
+/// enabled and available Coco server that serves them.
+pub(crate) fn refresh_if_stale(tauri_app_handle: &AppHandle) {
+    let now = chrono::Utc::now().timestamp();
+    if !RATES.read().unwrap().is_stale(now) {
+        return;
+    }
+    {
+        let mut state = REFRESH_STATE.lock().unwrap();
+        if state.running || now < state.next_attempt {
+            return;
+        }
+        state.running = true;
+    }
+
+    let tauri_app_handle = tauri_app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        let servers_without_rates = SERVERS_WITHOUT_RATES.lock().unwrap().clone();
+        let servers: Vec<_> = get_all_servers()
+            .await
+            .into_iter()
+            .filter(|server| {
+                server.enabled && server.available && !servers_without_rates.contains(&server.id)
+            })
+            .collect();
+
+        let mut errors = Vec::new();
+        for server in servers.iter() {
+            match refresh_currency_rates(tauri_app_handle.clone(), server.id.clone()).await {
+                Ok(_rates) => break,
+                Err(e) => errors.push(format!("[{}]: {}", server.id, e)),
+            }
+        }
+
+        let now = chrono::Utc::now().timestamp();
+        let mut state = REFRESH_STATE.lock().unwrap();
+        state.running = false;
+        // Refreshed, or there is no server to refresh from
+        if errors.len() < servers.len() || servers.is_empty() {
+            state.n_failures = 0;
+            state.next_attempt = now.saturating_add(REFRESH_RETRY_INTERVAL_SECS);
+            return;
+        }
+
+        state.n_failures += 1;
+        state.next_attempt = now.saturating_add(retry_interval_secs(state.n_failures));
+        // Don't repeat the warning while the servers keep failing
+        if state.n_failures == 1 {
+            log::warn!(
+                "failed to refresh currency rates, errors [{}]",
+                errors.join(", ")
+            );
+        } else {
+            log::debug!(
+                "failed to refresh currency rates, errors [{}]",
+                errors.join(", ")
+            );
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bundled_rates_are_valid() {
+        CurrencyRates::bundled().validate().unwrap();
+    }
+
+    #[test]
+    fn test_is_stale() {
+        let rates = CurrencyRates::bundled();
+        assert!(!rates.is_stale(BUNDLED_RATES_UPDATED + 24 * 60 * 60));
+        assert!(rates.is_stale(BUNDLED_RATES_UPDATED + STALE_AFTER_SECS + 1));
+    }
+
+    #[test]
+    fn test_retry_interval_secs() {
+        assert_eq!(retry_interval_secs(1), REFRESH_RETRY_INTERVAL_SECS);
+        assert_eq!(retry_interval_secs(2), 2 * REFRESH_RETRY_INTERVAL_SECS);
+        assert_eq!(retry_interval_secs(100), MAX_REFRESH_RETRY_INTERVAL_SECS);
+    }
+
+    #[test]
+    fn test_find_currency() {
+        let rates = CurrencyRates::bundled();
+        assert_eq!(rates.find_currency("usd").as_deref(), Some("USD"));
+        assert_eq!(rates.find_currency("€").as_deref(), Some("EUR"));
+        assert_eq!(rates.find_currency("XYZ"), None);
+    }
+
+    #[test]
+    fn test_convert() {
+        let rates = CurrencyRates {
+            base: "USD".into(),
+            updated: 0,
+            rates: HashMap::from([
+                ("USD".into(), 1.0),
+                ("EUR".into(), 0.5),
+                ("CNY".into(), 7.0),
+            ]),
+        };
+        assert_eq!(rates.convert(10.0, "USD", "EUR"), Some(5.0));
+        assert_eq!(rates.convert(5.0, "EUR", "CNY"), Some(70.0));
+        assert_eq!(rates.convert(5.0, "EUR", "JPY"), None);
+
+        let mut invalid = rates.clone();
+        invalid.rates.insert("JPY".into(), 0.0);
+        assert!(invalid.validate().is_err());
+    }
+}
@@ -1,12 +1,19 @@
+pub(crate) mod conversion;
+pub(crate) mod currency;
+pub(crate) mod units;
+
 use super::super::LOCAL_QUERY_SOURCE_TYPE;
 use crate::common::{
     document::{DataSourceReference, Document},
+    document_action::DocumentAction,
     error::SearchError,
     search::{QueryResponse, QuerySource, SearchQuery},
     traits::SearchSource,
 };
 use async_trait::async_trait;
 use chinese_number::{ChineseCase, ChineseCountMethod, ChineseVariant, NumberToChinese};
+use chrono::DateTime;
+use conversion::Conversion;
 use num2words::Num2Words;
 use serde_json::Value;
 use std::collections::HashMap;
@@ -102,11 +109,10 @@ fn parse_result(num: f64) -> Value {
     let to_en = Num2Words::new(num)
         .to_words()
         .map(|s| {
-            let mut chars = s.chars();
             let mut result = String::new();
             let mut capitalize = true;
 
-            while let Some(c) = chars.next() {
+            for c in s.chars() {
                 if c == ' ' || c == '-' {
                     result.push(c);
                     capitalize = true;
@@ -129,6 +135,69 @@ fn parse_result(num: f64) -> Value {
     Value::Object(result_json)
 }
 
+/// Return what is converted, in English and Chinese.  For currency
+/// conversions, the date of the rates is included.
+fn conversion_kind(conversion: &Conversion) -> (String, String) {
+    match conversion
+        .rates_updated
+        .and_then(|updated| DateTime::from_timestamp(updated, 0))
+    {
+        Some(updated) => {
+            let date = updated.format("%Y-%m-%d");
+            (
+                format!("{} (rates of {})", conversion.kind_en, date),
+                format!("{}（{} 汇率）", conversion.kind_zh, date),
+            )
+        }
+        None => (
+            conversion.kind_en.to_string(),
+            conversion.kind_zh.to_string(),
+        ),
+    }
+}
+
+/// Return the document of a conversion, e.g., "10 km in miles".
+fn conversion_document(original_query: &str, conversion: Conversion) -> Document {
+    let mut query_json = serde_json::Map::new();
+    query_json.insert("type".to_string(), Value::String("conversion".to_string()));
+    query_json.insert(
+        "value".to_string(),
+        Value::String(original_query.to_string()),
+    );
+
+    let mut result_json = serde_json::Map::new();
+    result_json.insert(
+        "value".to_string(),
+        Value::String(conversion.formatted.clone()),
+    );
+    result_json.insert("raw".to_string(), Value::String(conversion.raw.clone()));
+    let (kind_en, kind_zh) = conversion_kind(&conversion);
+    result_json.insert("toZh".to_string(), Value::String(kind_zh));
+    result_json.insert("toEn".to_string(), Value::String(kind_en));
+
+    let payload = HashMap::from([
+        ("query".to_string(), Value::Object(query_json)),
+        ("result".to_string(), Value::Object(result_json)),
+    ]);
+
+    Document {
+        id: DATA_SOURCE_ID.to_string(),
+        category: Some(DATA_SOURCE_ID.to_string()),
+        payload: Some(payload),
+        actions: Some(vec![
+            DocumentAction::copy_formatted_value(&conversion.formatted),
+            DocumentAction::copy_raw_value(&conversion.raw),
+        ]),
+        source: Some(DataSourceReference {
+            r#type: Some(LOCAL_QUERY_SOURCE_TYPE.into()),
+            name: Some(DATA_SOURCE_ID.into()),
+            id: Some(DATA_SOURCE_ID.into()),
+            icon: Some(String::from("font_Calculator")),
+        }),
+        ..Default::default()
+    }
+}
+
 #[async_trait]
 impl SearchSource for CalculatorSource {
     fn get_type(&self) -> QuerySource {
@@ -142,9 +211,20 @@ impl SearchSource for CalculatorSource {
         }
     }
 
+    /// There is at most 1 hit, the result of the expression or conversion.
+    fn supports_pagination(&self) -> bool {
+        false
+    }
+
+    /// Load the cached currency rates.
+    async fn init(&self, tauri_app_handle: &AppHandle) -> Result<(), SearchError> {
+        currency::load_cache(tauri_app_handle);
+        Ok(())
+    }
+
     async fn search(
         &self,
-        _tauri_app_handle: AppHandle,
+        tauri_app_handle: AppHandle,
         query: SearchQuery,
     ) -> Result<QueryResponse, SearchError> {
         let Some(query_string) = query.query_strings.get("query") else {
@@ -175,7 +255,22 @@ impl SearchSource for CalculatorSource {
         let eval_query = strip_thousand_separators(query_string);
         let query_source = self.get_type();
         let base_score = self.base_score;
+        let currency_rates = currency::current_rates();
         let closure = move || -> QueryResponse {
+            if let Some(conversion) = conversion::convert(&eval_query, &currency_rates) {
+                if conversion.rates_updated.is_some() {
+                    currency::refresh_if_stale(&tauri_app_handle);
+                }
+
+                return QueryResponse {
+                    source: query_source,
+                    hits: vec![(conversion_document(&original_query, conversion), base_score)],
+                    total_hits: 1,
+                    // Local search source does not support aggregations
+                    aggregations: None,
+                };
+            }
+
             let Ok(tokens) = meval::tokenizer::tokenize(&eval_query) else {
                 // Invalid expression, return nothing.
                 return QueryResponse {
@@ -305,6 +400,28 @@ mod tests {
         assert!((result - expected).abs() < 1e-10);
     }
 
+    #[test]
+    fn test_conversion_document() {
+        let conversion = conversion::convert("10 km in miles", &currency::current_rates()).unwrap();
+        let doc = conversion_document("10 km in miles", conversion);
+
+        let payload = doc.payload.unwrap();
+        assert_eq!(payload["query"]["type"], "conversion");
+        assert_eq!(payload["result"]["value"], "6.213711922 mi");
+        assert_eq!(payload["result"]["toEn"], "Length");
+
+        let actions = doc.actions.unwrap();
+        assert_eq!(actions.len(), 2);
+        assert_eq!(actions[0].id, "copy_formatted_value");
+        assert_eq!(actions[1].id, "copy_raw_value");
+
+        let rates = currency::current_rates();
+        let conversion = conversion::convert("10 usd in eur", &rates).unwrap();
+        let doc = conversion_document("10 usd in eur", conversion);
+        let payload = doc.payload.unwrap();
+        assert_eq!(payload["result"]["toEn"], "Currency (rates of 2024-04-01)");
+    }
+
     #[test]
     fn test_parse_query_preserves_original() {
         let query = parse_query("5,032,104/171");
@@ -0,0 +1,354 @@
+//! Units of measurement supported by unit conversions.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Dimension {
+    Length,
+    Area,
+    Volume,
+    Mass,
+    Time,
+    Speed,
+    Data,
+    Temperature,
+}
+
+impl Dimension {
+    pub(crate) fn name_en(&self) -> &'static str {
+        match self {
+            Self::Length => "Length",
+            Self::Area => "Area",
+            Self::Volume => "Volume",
+            Self::Mass => "Mass",
+            Self::Time => "Time",
+            Self::Speed => "Speed",
+            Self::Data => "Data Size",
+            Self::Temperature => "Temperature",
+        }
+    }
+
+    pub(crate) fn name_zh(&self) -> &'static str {
+        match self {
+            Self::Length => "长度",
+            Self::Area => "面积",
+            Self::Volume => "体积",
+            Self::Mass => "质量",
+            Self::Time => "时间",
+            Self::Speed => "速度",
+            Self::Data => "数据大小",
+            Self::Temperature => "温度",
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) struct Unit {
+    /// Used to display the converted value.
+    pub(crate) symbol: &'static str,
+    /// Names that users can type, besides the symbol.
+    aliases: &'static [&'static str],
+    pub(crate) dimension: Dimension,
+    /// `value_in_base_unit = value * factor + offset`, the base units are
+    /// meter, square meter, liter, kilogram, second, meter per second, byte
+    /// and kelvin.
+    factor: f64,
+    offset: f64,
+}
+
+impl Unit {
+    const fn new(
+        symbol: &'static str,
+        aliases: &'static [&'static str],
+        dimension: Dimension,
+        factor: f64,
+    ) -> Self {
+        Self {
+            symbol,
+            aliases,
+            dimension,
+            factor,
+            offset: 0.0,
+        }
+    }
+
+    pub(crate) fn to_base(&self, value: f64) -> f64 {
+        value * self.factor + self.offset
+    }
+
+    pub(crate) fn convert_from_base(&self, value: f64) -> f64 {
+        (value - self.offset) / self.factor
+    }
+
+    /// Units with an offset, i.e., temperature units other than kelvin,
+    /// cannot be added together.
+    pub(crate) fn is_affine(&self) -> bool {
+        self.offset != 0.0
+    }
+}
+
+const KIB: f64 = 1024.0;
+
+/// When looking up a unit name case-insensitively, the first match wins, so
+/// bytes are listed before bits, e.g., "mb" means megabytes.
+static UNITS: &[Unit] = &[
+    // Length
+    Unit::new(
+        "mm",
+        &["millimeter", "millimeters", "millimetre"],
+        Dimension::Length,
+        0.001,
+    ),
+    Unit::new(
+        "cm",
+        &["centimeter", "centimeters", "centimetre"],
+        Dimension::Length,
+        0.01,
+    ),
+    Unit::new(
+        "m",
+        &["meter", "meters", "metre", "metres"],
+        Dimension::Length,
+        1.0,
+    ),
+    Unit::new(
+        "km",
+        &["kilometer", "kilometers", "kilometre"],
+        Dimension::Length,
+        1000.0,
+    ),
+    Unit::new("in", &["inch", "inches", "\""], Dimension::Length, 0.0254),
+    Unit::new("ft", &["foot", "feet", "'"], Dimension::Length, 0.3048),
+    Unit::new("yd", &["yard", "yards"], Dimension::Length, 0.9144),
+    Unit::new("mi", &["mile", "miles"], Dimension::Length, 1609.344),
+    Unit::new(
+        "nmi",
+        &["nautical mile", "nautical miles"],
+        Dimension::Length,
+        1852.0,
+    ),
+    // Area
+    Unit::new(
+        "m²",
+        &["m2", "sqm", "square meter", "square meters"],
+        Dimension::Area,
+        1.0,
+    ),
+    Unit::new(
+        "km²",
+        &["km2", "square kilometer", "square kilometers"],
+        Dimension::Area,
+        1e6,
+    ),
+    Unit::new(
+        "ft²",
+        &["ft2", "sqft", "square foot", "square feet"],
+        Dimension::Area,
+        0.092_903_04,
+    ),
+    Unit::new("ha", &["hectare", "hectares"], Dimension::Area, 10_000.0),
+    Unit::new("acre", &["acres", "ac"], Dimension::Area, 4_046.856_422_4),
+    // Volume
+    Unit::new(
+        "ml",
+        &["milliliter", "milliliters", "millilitre", "mL"],
+        Dimension::Volume,
+        0.001,
+    ),
+    Unit::new(
+        "l",
+        &["liter", "liters", "litre", "litres", "L"],
+        Dimension::Volume,
+        1.0,
+    ),
+    Unit::new(
+        "m³",
+        &["m3", "cubic meter", "cubic meters"],
+        Dimension::Volume,
+        1000.0,
+    ),
+    Unit::new(
+        "gal",
+        &["gallon", "gallons"],
+        Dimension::Volume,
+        3.785_411_784,
+    ),
+    Unit::new("qt", &["quart", "quarts"], Dimension::Volume, 0.946_352_946),
+    Unit::new("pt", &["pint", "pints"], Dimension::Volume, 0.473_176_473),
+    Unit::new("cup", &["cups"], Dimension::Volume, 0.236_588_236_5),
+    Unit::new(
+        "fl oz",
+        &["floz", "fluid ounce", "fluid ounces"],
+        Dimension::Volume,
+        0.029_573_529_562_5,
+    ),
+    // Mass
+    Unit::new("mg", &["milligram", "milligrams"], Dimension::Mass, 1e-6),
+    Unit::new("g", &["gram", "grams"], Dimension::Mass, 0.001),
+    Unit::new(
+        "kg",
+        &["kilogram", "kilograms", "kilo", "kilos"],
+        Dimension::Mass,
+        1.0,
+    ),
+    Unit::new(
+        "t",
+        &["tonne", "tonnes", "ton", "tons"],
+        Dimension::Mass,
+        1000.0,
+    ),
+    Unit::new(
+        "oz",
+        &["ounce", "ounces"],
+        Dimension::Mass,
+        0.028_349_523_125,
+    ),
+    Unit::new(
+        "lb",
+        &["lbs", "pound", "pounds"],
+        Dimension::Mass,
+        0.453_592_37,
+    ),
+    Unit::new("st", &["stone", "stones"], Dimension::Mass, 6.350_293_18),
+    Unit::new("斤", &["jin"], Dimension::Mass, 0.5),
+    // Time
+    Unit::new(
+        "ms",
+        &["millisecond", "milliseconds"],
+        Dimension::Time,
+        0.001,
+    ),
+    Unit::new(
+        "s",
+        &["sec", "secs", "second", "seconds"],
+        Dimension::Time,
+        1.0,
+    ),
+    Unit::new("min", &["mins", "minute", "minutes"], Dimension::Time, 60.0),
+    Unit::new(
+        "h",
+        &["hr", "hrs", "hour", "hours"],
+        Dimension::Time,
+        3600.0,
+    ),
+    Unit::new("d", &["day", "days"], Dimension::Time, 86_400.0),
+    Unit::new("wk", &["week", "weeks"], Dimension::Time, 604_800.0),
+    Unit::new("yr", &["year", "years"], Dimension::Time, 31_557_600.0),
+    // Speed
+    Unit::new("m/s", &["mps"], Dimension::Speed, 1.0),
+    Unit::new("km/h", &["kmh", "kph"], Dimension::Speed, 1000.0 / 3600.0),
+    Unit::new("mph", &["mi/h"], Dimension::Speed, 1609.344 / 3600.0),
+    Unit::new(
+        "kn",
+        &["knot", "knots", "kt"],
+        Dimension::Speed,
+        1852.0 / 3600.0,
+    ),
+    // Data, SI prefixes are powers of 1000 and IEC prefixes are powers of 1024
+    Unit::new("B", &["byte", "bytes"], Dimension::Data, 1.0),
+    Unit::new("KB", &["kB", "kilobyte", "kilobytes"], Dimension::Data, 1e3),
+    Unit::new("MB", &["megabyte", "megabytes"], Dimension::Data, 1e6),
+    Unit::new("GB", &["gigabyte", "gigabytes"], Dimension::Data, 1e9),
+    Unit::new("TB", &["terabyte", "terabytes"], Dimension::Data, 1e12),
+    Unit::new("KiB", &["kibibyte", "kibibytes"], Dimension::Data, KIB),
+    Unit::new(
+        "MiB",
+        &["mebibyte", "mebibytes"],
+        Dimension::Data,
+        KIB * KIB,
+    ),
+    Unit::new(
+        "GiB",
+        &["gibibyte", "gibibytes"],
+        Dimension::Data,
+        KIB * KIB * KIB,
+    ),
+    Unit::new(
+        "TiB",
+        &["tebibyte", "tebibytes"],
+        Dimension::Data,
+        KIB * KIB * KIB * KIB,
+    ),
+    Unit::new("bit", &["bits", "b"], Dimension::Data, 0.125),
+    Unit::new(
+        "Kb",
+        &["kbit", "kilobit", "kilobits"],
+        Dimension::Data,
+        125.0,
+    ),
+    Unit::new(
+        "Mb",
+        &["mbit", "megabit", "megabits"],
+        Dimension::Data,
+        125_000.0,
+    ),
+    Unit::new(
+        "Gb",
+        &["gbit", "gigabit", "gigabits"],
+        Dimension::Data,
+        125_000_000.0,
+    ),
+    // Temperature
+    Unit {
+        symbol: "°C",
+        aliases: &["c", "celsius", "degc", "℃"],
+        dimension: Dimension::Temperature,
+        factor: 1.0,
+        offset: 273.15,
+    },
+    Unit {
+        symbol: "°F",
+        aliases: &["f", "fahrenheit", "degf", "℉"],
+        dimension: Dimension::Temperature,
+        factor: 5.0 / 9.0,
+        offset: 459.67 * 5.0 / 9.0,
+    },
+    Unit::new("K", &["kelvin", "kelvins"], Dimension::Temperature, 1.0),
+];
+
+/// Look up the unit named `name`, case-sensitively first, so that "Mb" and
+/// "MB" are told apart, and then case-insensitively.
+pub(crate) fn find_unit(name: &str) -> Option<&'static Unit> {
+    let name = name.trim();
+    let is_name_of = |unit: &Unit, eq: &dyn Fn(&str) -> bool| {
+        eq(unit.symbol) || unit.aliases.iter().any(|alias| eq(alias))
+    };
+
+    UNITS
+        .iter()
+        .find(|unit| is_name_of(unit, &|n| n == name))
+        .or_else(|| {
+            UNITS
+                .iter()
+                .find(|unit| is_name_of(unit, &|n| n.eq_ignore_ascii_case(name)))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_unit() {
+        assert_eq!(find_unit("km").unwrap().symbol, "km");
+        assert_eq!(find_unit("Miles").unwrap().symbol, "mi");
+        assert_eq!(find_unit("MB").unwrap().symbol, "MB");
+        assert_eq!(find_unit("Mb").unwrap().symbol, "Mb");
+        // Falls back to case-insensitive, where bytes win
+        assert_eq!(find_unit("mb").unwrap().symbol, "MB");
+        assert_eq!(find_unit("F").unwrap().symbol, "°F");
+        assert!(find_unit("furlong").is_none());
+    }
+
+    #[test]
+    fn test_temperature() {
+        let celsius = find_unit("c").unwrap();
+        let fahrenheit = find_unit("f").unwrap();
+        let kelvin = find_unit("K").unwrap();
+
+        let boiling = celsius.to_base(100.0);
+        assert!((kelvin.convert_from_base(boiling) - 373.15).abs() < 1e-9);
+        assert!((fahrenheit.convert_from_base(boiling) - 212.0).abs() < 1e-9);
+        assert!((celsius.convert_from_base(fahrenheit.to_base(-40.0)) + 40.0).abs() < 1e-9);
+        assert!(celsius.is_affine());
+        assert!(!kelvin.is_affine());
+    }
+}
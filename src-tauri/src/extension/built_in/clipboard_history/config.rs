@@ -0,0 +1,121 @@
+//! Clipboard History configuration and its getter/setter.
+
+use serde::Deserialize;
+use serde::Serialize;
+use std::time::Duration;
+use tauri::AppHandle;
+use tauri_plugin_store::StoreExt;
+
+// Tauri store keys for the clipboard history configuration
+const TAURI_STORE_CLIPBOARD_HISTORY_CONFIG: &str = "clipboard_history_config";
+const TAURI_STORE_KEY_CONFIG: &str = "config";
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ClipboardHistoryConfig {
+    /// Maximum number of unpinned entries, the oldest ones are dropped first.
+    pub max_entries: usize,
+    /// Unpinned entries older than this are dropped, `None` keeps them
+    /// forever.
+    pub retention_days: Option<u32>,
+    /// Clipboard changes made by these applications are not recorded, they
+    /// are matched case-insensitively against the application name, bundle
+    /// identifier (macOS) and executable name.  If this list is not empty,
+    /// changes made by the applications that cannot be found (e.g., on
+    /// Wayland) are not recorded either.
+    pub ignored_apps: Vec<String>,
+    /// Encrypt the history on disk, the key is kept in the OS keyring.
+    pub encrypt: bool,
+    pub record_images: bool,
+}
+
+impl Default for ClipboardHistoryConfig {
+    fn default() -> Self {
+        Self {
+            max_entries: 500,
+            retention_days: Some(30),
+            // Common password managers
+            ignored_apps: vec![
+                "1Password".into(),
+                "com.1password.1password".into(),
+                "Bitwarden".into(),
+                "com.bitwarden.desktop".into(),
+                "KeePassXC".into(),
+                "org.keepassxc.keepassxc".into(),
+            ],
+            encrypt: false,
+            record_images: true,
+        }
+    }
+}
+
+impl ClipboardHistoryConfig {
+    pub(crate) fn get(tauri_app_handle: &AppHandle) -> Self {
+        let store = tauri_app_handle
+            .store(TAURI_STORE_CLIPBOARD_HISTORY_CONFIG)
+            .unwrap_or_else(|e| {
+                panic!(
+                    "store [{}] not found/loaded, error [{}]",
+                    TAURI_STORE_CLIPBOARD_HISTORY_CONFIG, e
+                )
+            });
+
+        match store.get(TAURI_STORE_KEY_CONFIG) {
+            Some(config) => serde_json::from_value(config.clone()).unwrap_or_else(|e| {
+                panic!(
+                    "Failed to deserialize clipboard history config from store. Invalid JSON: {:?}, error: {}",
+                    config, e
+                )
+            }),
+            None => {
+                let default_config = Self::default();
+                store.set(
+                    TAURI_STORE_KEY_CONFIG,
+                    serde_json::to_value(&default_config).unwrap(),
+                );
+                default_config
+            }
+        }
+    }
+
+    pub(crate) fn retention(&self) -> Option<Duration> {
+        self.retention_days
+            .map(|days| Duration::from_secs(u64::from(days) * SECONDS_PER_DAY))
+    }
+
+    /// Return true if clipboard changes made by `app` should not be recorded.
+    pub(crate) fn is_app_ignored(&self, app: &str) -> bool {
+        self.ignored_apps
+            .iter()
+            .any(|ignored| ignored.eq_ignore_ascii_case(app))
+    }
+}
+
+pub(super) fn set(
+    tauri_app_handle: &AppHandle,
+    config: &ClipboardHistoryConfig,
+) -> Result<(), String> {
+    let store = tauri_app_handle
+        .store(TAURI_STORE_CLIPBOARD_HISTORY_CONFIG)
+        .map_err(|e| e.to_string())?;
+    store.set(
+        TAURI_STORE_KEY_CONFIG,
+        serde_json::to_value(config).map_err(|e| e.to_string())?,
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_app_ignored() {
+        let config = ClipboardHistoryConfig::default();
+        assert!(config.is_app_ignored("keepassxc"));
+        assert!(config.is_app_ignored("com.1password.1password"));
+        assert!(!config.is_app_ignored("Terminal"));
+    }
+}
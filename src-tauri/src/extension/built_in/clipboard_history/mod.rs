@@ -0,0 +1,809 @@
+//! Clipboard History extension, it records the text, images and file lists
+//! copied to the clipboard, and makes them searchable.
+//!
+//! It is disabled by default as the history may contain sensitive content.
+//!
+//! The clipboard is polled while this extension is enabled.  Changes made by
+//! the applications in the ignore list (e.g., password managers), or marked as
+//! concealed, are not recorded.  If the application cannot be found, e.g., on
+//! Wayland, changes are recorded, and a warning is logged once.  Opening an
+//! entry pastes it again into the frontmost application.
+
+pub(crate) mod config;
+mod platform;
+pub(crate) mod storage;
+
+use super::super::LOCAL_QUERY_SOURCE_TYPE;
+use crate::common::document::{DataSourceReference, Document, OnOpened};
+use crate::common::document_action::DocumentAction;
+use crate::common::error::SearchError;
+use crate::common::search::{QueryResponse, QuerySource, SearchQuery};
+use crate::common::traits::SearchSource;
+use crate::search::fuzzy::{fuzzy_match, fuzzy_score};
+use crate::search::preview::{DocumentPreview, truncated_text_preview};
+use async_trait::async_trait;
+use config::ClipboardHistoryConfig;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{LazyLock, Mutex};
+use std::time::Duration;
+use storage::{ClipboardContent, ClipboardEntry, ClipboardHistory, fnv1a_hash};
+use tauri::{AppHandle, Manager};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+use tokio_util::sync::CancellationToken;
+
+pub(crate) const EXTENSION_ID: &str = "Clipboard History";
+
+/// JSON file for this extension.
+pub(crate) const PLUGIN_JSON_FILE: &str = r#"
+{
+  "id": "Clipboard History",
+  "name": "Clipboard History",
+  "platforms": ["macos", "linux", "windows"],
+  "description": "Search and paste what you copied before",
+  "icon": "font_file_txt",
+  "type": "extension",
+  "enabled": false
+}
+"#;
+
+/// Name of the history directory, under the app data directory.
+const HISTORY_DIR: &str = "clipboard_history";
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+/// Polling slows down to this while the same image stays on the clipboard,
+/// see `next_poll_interval()`.
+const MAX_POLL_INTERVAL: Duration = Duration::from_secs(4);
+/// Larger clipboard contents are not recorded.
+const MAX_TEXT_BYTES: usize = 1024 * 1024;
+const MAX_IMAGE_BYTES: usize = 64 * 1024 * 1024;
+
+/// Coco should be hidden, and the previous application focused, before
+/// sending the paste shortcut.
+const PASTE_DELAY: Duration = Duration::from_millis(200);
+
+const TITLE_MAX_CHARS: usize = 100;
+/// Longer texts are matched by substring only, fuzzy matching them is slow
+/// and produces noise.
+const FUZZY_MATCH_MAX_CHARS: usize = 256;
+const SUBSTRING_MATCH_SCORE: f64 = 0.3;
+const SOURCE_APP_MATCH_WEIGHT: f64 = 0.5;
+/// The oldest entry gets its score reduced by this fraction.
+const RECENCY_WEIGHT: f64 = 0.2;
+const PINNED_BOOST: f64 = 1.2;
+
+/// The history, `Some` while this extension is enabled.
+static HISTORY: LazyLock<Mutex<Option<ClipboardHistory>>> = LazyLock::new(Mutex::default);
+
+/// Set once the warning that the ignore list does not take effect is logged.
+static UNKNOWN_APP_WARNED: AtomicBool = AtomicBool::new(false);
+
+fn with_history<T>(
+    f: impl FnOnce(&mut ClipboardHistory) -> Result<T, String>,
+) -> Result<T, String> {
+    let mut opt_history = HISTORY.lock().unwrap();
+    let history = opt_history
+        .as_mut()
+        .ok_or_else(|| format!("extension [{}] is not enabled", EXTENSION_ID))?;
+    f(history)
+}
+
+fn history_dir(tauri_app_handle: &AppHandle) -> PathBuf {
+    tauri_app_handle
+        .path()
+        .app_data_dir()
+        .expect("app data directory not found")
+        .join(HISTORY_DIR)
+}
+
+fn open_history(
+    tauri_app_handle: &AppHandle,
+    config: &ClipboardHistoryConfig,
+) -> Result<ClipboardHistory, String> {
+    let key = if config.encrypt {
+        Some(storage::load_or_create_key()?)
+    } else {
+        None
+    };
+    ClipboardHistory::open(&history_dir(tauri_app_handle), key)
+}
+
+fn now_millis() -> i64 {
+    chrono::Utc::now().timestamp_millis()
+}
+
+/// Turn text into clipboard content.  File managers put the copied files on
+/// the clipboard as "file://" URIs, one per line, optionally preceded by a
+/// "copy" or "cut" line (GNOME).
+fn text_content(text: String) -> ClipboardContent {
+    let lines: Vec<&str> = text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && *line != "copy" && *line != "cut")
+        .collect();
+    let opt_paths: Option<Vec<String>> = lines
+        .iter()
+        .map(|line| {
+            let url = url::Url::parse(line)
+                .ok()
+                .filter(|url| url.scheme() == "file")?;
+            let path = url.to_file_path().ok()?;
+            Some(path.to_string_lossy().into_owned())
+        })
+        .collect();
+
+    match opt_paths {
+        Some(paths) if !paths.is_empty() => ClipboardContent::Files { paths },
+        _ => ClipboardContent::Text { text },
+    }
+}
+
+/// Read the clipboard, return its content, and the pixels if it is an image.
+fn read_clipboard(
+    tauri_app_handle: &AppHandle,
+    record_images: bool,
+) -> Option<(ClipboardContent, Option<Vec<u8>>)> {
+    let clipboard = tauri_app_handle.clipboard();
+
+    if let Ok(text) = clipboard.read_text()
+        && !text.trim().is_empty()
+    {
+        if text.len() > MAX_TEXT_BYTES {
+            return None;
+        }
+        return Some((text_content(text), None));
+    }
+
+    if record_images && let Ok(image) = clipboard.read_image() {
+        let pixels = image.rgba();
+        if pixels.is_empty() || pixels.len() > MAX_IMAGE_BYTES {
+            return None;
+        }
+        let content = ClipboardContent::Image {
+            width: image.width(),
+            height: image.height(),
+            hash: fnv1a_hash(pixels),
+        };
+        return Some((content, Some(pixels.to_vec())));
+    }
+
+    None
+}
+
+/// Return true if `opt_app`, the names of the application that changed the
+/// clipboard, is in the ignore list.  Unknown applications are not ignored.
+fn is_from_ignored_app(config: &ClipboardHistoryConfig, opt_app: Option<&[String]>) -> bool {
+    opt_app.is_some_and(|app_names| app_names.iter().any(|name| config.is_app_ignored(name)))
+}
+
+/// Without a change count, e.g., on Linux, the clipboard is read on every
+/// poll, and reading an image decodes and hashes all its pixels.  Poll less
+/// often while the same image stays on the clipboard, texts are cheap to read.
+fn next_poll_interval(poll_interval: Duration, unchanged_image: bool) -> Duration {
+    if unchanged_image {
+        (poll_interval * 2).min(MAX_POLL_INTERVAL)
+    } else {
+        POLL_INTERVAL
+    }
+}
+
+/// Poll the clipboard and record its changes until `token` is cancelled.
+async fn monitor_clipboard(tauri_app_handle: AppHandle, token: CancellationToken) {
+    let mut last_change_count = platform::change_count();
+    // What is on the clipboard when we start is not copied by the user now,
+    // don't record it.
+    let mut last_content = {
+        let tauri_app_handle = tauri_app_handle.clone();
+        tokio::task::spawn_blocking(move || read_clipboard(&tauri_app_handle, true))
+            .await
+            .ok()
+            .flatten()
+            .map(|(content, _pixels)| content)
+    };
+
+    let mut poll_interval = POLL_INTERVAL;
+
+    loop {
+        tokio::select! {
+            _ = token.cancelled() => return,
+            _ = tokio::time::sleep(poll_interval) => {}
+        }
+
+        // Reading the clipboard can be expensive, e.g., large images, don't
+        // read it if the platform tells us that it has not changed.
+        let change_count = platform::change_count();
+        if change_count.is_some() && change_count == last_change_count {
+            continue;
+        }
+        last_change_count = change_count;
+
+        let config = ClipboardHistoryConfig::get(&tauri_app_handle);
+        let record_images = config.record_images;
+        let tauri_app_handle_clone = tauri_app_handle.clone();
+        let Ok(Some((content, opt_pixels))) = tokio::task::spawn_blocking(move || {
+            read_clipboard(&tauri_app_handle_clone, record_images)
+        })
+        .await
+        else {
+            continue;
+        };
+
+        let unchanged = last_content.as_ref() == Some(&content);
+        let unchanged_image = change_count.is_none()
+            && unchanged
+            && matches!(content, ClipboardContent::Image { .. });
+        poll_interval = next_poll_interval(poll_interval, unchanged_image);
+        if unchanged {
+            continue;
+        }
+        last_content = Some(content.clone());
+
+        let Ok((concealed, opt_app)) =
+            tokio::task::spawn_blocking(|| (platform::is_concealed(), platform::frontmost_app()))
+                .await
+        else {
+            continue;
+        };
+        if concealed {
+            log::debug!("clipboard content is marked as concealed, not recorded");
+            continue;
+        }
+        if is_from_ignored_app(&config, opt_app.as_deref()) {
+            log::debug!(
+                "clipboard changed by ignored application [{:?}], not recorded",
+                opt_app
+            );
+            continue;
+        }
+        if opt_app.is_none()
+            && !config.ignored_apps.is_empty()
+            && !UNKNOWN_APP_WARNED.swap(true, Ordering::Relaxed)
+        {
+            log::warn!(
+                "cannot find the application that changed the clipboard, the ignore list does not take effect"
+            );
+        }
+
+        let source_app = opt_app.and_then(|names| names.into_iter().next());
+        let now = now_millis();
+        let result = with_history(|history| {
+            history.record(content, opt_pixels.as_deref(), source_app, now)?;
+            history.apply_retention(config.max_entries, config.retention(), now);
+            history.save()
+        });
+        if let Err(e) = result {
+            log::error!("failed to record clipboard change, error [{}]", e);
+        }
+    }
+}
+
+/// Put the entry `id` back onto the clipboard.
+pub(crate) fn copy_entry(tauri_app_handle: &AppHandle, id: &str) -> Result<(), String> {
+    let (content, opt_pixels) = with_history(|history| {
+        let entry = history
+            .get(id)
+            .ok_or_else(|| format!("clipboard entry [{}] does not exist", id))?;
+        let opt_pixels = match entry.content {
+            ClipboardContent::Image { .. } => Some(history.read_image(id)?),
+            _ => None,
+        };
+        Ok((entry.content.clone(), opt_pixels))
+    })?;
+
+    let clipboard = tauri_app_handle.clipboard();
+    match content {
+        ClipboardContent::Text { text } => clipboard.write_text(text),
+        ClipboardContent::Image { width, height, .. } => {
+            let pixels = opt_pixels.expect("pixels are read for image entries");
+            clipboard.write_image(&tauri::image::Image::new_owned(pixels, width, height))
+        }
+        // The clipboard plugin cannot write file lists, write their URIs,
+        // which most file managers and editors accept.
+        ClipboardContent::Files { paths } => {
+            let uris: Vec<String> = paths
+                .iter()
+                .filter_map(|path| url::Url::from_file_path(path).ok())
+                .map(String::from)
+                .collect();
+            clipboard.write_text(uris.join("\n"))
+        }
+    }
+    .map_err(|e| e.to_string())
+}
+
+/// Paste the entry `id` again into the frontmost application, called when
+/// an entry is opened.
+pub(crate) async fn paste_entry(tauri_app_handle: &AppHandle, id: &str) -> Result<(), String> {
+    copy_entry(tauri_app_handle, id)?;
+    crate::hide_coco(tauri_app_handle.clone()).await;
+
+    // Don't block opening, it has a timeout
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(PASTE_DELAY).await;
+        if let Err(e) = crate::util::keyboard::simulate_paste().await {
+            log::warn!("failed to paste clipboard entry, error [{}]", e);
+        }
+    });
+
+    Ok(())
+}
+
+pub(crate) fn pin_entry(id: &str, pinned: bool) -> Result<(), String> {
+    with_history(|history| {
+        if !history.set_pinned(id, pinned) {
+            return Err(format!("clipboard entry [{}] does not exist", id));
+        }
+        history.save()
+    })
+}
+
+pub(crate) fn delete_entry(id: &str) -> Result<(), String> {
+    with_history(|history| {
+        if !history.remove(id) {
+            return Err(format!("clipboard entry [{}] does not exist", id));
+        }
+        history.save()
+    })
+}
+
+fn entry_title(entry: &ClipboardEntry) -> String {
+    match entry.content {
+        ClipboardContent::Text { ref text } => {
+            let first_line = text.trim().lines().next().unwrap_or_default();
+            let mut title: String = first_line.chars().take(TITLE_MAX_CHARS).collect();
+            if title.len() < text.trim().len() {
+                title.push('…');
+            }
+            title
+        }
+        ClipboardContent::Image { width, height, .. } => format!("Image ({}×{})", width, height),
+        ClipboardContent::Files { ref paths } => {
+            let names: Vec<String> = paths
+                .iter()
+                .map(|path| {
+                    std::path::Path::new(path)
+                        .file_name()
+                        .map_or_else(|| path.clone(), |name| name.to_string_lossy().into_owned())
+                })
+                .collect();
+            names.join(", ")
+        }
+    }
+}
+
+/// Return how well `query_string` matches `entry`, and the matched ranges
+/// of its title.
+fn match_entry(
+    query_string: &str,
+    entry: &ClipboardEntry,
+) -> Option<(f64, Option<Vec<std::ops::Range<usize>>>)> {
+    let (content_score, ranges) = match entry.content {
+        ClipboardContent::Text { ref text } => {
+            if text.chars().count() <= FUZZY_MATCH_MAX_CHARS && !text.trim().contains('\n') {
+                match fuzzy_match(query_string, text.trim()) {
+                    Some(text_match) => {
+                        // The title is truncated, drop the ranges beyond it
+                        let ranges = text_match
+                            .ranges
+                            .into_iter()
+                            .filter(|range| range.start < TITLE_MAX_CHARS)
+                            .map(|range| range.start..range.end.min(TITLE_MAX_CHARS))
+                            .collect();
+                        (text_match.score, Some(ranges))
+                    }
+                    None => (0.0, None),
+                }
+            } else if text.to_lowercase().contains(&query_string.to_lowercase()) {
+                (SUBSTRING_MATCH_SCORE, None)
+            } else {
+                (0.0, None)
+            }
+        }
+        // So that users can list the images by searching "image"
+        ClipboardContent::Image { .. } => (fuzzy_score(query_string, "Image").unwrap_or(0.0), None),
+        ClipboardContent::Files { ref paths } => {
+            let best = paths
+                .iter()
+                .filter_map(|path| fuzzy_score(query_string, path))
+                .fold(0.0, f64::max);
+            (best, None)
+        }
+    };
+    let source_app_score = entry
+        .source_app
+        .as_deref()
+        .and_then(|app| fuzzy_score(query_string, app))
+        .map_or(0.0, |score| score * SOURCE_APP_MATCH_WEIGHT);
+
+    let score = content_score.max(source_app_score);
+    (score > 0.0).then_some((score, ranges))
+}
+
+fn entry_to_document(
+    entry: &ClipboardEntry,
+    title_match_ranges: Option<Vec<std::ops::Range<usize>>>,
+) -> Document {
+    let (subcategory, icon, summary) = match entry.content {
+        ClipboardContent::Text { ref text } => (
+            "Text",
+            "font_file_txt",
+            Some(text.chars().take(TITLE_MAX_CHARS * 2).collect()),
+        ),
+        ClipboardContent::Image { .. } => ("Image", "font_file_image", None),
+        ClipboardContent::Files { ref paths } => {
+            ("Files", "font_file_folder", Some(paths.join("\n")))
+        }
+    };
+    let updated = chrono::DateTime::from_timestamp_millis(entry.copied_at).map(|t| t.to_rfc3339());
+
+    Document {
+        id: entry.id.clone(),
+        title: Some(entry_title(entry)),
+        title_match_ranges,
+        summary,
+        updated,
+        category: Some(EXTENSION_ID.into()),
+        subcategory: Some(subcategory.into()),
+        tags: entry.pinned.then(|| vec!["pinned".to_string()]),
+        icon: Some(icon.into()),
+        on_opened: Some(OnOpened::ClipboardEntry {
+            id: entry.id.clone(),
+        }),
+        actions: Some(vec![
+            DocumentAction::copy_clipboard_entry(&entry.id),
+            DocumentAction::pin_clipboard_entry(&entry.id, !entry.pinned),
+            DocumentAction::delete_clipboard_entry(&entry.id),
+        ]),
+        source: Some(DataSourceReference {
+            r#type: Some(EXTENSION_ID.into()),
+            name: entry.source_app.clone(),
+            id: Some(EXTENSION_ID.into()),
+            icon: None,
+        }),
+        ..Default::default()
+    }
+}
+
+/// Search source of the Clipboard History extension, enabling it starts
+/// recording the clipboard.
+pub(crate) struct ClipboardHistorySearchSource {
+    base_score: f64,
+    monitor: Mutex<Option<CancellationToken>>,
+}
+
+impl ClipboardHistorySearchSource {
+    pub(crate) fn new(base_score: f64) -> Self {
+        Self {
+            base_score,
+            monitor: Mutex::default(),
+        }
+    }
+}
+
+#[async_trait]
+impl SearchSource for ClipboardHistorySearchSource {
+    fn get_type(&self) -> QuerySource {
+        QuerySource {
+            r#type: LOCAL_QUERY_SOURCE_TYPE.into(),
+            name: hostname::get()
+                .unwrap_or(EXTENSION_ID.into())
+                .to_string_lossy()
+                .into(),
+            id: EXTENSION_ID.into(),
+        }
+    }
+
+    /// Load the history and start recording.
+    async fn init(&self, tauri_app_handle: &AppHandle) -> Result<(), SearchError> {
+        let config = ClipboardHistoryConfig::get(tauri_app_handle);
+        let mut history = open_history(tauri_app_handle, &config)
+            .map_err(|error| SearchError::InternalError { error })?;
+        history.apply_retention(config.max_entries, config.retention(), now_millis());
+        *HISTORY.lock().unwrap() = Some(history);
+
+        let token = CancellationToken::new();
+        tauri::async_runtime::spawn(monitor_clipboard(tauri_app_handle.clone(), token.clone()));
+        if let Some(replaced) = self.monitor.lock().unwrap().replace(token) {
+            replaced.cancel();
+        }
+
+        Ok(())
+    }
+
+    /// Stop recording and unload the history.
+    async fn shutdown(&self, _tauri_app_handle: &AppHandle) {
+        if let Some(token) = self.monitor.lock().unwrap().take() {
+            token.cancel();
+        }
+        if let Some(history) = HISTORY.lock().unwrap().take()
+            && let Err(e) = history.save()
+        {
+            log::error!("failed to save clipboard history, error [{}]", e);
+        }
+    }
+
+    async fn preview(
+        &self,
+        _tauri_app_handle: &AppHandle,
+        document: &Document,
+    ) -> Result<Option<DocumentPreview>, SearchError> {
+        let opt_content = HISTORY
+            .lock()
+            .unwrap()
+            .as_ref()
+            .and_then(|history| history.get(&document.id))
+            .map(|entry| entry.content.clone());
+
+        let opt_preview = match opt_content {
+            Some(ClipboardContent::Text { text }) => Some(truncated_text_preview(&text)),
+            Some(ClipboardContent::Image { width, height, .. }) => Some(DocumentPreview::Image {
+                format: None,
+                width: width as usize,
+                height: height as usize,
+                // RGBA
+                size: u64::from(width) * u64::from(height) * 4,
+            }),
+            Some(ClipboardContent::Files { .. }) | None => None,
+        };
+
+        Ok(opt_preview)
+    }
+
+    async fn search(
+        &self,
+        _tauri_app_handle: AppHandle,
+        query: SearchQuery,
+    ) -> Result<QueryResponse, SearchError> {
+        let query_string = query
+            .query_strings
+            .get("query")
+            .map(|query_string| query_string.trim())
+            .unwrap_or_default();
+        if query_string.is_empty() {
+            return Ok(QueryResponse {
+                source: self.get_type(),
+                hits: Vec::new(),
+                total_hits: 0,
+                aggregations: None,
+            });
+        }
+
+        let mut hits: Vec<(Document, f64)> = {
+            let opt_history = HISTORY.lock().unwrap();
+            let entries = opt_history
+                .as_ref()
+                .map(ClipboardHistory::entries)
+                .unwrap_or_default();
+            let n_entries = entries.len();
+
+            entries
+                .iter()
+                .enumerate()
+                .filter_map(|(idx, entry)| {
+                    let (match_score, ranges) = match_entry(query_string, entry)?;
+                    let recency = 1.0 - RECENCY_WEIGHT * idx as f64 / n_entries as f64;
+                    let pinned_boost = if entry.pinned { PINNED_BOOST } else { 1.0 };
+                    let score = self.base_score * match_score * recency * pinned_boost;
+                    Some((entry_to_document(entry, ranges), score))
+                })
+                .collect()
+        };
+        hits.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+
+        let total_hits = hits.len();
+        let hits = hits
+            .into_iter()
+            .skip(query.from as usize)
+            .take(query.size as usize)
+            .collect();
+
+        Ok(QueryResponse {
+            source: self.get_type(),
+            hits,
+            total_hits,
+            aggregations: None,
+        })
+    }
+}
+
+#[tauri::command]
+pub(crate) async fn get_clipboard_history_config(
+    tauri_app_handle: AppHandle,
+) -> ClipboardHistoryConfig {
+    ClipboardHistoryConfig::get(&tauri_app_handle)
+}
+
+/// Update the config, the retention limits and the encryption apply to the
+/// existing entries immediately.
+#[tauri::command]
+pub(crate) async fn set_clipboard_history_config(
+    tauri_app_handle: AppHandle,
+    config: ClipboardHistoryConfig,
+) -> Result<(), String> {
+    let old_config = ClipboardHistoryConfig::get(&tauri_app_handle);
+    let new_key = if config.encrypt && !old_config.encrypt {
+        Some(Some(storage::load_or_create_key()?))
+    } else if !config.encrypt && old_config.encrypt {
+        Some(None)
+    } else {
+        None
+    };
+
+    {
+        let mut opt_history = HISTORY.lock().unwrap();
+        // The history is not loaded if this extension is disabled, load it to
+        // migrate the files on disk if the encryption changes.
+        let mut unloaded_history;
+        let history = match opt_history.as_mut() {
+            Some(history) => history,
+            None if new_key.is_some() => {
+                unloaded_history = open_history(&tauri_app_handle, &old_config)?;
+                &mut unloaded_history
+            }
+            None => return config::set(&tauri_app_handle, &config),
+        };
+
+        if let Some(key) = new_key {
+            history.set_key(key)?;
+        }
+        history.apply_retention(config.max_entries, config.retention(), now_millis());
+        history.save()?;
+    }
+
+    config::set(&tauri_app_handle, &config)
+}
+
+/// Return all the entries, newest first.
+#[tauri::command]
+pub(crate) async fn list_clipboard_history() -> Result<Vec<ClipboardEntry>, String> {
+    with_history(|history| Ok(history.entries().to_vec()))
+}
+
+#[tauri::command]
+pub(crate) async fn pin_clipboard_entry(id: String, pinned: bool) -> Result<(), String> {
+    pin_entry(&id, pinned)
+}
+
+#[tauri::command]
+pub(crate) async fn delete_clipboard_entry(id: String) -> Result<(), String> {
+    delete_entry(&id)
+}
+
+/// Remove all the unpinned entries.
+#[tauri::command]
+pub(crate) async fn clear_clipboard_history() -> Result<(), String> {
+    with_history(|history| {
+        history.clear();
+        history.save()
+    })
+}
+
+#[tauri::command]
+pub(crate) async fn paste_clipboard_entry(
+    tauri_app_handle: AppHandle,
+    id: String,
+) -> Result<(), String> {
+    paste_entry(&tauri_app_handle, &id).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ops::Range;
+
+    fn entry(content: ClipboardContent) -> ClipboardEntry {
+        ClipboardEntry {
+            id: "1".into(),
+            content,
+            source_app: Some("Terminal".into()),
+            copied_at: 0,
+            pinned: false,
+        }
+    }
+
+    #[test]
+    fn test_text_content_detects_file_lists() {
+        assert_eq!(
+            text_content("file:///tmp/a.txt\nfile:///tmp/b%20c.txt".into()),
+            ClipboardContent::Files {
+                paths: vec!["/tmp/a.txt".into(), "/tmp/b c.txt".into()]
+            }
+        );
+        assert_eq!(
+            text_content("copy\nfile:///tmp/a.txt".into()),
+            ClipboardContent::Files {
+                paths: vec!["/tmp/a.txt".into()]
+            }
+        );
+        assert_eq!(
+            text_content("see file:///tmp/a.txt".into()),
+            ClipboardContent::Text {
+                text: "see file:///tmp/a.txt".into()
+            }
+        );
+        assert_eq!(
+            text_content("https://example.com".into()),
+            ClipboardContent::Text {
+                text: "https://example.com".into()
+            }
+        );
+    }
+
+    #[test]
+    fn test_entry_title() {
+        let text = entry(ClipboardContent::Text {
+            text: "  first line\nsecond line".into(),
+        });
+        assert_eq!(entry_title(&text), "first line…");
+
+        let files = entry(ClipboardContent::Files {
+            paths: vec!["/tmp/a.txt".into(), "/tmp/b.txt".into()],
+        });
+        assert_eq!(entry_title(&files), "a.txt, b.txt");
+    }
+
+    #[test]
+    fn test_next_poll_interval() {
+        let mut poll_interval = POLL_INTERVAL;
+        for _ in 0..10 {
+            poll_interval = next_poll_interval(poll_interval, true);
+        }
+        assert_eq!(poll_interval, MAX_POLL_INTERVAL);
+        assert_eq!(next_poll_interval(poll_interval, false), POLL_INTERVAL);
+    }
+
+    #[test]
+    fn test_is_from_ignored_app() {
+        let config = ClipboardHistoryConfig::default();
+        assert!(!config.ignored_apps.is_empty());
+
+        let ignored = vec!["keepassxc".to_string()];
+        assert!(is_from_ignored_app(&config, Some(&ignored)));
+        let not_ignored = vec!["Terminal".to_string()];
+        assert!(!is_from_ignored_app(&config, Some(&not_ignored)));
+        // Unknown applications, e.g., on Wayland, are recorded
+        assert!(!is_from_ignored_app(&config, None));
+    }
+
+    #[test]
+    fn test_match_entry() {
+        let short_text = entry(ClipboardContent::Text {
+            text: "cargo build --release".into(),
+        });
+        let (score, ranges) = match_entry("cargo", &short_text).unwrap();
+        assert!(score > SUBSTRING_MATCH_SCORE);
+        assert_eq!(ranges, Some(vec![Range { start: 0, end: 5 }]));
+
+        // Ranges are within the truncated title
+        let text = format!("{}cargo", "-".repeat(TITLE_MAX_CHARS - 2));
+        let truncated_text = entry(ClipboardContent::Text { text });
+        let (_, ranges) = match_entry("cargo", &truncated_text).unwrap();
+        assert_eq!(
+            ranges,
+            Some(vec![Range {
+                start: TITLE_MAX_CHARS - 2,
+                end: TITLE_MAX_CHARS
+            }])
+        );
+
+        // Multi-line texts are matched by substring
+        let long_text = entry(ClipboardContent::Text {
+            text: "fn main() {\n    println!(\"hello\");\n}".into(),
+        });
+        let (score, ranges) = match_entry("PRINTLN", &long_text).unwrap();
+        assert_eq!(score, SUBSTRING_MATCH_SCORE);
+        assert!(ranges.is_none());
+        assert!(match_entry("prntln", &long_text).is_none());
+
+        // Matched by the source application
+        let (score, _) = match_entry("terminal", &short_text).unwrap();
+        assert!(score <= SOURCE_APP_MATCH_WEIGHT);
+
+        let image = entry(ClipboardContent::Image {
+            width: 1,
+            height: 1,
+            hash: 0,
+        });
+        assert!(match_entry("image", &image).is_some());
+    }
+}
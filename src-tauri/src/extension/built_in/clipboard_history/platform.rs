@@ -0,0 +1,184 @@
+//! Platform-specific parts: finding out which application copied, and if the
+//! clipboard has changed or is marked as concealed.
+
+/// The application that is currently frontmost, as its names, the first one
+/// is the display name.  These are matched against the ignore list.
+///
+/// `None` if it cannot be found, e.g., on Wayland, where there is no portable
+/// way to get the focused window.
+#[cfg(target_os = "macos")]
+pub(super) fn frontmost_app() -> Option<Vec<String>> {
+    use objc2_app_kit::NSWorkspace;
+
+    let workspace = unsafe { NSWorkspace::sharedWorkspace() };
+    let frontmost_app = unsafe { workspace.frontmostApplication() }?;
+
+    let mut names = Vec::new();
+    if let Some(name) = unsafe { frontmost_app.localizedName() } {
+        names.push(name.to_string());
+    }
+    if let Some(bundle_id) = unsafe { frontmost_app.bundleIdentifier() } {
+        names.push(bundle_id.to_string());
+    }
+
+    (!names.is_empty()).then_some(names)
+}
+
+/// Only X11 is supported, via `xdotool`.
+#[cfg(target_os = "linux")]
+pub(super) fn frontmost_app() -> Option<Vec<String>> {
+    let output = std::process::Command::new("xdotool")
+        .args(["getactivewindow", "getwindowpid"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let pid = String::from_utf8_lossy(&output.stdout).trim().to_string();
+
+    let mut names = Vec::new();
+    if let Ok(comm) = std::fs::read_to_string(format!("/proc/{}/comm", pid)) {
+        names.push(comm.trim().to_string());
+    }
+    if let Ok(exe) = std::fs::read_link(format!("/proc/{}/exe", pid))
+        && let Some(exe_name) = exe.file_name()
+    {
+        names.push(exe_name.to_string_lossy().into_owned());
+    }
+
+    (!names.is_empty()).then_some(names)
+}
+
+#[cfg(target_os = "windows")]
+pub(super) fn frontmost_app() -> Option<Vec<String>> {
+    use std::path::Path;
+    use windows::Win32::Foundation::CloseHandle;
+    use windows::Win32::System::Threading::{
+        OpenProcess, PROCESS_NAME_WIN32, PROCESS_QUERY_LIMITED_INFORMATION,
+        QueryFullProcessImageNameW,
+    };
+    use windows::Win32::UI::WindowsAndMessaging::{GetForegroundWindow, GetWindowThreadProcessId};
+    use windows::core::PWSTR;
+
+    let mut pid = 0_u32;
+    unsafe { GetWindowThreadProcessId(GetForegroundWindow(), Some(&mut pid)) };
+    if pid == 0 {
+        return None;
+    }
+
+    let process = unsafe { OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid) }.ok()?;
+    let mut buf = [0_u16; 1024];
+    let mut len = buf.len() as u32;
+    let result = unsafe {
+        QueryFullProcessImageNameW(
+            process,
+            PROCESS_NAME_WIN32,
+            PWSTR(buf.as_mut_ptr()),
+            &mut len,
+        )
+    };
+    let _ = unsafe { CloseHandle(process) };
+    result.ok()?;
+
+    let exe_path = String::from_utf16_lossy(&buf[..len as usize]);
+    let exe_path = Path::new(&exe_path);
+    let names = [exe_path.file_stem(), exe_path.file_name()]
+        .into_iter()
+        .flatten()
+        .map(|name| name.to_string_lossy().into_owned())
+        .collect();
+
+    Some(names)
+}
+
+/// A counter that changes whenever the clipboard changes, so that the
+/// clipboard won't be read if it has not changed.
+///
+/// `None` if the platform does not have one, then the clipboard has to be
+/// read to find out.
+#[cfg(target_os = "macos")]
+pub(super) fn change_count() -> Option<i64> {
+    use objc2_app_kit::NSPasteboard;
+
+    let pasteboard = unsafe { NSPasteboard::generalPasteboard() };
+    Some(unsafe { pasteboard.changeCount() } as i64)
+}
+
+#[cfg(target_os = "linux")]
+pub(super) fn change_count() -> Option<i64> {
+    None
+}
+
+#[cfg(target_os = "windows")]
+pub(super) fn change_count() -> Option<i64> {
+    use windows::Win32::System::DataExchange::GetClipboardSequenceNumber;
+
+    Some(i64::from(unsafe { GetClipboardSequenceNumber() }))
+}
+
+/// Return true if the application that copied marked the content as
+/// concealed, i.e., a password, or transient, see <http://nspasteboard.org>.
+#[cfg(target_os = "macos")]
+pub(super) fn is_concealed() -> bool {
+    use objc2_app_kit::NSPasteboard;
+
+    const CONCEALED_TYPES: &[&str] = &[
+        "org.nspasteboard.ConcealedType",
+        "org.nspasteboard.TransientType",
+        "org.nspasteboard.AutoGeneratedType",
+    ];
+
+    let pasteboard = unsafe { NSPasteboard::generalPasteboard() };
+    let Some(types) = (unsafe { pasteboard.types() }) else {
+        return false;
+    };
+
+    types
+        .iter()
+        .any(|r#type| CONCEALED_TYPES.contains(&r#type.to_string().as_str()))
+}
+
+/// Password managers (e.g., KeePassXC) offer the `x-kde-passwordManagerHint`
+/// target, the targets are listed with `wl-paste` on Wayland, and `xclip` on
+/// X11.
+#[cfg(target_os = "linux")]
+pub(super) fn is_concealed() -> bool {
+    const PASSWORD_MANAGER_HINT: &str = "x-kde-passwordManagerHint";
+
+    let mut command = if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+        let mut command = std::process::Command::new("wl-paste");
+        command.arg("--list-types");
+        command
+    } else {
+        let mut command = std::process::Command::new("xclip");
+        command.args(["-selection", "clipboard", "-target", "TARGETS", "-out"]);
+        command
+    };
+
+    match command.output() {
+        Ok(output) if output.status.success() => String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .any(|target| target.trim() == PASSWORD_MANAGER_HINT),
+        _ => false,
+    }
+}
+
+/// Applications exclude their content from clipboard managers by putting
+/// these formats on the clipboard.
+#[cfg(target_os = "windows")]
+pub(super) fn is_concealed() -> bool {
+    use windows::Win32::System::DataExchange::{
+        IsClipboardFormatAvailable, RegisterClipboardFormatW,
+    };
+    use windows::core::w;
+
+    [
+        w!("ExcludeClipboardContentFromMonitorProcessing"),
+        w!("Clipboard Viewer Ignore"),
+    ]
+    .into_iter()
+    .any(|format_name| {
+        let format = unsafe { RegisterClipboardFormatW(format_name) };
+        format != 0 && unsafe { IsClipboardFormatAvailable(format) }.is_ok()
+    })
+}
@@ -0,0 +1,465 @@
+//! On-disk clipboard history.
+//!
+//! Layout of the history directory:
+//!
+//! * `history.json` or `history.enc`: the entries, newest first
+//! * `images/<entry ID>`: RGBA pixels of the image entries
+//!
+//! When encryption is enabled, every file is encrypted with ChaCha20-Poly1305,
+//! stored as the 12-byte nonce followed by the ciphertext.
+
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+const HISTORY_FILE: &str = "history.json";
+const ENCRYPTED_HISTORY_FILE: &str = "history.enc";
+const IMAGES_DIR: &str = "images";
+const NONCE_LEN: usize = 12;
+
+/// The encryption key is stored in the OS keyring under this service and user.
+const KEYRING_SERVICE: &str = "coco-clipboard-history";
+const KEYRING_USER: &str = "encryption-key";
+
+pub(crate) type EncryptionKey = Key;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub(crate) enum ClipboardContent {
+    Text {
+        text: String,
+    },
+    /// The pixels are stored in a separate file as they can be large.
+    Image {
+        width: u32,
+        height: u32,
+        /// FNV-1a hash of the pixels, to tell if 2 images are the same
+        /// without reading the files.
+        hash: u64,
+    },
+    Files {
+        paths: Vec<String>,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub(crate) struct ClipboardEntry {
+    pub(crate) id: String,
+    pub(crate) content: ClipboardContent,
+    /// Name of the application that was frontmost when it was copied.
+    pub(crate) source_app: Option<String>,
+    /// Unix timestamp in milliseconds, updated when the same content is
+    /// copied again.
+    pub(crate) copied_at: i64,
+    /// Pinned entries are exempt from the retention limits.
+    pub(crate) pinned: bool,
+}
+
+pub(crate) struct ClipboardHistory {
+    dir: PathBuf,
+    key: Option<EncryptionKey>,
+    /// Newest first.
+    entries: Vec<ClipboardEntry>,
+}
+
+impl ClipboardHistory {
+    /// Load the history in `dir`, which is decrypted with `key` if specified.
+    pub(crate) fn open(dir: &Path, key: Option<EncryptionKey>) -> Result<Self, String> {
+        std::fs::create_dir_all(dir.join(IMAGES_DIR))
+            .map_err(|e| format!("failed to create [{}], error [{}]", dir.display(), e))?;
+
+        let mut history = Self {
+            dir: dir.to_path_buf(),
+            key,
+            entries: Vec::new(),
+        };
+        let history_file = history.history_file();
+        if history_file.is_file() {
+            let content = history.read_file(&history_file)?;
+            history.entries = serde_json::from_slice(&content).map_err(|e| {
+                format!(
+                    "clipboard history [{}] is corrupted, error [{}]",
+                    history_file.display(),
+                    e
+                )
+            })?;
+        }
+
+        Ok(history)
+    }
+
+    pub(crate) fn entries(&self) -> &[ClipboardEntry] {
+        &self.entries
+    }
+
+    pub(crate) fn get(&self, id: &str) -> Option<&ClipboardEntry> {
+        self.entries.iter().find(|entry| entry.id == id)
+    }
+
+    /// Record `content` copied at `now`, `image_pixels` should be set for
+    /// image content.  If the same content is already in the history, it is
+    /// moved to the front instead.
+    pub(crate) fn record(
+        &mut self,
+        content: ClipboardContent,
+        image_pixels: Option<&[u8]>,
+        source_app: Option<String>,
+        now: i64,
+    ) -> Result<(), String> {
+        if let Some(idx) = self.entries.iter().position(|e| e.content == content) {
+            let mut entry = self.entries.remove(idx);
+            entry.copied_at = now;
+            entry.source_app = source_app;
+            self.entries.insert(0, entry);
+            return Ok(());
+        }
+
+        static COUNTER: AtomicU64 = AtomicU64::new(0);
+        let id = format!("{}-{}", now, COUNTER.fetch_add(1, Ordering::Relaxed));
+        if let Some(pixels) = image_pixels {
+            self.write_file(&self.image_file(&id), pixels)?;
+        }
+        self.entries.insert(
+            0,
+            ClipboardEntry {
+                id,
+                content,
+                source_app,
+                copied_at: now,
+                pinned: false,
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Drop the unpinned entries exceeding `max_entries` or older than
+    /// `retention`.
+    pub(crate) fn apply_retention(
+        &mut self,
+        max_entries: usize,
+        retention: Option<Duration>,
+        now: i64,
+    ) {
+        let oldest_allowed = retention.map(|retention| now - retention.as_millis() as i64);
+        let mut n_unpinned = 0;
+        let mut removed = Vec::new();
+        self.entries.retain(|entry| {
+            if entry.pinned {
+                return true;
+            }
+            n_unpinned += 1;
+            let keep = n_unpinned <= max_entries
+                && oldest_allowed.is_none_or(|oldest| entry.copied_at >= oldest);
+            if !keep {
+                removed.push(entry.id.clone());
+            }
+            keep
+        });
+
+        for id in removed {
+            self.remove_image_file(&id);
+        }
+    }
+
+    /// Return false if the entry does not exist.
+    pub(crate) fn set_pinned(&mut self, id: &str, pinned: bool) -> bool {
+        match self.entries.iter_mut().find(|entry| entry.id == id) {
+            Some(entry) => {
+                entry.pinned = pinned;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Return false if the entry does not exist.
+    pub(crate) fn remove(&mut self, id: &str) -> bool {
+        let n_entries = self.entries.len();
+        self.entries.retain(|entry| entry.id != id);
+        if self.entries.len() == n_entries {
+            return false;
+        }
+        self.remove_image_file(id);
+
+        true
+    }
+
+    /// Remove all the unpinned entries.
+    pub(crate) fn clear(&mut self) {
+        self.apply_retention(0, None, 0);
+    }
+
+    pub(crate) fn read_image(&self, id: &str) -> Result<Vec<u8>, String> {
+        self.read_file(&self.image_file(id))
+    }
+
+    /// Write the entries to disk.
+    pub(crate) fn save(&self) -> Result<(), String> {
+        let content = serde_json::to_vec(&self.entries).expect("entries should be serializable");
+        self.write_file(&self.history_file(), &content)
+    }
+
+    /// Re-encrypt the history with `key`, or decrypt it if `key` is `None`.
+    pub(crate) fn set_key(&mut self, key: Option<EncryptionKey>) -> Result<(), String> {
+        let old_history_file = self.history_file();
+        let mut images = Vec::new();
+        for entry in self.entries.iter() {
+            if matches!(entry.content, ClipboardContent::Image { .. }) {
+                images.push((entry.id.clone(), self.read_image(&entry.id)?));
+            }
+        }
+
+        self.key = key;
+        for (id, pixels) in images {
+            self.write_file(&self.image_file(&id), &pixels)?;
+        }
+        self.save()?;
+        if old_history_file != self.history_file() {
+            let _ = std::fs::remove_file(old_history_file);
+        }
+
+        Ok(())
+    }
+
+    fn history_file(&self) -> PathBuf {
+        match self.key {
+            Some(_) => self.dir.join(ENCRYPTED_HISTORY_FILE),
+            None => self.dir.join(HISTORY_FILE),
+        }
+    }
+
+    fn image_file(&self, id: &str) -> PathBuf {
+        self.dir.join(IMAGES_DIR).join(id)
+    }
+
+    fn remove_image_file(&self, id: &str) {
+        let image_file = self.image_file(id);
+        if image_file.exists()
+            && let Err(e) = std::fs::remove_file(&image_file)
+        {
+            log::warn!(
+                "failed to remove clipboard image [{}], error [{}]",
+                image_file.display(),
+                e
+            );
+        }
+    }
+
+    fn read_file(&self, path: &Path) -> Result<Vec<u8>, String> {
+        let content = std::fs::read(path)
+            .map_err(|e| format!("failed to read [{}], error [{}]", path.display(), e))?;
+        match self.key {
+            Some(ref key) => decrypt(key, &content)
+                .map_err(|e| format!("failed to decrypt [{}], {}", path.display(), e)),
+            None => Ok(content),
+        }
+    }
+
+    /// Write atomically so that a crash won't leave a truncated file.
+    fn write_file(&self, path: &Path, content: &[u8]) -> Result<(), String> {
+        let encrypted;
+        let content = match self.key {
+            Some(ref key) => {
+                encrypted = encrypt(key, content);
+                encrypted.as_slice()
+            }
+            None => content,
+        };
+
+        let tmp_path = path.with_extension("tmp");
+        std::fs::write(&tmp_path, content)
+            .and_then(|()| std::fs::rename(&tmp_path, path))
+            .map_err(|e| format!("failed to write [{}], error [{}]", path.display(), e))
+    }
+}
+
+fn encrypt(key: &EncryptionKey, plaintext: &[u8]) -> Vec<u8> {
+    let cipher = ChaCha20Poly1305::new(key);
+    let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, plaintext)
+        .expect("encryption should not fail");
+
+    let mut output = Vec::with_capacity(NONCE_LEN + ciphertext.len());
+    output.extend_from_slice(&nonce);
+    output.extend_from_slice(&ciphertext);
+    output
+}
+
+fn decrypt(key: &EncryptionKey, content: &[u8]) -> Result<Vec<u8>, String> {
+    if content.len() < NONCE_LEN {
+        return Err("content is too short".into());
+    }
+    let (nonce, ciphertext) = content.split_at(NONCE_LEN);
+
+    ChaCha20Poly1305::new(key)
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| "wrong key or corrupted content".to_string())
+}
+
+/// Load the encryption key from the OS keyring, generate one if it does not
+/// exist.
+pub(crate) fn load_or_create_key() -> Result<EncryptionKey, String> {
+    let entry = keyring::Entry::new(KEYRING_SERVICE, KEYRING_USER)
+        .map_err(|e| format!("failed to access the keyring, error [{}]", e))?;
+
+    match entry.get_secret() {
+        Ok(secret) => Key::from_exact_iter(secret)
+            .ok_or_else(|| "the clipboard history key in the keyring is malformed".to_string()),
+        Err(keyring::Error::NoEntry) => {
+            let key = ChaCha20Poly1305::generate_key(&mut OsRng);
+            entry
+                .set_secret(&key)
+                .map_err(|e| format!("failed to store the key in the keyring, error [{}]", e))?;
+            Ok(key)
+        }
+        Err(e) => Err(format!("failed to read the keyring, error [{}]", e)),
+    }
+}
+
+/// FNV-1a, stable across Rust versions unlike `DefaultHasher`, as the hashes
+/// are persisted.
+pub(crate) fn fnv1a_hash(bytes: &[u8]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+    const PRIME: u64 = 0x100000001b3;
+
+    bytes.iter().fold(OFFSET_BASIS, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(PRIME)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(text: &str) -> ClipboardContent {
+        ClipboardContent::Text { text: text.into() }
+    }
+
+    #[test]
+    fn test_record_dedups_and_orders_newest_first() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut history = ClipboardHistory::open(dir.path(), None).unwrap();
+
+        history.record(text("a"), None, None, 1).unwrap();
+        history.record(text("b"), None, None, 2).unwrap();
+        history
+            .record(text("a"), None, Some("Terminal".into()), 3)
+            .unwrap();
+
+        let entries = history.entries();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].content, text("a"));
+        assert_eq!(entries[0].copied_at, 3);
+        assert_eq!(entries[0].source_app.as_deref(), Some("Terminal"));
+        assert_eq!(entries[1].content, text("b"));
+    }
+
+    #[test]
+    fn test_retention_keeps_pinned_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut history = ClipboardHistory::open(dir.path(), None).unwrap();
+        for i in 0..5 {
+            history
+                .record(text(&i.to_string()), None, None, i * 1000)
+                .unwrap();
+        }
+        let oldest_id = history.entries()[4].id.clone();
+        assert!(history.set_pinned(&oldest_id, true));
+
+        // Keeps 2 unpinned entries, and the pinned one
+        history.apply_retention(2, None, 4000);
+        let texts: Vec<_> = history
+            .entries()
+            .iter()
+            .map(|e| e.content.clone())
+            .collect();
+        assert_eq!(texts, vec![text("4"), text("3"), text("0")]);
+
+        // Entries older than 500ms
+        history.apply_retention(10, Some(Duration::from_millis(500)), 4000);
+        let texts: Vec<_> = history
+            .entries()
+            .iter()
+            .map(|e| e.content.clone())
+            .collect();
+        assert_eq!(texts, vec![text("4"), text("0")]);
+
+        history.clear();
+        assert_eq!(history.entries().len(), 1);
+        assert!(history.entries()[0].pinned);
+    }
+
+    #[test]
+    fn test_persistence_and_images() {
+        let dir = tempfile::tempdir().unwrap();
+        let pixels = vec![255_u8; 2 * 2 * 4];
+        let image = ClipboardContent::Image {
+            width: 2,
+            height: 2,
+            hash: fnv1a_hash(&pixels),
+        };
+
+        let mut history = ClipboardHistory::open(dir.path(), None).unwrap();
+        history.record(text("a"), None, None, 1).unwrap();
+        history
+            .record(image.clone(), Some(&pixels), None, 2)
+            .unwrap();
+        history.save().unwrap();
+
+        let history = ClipboardHistory::open(dir.path(), None).unwrap();
+        assert_eq!(history.entries().len(), 2);
+        let image_id = history.entries()[0].id.clone();
+        assert_eq!(history.entries()[0].content, image);
+        assert_eq!(history.read_image(&image_id).unwrap(), pixels);
+
+        let mut history = history;
+        assert!(history.remove(&image_id));
+        assert!(!history.remove(&image_id));
+        assert!(history.read_image(&image_id).is_err());
+    }
+
+    #[test]
+    fn test_encryption() {
+        let dir = tempfile::tempdir().unwrap();
+        let key = ChaCha20Poly1305::generate_key(&mut OsRng);
+        let pixels = vec![7_u8; 4];
+
+        let mut history = ClipboardHistory::open(dir.path(), None).unwrap();
+        history.record(text("secret"), None, None, 1).unwrap();
+        history
+            .record(
+                ClipboardContent::Image {
+                    width: 1,
+                    height: 1,
+                    hash: fnv1a_hash(&pixels),
+                },
+                Some(&pixels),
+                None,
+                2,
+            )
+            .unwrap();
+        let image_id = history.entries()[0].id.clone();
+        history.save().unwrap();
+
+        history.set_key(Some(key)).unwrap();
+        assert!(!dir.path().join(HISTORY_FILE).exists());
+        let on_disk = std::fs::read(dir.path().join(ENCRYPTED_HISTORY_FILE)).unwrap();
+        assert!(!String::from_utf8_lossy(&on_disk).contains("secret"));
+        assert_ne!(
+            std::fs::read(dir.path().join(IMAGES_DIR).join(&image_id)).unwrap(),
+            pixels
+        );
+
+        let history = ClipboardHistory::open(dir.path(), Some(key)).unwrap();
+        assert_eq!(history.entries()[1].content, text("secret"));
+        assert_eq!(history.read_image(&image_id).unwrap(), pixels);
+
+        let wrong_key = ChaCha20Poly1305::generate_key(&mut OsRng);
+        assert!(ClipboardHistory::open(dir.path(), Some(wrong_key)).is_err());
+    }
+}
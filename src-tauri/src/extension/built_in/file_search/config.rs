@@ -1,6 +1,7 @@
 //! File Search configuration entries definition and getter/setter functions.
 
 use crate::extension::built_in::file_search::implementation::apply_config;
+use crate::extension::built_in::file_search::pattern::{InvalidPattern, validate};
 use serde::Deserialize;
 use serde::Serialize;
 use serde_json::Value;
@@ -14,6 +15,7 @@ const TAURI_STORE_KEY_SEARCH_BY: &str = "search_by";
 const TAURI_STORE_KEY_SEARCH_PATHS: &str = "search_paths";
 const TAURI_STORE_KEY_EXCLUDE_PATHS: &str = "exclude_paths";
 const TAURI_STORE_KEY_FILE_TYPES: &str = "file_types";
+const TAURI_STORE_KEY_LINUX_BACKEND: &str = "linux_backend";
 
 static HOME_DIR: LazyLock<String> = LazyLock::new(|| {
     let os_string = dirs::home_dir()
@@ -30,12 +32,30 @@ pub enum SearchBy {
     NameAndContents,
 }
 
+/// The backend that file search uses on Linux.  It is ignored on other
+/// platforms.
+#[derive(Debug, Clone, Serialize, Deserialize, Copy, PartialEq, Default)]
+pub enum LinuxBackend {
+    /// The indexer of the desktop environment, i.e., Tracker on GNOME and
+    /// Baloo on KDE.  On other desktop environments, the plocate/mlocate
+    /// database if it exists, otherwise the built-in indexer.
+    #[default]
+    Auto,
+    /// The plocate/mlocate database
+    Locate,
+    /// The built-in indexer
+    BuiltInIndexer,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FileSearchConfig {
     pub search_paths: Vec<String>,
     pub exclude_paths: Vec<String>,
     pub file_types: Vec<String>,
     pub search_by: SearchBy,
+    /// Configs that were saved before this option was added don't have it.
+    #[serde(default)]
+    pub linux_backend: LinuxBackend,
 }
 
 impl Default for FileSearchConfig {
@@ -49,6 +69,7 @@ impl Default for FileSearchConfig {
             exclude_paths: Vec::new(),
             file_types: Vec::new(),
             search_by: SearchBy::Name,
+            linux_backend: LinuxBackend::default(),
         }
     }
 }
@@ -174,11 +195,29 @@ impl FileSearchConfig {
             }
         };
 
+        let linux_backend = {
+            if let Some(linux_backend) = store.get(TAURI_STORE_KEY_LINUX_BACKEND) {
+                serde_json::from_value(linux_backend.clone()).unwrap_or_else(|e| {
+                    panic!(
+                        "Failed to deserialize 'linux_backend' from file system config store. Invalid JSON: {:?}, error: {}",
+                        linux_backend, e
+                    )
+                })
+            } else {
+                store.set(
+                    TAURI_STORE_KEY_LINUX_BACKEND,
+                    serde_json::to_value(default_config.linux_backend).unwrap(),
+                );
+                default_config.linux_backend
+            }
+        };
+
         Self {
             search_by,
             search_paths,
             exclude_paths,
             file_types,
+            linux_backend,
         }
     }
 }
@@ -194,6 +233,15 @@ pub async fn set_file_system_config(
     tauri_app_handle: AppHandle,
     config: FileSearchConfig,
 ) -> Result<(), String> {
+    let invalid_patterns = validate(&config.exclude_paths, &config.file_types);
+    if !invalid_patterns.is_empty() {
+        let errors: Vec<String> = invalid_patterns
+            .iter()
+            .map(|invalid| format!("[{}]: {}", invalid.pattern, invalid.error))
+            .collect();
+        return Err(format!("invalid patterns {}", errors.join(", ")));
+    }
+
     let store = tauri_app_handle
         .store(TAURI_STORE_FILE_SYSTEM_CONFIG)
         .map_err(|e| e.to_string())?;
@@ -208,9 +256,23 @@ pub async fn set_file_system_config(
         TAURI_STORE_KEY_SEARCH_BY,
         serde_json::to_value(config.search_by).unwrap(),
     );
+    store.set(
+        TAURI_STORE_KEY_LINUX_BACKEND,
+        serde_json::to_value(config.linux_backend).unwrap(),
+    );
 
     // Apply the config when we know that this set operation won't fail
     apply_config(&config)?;
 
     Ok(())
 }
+
+/// Return the invalid entries of `exclude_paths` and `file_types`, so that
+/// the settings page can highlight them before saving.
+#[tauri::command]
+pub async fn validate_file_search_patterns(
+    exclude_paths: Vec<String>,
+    file_types: Vec<String>,
+) -> Vec<InvalidPattern> {
+    validate(&exclude_paths, &file_types)
+}
@@ -0,0 +1,626 @@
+//! Metadata filters in file search queries.
+//!
+//! Besides the text to search for, a query can contain these filters:
+//!
+//! * `modified:<7d`, `modified:>=2024-01-01`: last modification time.  Ages
+//!   are in `h`ours, `d`ays, `w`eeks, `mo`nths or `y`ears, `<7d` means
+//!   modified less than 7 days ago.  Dates are in local time, a date without
+//!   comparison operator, e.g., `modified:2024-01-01`, matches that day.
+//! * `size:>10MB`: file size in `B`, `KB`, `MB`, `GB` or `TB`, 1KB is 1024
+//!   bytes.  Directories never match size filters.
+//! * `kind:pdf`, `kind:images,docx`: extensions, or file type groups, see
+//!   [`FILE_TYPE_GROUPS`](super::pattern::FILE_TYPE_GROUPS).
+//! * `in:~/Projects`: files in this directory.  Paths containing spaces
+//!   should be quoted, e.g., `in:"~/My Documents"`.
+//!
+//! Comparison operators are `<`, `<=`, `>`, `>=` and `=`.  Multiple `kind:`
+//! or `in:` filters match any of them, other filters should all match.
+//! Invalid filters are searched as text.  A query with filters only, e.g.,
+//! `kind:pdf modified:<7d`, matches all the files that pass them.
+
+use super::pattern::matches_file_types;
+use crate::common::document::Document;
+use camino::Utf8Path;
+use chrono::{DateTime, Local, NaiveDate};
+use std::fs::Metadata;
+use std::time::UNIX_EPOCH;
+
+const SECS_PER_HOUR: i64 = 60 * 60;
+const SECS_PER_DAY: i64 = 24 * SECS_PER_HOUR;
+
+/// Inclusive bounds of a value.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub(crate) struct Bounds<T> {
+    pub(crate) min: Option<T>,
+    pub(crate) max: Option<T>,
+}
+
+impl<T: PartialOrd + Copy> Bounds<T> {
+    fn contains(&self, value: T) -> bool {
+        self.min.is_none_or(|min| value >= min) && self.max.is_none_or(|max| value <= max)
+    }
+
+    /// Narrow `self` so that it only contains the values in both bounds.
+    fn intersect(&mut self, other: Self) {
+        if let Some(other_min) = other.min
+            && self.min.is_none_or(|min| other_min > min)
+        {
+            self.min = Some(other_min);
+        }
+        if let Some(other_max) = other.max
+            && self.max.is_none_or(|max| other_max < max)
+        {
+            self.max = Some(other_max);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct QueryFilters {
+    /// Last modification time, Unix timestamp in seconds
+    pub(crate) modified: Option<Bounds<i64>>,
+    /// In bytes
+    pub(crate) size: Option<Bounds<u64>>,
+    /// Lowercased extensions or file type groups
+    pub(crate) kinds: Vec<String>,
+    /// Absolute paths of directories
+    pub(crate) paths: Vec<String>,
+}
+
+/// A query split into the text to search for, and the filters.
+#[derive(Debug, PartialEq)]
+pub(crate) struct ParsedQuery {
+    pub(crate) text: String,
+    pub(crate) filters: QueryFilters,
+}
+
+/// Metadata that filters need.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct FileMetadata {
+    pub(crate) is_dir: bool,
+    /// In bytes
+    pub(crate) size: u64,
+    /// Last modification time, Unix timestamp in seconds
+    pub(crate) modified: i64,
+}
+
+impl From<&Metadata> for FileMetadata {
+    fn from(metadata: &Metadata) -> Self {
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |duration| duration.as_secs() as i64);
+
+        Self {
+            is_dir: metadata.is_dir(),
+            size: metadata.len(),
+            modified,
+        }
+    }
+}
+
+impl FileMetadata {
+    /// `None` if the metadata of the file at `path` cannot be read, e.g., it
+    /// has been removed since it got indexed.
+    pub(crate) fn read(path: &str) -> Option<Self> {
+        std::fs::metadata(path)
+            .ok()
+            .map(|metadata| Self::from(&metadata))
+    }
+
+    /// Fill in the `size` and `updated` fields of `document`, the document of
+    /// this file.
+    pub(crate) fn fill_document(&self, document: &mut Document) {
+        if !self.is_dir {
+            document.size = i64::try_from(self.size).ok();
+        }
+        document.updated = DateTime::from_timestamp(self.modified, 0)
+            .map(|modified| modified.with_timezone(&Local).to_rfc3339());
+    }
+}
+
+impl QueryFilters {
+    pub(crate) fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// Return true if the file at `path`, with `metadata`, matches all the
+    /// filters.
+    pub(crate) fn matches(&self, path: &str, metadata: &FileMetadata) -> bool {
+        let path = Utf8Path::new(path);
+
+        if !self.paths.is_empty()
+            && !self
+                .paths
+                .iter()
+                .any(|filter_path| path.starts_with(filter_path))
+        {
+            return false;
+        }
+
+        if !self.kinds.is_empty() {
+            let matches_kind = !metadata.is_dir
+                && path
+                    .extension()
+                    .is_some_and(|extension| matches_file_types(&self.kinds, extension));
+            if !matches_kind {
+                return false;
+            }
+        }
+
+        if let Some(size) = self.size
+            && (metadata.is_dir || !size.contains(metadata.size))
+        {
+            return false;
+        }
+
+        if let Some(modified) = self.modified
+            && !modified.contains(metadata.modified)
+        {
+            return false;
+        }
+
+        true
+    }
+
+    /// Read the metadata of the file at `path`, and return true if it matches
+    /// all the filters.
+    pub(crate) fn matches_file(&self, path: &str) -> bool {
+        if self.is_empty() {
+            return true;
+        }
+
+        FileMetadata::read(path).is_some_and(|metadata| self.matches(path, &metadata))
+    }
+
+    /// Return the directories that are in both `search_paths` and the `in:`
+    /// filters, i.e., the directories that should be searched.
+    pub(crate) fn narrow_search_paths(&self, search_paths: &[String]) -> Vec<String> {
+        if self.paths.is_empty() {
+            return search_paths.to_vec();
+        }
+
+        let mut narrowed = Vec::new();
+        for search_path in search_paths {
+            for filter_path in self.paths.iter() {
+                let narrower = if Utf8Path::new(filter_path).starts_with(search_path) {
+                    filter_path
+                } else if Utf8Path::new(search_path).starts_with(filter_path) {
+                    search_path
+                } else {
+                    continue;
+                };
+                if !narrowed.contains(narrower) {
+                    narrowed.push(narrower.clone());
+                }
+            }
+        }
+
+        narrowed
+    }
+}
+
+/// Split `query` at whitespace, except the whitespace in double quotes.
+fn split_terms(query: &str) -> Vec<&str> {
+    let mut terms = Vec::new();
+    let mut opt_term_start = None;
+    let mut in_quotes = false;
+
+    for (idx, char) in query.char_indices() {
+        if char == '"' {
+            in_quotes = !in_quotes;
+        }
+        if char.is_whitespace() && !in_quotes {
+            if let Some(term_start) = opt_term_start.take() {
+                terms.push(&query[term_start..idx]);
+            }
+        } else {
+            opt_term_start.get_or_insert(idx);
+        }
+    }
+    if let Some(term_start) = opt_term_start {
+        terms.push(&query[term_start..]);
+    }
+
+    terms
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Comparison {
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    Equal,
+}
+
+/// Split the comparison operator from `value`, no operator means equal.
+fn split_comparison(value: &str) -> (Comparison, &str) {
+    const OPERATORS: [(&str, Comparison); 5] = [
+        ("<=", Comparison::LessOrEqual),
+        (">=", Comparison::GreaterOrEqual),
+        ("<", Comparison::Less),
+        (">", Comparison::Greater),
+        ("=", Comparison::Equal),
+    ];
+
+    for (operator, comparison) in OPERATORS {
+        if let Some(rest) = value.strip_prefix(operator) {
+            return (comparison, rest);
+        }
+    }
+
+    (Comparison::Equal, value)
+}
+
+/// Split `value` into the leading number and the unit after it.
+fn split_number(value: &str) -> Option<(f64, &str)> {
+    let unit_start = value
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(value.len());
+    let number = value[..unit_start].parse::<f64>().ok()?;
+
+    Some((number, &value[unit_start..]))
+}
+
+fn parse_size(value: &str) -> Option<Bounds<u64>> {
+    let (comparison, value) = split_comparison(value);
+    let (number, unit) = split_number(value)?;
+    let unit_bytes: u64 = match unit.to_ascii_lowercase().as_str() {
+        "" | "b" => 1,
+        "k" | "kb" => 1 << 10,
+        "m" | "mb" => 1 << 20,
+        "g" | "gb" => 1 << 30,
+        "t" | "tb" => 1 << 40,
+        _ => return None,
+    };
+    let size = (number * unit_bytes as f64).round() as u64;
+
+    let bounds = match comparison {
+        Comparison::Less => Bounds {
+            min: None,
+            max: Some(size.checked_sub(1)?),
+        },
+        Comparison::LessOrEqual => Bounds {
+            min: None,
+            max: Some(size),
+        },
+        Comparison::Greater => Bounds {
+            min: Some(size.saturating_add(1)),
+            max: None,
+        },
+        Comparison::GreaterOrEqual => Bounds {
+            min: Some(size),
+            max: None,
+        },
+        Comparison::Equal => Bounds {
+            min: Some(size),
+            max: Some(size),
+        },
+    };
+
+    Some(bounds)
+}
+
+/// Unix timestamp of the local midnight starting `date`.
+fn start_of_day(date: NaiveDate) -> Option<i64> {
+    date.and_hms_opt(0, 0, 0)?
+        .and_local_timezone(Local)
+        .earliest()
+        .map(|start| start.timestamp())
+}
+
+fn parse_modified(value: &str, now: DateTime<Local>) -> Option<Bounds<i64>> {
+    let (comparison, value) = split_comparison(value);
+
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        let start = start_of_day(date)?;
+        let next_start = start_of_day(date.succ_opt()?)?;
+        let bounds = match comparison {
+            Comparison::Less => Bounds {
+                min: None,
+                max: Some(start - 1),
+            },
+            Comparison::LessOrEqual => Bounds {
+                min: None,
+                max: Some(next_start - 1),
+            },
+            Comparison::Greater => Bounds {
+                min: Some(next_start),
+                max: None,
+            },
+            Comparison::GreaterOrEqual => Bounds {
+                min: Some(start),
+                max: None,
+            },
+            Comparison::Equal => Bounds {
+                min: Some(start),
+                max: Some(next_start - 1),
+            },
+        };
+
+        return Some(bounds);
+    }
+
+    let (number, unit) = split_number(value)?;
+    let unit_secs = match unit.to_ascii_lowercase().as_str() {
+        "h" => SECS_PER_HOUR,
+        "d" => SECS_PER_DAY,
+        "w" => 7 * SECS_PER_DAY,
+        "mo" => 30 * SECS_PER_DAY,
+        "y" => 365 * SECS_PER_DAY,
+        _ => return None,
+    };
+    let time = now.timestamp() - (number * unit_secs as f64).round() as i64;
+
+    // Younger files are modified later
+    let bounds = match comparison {
+        Comparison::Less | Comparison::LessOrEqual => Bounds {
+            min: Some(time),
+            max: None,
+        },
+        Comparison::Greater | Comparison::GreaterOrEqual => Bounds {
+            min: None,
+            max: Some(time),
+        },
+        // An exact age is unlikely what users want
+        Comparison::Equal => return None,
+    };
+
+    Some(bounds)
+}
+
+fn parse_kinds(value: &str) -> Option<Vec<String>> {
+    value
+        .split(',')
+        .map(|kind| {
+            let kind = kind.trim_start_matches('.').to_lowercase();
+            // Kinds are used in Tracker's SPARQL, only allow safe chars.
+            let is_valid = !kind.is_empty()
+                && kind
+                    .chars()
+                    .all(|c| c.is_alphanumeric() || matches!(c, '+' | '-' | '_'));
+            is_valid.then_some(kind)
+        })
+        .collect()
+}
+
+fn parse_path(value: &str) -> Option<String> {
+    let mut path = if value == "~" || value.starts_with("~/") {
+        let home = dirs::home_dir()?;
+        format!("{}{}", home.to_str()?, &value[1..])
+    } else {
+        value.to_string()
+    };
+    // Keep the root path
+    while path.len() > 1 && path.ends_with(['/', '\\']) {
+        path.pop();
+    }
+
+    Utf8Path::new(&path).is_absolute().then_some(path)
+}
+
+/// Parse `term` as a filter and add it to `filters`, return false if it is
+/// not a valid filter.
+fn parse_filter(term: &str, now: DateTime<Local>, filters: &mut QueryFilters) -> bool {
+    let Some((key, value)) = term.split_once(':') else {
+        return false;
+    };
+    let value = value
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .unwrap_or(value);
+    if value.is_empty() {
+        return false;
+    }
+
+    match key.to_ascii_lowercase().as_str() {
+        "modified" => {
+            let Some(bounds) = parse_modified(value, now) else {
+                return false;
+            };
+            filters.modified.get_or_insert_default().intersect(bounds);
+        }
+        "size" => {
+            let Some(bounds) = parse_size(value) else {
+                return false;
+            };
+            filters.size.get_or_insert_default().intersect(bounds);
+        }
+        "kind" => {
+            let Some(kinds) = parse_kinds(value) else {
+                return false;
+            };
+            filters.kinds.extend(kinds);
+        }
+        "in" => {
+            let Some(path) = parse_path(value) else {
+                return false;
+            };
+            filters.paths.push(path);
+        }
+        _ => return false,
+    }
+
+    true
+}
+
+/// Split `query` into the text to search for and the filters.
+pub(crate) fn parse_query(query: &str) -> ParsedQuery {
+    parse_query_at(query, Local::now())
+}
+
+fn parse_query_at(query: &str, now: DateTime<Local>) -> ParsedQuery {
+    let mut filters = QueryFilters::default();
+    let text_terms: Vec<&str> = split_terms(query)
+        .into_iter()
+        .filter(|term| !parse_filter(term, now, &mut filters))
+        .collect();
+
+    // Keep the query as is if there is no filter
+    let text = if filters.is_empty() {
+        query.trim().to_string()
+    } else {
+        text_terms.join(" ")
+    };
+
+    ParsedQuery { text, filters }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn now() -> DateTime<Local> {
+        DateTime::from_timestamp(1_700_000_000, 0)
+            .unwrap()
+            .with_timezone(&Local)
+    }
+
+    fn parse(query: &str) -> ParsedQuery {
+        parse_query_at(query, now())
+    }
+
+    #[test]
+    fn test_split_terms() {
+        assert_eq!(
+            split_terms(r#" report  in:"~/My Documents" kind:pdf "#),
+            vec!["report", r#"in:"~/My Documents""#, "kind:pdf"]
+        );
+        assert!(split_terms("   ").is_empty());
+    }
+
+    #[test]
+    fn test_parse_query_without_filters() {
+        let parsed = parse(" weekly  report ");
+        assert_eq!(parsed.text, "weekly  report");
+        assert!(parsed.filters.is_empty());
+
+        // Invalid filters are searched as text
+        let parsed = parse("size:big kind:*.pdf modified:7d note:1");
+        assert_eq!(parsed.text, "size:big kind:*.pdf modified:7d note:1");
+        assert!(parsed.filters.is_empty());
+    }
+
+    #[test]
+    fn test_parse_query() {
+        let parsed = parse("report size:>10MB kind:pdf,.DOCX modified:<7d in:/data/ in:/home/user");
+        assert_eq!(parsed.text, "report");
+        assert_eq!(
+            parsed.filters,
+            QueryFilters {
+                modified: Some(Bounds {
+                    min: Some(now().timestamp() - 7 * SECS_PER_DAY),
+                    max: None,
+                }),
+                size: Some(Bounds {
+                    min: Some(10 * 1024 * 1024 + 1),
+                    max: None,
+                }),
+                kinds: vec!["pdf".into(), "docx".into()],
+                paths: vec!["/data".into(), "/home/user".into()],
+            }
+        );
+
+        let home = dirs::home_dir().unwrap();
+        let parsed = parse(r#"in:"~/My Documents" notes"#);
+        assert_eq!(parsed.text, "notes");
+        assert_eq!(
+            parsed.filters.paths,
+            vec![format!("{}/My Documents", home.to_str().unwrap())]
+        );
+    }
+
+    #[test]
+    fn test_parse_bounds() {
+        assert_eq!(
+            parse_size("<=1.5k"),
+            Some(Bounds {
+                min: None,
+                max: Some(1536)
+            })
+        );
+        assert_eq!(parse_size("<0"), None);
+        assert_eq!(parse_size(">10PB"), None);
+
+        let date = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let start = start_of_day(date).unwrap();
+        let next_start = start_of_day(date.succ_opt().unwrap()).unwrap();
+        assert_eq!(
+            parse_modified("2024-01-01", now()),
+            Some(Bounds {
+                min: Some(start),
+                max: Some(next_start - 1)
+            })
+        );
+        assert_eq!(
+            parse_modified(">2024-01-01", now()),
+            Some(Bounds {
+                min: Some(next_start),
+                max: None
+            })
+        );
+        assert_eq!(
+            parse_modified(">=2w", now()),
+            Some(Bounds {
+                min: None,
+                max: Some(now().timestamp() - 14 * SECS_PER_DAY)
+            })
+        );
+
+        // Multiple filters are intersected
+        let parsed = parse("modified:>2024-01-01 modified:<2024-01-01");
+        let modified = parsed.filters.modified.unwrap();
+        assert!(modified.min > modified.max);
+    }
+
+    #[test]
+    fn test_matches() {
+        let filters = parse("x kind:documents size:<1KB modified:<1d in:/home/user").filters;
+        let metadata = FileMetadata {
+            is_dir: false,
+            size: 100,
+            modified: now().timestamp() - SECS_PER_HOUR,
+        };
+
+        assert!(filters.matches("/home/user/notes.MD", &metadata));
+        assert!(!filters.matches("/home/user2/notes.md", &metadata));
+        assert!(!filters.matches("/home/user/photo.png", &metadata));
+        assert!(!filters.matches(
+            "/home/user/notes.md",
+            &FileMetadata {
+                size: 1024,
+                ..metadata
+            }
+        ));
+        assert!(!filters.matches(
+            "/home/user/notes.md",
+            &FileMetadata {
+                modified: now().timestamp() - 2 * SECS_PER_DAY,
+                ..metadata
+            }
+        ));
+        assert!(!filters.matches(
+            "/home/user/notes.md",
+            &FileMetadata {
+                is_dir: true,
+                ..metadata
+            }
+        ));
+    }
+
+    #[test]
+    fn test_narrow_search_paths() {
+        let search_paths = vec!["/home/user/Documents".to_string(), "/data".to_string()];
+        let filters = parse("x in:/home/user in:/data/projects in:/tmp").filters;
+        assert_eq!(
+            filters.narrow_search_paths(&search_paths),
+            vec!["/home/user/Documents", "/data/projects"]
+        );
+        assert_eq!(
+            QueryFilters::default().narrow_search_paths(&search_paths),
+            search_paths
+        );
+    }
+}
@@ -2,7 +2,10 @@
 
 use super::super::super::EXTENSION_ID;
 use super::super::super::config::FileSearchConfig;
+use super::super::super::filter::{FileMetadata, QueryFilters};
+use super::super::super::pattern::{ExcludePattern, expand_file_types};
 use super::super::should_be_filtered_out;
+use super::{load_added_items, save_added_items, sync_excluded_items};
 use crate::common::document::DataSourceReference;
 use crate::extension::LOCAL_QUERY_SOURCE_TYPE;
 use crate::util::file::sync_get_file_icon;
@@ -11,6 +14,7 @@ use crate::{
     extension::built_in::file_search::config::SearchBy,
 };
 use camino::Utf8Path;
+use chrono::{DateTime, SecondsFormat};
 use gio::Cancellable;
 use gio::Settings;
 use gio::prelude::SettingsExtManual;
@@ -25,14 +29,29 @@ const SERVICE_NAME: &str = "org.freedesktop.Tracker3.Miner.Files";
 /// case, we use this score.
 const SCORE: f64 = 1.0;
 
+/// Return true if we can connect to Tracker's file miner.
+pub(super) fn is_available() -> bool {
+    SparqlConnection::bus_new(SERVICE_NAME, None, None)
+        .map(|conn| conn.close())
+        .is_ok()
+}
+
 /// Helper function to return different SPARQL queries depending on the different configurations.
-fn query_sparql(query_string: &str, config: &FileSearchConfig) -> String {
+fn query_sparql(query_string: &str, config: &FileSearchConfig, filters: &QueryFilters) -> String {
+    let filter_patterns = filter_patterns(filters);
+    if query_string.is_empty() {
+        // Match all the files
+        return format!(
+            "SELECT nie:url(?file_item) WHERE {{ ?file_item nfo:fileName ?fileName .{filter_patterns} }}"
+        );
+    }
+
     match config.search_by {
         SearchBy::Name => {
             // Cannot use the inverted index as that searches for all the attributes,
             // but we only want to search the filename.
             format!(
-                "SELECT nie:url(?file_item) WHERE {{ ?file_item nfo:fileName ?fileName . FILTER(regex(?fileName, '{query_string}', 'i')) }}"
+                "SELECT nie:url(?file_item) WHERE {{ ?file_item nfo:fileName ?fileName . FILTER(regex(?fileName, '{query_string}', 'i')){filter_patterns} }}"
             )
         }
         SearchBy::NameAndContents => {
@@ -40,12 +59,98 @@ fn query_sparql(query_string: &str, config: &FileSearchConfig) -> String {
             // OR
             // filename search
             format!(
-                "SELECT nie:url(?file_item) fts:rank(?file_item) WHERE {{ {{ ?file_item fts:match '{query_string}' }} UNION {{ ?file_item nfo:fileName ?fileName . FILTER(regex(?fileName, '{query_string}', 'i'))  }} }} ORDER BY DESC fts:rank(?file_item)"
+                "SELECT nie:url(?file_item) fts:rank(?file_item) WHERE {{ {{ ?file_item fts:match '{query_string}' }} UNION {{ ?file_item nfo:fileName ?fileName . FILTER(regex(?fileName, '{query_string}', 'i'))  }}{filter_patterns} }} ORDER BY DESC fts:rank(?file_item)"
             )
         }
     }
 }
 
+/// Helper function to translate the query filters to SPARQL patterns, each
+/// of them starts with a space.
+///
+/// Tracker stores percent-encoded URLs, `in:` filters are encoded by GLib, the
+/// same way as Tracker does.
+fn filter_patterns(filters: &QueryFilters) -> String {
+    fn date_time(timestamp: i64) -> String {
+        let date_time = DateTime::from_timestamp(timestamp, 0).unwrap_or_default();
+        format!(
+            "\"{}\"^^xsd:dateTime",
+            date_time.to_rfc3339_opts(SecondsFormat::Secs, true)
+        )
+    }
+
+    let mut patterns = String::new();
+
+    if let Some(modified) = filters.modified {
+        patterns.push_str(" ?file_item nfo:fileLastModified ?modified .");
+        if let Some(min) = modified.min {
+            patterns.push_str(&format!(" FILTER(?modified >= {})", date_time(min)));
+        }
+        if let Some(max) = modified.max {
+            patterns.push_str(&format!(" FILTER(?modified <= {})", date_time(max)));
+        }
+    }
+
+    if let Some(size) = filters.size {
+        patterns.push_str(" ?file_item nfo:fileSize ?fileSize .");
+        if let Some(min) = size.min {
+            patterns.push_str(&format!(" FILTER(?fileSize >= {})", min));
+        }
+        if let Some(max) = size.max {
+            patterns.push_str(&format!(" FILTER(?fileSize <= {})", max));
+        }
+    }
+
+    // If some paths cannot be converted, `in:` filters are checked against the
+    // hits only.
+    let res_path_conditions = filters
+        .paths
+        .iter()
+        .map(|path| {
+            glib::filename_to_uri(path, None).map(|url| {
+                // Percent-encoded URLs contain no '"' or '\\'
+                let url = url.trim_end_matches('/');
+                format!("?url = \"{url}\" || STRSTARTS(?url, \"{url}/\")")
+            })
+        })
+        .collect::<Result<Vec<_>, _>>();
+    if let Ok(path_conditions) = res_path_conditions
+        && !path_conditions.is_empty()
+    {
+        patterns.push_str(&format!(
+            " ?file_item nie:url ?url . FILTER({})",
+            path_conditions.join(" || ")
+        ));
+    }
+
+    if !filters.kinds.is_empty() {
+        // Kinds only contain alphanumeric chars, '+', '-' and '_', put the
+        // latter in brackets to escape them.
+        let extensions = expand_file_types(&filters.kinds)
+            .iter()
+            .map(|extension| {
+                extension
+                    .chars()
+                    .map(|c| {
+                        if c.is_alphanumeric() {
+                            c.to_string()
+                        } else {
+                            format!("[{}]", c)
+                        }
+                    })
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("|");
+        patterns.push_str(&format!(
+            " ?file_item nfo:fileName ?kindFileName . FILTER(regex(?kindFileName, '[.]({})$', 'i'))",
+            extensions
+        ));
+    }
+
+    patterns
+}
+
 /// Helper function to replace unsupported characters with whitespace.
 ///
 /// Tracker will error out if it encounters these characters.
@@ -74,9 +179,13 @@ struct Query {
 }
 
 impl Query {
-    fn new(query_string: &str, config: &FileSearchConfig) -> Result<Self, String> {
+    fn new(
+        query_string: &str,
+        config: &FileSearchConfig,
+        filters: &QueryFilters,
+    ) -> Result<Self, String> {
         let query_string = query_string_cleanup(query_string);
-        let sparql = query_sparql(&query_string, config);
+        let sparql = query_sparql(&query_string, config, filters);
         let conn =
             SparqlConnection::bus_new(SERVICE_NAME, None, None).map_err(|e| e.to_string())?;
         let cursor = conn
@@ -157,9 +266,10 @@ pub(crate) async fn hits(
     from: usize,
     size: usize,
     config: &FileSearchConfig,
+    filters: &QueryFilters,
 ) -> Result<Vec<(Document, f64)>, String> {
     // Special cases that will make querying faster.
-    if query_string.is_empty() || size == 0 || config.search_paths.is_empty() {
+    if size == 0 || config.search_paths.is_empty() {
         return Ok(Vec::new());
     }
 
@@ -178,7 +288,7 @@ pub(crate) async fn hits(
     let mut num_skipped = 0;
     let should_skip = from;
 
-    let query = Query::new(query_string, config)?;
+    let query = Query::new(query_string, config, filters)?;
     for res_entry in query {
         let (file_path, score) = res_entry?;
 
@@ -186,6 +296,10 @@ pub(crate) async fn hits(
         if should_be_filtered_out(config, &file_path, true, true, true) {
             continue;
         }
+        // The metadata in Tracker could be outdated
+        if !filters.matches_file(&file_path) {
+            continue;
+        }
 
         // Process the `from` parameter.
         if need_to_skip && num_skipped < should_skip {
@@ -216,7 +330,7 @@ pub(crate) async fn hits(
             url: file_path.to_string(),
         };
 
-        let doc = Document {
+        let mut doc = Document {
             id: file_path.to_string(),
             title: Some(file_name.to_string()),
             source: Some(DataSourceReference {
@@ -231,6 +345,9 @@ pub(crate) async fn hits(
             icon: Some(icon.to_string()),
             ..Default::default()
         };
+        if let Some(metadata) = FileMetadata::read(&doc.id) {
+            metadata.fill_document(&mut doc);
+        }
 
         result_hits.push((doc, score));
 
@@ -292,9 +409,12 @@ pub(crate) fn apply_config(config: &FileSearchConfig) -> Result<(), String> {
     // 5. index-single-directories: List of directories to index without inspecting subfolders,
     //    ['$HOME', '&DOWNLOAD']
     //
-    // The first 3 entries specify patterns, in order to use them, we have to walk
-    // through the whole directory tree listed in search paths, which is impractical.
-    // So we only use the last 2 entries.
+    // The first 3 entries specify patterns, they cannot be derived from search
+    // paths, so we only use them to exclude the exclude patterns that match file
+    // names, e.g., `node_modules` or `*.tmp`, which are added to the 1st and 3rd
+    // entries.  These settings are shared with the whole desktop, the names we
+    // added are recorded, and removed once they are no longer excluded.  Other
+    // exclude patterns, and file types, are post-filtered in `hits()`.
     //
     //
     // Just want to mention that setting search path to "/home" could break Tracker:
@@ -314,6 +434,8 @@ pub(crate) fn apply_config(config: &FileSearchConfig) -> Result<(), String> {
     const TRACKER_SETTINGS_SCHEMA: &str = "org.freedesktop.Tracker3.Miner.Files";
     const KEY_INDEX_RECURSIVE_DIRECTORIES: &str = "index-recursive-directories";
     const KEY_INDEX_SINGLE_DIRECTORIES: &str = "index-single-directories";
+    const KEY_IGNORED_DIRECTORIES: &str = "ignored-directories";
+    const KEY_IGNORED_FILES: &str = "ignored-files";
 
     let search_paths = &config.search_paths;
 
@@ -337,12 +459,83 @@ pub(crate) fn apply_config(config: &FileSearchConfig) -> Result<(), String> {
         .set_strv(KEY_INDEX_SINGLE_DIRECTORIES, single_list)
         .expect("key is not be read-only");
 
+    let excluded_names: Vec<String> = config
+        .exclude_paths
+        .iter()
+        .filter_map(|exclude_path| match ExcludePattern::parse(exclude_path) {
+            Ok(ExcludePattern::Name(name)) => Some(name),
+            _ => None,
+        })
+        .collect();
+    for key in [KEY_IGNORED_DIRECTORIES, KEY_IGNORED_FILES] {
+        let mut list: Vec<String> = settings
+            .strv(key)
+            .iter()
+            .map(|item| item.to_string())
+            .collect();
+        let store_key = format!("tracker:{}", key);
+        let added = sync_excluded_items(&mut list, &load_added_items(&store_key), &excluded_names)?;
+        settings.set_strv(key, list).expect("key is not read-only");
+        save_added_items(&store_key, &added)?;
+    }
+
     Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::extension::built_in::file_search::config::LinuxBackend;
+    use crate::extension::built_in::file_search::filter::Bounds;
+
+    #[test]
+    fn test_query_sparql_with_filters() {
+        let config = FileSearchConfig {
+            search_paths: vec!["/home/user".into()],
+            exclude_paths: vec![],
+            file_types: vec![],
+            search_by: SearchBy::Name,
+            linux_backend: LinuxBackend::Auto,
+        };
+        let filters = QueryFilters {
+            modified: Some(Bounds {
+                min: Some(1_700_000_000),
+                max: None,
+            }),
+            size: Some(Bounds {
+                min: None,
+                max: Some(1024),
+            }),
+            kinds: vec!["pdf".into(), "c++".into()],
+            paths: vec!["/home/user/My Documents".into()],
+        };
+
+        assert_eq!(
+            query_sparql("report", &config, &QueryFilters::default()),
+            "SELECT nie:url(?file_item) WHERE { ?file_item nfo:fileName ?fileName . FILTER(regex(?fileName, 'report', 'i')) }"
+        );
+        assert_eq!(
+            query_sparql("report", &config, &filters),
+            "SELECT nie:url(?file_item) WHERE { ?file_item nfo:fileName ?fileName . FILTER(regex(?fileName, 'report', 'i')) \
+             ?file_item nfo:fileLastModified ?modified . FILTER(?modified >= \"2023-11-14T22:13:20Z\"^^xsd:dateTime) \
+             ?file_item nfo:fileSize ?fileSize . FILTER(?fileSize <= 1024) \
+             ?file_item nie:url ?url . FILTER(?url = \"file:///home/user/My%20Documents\" || STRSTARTS(?url, \"file:///home/user/My%20Documents/\")) \
+             ?file_item nfo:fileName ?kindFileName . FILTER(regex(?kindFileName, '[.](pdf|c[+][+])$', 'i')) }"
+        );
+        // Filters only
+        assert_eq!(
+            query_sparql(
+                "",
+                &config,
+                &QueryFilters {
+                    kinds: vec!["pdf".into()],
+                    ..Default::default()
+                }
+            ),
+            "SELECT nie:url(?file_item) WHERE { ?file_item nfo:fileName ?fileName . \
+             ?file_item nfo:fileName ?kindFileName . FILTER(regex(?kindFileName, '[.](pdf)$', 'i')) }"
+        );
+    }
 
     #[test]
     fn test_query_string_cleanup_basic() {
@@ -0,0 +1,441 @@
+//! Inverted index of file contents, and snippets of the matched contents.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::ops::Range;
+use std::sync::Arc;
+
+/// Only this many distinct tokens of a file are indexed.
+const MAX_TOKENS_PER_FILE: usize = 20_000;
+/// Longer tokens are unlikely to be searched, e.g., hashes and base64.
+const MAX_TOKEN_LEN: usize = 40;
+/// Snippets start at most this many chars before the first matched term.
+const SNIPPET_CHARS_BEFORE: usize = 40;
+const SNIPPET_MAX_CHARS: usize = 160;
+/// The beginning of the contents is kept for snippets, terms that only occur
+/// after it get their snippets from windows around their first occurrences.
+const SNIPPET_SOURCE_MAX_CHARS: usize = 4_000;
+
+/// Ideographs and syllables are indexed individually as they are not
+/// separated by spaces.
+fn is_cjk(char: char) -> bool {
+    matches!(char,
+        '\u{3040}'..='\u{30FF}' // Hiragana and Katakana
+        | '\u{3400}'..='\u{4DBF}' // CJK Unified Ideographs Extension A
+        | '\u{4E00}'..='\u{9FFF}' // CJK Unified Ideographs
+        | '\u{AC00}'..='\u{D7AF}' // Hangul Syllables
+        | '\u{F900}'..='\u{FAFF}' // CJK Compatibility Ideographs
+    )
+}
+
+/// Split `text` into lowercased tokens, along with their byte ranges in
+/// `text`.  A token is a run of alphanumeric chars, or a CJK char.
+fn tokens_with_ranges(text: &str) -> Vec<(Range<usize>, String)> {
+    let mut tokens = Vec::new();
+    let mut opt_token_start = None;
+
+    let mut push_token = |range: Range<usize>| {
+        let token = text[range.clone()].to_lowercase();
+        if token.chars().count() <= MAX_TOKEN_LEN {
+            tokens.push((range, token));
+        }
+    };
+
+    for (idx, char) in text.char_indices() {
+        if char.is_alphanumeric() && !is_cjk(char) {
+            opt_token_start.get_or_insert(idx);
+            continue;
+        }
+
+        if let Some(token_start) = opt_token_start.take() {
+            push_token(token_start..idx);
+        }
+        if is_cjk(char) {
+            push_token(idx..idx + char.len_utf8());
+        }
+    }
+    if let Some(token_start) = opt_token_start {
+        push_token(token_start..text.len());
+    }
+
+    tokens
+}
+
+/// Distinct tokens of `text`, see [`tokens_with_ranges`].
+pub(super) fn tokenize(text: &str) -> Vec<String> {
+    let mut seen = HashSet::new();
+    tokens_with_ranges(text)
+        .into_iter()
+        .map(|(_range, token)| token)
+        .filter(|token| seen.insert(token.clone()))
+        .collect()
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct IndexedContent {
+    /// Last modification time of the file when it was indexed, Unix
+    /// timestamp in seconds.
+    modified: i64,
+    /// Distinct tokens of the contents.
+    tokens: Vec<String>,
+    /// Beginning of the contents with whitespace collapsed, snippets are taken
+    /// from it.
+    snippet_source: String,
+    /// Parts of the contents around the first occurrences of the tokens that
+    /// are not in `snippet_source`, with whitespace collapsed.
+    snippet_windows: Vec<String>,
+    /// Index into `snippet_windows` for each of `tokens`, `None` if the token
+    /// is in `snippet_source`.
+    token_windows: Vec<Option<u32>>,
+}
+
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub(super) struct ContentIndex {
+    /// Keyed by file path
+    files: BTreeMap<String, IndexedContent>,
+    /// Token to the paths of the files containing it.  It is not persisted,
+    /// call [`ContentIndex::rebuild_postings`] after deserializing.
+    #[serde(skip)]
+    postings: HashMap<String, HashSet<Arc<str>>>,
+}
+
+impl ContentIndex {
+    pub(super) fn rebuild_postings(&mut self) {
+        self.postings.clear();
+        for (path, content) in self.files.iter() {
+            let path: Arc<str> = Arc::from(path.as_str());
+            for token in content.tokens.iter() {
+                self.postings
+                    .entry(token.clone())
+                    .or_default()
+                    .insert(Arc::clone(&path));
+            }
+        }
+    }
+
+    /// Return true if the file at `path` has not been indexed since it was
+    /// modified at `modified`.
+    pub(super) fn is_outdated(&self, path: &str, modified: i64) -> bool {
+        self.files
+            .get(path)
+            .is_none_or(|content| content.modified != modified)
+    }
+
+    /// Index `text` as the contents of the file at `path`.  Files that we
+    /// fail to extract text from should be inserted with empty text, so that
+    /// they won't be retried until they are modified.
+    pub(super) fn insert(&mut self, path: String, modified: i64, text: &str) {
+        self.remove(&path);
+
+        let mut tokens = tokenize(text);
+        tokens.truncate(MAX_TOKENS_PER_FILE);
+        let shared_path: Arc<str> = Arc::from(path.as_str());
+        for token in tokens.iter() {
+            self.postings
+                .entry(token.clone())
+                .or_default()
+                .insert(Arc::clone(&shared_path));
+        }
+        let collapsed = collapse_whitespace(text);
+        let head_len = collapsed
+            .char_indices()
+            .nth(SNIPPET_SOURCE_MAX_CHARS)
+            .map_or(collapsed.len(), |(idx, _char)| idx);
+        let (snippet_windows, token_windows) = snippet_windows(&collapsed, head_len, &tokens);
+        self.files.insert(
+            path,
+            IndexedContent {
+                modified,
+                tokens,
+                snippet_source: collapsed[..head_len].to_string(),
+                snippet_windows,
+                token_windows,
+            },
+        );
+    }
+
+    pub(super) fn remove(&mut self, path: &str) {
+        let Some(content) = self.files.remove(path) else {
+            return;
+        };
+        for token in content.tokens {
+            if let Some(paths) = self.postings.get_mut(&token) {
+                paths.remove(path);
+                if paths.is_empty() {
+                    self.postings.remove(&token);
+                }
+            }
+        }
+    }
+
+    /// Remove the files for which `keep` returns false.
+    pub(super) fn retain(&mut self, mut keep: impl FnMut(&str) -> bool) {
+        let removed: Vec<String> = self
+            .files
+            .keys()
+            .filter(|path| !keep(path))
+            .cloned()
+            .collect();
+        for path in removed {
+            self.remove(&path);
+        }
+    }
+
+    /// Return the paths of the files that contain all the `terms`.
+    pub(super) fn search(&self, terms: &[String]) -> HashSet<&str> {
+        let Some((first, rest)) = terms.split_first() else {
+            return HashSet::new();
+        };
+        let Some(paths) = self.postings.get(first) else {
+            return HashSet::new();
+        };
+
+        paths
+            .iter()
+            .filter(|path| {
+                rest.iter().all(|term| {
+                    self.postings
+                        .get(term)
+                        .is_some_and(|paths| paths.contains(*path))
+                })
+            })
+            .map(|path| path.as_ref())
+            .collect()
+    }
+
+    /// Return the snippet of the contents of the file at `path`, see
+    /// [`snippet`].  It is taken from the beginning of the contents, or the
+    /// window of the term that occurs first.
+    pub(super) fn snippet(&self, path: &str, terms: &[String]) -> Option<String> {
+        let content = self.files.get(path)?;
+        snippet(&content.snippet_source, terms).or_else(|| {
+            let window = terms
+                .iter()
+                .filter_map(|term| {
+                    let idx = content.tokens.iter().position(|token| token == term)?;
+                    content.token_windows[idx]
+                })
+                .min()?;
+            snippet(&content.snippet_windows[window as usize], terms)
+        })
+    }
+}
+
+/// Return `text` with whitespace collapsed into single spaces.
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace()
+        .flat_map(|word| std::iter::once(' ').chain(word.chars()))
+        // The leading space
+        .skip(1)
+        .collect()
+}
+
+/// Return the windows of the `tokens` that are not in the first `head_len`
+/// bytes of the whitespace-collapsed `text`, and the window of each token,
+/// see `IndexedContent`.  A window is shared by the tokens it covers.
+fn snippet_windows(
+    text: &str,
+    head_len: usize,
+    tokens: &[String],
+) -> (Vec<String>, Vec<Option<u32>>) {
+    let token_indexes: HashMap<&str, usize> = tokens
+        .iter()
+        .enumerate()
+        .map(|(idx, token)| (token.as_str(), idx))
+        .collect();
+    let mut token_windows = vec![None; tokens.len()];
+    let mut seen = HashSet::new();
+    let mut windows = Vec::new();
+    let mut last_window_end = 0;
+
+    for (range, token) in tokens_with_ranges(text) {
+        let Some(&idx) = token_indexes.get(token.as_str()) else {
+            continue;
+        };
+        if !seen.insert(idx) || range.end <= head_len {
+            continue;
+        }
+        if windows.is_empty() || range.end > last_window_end {
+            let (window, window_end) = snippet_window(text, range.start);
+            windows.push(window);
+            last_window_end = window_end;
+        }
+        token_windows[idx] = Some((windows.len() - 1) as u32);
+    }
+
+    (windows, token_windows)
+}
+
+/// Return the part of `text` that a snippet of the term at `match_start`
+/// could show, starting and ending at word boundaries, and the byte index of
+/// its end in `text`.
+fn snippet_window(text: &str, match_start: usize) -> (String, usize) {
+    let mut start = text[..match_start]
+        .char_indices()
+        .rev()
+        .nth(SNIPPET_CHARS_BEFORE - 1)
+        .map_or(0, |(idx, _char)| idx);
+    if start > 0
+        && let Some(space) = text[start..match_start].find(' ')
+    {
+        start += space + 1;
+    }
+    // Longer than a snippet, so that snippets of the tokens near its end
+    // are not cut short.
+    let mut end = text[match_start..]
+        .char_indices()
+        .nth(SNIPPET_MAX_CHARS * 2)
+        .map_or(text.len(), |(idx, _char)| match_start + idx);
+    end = text[end..]
+        .find(' ')
+        .map_or(text.len(), |space| end + space);
+
+    let mut window = String::new();
+    if start > 0 {
+        window.push('…');
+    }
+    window.push_str(&text[start..end]);
+
+    (window, end)
+}
+
+/// Return the part of `text` around the first occurrence of the `terms`, with
+/// whitespace collapsed.  `None` if none of them occurs.
+fn snippet(text: &str, terms: &[String]) -> Option<String> {
+    let (first_match, _token) = tokens_with_ranges(text)
+        .into_iter()
+        .find(|(_range, token)| terms.contains(token))?;
+
+    // Start at a word boundary if possible
+    let before = &text[..first_match.start];
+    let mut start = before
+        .char_indices()
+        .rev()
+        .nth(SNIPPET_CHARS_BEFORE - 1)
+        .map_or(0, |(idx, _char)| idx);
+    if start > 0
+        && let Some(space) = text[start..first_match.start].find(char::is_whitespace)
+    {
+        start += space;
+    }
+
+    let mut snippet = String::new();
+    if start > 0 {
+        snippet.push('…');
+    }
+    let mut num_chars = 0;
+    for word in text[start..].split_whitespace() {
+        let word_len = word.chars().count();
+        if num_chars + word_len > SNIPPET_MAX_CHARS {
+            // Text that is not separated by spaces, e.g., Chinese
+            if num_chars == 0 {
+                snippet.extend(word.chars().take(SNIPPET_MAX_CHARS));
+            }
+            snippet.push('…');
+            break;
+        }
+        if num_chars > 0 {
+            snippet.push(' ');
+        }
+        snippet.push_str(word);
+        num_chars += word_len + 1;
+    }
+
+    Some(snippet)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn terms(query: &str) -> Vec<String> {
+        tokenize(query)
+    }
+
+    #[test]
+    fn test_tokenize() {
+        assert_eq!(
+            tokenize("Hello, World! hello_world 2024 文件搜索"),
+            vec!["hello", "world", "2024", "文", "件", "搜", "索"]
+        );
+        assert!(tokenize(&"a".repeat(MAX_TOKEN_LEN + 1)).is_empty());
+    }
+
+    #[test]
+    fn test_search() {
+        let mut index = ContentIndex::default();
+        index.insert("/a.md".into(), 1, "Coco AI searches files");
+        index.insert("/b.md".into(), 1, "Coco AI reads files");
+
+        assert_eq!(index.search(&terms("coco files")).len(), 2);
+        assert_eq!(
+            index.search(&terms("searches coco")),
+            HashSet::from(["/a.md"])
+        );
+        assert!(index.search(&terms("coco missing")).is_empty());
+        assert!(index.search(&[]).is_empty());
+
+        // Re-indexing replaces the old tokens
+        index.insert("/a.md".into(), 2, "nothing");
+        assert_eq!(index.search(&terms("coco")), HashSet::from(["/b.md"]));
+        assert!(index.is_outdated("/a.md", 1));
+        assert!(!index.is_outdated("/a.md", 2));
+
+        index.retain(|path| path != "/b.md");
+        assert!(index.search(&terms("coco")).is_empty());
+        assert!(!index.postings.contains_key("coco"));
+
+        let mut rebuilt = ContentIndex {
+            files: serde_json::from_value(serde_json::to_value(&index.files).unwrap()).unwrap(),
+            postings: HashMap::new(),
+        };
+        rebuilt.rebuild_postings();
+        assert_eq!(rebuilt, index);
+    }
+
+    #[test]
+    fn test_snippet() {
+        assert_eq!(
+            snippet("A short   note\nabout Coco.", &terms("coco")).as_deref(),
+            Some("A short note about Coco.")
+        );
+        assert_eq!(snippet("A short note", &terms("coco")), None);
+
+        let text = format!("{} needle {}", "hay ".repeat(50), "straw ".repeat(50));
+        let snippet = snippet(&text, &terms("needle")).unwrap();
+        assert!(snippet.starts_with("…hay"));
+        assert!(snippet.contains("needle straw"));
+        assert!(snippet.ends_with('…'));
+        assert!(snippet.chars().count() <= SNIPPET_MAX_CHARS + 2);
+
+        let text = "文".repeat(SNIPPET_MAX_CHARS * 2);
+        let snippet = super::snippet(&text, &terms("文")).unwrap();
+        assert_eq!(snippet.chars().count(), SNIPPET_MAX_CHARS + 1);
+    }
+
+    #[test]
+    fn test_snippet_of_indexed_contents() {
+        let mut index = ContentIndex::default();
+        let text = format!("A  note\nabout Coco. {} needle", "hay ".repeat(2_000));
+        index.insert("/a.md".into(), 1, &text);
+
+        assert_eq!(
+            index.files["/a.md"].snippet_source.chars().count(),
+            SNIPPET_SOURCE_MAX_CHARS
+        );
+        assert_eq!(
+            index
+                .snippet("/a.md", &terms("coco"))
+                .as_deref()
+                .map(|s| &s[..22]),
+            Some("A note about Coco. hay")
+        );
+        // Beyond the snippet source, taken from the window of the term
+        assert_eq!(index.search(&terms("needle")), HashSet::from(["/a.md"]));
+        let snippet = index.snippet("/a.md", &terms("needle")).unwrap();
+        assert!(snippet.starts_with("…hay"));
+        assert!(snippet.ends_with("hay needle"));
+        assert_eq!(index.files["/a.md"].snippet_windows.len(), 1);
+        assert_eq!(index.snippet("/b.md", &terms("coco")), None);
+    }
+}
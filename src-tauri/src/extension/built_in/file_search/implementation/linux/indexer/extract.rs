@@ -0,0 +1,321 @@
+//! Extract text from files so that their contents can be indexed.
+//!
+//! Supported formats:
+//!
+//! * Plain text, Markdown and source code, recognized by their extensions
+//! * PDF
+//! * DOCX and ODT, only the text of the paragraphs
+
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Mutex};
+use std::time::Duration;
+
+/// Extensions of the files that are read as text.
+const TEXT_EXTENSIONS: &[&str] = &[
+    "txt", "text", "md", "markdown", "rst", "org", "adoc", "tex", "csv", "tsv", "log", "json",
+    "yaml", "yml", "toml", "ini", "cfg", "conf", "xml", "html", "htm", "css", "scss", "less", "rs",
+    "go", "c", "h", "cc", "cpp", "hpp", "cs", "java", "kt", "kts", "scala", "swift", "m", "py",
+    "rb", "php", "pl", "lua", "r", "jl", "dart", "js", "jsx", "mjs", "ts", "tsx", "vue", "svelte",
+    "sh", "bash", "zsh", "fish", "ps1", "sql", "graphql", "proto", "hs", "ml", "ex", "exs", "erl",
+    "clj", "el", "vim", "zig", "nim",
+];
+const PDF_EXTENSION: &str = "pdf";
+const DOCX_EXTENSION: &str = "docx";
+const ODT_EXTENSION: &str = "odt";
+
+/// Only this many bytes of a text file are read, and extracted text is
+/// truncated to this many bytes.
+const MAX_TEXT_SIZE: usize = 1024 * 1024;
+/// PDF, DOCX and ODT files larger than this are skipped.
+const MAX_DOCUMENT_FILE_SIZE: u64 = 20 * 1024 * 1024;
+/// PDF, DOCX and ODT files that take longer than this to extract are skipped.
+const EXTRACT_TIMEOUT: Duration = Duration::from_secs(5);
+/// Timed out workers keep running, at most this many workers run at once,
+/// more PDF, DOCX and ODT files are skipped.
+const MAX_RUNNING_EXTRACTIONS: usize = 4;
+/// A text file is considered binary if a NUL byte is found at its beginning.
+const BINARY_DETECTION_SIZE: usize = 8 * 1024;
+
+fn extension_of(path: &Path) -> Option<String> {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .map(|extension| extension.to_ascii_lowercase())
+}
+
+/// Return true if we can extract text from the file at `path`.
+pub(super) fn is_supported(path: &Path) -> bool {
+    let Some(extension) = extension_of(path) else {
+        return false;
+    };
+
+    TEXT_EXTENSIONS.contains(&extension.as_str())
+        || [PDF_EXTENSION, DOCX_EXTENSION, ODT_EXTENSION].contains(&extension.as_str())
+}
+
+/// Extract the text of the file at `path`.  `None` if it is not supported,
+/// cannot be read, or exceeds the budgets.
+pub(super) fn extract_text(path: &Path) -> Option<String> {
+    let extension = extension_of(path)?;
+    let mut text = match extension.as_str() {
+        PDF_EXTENSION => extract_document(path, |path| {
+            // pdf-extract panics on some malformed files, which is fine as it
+            // runs in a separate thread.
+            pdf_extract::extract_text(path).ok()
+        })?,
+        DOCX_EXTENSION => extract_document(path, |path| {
+            zipped_xml_text(path, "word/document.xml", &["w:p", "w:br", "w:tab"])
+        })?,
+        ODT_EXTENSION => extract_document(path, |path| {
+            zipped_xml_text(
+                path,
+                "content.xml",
+                &["text:p", "text:h", "text:s", "text:tab"],
+            )
+        })?,
+        extension if TEXT_EXTENSIONS.contains(&extension) => read_text(path)?,
+        _ => return None,
+    };
+
+    truncate(&mut text, MAX_TEXT_SIZE);
+    Some(text)
+}
+
+fn read_text(path: &Path) -> Option<String> {
+    let file = File::open(path).ok()?;
+    let mut bytes = Vec::new();
+    file.take(MAX_TEXT_SIZE as u64)
+        .read_to_end(&mut bytes)
+        .ok()?;
+
+    let head = &bytes[..bytes.len().min(BINARY_DETECTION_SIZE)];
+    if head.contains(&0) {
+        return None;
+    }
+
+    Some(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// Paths of the files that the workers of `extract_document()` are
+/// extracting, including the ones that have timed out.
+static RUNNING_EXTRACTIONS: LazyLock<Mutex<HashSet<PathBuf>>> = LazyLock::new(Mutex::default);
+
+/// A file in `RUNNING_EXTRACTIONS`, removed when dropped, i.e., when its
+/// worker finishes or panics.
+struct RunningExtraction(PathBuf);
+
+impl RunningExtraction {
+    /// `None` if the file is still being extracted by a timed out worker, or
+    /// too many workers are running.
+    fn start(path: &Path) -> Option<Self> {
+        let mut running = RUNNING_EXTRACTIONS.lock().unwrap();
+        if running.len() >= MAX_RUNNING_EXTRACTIONS || running.contains(path) {
+            return None;
+        }
+        running.insert(path.to_path_buf());
+
+        Some(Self(path.to_path_buf()))
+    }
+}
+
+impl Drop for RunningExtraction {
+    fn drop(&mut self) {
+        RUNNING_EXTRACTIONS.lock().unwrap().remove(&self.0);
+    }
+}
+
+/// Run `extractor` in a separate thread, give up if it takes too long.
+fn extract_document(
+    path: &Path,
+    extractor: impl FnOnce(&Path) -> Option<String> + Send + 'static,
+) -> Option<String> {
+    let file_size = std::fs::metadata(path).ok()?.len();
+    if file_size > MAX_DOCUMENT_FILE_SIZE {
+        return None;
+    }
+
+    let Some(running_extraction) = RunningExtraction::start(path) else {
+        log::debug!(
+            "too many text extractions are running, skipping [{}]",
+            path.display()
+        );
+        return None;
+    };
+    let path: PathBuf = path.to_path_buf();
+    let (tx, rx) = std::sync::mpsc::channel();
+    std::thread::Builder::new()
+        .name("file-text-extractor".into())
+        .spawn(move || {
+            let _running_extraction = running_extraction;
+            // The receiver has given up
+            let _ = tx.send(extractor(&path));
+        })
+        .ok()?;
+
+    // If it times out, the thread will be left running until it finishes.
+    rx.recv_timeout(EXTRACT_TIMEOUT).ok().flatten()
+}
+
+/// Read the XML file `entry` in the zip archive at `path`, and return its
+/// text, see [`xml_text`].
+fn zipped_xml_text(path: &Path, entry: &str, separating_tags: &[&str]) -> Option<String> {
+    let file = File::open(path).ok()?;
+    let mut archive = zip::ZipArchive::new(file).ok()?;
+    let mut xml = String::new();
+    archive
+        .by_name(entry)
+        .ok()?
+        .take(MAX_DOCUMENT_FILE_SIZE)
+        .read_to_string(&mut xml)
+        .ok()?;
+
+    Some(xml_text(&xml, separating_tags))
+}
+
+/// Strip the tags of `xml` and unescape the predefined entities.  Elements in
+/// `separating_tags` separate the text, a line break is added at the end of
+/// them, or a space if they are empty elements, e.g., `<text:s/>`.
+fn xml_text(xml: &str, separating_tags: &[&str]) -> String {
+    let mut text = String::with_capacity(xml.len() / 2);
+    let mut rest = xml;
+    while let Some(tag_start) = rest.find('<') {
+        text.push_str(&unescape_xml(&rest[..tag_start]));
+
+        let Some(tag_len) = rest[tag_start..].find('>') else {
+            return text;
+        };
+        let tag = &rest[tag_start + 1..tag_start + tag_len];
+        let name = tag
+            .trim_start_matches('/')
+            .split(|c: char| c.is_whitespace() || c == '/')
+            .next()
+            .unwrap_or_default();
+        if separating_tags.contains(&name) {
+            if tag.starts_with('/') {
+                text.push('\n');
+            } else if tag.ends_with('/') {
+                text.push(' ');
+            }
+        }
+
+        rest = &rest[tag_start + tag_len + 1..];
+    }
+    text.push_str(&unescape_xml(rest));
+
+    text
+}
+
+fn unescape_xml(str: &str) -> String {
+    if !str.contains('&') {
+        return str.to_string();
+    }
+
+    str.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Truncate `text` to at most `max_len` bytes, at a char boundary.
+fn truncate(text: &mut String, max_len: usize) {
+    if text.len() <= max_len {
+        return;
+    }
+
+    let mut len = max_len;
+    while !text.is_char_boundary(len) {
+        len -= 1;
+    }
+    text.truncate(len);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_extract_plain_text() {
+        let dir = TempDir::new().unwrap();
+        let markdown = dir.path().join("README.MD");
+        std::fs::write(&markdown, "# Coco\n\nSearch everything.").unwrap();
+        let binary = dir.path().join("data.txt");
+        std::fs::write(&binary, b"\x00\x01\x02").unwrap();
+        let image = dir.path().join("photo.jpg");
+        std::fs::write(&image, "not really an image").unwrap();
+
+        assert!(is_supported(&markdown));
+        assert_eq!(
+            extract_text(&markdown).as_deref(),
+            Some("# Coco\n\nSearch everything.")
+        );
+        assert_eq!(extract_text(&binary), None);
+        assert!(!is_supported(&image));
+        assert_eq!(extract_text(&image), None);
+    }
+
+    #[test]
+    fn test_extract_docx() {
+        let dir = TempDir::new().unwrap();
+        let docx = dir.path().join("report.docx");
+        let mut writer = zip::ZipWriter::new(File::create(&docx).unwrap());
+        writer
+            .start_file(
+                "word/document.xml",
+                zip::write::SimpleFileOptions::default(),
+            )
+            .unwrap();
+        writer
+            .write_all(
+                br#"<w:document><w:body><w:p><w:r><w:t>Quarterly</w:t></w:r><w:r><w:t xml:space="preserve"> report</w:t></w:r></w:p><w:p><w:r><w:t>Q&amp;A</w:t></w:r></w:p></w:body></w:document>"#,
+            )
+            .unwrap();
+        writer.finish().unwrap();
+
+        assert_eq!(
+            extract_text(&docx).as_deref(),
+            Some("Quarterly report\nQ&A\n")
+        );
+    }
+
+    #[test]
+    fn test_extract_document_skips_running_files() {
+        let dir = TempDir::new().unwrap();
+        let pdf = dir.path().join("slow.pdf");
+        std::fs::write(&pdf, "").unwrap();
+
+        // A timed out worker is still extracting it
+        let running_extraction = RunningExtraction::start(&pdf).unwrap();
+        assert_eq!(extract_document(&pdf, |_path| Some("text".into())), None);
+
+        drop(running_extraction);
+        assert_eq!(
+            extract_document(&pdf, |_path| Some("text".into())).as_deref(),
+            Some("text")
+        );
+    }
+
+    #[test]
+    fn test_xml_text() {
+        assert_eq!(
+            xml_text(
+                r#"<office:text><text:h>Title</text:h><text:p>a<text:s/>b &lt;c&gt;</text:p></office:text>"#,
+                &["text:p", "text:h", "text:s"]
+            ),
+            "Title\na b <c>\n"
+        );
+        // Truncated XML
+        assert_eq!(xml_text("<a>text<b", &[]), "text");
+    }
+
+    #[test]
+    fn test_truncate() {
+        let mut text = String::from("搜索");
+        truncate(&mut text, 4);
+        assert_eq!(text, "搜");
+    }
+}
@@ -0,0 +1,960 @@
+//! Built-in file indexer, used on desktop environments that come with no file
+//! indexer that we support, e.g., XFCE, Sway and i3.
+//!
+//! It walks the search paths and keeps the names of the files that pass the
+//! filters of [`FileSearchConfig`] in an index.  The index is persisted to
+//! `<app data>/file_search/index.json` so that it is searchable right after
+//! Coco starts, then it is reconciled with the file system in background, and
+//! kept up to date by watching the search paths.
+//!
+//! Hidden files and directories are not indexed.
+//!
+//! With [`SearchBy::NameAndContents`], the text of the supported files (see
+//! `extract.rs`) is tokenized and put into an inverted index (see
+//! `content.rs`), after the names are indexed.  Content hits come with a
+//! snippet of the matched text, taken from the parts of the text kept in the
+//! index.
+
+mod content;
+mod extract;
+
+use super::super::super::EXTENSION_ID;
+use super::super::super::config::{FileSearchConfig, SearchBy};
+use super::super::super::filter::{FileMetadata, QueryFilters};
+use super::super::should_be_filtered_out;
+use crate::GLOBAL_TAURI_APP_HANDLE;
+use crate::common::document::{DataSourceReference, Document, OnOpened};
+use crate::extension::LOCAL_QUERY_SOURCE_TYPE;
+use crate::util::file::sync_get_file_icon;
+use camino::{Utf8Path, Utf8PathBuf};
+use content::ContentIndex;
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::Metadata;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant, UNIX_EPOCH};
+use tauri::Manager;
+use walkdir::WalkDir;
+
+const INDEX_DIR: &str = "file_search";
+const INDEX_FILE: &str = "index.json";
+/// Bump it when the format of the index file changes, index files of other
+/// versions will be discarded.
+const INDEX_FORMAT_VERSION: u32 = 3;
+
+/// Wait for this long after a file change, so that a burst of changes gets
+/// applied at once.
+const UPDATE_DEBOUNCE: Duration = Duration::from_secs(1);
+/// Don't postpone applying changes forever if files keep changing.
+const UPDATE_MAX_DELAY: Duration = Duration::from_secs(10);
+/// Saving rewrites the whole index file, do it at most this often.
+const SAVE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Scores of the name matches, better matches come first.
+const SCORE_EXACT: f64 = 3.0;
+const SCORE_PREFIX: f64 = 2.0;
+const SCORE_SUBSTRING: f64 = 1.0;
+/// Score of content matches, it is added to the name score if the name also
+/// matches.
+const SCORE_CONTENT: f64 = 0.5;
+
+/// The configuration entries that decide which files are indexed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct IndexScope {
+    search_paths: Vec<String>,
+    exclude_paths: Vec<String>,
+    file_types: Vec<String>,
+    search_by: SearchBy,
+}
+
+impl From<&FileSearchConfig> for IndexScope {
+    fn from(config: &FileSearchConfig) -> Self {
+        Self {
+            search_paths: config.search_paths.clone(),
+            exclude_paths: config.exclude_paths.clone(),
+            file_types: config.file_types.clone(),
+            search_by: config.search_by,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct FileEntry {
+    is_dir: bool,
+    /// In bytes
+    size: u64,
+    /// Last modification time, Unix timestamp in seconds.
+    modified: i64,
+}
+
+impl From<&Metadata> for FileEntry {
+    fn from(metadata: &Metadata) -> Self {
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |duration| duration.as_secs() as i64);
+
+        Self {
+            is_dir: metadata.is_dir(),
+            size: metadata.len(),
+            modified,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct FileIndex {
+    version: u32,
+    scope: IndexScope,
+    /// Keyed by file path
+    entries: BTreeMap<String, FileEntry>,
+    /// Always empty unless searching by contents
+    contents: ContentIndex,
+}
+
+impl From<&FileEntry> for FileMetadata {
+    fn from(entry: &FileEntry) -> Self {
+        Self {
+            is_dir: entry.is_dir,
+            size: entry.size,
+            modified: entry.modified,
+        }
+    }
+}
+
+/// A file that matches the query.
+#[derive(Debug, PartialEq)]
+struct IndexHit {
+    path: String,
+    score: f64,
+    /// Snippet of the contents, if they match the query.
+    snippet: Option<String>,
+    metadata: FileMetadata,
+}
+
+impl FileIndex {
+    fn new(scope: IndexScope) -> Self {
+        Self {
+            version: INDEX_FORMAT_VERSION,
+            scope,
+            entries: BTreeMap::new(),
+            contents: ContentIndex::default(),
+        }
+    }
+
+    /// Load the index stored in `index_file`.  An empty index will be returned
+    /// if it does not exist, is invalid, or was built for another scope.
+    fn load(index_file: &Path, scope: IndexScope) -> Self {
+        if !index_file.is_file() {
+            return Self::new(scope);
+        }
+
+        let result = std::fs::read(index_file)
+            .map_err(|e| e.to_string())
+            .and_then(|content| {
+                serde_json::from_slice::<FileIndex>(&content).map_err(|e| e.to_string())
+            });
+        match result {
+            Ok(mut index) if index.version == INDEX_FORMAT_VERSION && index.scope == scope => {
+                index.contents.rebuild_postings();
+                index
+            }
+            Ok(_outdated) => Self::new(scope),
+            Err(e) => {
+                log::warn!(
+                    "ignoring invalid file index [{}], error [{}]",
+                    index_file.display(),
+                    e
+                );
+                Self::new(scope)
+            }
+        }
+    }
+
+    /// Write the index to `index_file`, atomically.
+    fn save(&self, index_file: &Path) -> Result<(), String> {
+        let index_dir = index_file.parent().expect("index file has a parent");
+        std::fs::create_dir_all(index_dir)
+            .map_err(|e| format!("failed to create [{}], error [{}]", index_dir.display(), e))?;
+
+        let tmp_file = index_file.with_extension("json.tmp");
+        let content = serde_json::to_vec(self).expect("index should be serializable");
+        std::fs::write(&tmp_file, content)
+            .map_err(|e| format!("failed to write [{}], error [{}]", tmp_file.display(), e))?;
+        std::fs::rename(&tmp_file, index_file).map_err(|e| {
+            format!(
+                "failed to rename [{}] to [{}], error [{}]",
+                tmp_file.display(),
+                index_file.display(),
+                e
+            )
+        })
+    }
+
+    /// Sync the entry of `path`, and its descendants if it is a directory,
+    /// with the file system.
+    fn update_path(&mut self, config: &FileSearchConfig, path: &Utf8Path) {
+        self.remove_subtree(path);
+
+        let Ok(metadata) = std::fs::symlink_metadata(path) else {
+            // It has been removed
+            return;
+        };
+        if is_pruned(config, path) {
+            return;
+        }
+        if !should_be_filtered_out(config, path.as_str(), false, false, true) {
+            self.entries
+                .insert(path.to_string(), FileEntry::from(&metadata));
+        }
+        if metadata.is_dir() {
+            // It could be moved here with its contents, in which case we
+            // won't get events for the contents.
+            let never_cancelled = AtomicBool::new(false);
+            walk(
+                config,
+                path.as_std_path(),
+                &never_cancelled,
+                |path, entry| {
+                    self.entries.insert(path, entry);
+                },
+            );
+        }
+    }
+
+    /// Remove the entries of `path` and its descendants.
+    fn remove_subtree(&mut self, path: &Utf8Path) {
+        self.entries.remove(path.as_str());
+        self.contents.remove(path.as_str());
+
+        let prefix = format!("{}/", path.as_str().trim_end_matches('/'));
+        let descendants: Vec<String> = self
+            .entries
+            .range(prefix.clone()..)
+            .take_while(|(entry_path, _)| entry_path.starts_with(&prefix))
+            .map(|(entry_path, _)| entry_path.clone())
+            .collect();
+        for descendant in descendants {
+            self.entries.remove(&descendant);
+            self.contents.remove(&descendant);
+        }
+    }
+
+    /// Drop the contents of the files that are no longer indexed, and return
+    /// the files whose contents should be (re-)indexed, along with their
+    /// modification time.
+    fn outdated_contents(&mut self) -> Vec<(String, i64)> {
+        let entries = &self.entries;
+        self.contents.retain(|path| entries.contains_key(path));
+
+        self.entries
+            .iter()
+            .filter(|(path, entry)| {
+                !entry.is_dir
+                    && self.contents.is_outdated(path, entry.modified)
+                    && extract::is_supported(Path::new(path))
+            })
+            .map(|(path, entry)| (path.clone(), entry.modified))
+            .collect()
+    }
+
+    /// Return the files whose names contain `query_string`, case-insensitively,
+    /// or whose contents contain all its terms, and that match the `filters`,
+    /// best matches first.  An empty `query_string` matches all the files.
+    fn search(
+        &self,
+        query_string: &str,
+        filters: &QueryFilters,
+        from: usize,
+        size: usize,
+    ) -> Vec<IndexHit> {
+        let query = query_string.to_lowercase();
+        let terms = content::tokenize(query_string);
+        let content_matches = self.contents.search(&terms);
+
+        let mut hits: Vec<(&String, f64, bool, FileMetadata)> = self
+            .entries
+            .iter()
+            .filter_map(|(path, entry)| {
+                let metadata = FileMetadata::from(entry);
+                if !filters.matches(path, &metadata) {
+                    return None;
+                }
+
+                let file_name = Utf8Path::new(path).file_name()?;
+                let opt_name_score = name_score(&file_name.to_lowercase(), &query);
+                let content_matched = content_matches.contains(path.as_str());
+                let score = match (opt_name_score, content_matched) {
+                    (Some(name_score), true) => name_score + SCORE_CONTENT,
+                    (Some(name_score), false) => name_score,
+                    (None, true) => SCORE_CONTENT,
+                    (None, false) => return None,
+                };
+
+                Some((path, score, content_matched, metadata))
+            })
+            .collect();
+
+        // Stable sort, ties are in path order
+        hits.sort_by(|(_, score_a, _, _), (_, score_b, _, _)| score_b.total_cmp(score_a));
+        hits.into_iter()
+            .skip(from)
+            .take(size)
+            .map(|(path, score, content_matched, metadata)| IndexHit {
+                path: path.clone(),
+                score,
+                snippet: content_matched
+                    .then(|| self.contents.snippet(path, &terms))
+                    .flatten(),
+                metadata,
+            })
+            .collect()
+    }
+}
+
+/// Score how well the lowercased `file_name` matches the lowercased `query`,
+/// `None` if it does not match.
+fn name_score(file_name: &str, query: &str) -> Option<f64> {
+    let stem = file_name
+        .rsplit_once('.')
+        .map_or(file_name, |(stem, _extension)| stem);
+
+    if file_name == query || stem == query {
+        Some(SCORE_EXACT)
+    } else if file_name.starts_with(query) {
+        Some(SCORE_PREFIX)
+    } else if file_name.contains(query) {
+        Some(SCORE_SUBSTRING)
+    } else {
+        None
+    }
+}
+
+/// If `path` and its descendants should not be indexed, i.e., it is not in
+/// any search path, it is hidden, or it is excluded.
+fn is_pruned(config: &FileSearchConfig, path: &Utf8Path) -> bool {
+    let Some(search_path) = config
+        .search_paths
+        .iter()
+        .map(Utf8Path::new)
+        .find(|search_path| path.starts_with(search_path))
+    else {
+        return true;
+    };
+    let is_hidden = path
+        .strip_prefix(search_path)
+        .expect("path starts with the search path")
+        .components()
+        .any(|component| component.as_str().starts_with('.'));
+
+    is_hidden || should_be_filtered_out(config, path.as_str(), false, true, false)
+}
+
+/// Walk the descendants of `root`, and call `on_entry` with the ones that
+/// should be indexed.  Return false if it gets cancelled.
+fn walk(
+    config: &FileSearchConfig,
+    root: &Path,
+    cancelled: &AtomicBool,
+    mut on_entry: impl FnMut(String, FileEntry),
+) -> bool {
+    let walker = WalkDir::new(root)
+        .min_depth(1)
+        .into_iter()
+        .filter_entry(|dir_entry| {
+            Utf8Path::from_path(dir_entry.path()).is_some_and(|path| !is_pruned(config, path))
+        });
+
+    for res_dir_entry in walker {
+        if cancelled.load(Ordering::Relaxed) {
+            return false;
+        }
+
+        let dir_entry = match res_dir_entry {
+            Ok(dir_entry) => dir_entry,
+            Err(e) => {
+                log::debug!("file indexer skipped an entry, error [{}]", e);
+                continue;
+            }
+        };
+        let path = Utf8Path::from_path(dir_entry.path()).expect("filtered out non-UTF-8 paths");
+        if should_be_filtered_out(config, path.as_str(), false, false, true) {
+            continue;
+        }
+        let Ok(metadata) = dir_entry.metadata() else {
+            continue;
+        };
+
+        on_entry(path.to_string(), FileEntry::from(&metadata));
+    }
+
+    true
+}
+
+/// Walk all the search paths, return `None` if it gets cancelled.
+fn scan(config: &FileSearchConfig, cancelled: &AtomicBool) -> Option<BTreeMap<String, FileEntry>> {
+    let mut entries = BTreeMap::new();
+    for search_path in config.search_paths.iter() {
+        let completed = walk(config, Path::new(search_path), cancelled, |path, entry| {
+            entries.insert(path, entry);
+        });
+        if !completed {
+            return None;
+        }
+    }
+
+    Some(entries)
+}
+
+/// A running indexer.
+struct Indexer {
+    scope: IndexScope,
+    index: Arc<RwLock<FileIndex>>,
+    /// Stops scanning and saving the index.
+    cancelled: Arc<AtomicBool>,
+    /// Dropping it stops the update thread.  `None` if the watcher cannot be
+    /// created, the index won't be updated until the next start.  The same
+    /// applies to the search paths that cannot be watched.
+    _watcher: Option<RecommendedWatcher>,
+}
+
+static INDEXER: Mutex<Option<Indexer>> = Mutex::new(None);
+
+impl Indexer {
+    fn start(config: FileSearchConfig, index_file: PathBuf) -> Self {
+        let scope = IndexScope::from(&config);
+        let index = Arc::new(RwLock::new(FileIndex::new(scope.clone())));
+        let cancelled = Arc::new(AtomicBool::new(false));
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        let watcher = notify::recommended_watcher(move |res| {
+            // The update thread has exited
+            let _ = tx.send(res);
+        })
+        .map(|mut watcher| {
+            // A missing search path should not stop watching the others
+            for search_path in config.search_paths.iter() {
+                if let Err(e) = watcher.watch(Path::new(search_path), RecursiveMode::Recursive) {
+                    log::warn!(
+                        "failed to watch file search path [{}], error [{}]",
+                        search_path,
+                        e
+                    );
+                }
+            }
+            watcher
+        });
+        let watcher = match watcher {
+            Ok(watcher) => Some(watcher),
+            Err(e) => {
+                log::warn!(
+                    "failed to create the file search path watcher, error [{}]",
+                    e
+                );
+                None
+            }
+        };
+
+        let thread_index = Arc::clone(&index);
+        let thread_cancelled = Arc::clone(&cancelled);
+        std::thread::Builder::new()
+            .name("file-indexer".into())
+            .spawn(move || update_loop(&config, &index_file, &thread_index, &thread_cancelled, rx))
+            .expect("failed to spawn thread");
+
+        Self {
+            scope,
+            index,
+            cancelled,
+            _watcher: watcher,
+        }
+    }
+
+    fn stop(self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Index the contents of the files that are new or modified, when searching
+/// by contents.  Return false if it gets cancelled.
+fn sync_contents(
+    config: &FileSearchConfig,
+    index: &RwLock<FileIndex>,
+    cancelled: &AtomicBool,
+) -> bool {
+    if config.search_by != SearchBy::NameAndContents {
+        return true;
+    }
+
+    let outdated = index.write().unwrap().outdated_contents();
+    for (path, modified) in outdated {
+        if cancelled.load(Ordering::Relaxed) {
+            return false;
+        }
+
+        // Don't hold the lock while extracting, it can take seconds.
+        let text = extract::extract_text(Path::new(&path)).unwrap_or_default();
+        let mut index = index.write().unwrap();
+        // It could be removed during extraction
+        if index.entries.contains_key(&path) {
+            index.contents.insert(path, modified, &text);
+        }
+    }
+
+    true
+}
+
+fn save_index(index: &RwLock<FileIndex>, index_file: &Path, cancelled: &AtomicBool) {
+    // A stopped indexer should not overwrite the index of the running one.
+    if cancelled.load(Ordering::Relaxed) {
+        return;
+    }
+    if let Err(e) = index.read().unwrap().save(index_file) {
+        log::warn!("failed to save the file index, error [{}]", e);
+    }
+}
+
+/// Load the index, reconcile it with the file system, then apply the file
+/// changes.  It returns when the indexer is stopped.
+fn update_loop(
+    config: &FileSearchConfig,
+    index_file: &Path,
+    index: &RwLock<FileIndex>,
+    cancelled: &AtomicBool,
+    rx: Receiver<notify::Result<notify::Event>>,
+) {
+    let scope = IndexScope::from(config);
+    *index.write().unwrap() = FileIndex::load(index_file, scope);
+
+    let Some(entries) = scan(config, cancelled) else {
+        return;
+    };
+    index.write().unwrap().entries = entries;
+    save_index(index, index_file, cancelled);
+    if !sync_contents(config, index, cancelled) {
+        return;
+    }
+    save_index(index, index_file, cancelled);
+    let mut last_saved = Instant::now();
+    let mut dirty = false;
+
+    loop {
+        let first = match rx.recv_timeout(SAVE_INTERVAL) {
+            Ok(event) => Some(event),
+            Err(RecvTimeoutError::Timeout) => None,
+            Err(RecvTimeoutError::Disconnected) => return,
+        };
+
+        if let Some(first) = first {
+            let mut events = vec![first];
+            let burst_start = Instant::now();
+            while burst_start.elapsed() < UPDATE_MAX_DELAY {
+                match rx.recv_timeout(UPDATE_DEBOUNCE) {
+                    Ok(event) => events.push(event),
+                    Err(RecvTimeoutError::Timeout) => break,
+                    Err(RecvTimeoutError::Disconnected) => return,
+                }
+            }
+            if cancelled.load(Ordering::Relaxed) {
+                return;
+            }
+
+            let mut rescan = false;
+            let mut changed_paths = BTreeSet::new();
+            for event in events {
+                match event {
+                    Ok(event) => {
+                        rescan |= event.need_rescan();
+                        changed_paths.extend(event.paths);
+                    }
+                    Err(e) => log::warn!("file search path watcher error [{}]", e),
+                }
+            }
+
+            if rescan {
+                let Some(entries) = scan(config, cancelled) else {
+                    return;
+                };
+                index.write().unwrap().entries = entries;
+            } else {
+                let mut index = index.write().unwrap();
+                for path in changed_paths {
+                    if let Some(path) = Utf8Path::from_path(&path) {
+                        index.update_path(config, path);
+                    }
+                }
+            }
+            if !sync_contents(config, index, cancelled) {
+                return;
+            }
+            dirty = true;
+        }
+
+        if dirty && last_saved.elapsed() >= SAVE_INTERVAL {
+            save_index(index, index_file, cancelled);
+            last_saved = Instant::now();
+            dirty = false;
+        }
+    }
+}
+
+fn index_file() -> PathBuf {
+    GLOBAL_TAURI_APP_HANDLE
+        .get()
+        .expect("global tauri app handle not set")
+        .path()
+        .app_data_dir()
+        .expect("app data directory not found")
+        .join(INDEX_DIR)
+        .join(INDEX_FILE)
+}
+
+/// Start indexing with `config`, or restart if the indexing scope changed.
+pub(crate) fn apply_config(config: &FileSearchConfig) -> Result<(), String> {
+    let scope = IndexScope::from(config);
+    let mut opt_indexer = INDEXER.lock().unwrap();
+    if let Some(indexer) = opt_indexer.as_ref()
+        && indexer.scope == scope
+    {
+        return Ok(());
+    }
+
+    if let Some(indexer) = opt_indexer.take() {
+        indexer.stop();
+    }
+    *opt_indexer = Some(Indexer::start(config.clone(), index_file()));
+
+    Ok(())
+}
+
+/// Stop the indexer, if it is running.
+pub(crate) fn stop() {
+    if let Some(indexer) = INDEXER.lock().unwrap().take() {
+        indexer.stop();
+    }
+}
+
+fn file_document(file_path: String) -> Document {
+    let icon = sync_get_file_icon(&file_path);
+    let file_path_of_type_path = Utf8PathBuf::from(&file_path);
+    let r#where = file_path_of_type_path
+        .parent()
+        .unwrap_or_else(|| {
+            panic!(
+                "expect path [{}] to have a parent, but it does not",
+                file_path
+            );
+        })
+        .to_string();
+    let file_name = file_path_of_type_path.file_name().unwrap_or_else(|| {
+        panic!(
+            "expect path [{}] to have a file name, but it does not",
+            file_path
+        );
+    });
+    let on_opened = OnOpened::Document {
+        url: file_path.clone(),
+    };
+
+    Document {
+        id: file_path.clone(),
+        title: Some(file_name.to_string()),
+        source: Some(DataSourceReference {
+            r#type: Some(LOCAL_QUERY_SOURCE_TYPE.into()),
+            name: Some(EXTENSION_ID.into()),
+            id: Some(EXTENSION_ID.into()),
+            icon: Some(String::from("font_Filesearch")),
+        }),
+        category: Some(r#where),
+        on_opened: Some(on_opened),
+        url: Some(file_path),
+        icon: Some(icon.to_string()),
+        ..Default::default()
+    }
+}
+
+pub(crate) async fn hits(
+    query_string: &str,
+    from: usize,
+    size: usize,
+    _config: &FileSearchConfig,
+    filters: &QueryFilters,
+) -> Result<Vec<(Document, f64)>, String> {
+    if size == 0 {
+        return Ok(Vec::new());
+    }
+
+    let index = INDEXER
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map(|indexer| Arc::clone(&indexer.index))
+        .ok_or_else(|| "built-in file indexer is not running".to_string())?;
+    // Files are filtered by the config when they get indexed
+    let index_hits = index
+        .read()
+        .unwrap()
+        .search(query_string, filters, from, size);
+
+    let hits = index_hits
+        .into_iter()
+        .map(|index_hit| {
+            let mut document = file_document(index_hit.path);
+            index_hit.metadata.fill_document(&mut document);
+            document.summary = index_hit.snippet;
+            (document, index_hit.score)
+        })
+        .collect();
+
+    Ok(hits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extension::built_in::file_search::config::LinuxBackend;
+    use crate::extension::built_in::file_search::filter::Bounds;
+    use tempfile::TempDir;
+
+    fn config(root: &Utf8Path) -> FileSearchConfig {
+        FileSearchConfig {
+            search_paths: vec![root.to_string()],
+            exclude_paths: vec![root.join("excluded").to_string()],
+            file_types: vec![],
+            search_by: SearchBy::Name,
+            linux_backend: LinuxBackend::Auto,
+        }
+    }
+
+    fn create_file(path: &Utf8Path) {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, "content").unwrap();
+    }
+
+    fn setup() -> (TempDir, Utf8PathBuf) {
+        let dir = TempDir::new().unwrap();
+        let root = Utf8PathBuf::from_path_buf(dir.path().to_path_buf()).unwrap();
+        create_file(&root.join("notes.txt"));
+        create_file(&root.join("projects/coco/notes.md"));
+        create_file(&root.join("projects/coco/my_notes_backup.txt"));
+        create_file(&root.join(".hidden/notes.txt"));
+        create_file(&root.join("excluded/notes.txt"));
+        (dir, root)
+    }
+
+    fn index_of(root: &Utf8Path, config: &FileSearchConfig) -> FileIndex {
+        let mut index = FileIndex::new(IndexScope::from(config));
+        index.entries = scan(config, &AtomicBool::new(false)).unwrap();
+        assert!(
+            index
+                .entries
+                .keys()
+                .all(|path| path.starts_with(root.as_str()))
+        );
+        index
+    }
+
+    fn searched_paths(index: &FileIndex, root: &Utf8Path, query: &str) -> Vec<String> {
+        index
+            .search(query, &QueryFilters::default(), 0, 10)
+            .into_iter()
+            .map(|index_hit| index_hit.path[root.as_str().len()..].to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_scan_and_search() {
+        let (_dir, root) = setup();
+        let config = config(&root);
+        let index = index_of(&root, &config);
+
+        assert_eq!(
+            searched_paths(&index, &root, "NOTES"),
+            vec![
+                "/notes.txt",
+                "/projects/coco/notes.md",
+                "/projects/coco/my_notes_backup.txt"
+            ]
+        );
+        assert_eq!(
+            searched_paths(&index, &root, "coco"),
+            vec!["/projects/coco"]
+        );
+        assert!(searched_paths(&index, &root, "hidden").is_empty());
+        assert!(searched_paths(&index, &root, "excluded").is_empty());
+        assert_eq!(
+            index.search("notes", &QueryFilters::default(), 1, 1).len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_scan_with_file_types() {
+        let (_dir, root) = setup();
+        let mut config = config(&root);
+        config.file_types = vec!["md".into()];
+        let index = index_of(&root, &config);
+
+        assert_eq!(
+            searched_paths(&index, &root, "notes"),
+            vec!["/projects/coco/notes.md"]
+        );
+        // Directories don't have the extension
+        assert!(searched_paths(&index, &root, "projects").is_empty());
+    }
+
+    #[test]
+    fn test_search_with_filters() {
+        let (_dir, root) = setup();
+        let config = config(&root);
+        let index = index_of(&root, &config);
+        let searched_paths = |filters: QueryFilters| -> Vec<String> {
+            index
+                .search("notes", &filters, 0, 10)
+                .into_iter()
+                .map(|index_hit| index_hit.path[root.as_str().len()..].to_string())
+                .collect()
+        };
+
+        assert_eq!(
+            searched_paths(QueryFilters {
+                kinds: vec!["txt".into()],
+                paths: vec![root.join("projects").to_string()],
+                ..Default::default()
+            }),
+            vec!["/projects/coco/my_notes_backup.txt"]
+        );
+        // Files are smaller than 1KB
+        assert!(
+            searched_paths(QueryFilters {
+                size: Some(Bounds {
+                    min: Some(1024),
+                    max: None,
+                }),
+                ..Default::default()
+            })
+            .is_empty()
+        );
+
+        // Filters only
+        let mut paths: Vec<String> = index
+            .search(
+                "",
+                &QueryFilters {
+                    kinds: vec!["txt".into()],
+                    ..Default::default()
+                },
+                0,
+                10,
+            )
+            .into_iter()
+            .map(|index_hit| index_hit.path[root.as_str().len()..].to_string())
+            .collect();
+        paths.sort();
+        assert_eq!(
+            paths,
+            vec!["/notes.txt", "/projects/coco/my_notes_backup.txt"]
+        );
+    }
+
+    #[test]
+    fn test_update_path() {
+        let (_dir, root) = setup();
+        let config = config(&root);
+        let mut index = index_of(&root, &config);
+
+        create_file(&root.join("todo.txt"));
+        index.update_path(&config, &root.join("todo.txt"));
+        assert_eq!(searched_paths(&index, &root, "todo"), vec!["/todo.txt"]);
+
+        // A directory moved here along with its contents
+        std::fs::rename(root.join("projects"), root.join("archive")).unwrap();
+        index.update_path(&config, &root.join("projects"));
+        index.update_path(&config, &root.join("archive"));
+        assert_eq!(
+            searched_paths(&index, &root, "notes.md"),
+            vec!["/archive/coco/notes.md"]
+        );
+        assert!(searched_paths(&index, &root, "projects").is_empty());
+
+        // Changes in hidden and excluded directories are ignored
+        create_file(&root.join("excluded/todo.txt"));
+        index.update_path(&config, &root.join("excluded/todo.txt"));
+        assert_eq!(searched_paths(&index, &root, "todo"), vec!["/todo.txt"]);
+
+        std::fs::remove_file(root.join("todo.txt")).unwrap();
+        index.update_path(&config, &root.join("todo.txt"));
+        assert!(searched_paths(&index, &root, "todo").is_empty());
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let (dir, root) = setup();
+        let config = config(&root);
+        let index = index_of(&root, &config);
+        let index_file = dir.path().join("index/index.json");
+
+        index.save(&index_file).unwrap();
+        assert_eq!(
+            FileIndex::load(&index_file, IndexScope::from(&config)),
+            index
+        );
+
+        // Index built for another scope is discarded
+        let mut other_config = config.clone();
+        other_config.file_types = vec!["md".into()];
+        let loaded = FileIndex::load(&index_file, IndexScope::from(&other_config));
+        assert!(loaded.entries.is_empty());
+    }
+
+    #[test]
+    fn test_search_contents() {
+        let (_dir, root) = setup();
+        let mut config = config(&root);
+        config.search_by = SearchBy::NameAndContents;
+        let notes = root.join("projects/coco/notes.md");
+        std::fs::write(&notes, "These notes: Coco searches file contents").unwrap();
+
+        let index = RwLock::new(index_of(&root, &config));
+        assert!(sync_contents(&config, &index, &AtomicBool::new(false)));
+        let mut index = index.into_inner().unwrap();
+        assert!(index.outdated_contents().is_empty());
+
+        assert_eq!(
+            index.search("Searches", &QueryFilters::default(), 0, 10),
+            vec![IndexHit {
+                path: notes.to_string(),
+                score: SCORE_CONTENT,
+                snippet: Some("These notes: Coco searches file contents".into()),
+                metadata: FileMetadata::from(&index.entries[notes.as_str()]),
+            }]
+        );
+        // Matching both name and contents ranks higher
+        assert_eq!(
+            searched_paths(&index, &root, "notes")[..2],
+            ["/projects/coco/notes.md", "/notes.txt"]
+        );
+
+        std::fs::remove_file(&notes).unwrap();
+        index.update_path(&config, &notes);
+        assert!(
+            index
+                .search("searches", &QueryFilters::default(), 0, 10)
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_name_score() {
+        assert_eq!(name_score("notes.txt", "notes"), Some(SCORE_EXACT));
+        assert_eq!(name_score("notes.txt", "notes.txt"), Some(SCORE_EXACT));
+        assert_eq!(name_score("notes_2.txt", "notes"), Some(SCORE_PREFIX));
+        assert_eq!(name_score("my_notes.txt", "notes"), Some(SCORE_SUBSTRING));
+        assert_eq!(name_score("todo.txt", "notes"), None);
+    }
+}
@@ -3,12 +3,16 @@
 use super::super::super::EXTENSION_ID;
 use super::super::super::config::FileSearchConfig;
 use super::super::super::config::SearchBy;
+use super::super::super::filter::{FileMetadata, QueryFilters};
+use super::super::super::pattern::ExcludePattern;
 use super::super::should_be_filtered_out;
+use super::{load_added_items, save_added_items, sync_excluded_items};
 use crate::common::document::{DataSourceReference, Document};
 use crate::extension::LOCAL_QUERY_SOURCE_TYPE;
 use crate::extension::OnOpened;
 use crate::util::file::sync_get_file_icon;
 use camino::Utf8Path;
+use chrono::{DateTime, Local, NaiveDate};
 use configparser::ini::Ini;
 use configparser::ini::WriteOptions;
 use futures::stream::Stream;
@@ -33,14 +37,26 @@ fn cli_tool_lookup() -> Option<PathBuf> {
     res_path.ok()
 }
 
+/// Return true if Baloo's CLI tool is installed.
+pub(super) fn is_available() -> bool {
+    cli_tool_lookup().is_some()
+}
+
 pub(crate) async fn hits(
     query_string: &str,
     _from: usize,
     size: usize,
     config: &FileSearchConfig,
+    filters: &QueryFilters,
 ) -> Result<Vec<(Document, f64)>, String> {
     // Special cases that will make querying faster.
-    if query_string.is_empty() || size == 0 || config.search_paths.is_empty() {
+    if size == 0 || config.search_paths.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    // `in:` filters are outside of the search paths, without this, baloosearch
+    // would search everywhere as no directory is specified.
+    if filters.narrow_search_paths(&config.search_paths).is_empty() {
         return Ok(Vec::new());
     }
 
@@ -50,7 +66,7 @@ pub(crate) async fn hits(
     };
 
     let (mut iter, _baloosearch_child_process) =
-        execute_baloosearch_query(tool_path, query_string, size, config)?;
+        execute_baloosearch_query(tool_path, query_string, size, config, filters)?;
 
     // Convert results to documents
     let mut hits: Vec<(Document, f64)> = Vec::new();
@@ -79,7 +95,7 @@ pub(crate) async fn hits(
             url: file_path.clone(),
         };
 
-        let doc = Document {
+        let mut doc = Document {
             id: file_path.clone(),
             title: Some(file_name.to_string()),
             source: Some(DataSourceReference {
@@ -94,6 +110,9 @@ pub(crate) async fn hits(
             icon: Some(icon.to_string()),
             ..Default::default()
         };
+        if let Some(metadata) = FileMetadata::read(&doc.id) {
+            metadata.fill_document(&mut doc);
+        }
 
         hits.push((doc, SCORE));
     }
@@ -102,11 +121,22 @@ pub(crate) async fn hits(
 }
 
 /// Return an array containing the `baloosearch` command and its arguments.
+///
+/// `modified:` and `in:` filters are translated, with the modification time
+/// rounded to days as Baloo compares dates.  All the filters are checked
+/// against the hits anyway.
 fn build_baloosearch_query(
     tool_path: PathBuf,
     query_string: &str,
     config: &FileSearchConfig,
+    filters: &QueryFilters,
 ) -> Vec<String> {
+    /// Local date of `timestamp`
+    fn date(timestamp: i64) -> Option<NaiveDate> {
+        DateTime::from_timestamp(timestamp, 0)
+            .map(|date_time| date_time.with_timezone(&Local).date_naive())
+    }
+
     let tool_path = tool_path
         .into_os_string()
         .into_string()
@@ -114,17 +144,35 @@ fn build_baloosearch_query(
 
     let mut args = vec![tool_path];
 
-    match config.search_by {
-        SearchBy::Name => {
-            args.push(format!("filename:{query_string}"));
+    if !query_string.is_empty() {
+        match config.search_by {
+            SearchBy::Name => {
+                args.push(format!("filename:{query_string}"));
+            }
+            SearchBy::NameAndContents => {
+                args.push(query_string.to_string());
+            }
+        }
+    } else if filters.modified.is_none() {
+        // Baloo needs at least 1 term, this one matches all the files
+        args.push("modified>=1970-01-01".into());
+    }
+
+    if let Some(modified) = filters.modified {
+        if let Some(min_date) = modified.min.and_then(date) {
+            args.push(format!("modified>={}", min_date.format("%Y-%m-%d")));
         }
-        SearchBy::NameAndContents => {
-            args.push(query_string.to_string());
+        if let Some(next_date) = modified
+            .max
+            .and_then(date)
+            .and_then(|max_date| max_date.succ_opt())
+        {
+            args.push(format!("modified<{}", next_date.format("%Y-%m-%d")));
         }
     }
 
-    for search_path in config.search_paths.iter() {
-        args.extend_from_slice(&["-d".into(), search_path.clone()]);
+    for search_path in filters.narrow_search_paths(&config.search_paths) {
+        args.extend_from_slice(&["-d".into(), search_path]);
     }
 
     args
@@ -144,8 +192,9 @@ fn execute_baloosearch_query(
     query_string: &str,
     size: usize,
     config: &FileSearchConfig,
+    filters: &QueryFilters,
 ) -> Result<(impl Stream<Item = std::io::Result<String>>, Child), String> {
-    let args = build_baloosearch_query(tool_path, query_string, config);
+    let args = build_baloosearch_query(tool_path, query_string, config, filters);
 
     let (rx, tx) = std::io::pipe().unwrap();
     let rx_owned = OwnedFd::from(rx);
@@ -162,11 +211,15 @@ fn execute_baloosearch_query(
         .spawn()
         .map_err(|e| format!("Failed to spawn baloosearch: {e}"))?;
     let config_clone = config.clone();
+    let filters_clone = filters.clone();
     let iter = lines
         .filter(move |res_path| {
             std::future::ready({
                 match res_path {
-                    Ok(path) => !should_be_filtered_out(&config_clone, path, false, true, true),
+                    Ok(path) => {
+                        !should_be_filtered_out(&config_clone, path, false, true, true)
+                            && filters_clone.matches_file(path)
+                    }
                     Err(_) => {
                         // Don't filter out Err() values
                         true
@@ -187,6 +240,7 @@ pub(crate) fn apply_config(config: &FileSearchConfig) -> Result<(), String> {
     // * only basic indexing: If true, Baloo only indexes file names
     // * folders: directories to index
     // * exclude folders: directories to skip
+    // * exclude filters: file name patterns to skip
     //
     // ```ini
     // [Basic Settings]
@@ -196,12 +250,22 @@ pub(crate) fn apply_config(config: &FileSearchConfig) -> Result<(), String> {
     // only basic indexing=true
     // folders[$e]=$HOME/
     // exclude folders[$e]=$HOME/FolderA/,$HOME/FolderB/
+    // exclude filters=*~,*.part,node_modules
     // ```
+    //
+    // Exclude patterns that are absolute paths go to "exclude folders", the
+    // ones matching file names go to "exclude filters".  The entries we added
+    // are recorded, and removed once they are no longer excluded, as this file
+    // is shared with the whole desktop.  All the exclude patterns, and file
+    // types, are post-filtered in `hits()` anyway.
 
     const SECTION_GENERAL: &str = "General";
     const KEY_INCLUDE_FOLDERS: &str = "folders[$e]";
     const KEY_EXCLUDE_FOLDERS: &str = "exclude folders[$e]";
+    const KEY_EXCLUDE_FILTERS: &str = "exclude filters";
     const FOLDERS_SEPARATOR: &str = ",";
+    const STORE_KEY_EXCLUDE_FOLDERS: &str = "baloo:exclude folders";
+    const STORE_KEY_EXCLUDE_FILTERS: &str = "baloo:exclude filters";
 
     let rc_file_path = {
         let mut home = dirs::home_dir()
@@ -286,6 +350,51 @@ pub(crate) fn apply_config(config: &FileSearchConfig) -> Result<(), String> {
         ensure_path_and_descendants_not_excluded(&mut exclude_folders, search_path);
     }
 
+    // If "exclude filters" is not set, Baloo uses its default filters, which
+    // would be lost if we set it, so we only add to it when it is set.
+    let opt_exclude_filters_str = baloo_config.get(SECTION_GENERAL, KEY_EXCLUDE_FILTERS);
+    let mut opt_exclude_filters = opt_exclude_filters_str.map(|str| {
+        str.split(FOLDERS_SEPARATOR)
+            .map(|str| str.to_string())
+            .collect::<Vec<String>>()
+    });
+
+    let mut excluded_folders = Vec::new();
+    let mut excluded_names = Vec::new();
+    for exclude_path in config.exclude_paths.iter() {
+        match ExcludePattern::parse(exclude_path) {
+            // Excluding an ancestor of a search path would exclude the search
+            // path as well
+            Ok(ExcludePattern::Path(path))
+                if !config
+                    .search_paths
+                    .iter()
+                    .any(|search_path| Utf8Path::new(search_path).starts_with(&path)) =>
+            {
+                excluded_folders.push(path)
+            }
+            // The separator cannot be escaped
+            Ok(ExcludePattern::Name(name)) if !name.contains(FOLDERS_SEPARATOR) => {
+                excluded_names.push(name)
+            }
+            _ => {}
+        }
+    }
+
+    let added_folders = sync_excluded_items(
+        &mut exclude_folders,
+        &load_added_items(STORE_KEY_EXCLUDE_FOLDERS),
+        &excluded_folders,
+    )?;
+    let added_filters = match opt_exclude_filters.as_mut() {
+        Some(exclude_filters) => sync_excluded_items(
+            exclude_filters,
+            &load_added_items(STORE_KEY_EXCLUDE_FILTERS),
+            &excluded_names,
+        )?,
+        None => Vec::new(),
+    };
+
     let include_folders_str: String = include_folders.as_slice().join(FOLDERS_SEPARATOR);
     let exclude_folders_str: String = exclude_folders.as_slice().join(FOLDERS_SEPARATOR);
 
@@ -299,10 +408,79 @@ pub(crate) fn apply_config(config: &FileSearchConfig) -> Result<(), String> {
         KEY_EXCLUDE_FOLDERS,
         Some(exclude_folders_str),
     );
+    if let Some(exclude_filters) = opt_exclude_filters {
+        let _ = baloo_config.set(
+            SECTION_GENERAL,
+            KEY_EXCLUDE_FILTERS,
+            Some(exclude_filters.as_slice().join(FOLDERS_SEPARATOR)),
+        );
+    }
 
     baloo_config
         .pretty_write(rc_file_path.as_path(), &WriteOptions::new())
         .map_err(|e| e.to_string())?;
 
+    save_added_items(STORE_KEY_EXCLUDE_FOLDERS, &added_folders)?;
+    save_added_items(STORE_KEY_EXCLUDE_FILTERS, &added_filters)?;
+
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extension::built_in::file_search::config::LinuxBackend;
+    use crate::extension::built_in::file_search::filter::Bounds;
+
+    #[test]
+    fn test_build_baloosearch_query_with_filters() {
+        let config = FileSearchConfig {
+            search_paths: vec!["/home/user".into(), "/data".into()],
+            exclude_paths: vec![],
+            file_types: vec![],
+            search_by: SearchBy::Name,
+            linux_backend: LinuxBackend::Auto,
+        };
+        let filters = QueryFilters {
+            modified: Some(Bounds {
+                min: Some(1_700_000_000),
+                max: None,
+            }),
+            size: None,
+            kinds: vec![],
+            paths: vec!["/home/user/Documents".into()],
+        };
+        let min_date = DateTime::from_timestamp(1_700_000_000, 0)
+            .unwrap()
+            .with_timezone(&Local)
+            .date_naive();
+
+        assert_eq!(
+            build_baloosearch_query(PathBuf::from("baloosearch"), "report", &config, &filters),
+            vec![
+                "baloosearch".to_string(),
+                "filename:report".to_string(),
+                format!("modified>={}", min_date.format("%Y-%m-%d")),
+                "-d".to_string(),
+                "/home/user/Documents".to_string(),
+            ]
+        );
+        // Filters only
+        assert_eq!(
+            build_baloosearch_query(PathBuf::from("baloosearch"), "", &config, &filters)[1],
+            format!("modified>={}", min_date.format("%Y-%m-%d"))
+        );
+        assert_eq!(
+            build_baloosearch_query(
+                PathBuf::from("baloosearch"),
+                "",
+                &config,
+                &QueryFilters {
+                    kinds: vec!["pdf".into()],
+                    ..Default::default()
+                }
+            )[1],
+            "modified>=1970-01-01"
+        );
+    }
+}
@@ -0,0 +1,381 @@
+//! File search powered by the plocate/mlocate database.
+//!
+//! The database is updated periodically by `updatedb`, which runs as root and
+//! is configured in `/etc/updatedb.conf`, so we cannot change what it indexes.
+//! The search paths are put into the patterns so that only the files in them
+//! are matched, then the file names are checked, and the hits are filtered by
+//! the config and query filters.
+
+use super::super::super::EXTENSION_ID;
+use super::super::super::config::FileSearchConfig;
+use super::super::super::filter::{FileMetadata, QueryFilters};
+use super::super::should_be_filtered_out;
+use crate::common::document::{DataSourceReference, Document, OnOpened};
+use crate::extension::LOCAL_QUERY_SOURCE_TYPE;
+use crate::util::file::sync_get_file_icon;
+use camino::Utf8Path;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::LazyLock;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::process::Command;
+
+/// locate does not support scoring, use this score for all the documents.
+const SCORE: f64 = 1.0;
+
+/// plocate is preferred as it is much faster than mlocate.  `locate` could be
+/// either of them, or another implementation that does not support the
+/// options we use, so the tools are probed.
+const CLI_TOOLS: &[&str] = &["plocate", "mlocate", "locate"];
+
+static CLI_TOOL: LazyLock<Option<PathBuf>> = LazyLock::new(|| {
+    use which::which;
+
+    CLI_TOOLS
+        .iter()
+        .filter_map(|tool| which(tool).ok())
+        .find(|tool_path| is_supported(tool_path))
+});
+
+/// Default database paths of plocate and mlocate.
+const DEFAULT_DATABASES: &[&str] = &["/var/lib/plocate/plocate.db", "/var/lib/mlocate/mlocate.db"];
+
+fn cli_tool_lookup() -> Option<PathBuf> {
+    CLI_TOOL.clone()
+}
+
+/// Return true if the locate at `tool_path` is plocate or mlocate.
+fn is_supported(tool_path: &Path) -> bool {
+    std::process::Command::new(tool_path)
+        .arg("--version")
+        .stderr(Stdio::null())
+        .output()
+        .is_ok_and(|output| {
+            let version = String::from_utf8_lossy(&output.stdout);
+            version.contains("plocate") || version.contains("mlocate")
+        })
+}
+
+/// Return true if locate is installed and its database exists.
+pub(super) fn is_available() -> bool {
+    cli_tool_lookup().is_some()
+        && DEFAULT_DATABASES
+            .iter()
+            .any(|database| Path::new(database).exists())
+}
+
+/// Escape the glob metacharacters in `str`.
+fn escape_glob(str: &str) -> String {
+    let mut escaped = String::with_capacity(str.len());
+    for char in str.chars() {
+        if matches!(char, '*' | '?' | '[' | ']' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(char);
+    }
+
+    escaped
+}
+
+/// Return an array containing the `locate` command and its arguments.
+///
+/// There is a pattern for each of the `search_paths`, matching the paths
+/// under it that contain `query_string`.  The match could be in the names of
+/// the parent directories, the caller should check the file names.
+fn build_locate_query(
+    tool_path: &Path,
+    opt_database: Option<&Path>,
+    query_string: &str,
+    search_paths: &[String],
+) -> Vec<String> {
+    let tool_path = tool_path
+        .to_str()
+        .expect("binary path should be UTF-8 encoded")
+        .to_string();
+
+    let mut args = vec![
+        tool_path,
+        "--ignore-case".into(),
+        // Skip the files that have been removed since the last updatedb
+        "--existing".into(),
+        // File names may contain newlines
+        "--null".into(),
+    ];
+    if let Some(database) = opt_database {
+        let database = database
+            .to_str()
+            .expect("database path should be UTF-8 encoded");
+        args.extend_from_slice(&["--database".into(), database.into()]);
+    }
+    args.push("--".into());
+    // plocate narrows the candidates with the literal parts of the globs, by
+    // its trigram index.
+    for search_path in search_paths {
+        args.push(format!(
+            "{}/*{}*",
+            escape_glob(search_path.trim_end_matches('/')),
+            escape_glob(query_string)
+        ));
+    }
+
+    args
+}
+
+/// Run locate against `opt_database`, or the default database if `None`, and
+/// return the paths of the matched files.
+async fn locate(
+    tool_path: &Path,
+    opt_database: Option<&Path>,
+    query_string: &str,
+    from: usize,
+    size: usize,
+    config: &FileSearchConfig,
+    filters: &QueryFilters,
+) -> Result<Vec<String>, String> {
+    let search_paths = filters.narrow_search_paths(&config.search_paths);
+    if search_paths.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let args = build_locate_query(tool_path, opt_database, query_string, &search_paths);
+    let mut child = Command::new(&args[0])
+        .args(&args[1..])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        // The child process will be killed when the Child instance gets
+        // dropped, i.e., we have collected enough files.
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("Failed to spawn locate: {e}"))?;
+    let stdout = child.stdout.take().expect("stdout should be piped");
+    let mut segments = BufReader::new(stdout).split(b'\0');
+
+    let query = query_string.to_lowercase();
+    let mut num_skipped = 0;
+    let mut paths = Vec::with_capacity(size);
+    while let Some(segment) = segments.next_segment().await.map_err(|e| e.to_string())? {
+        // Paths that are not UTF-8 encoded are not supported
+        let Ok(path) = String::from_utf8(segment) else {
+            continue;
+        };
+        let name_matched = Utf8Path::new(&path)
+            .file_name()
+            .is_some_and(|file_name| file_name.to_lowercase().contains(&query));
+        if !name_matched
+            || should_be_filtered_out(config, &path, true, true, true)
+            || !filters.matches_file(&path)
+        {
+            continue;
+        }
+        if num_skipped < from {
+            num_skipped += 1;
+            continue;
+        }
+
+        paths.push(path);
+        if paths.len() >= size {
+            break;
+        }
+    }
+
+    Ok(paths)
+}
+
+pub(crate) async fn hits(
+    query_string: &str,
+    from: usize,
+    size: usize,
+    config: &FileSearchConfig,
+    filters: &QueryFilters,
+) -> Result<Vec<(Document, f64)>, String> {
+    // Special cases that will make querying faster.
+    if size == 0 || config.search_paths.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let Some(tool_path) = cli_tool_lookup() else {
+        return Err("neither plocate nor mlocate is installed".into());
+    };
+
+    let paths = locate(&tool_path, None, query_string, from, size, config, filters).await?;
+
+    // Convert results to documents
+    let mut hits: Vec<(Document, f64)> = Vec::with_capacity(paths.len());
+    for file_path in paths {
+        let icon = sync_get_file_icon(&file_path);
+        let file_path_of_type_path = camino::Utf8Path::new(&file_path);
+        let r#where = file_path_of_type_path
+            .parent()
+            .unwrap_or_else(|| {
+                panic!(
+                    "expect path [{}] to have a parent, but it does not",
+                    file_path
+                );
+            })
+            .to_string();
+
+        let file_name = file_path_of_type_path.file_name().unwrap_or_else(|| {
+            panic!(
+                "expect path [{}] to have a file name, but it does not",
+                file_path
+            );
+        });
+        let on_opened = OnOpened::Document {
+            url: file_path.clone(),
+        };
+
+        let mut doc = Document {
+            id: file_path.clone(),
+            title: Some(file_name.to_string()),
+            source: Some(DataSourceReference {
+                r#type: Some(LOCAL_QUERY_SOURCE_TYPE.into()),
+                name: Some(EXTENSION_ID.into()),
+                id: Some(EXTENSION_ID.into()),
+                icon: Some(String::from("font_Filesearch")),
+            }),
+            category: Some(r#where),
+            on_opened: Some(on_opened),
+            url: Some(file_path),
+            icon: Some(icon.to_string()),
+            ..Default::default()
+        };
+        if let Some(metadata) = FileMetadata::read(&doc.id) {
+            metadata.fill_document(&mut doc);
+        }
+
+        hits.push((doc, SCORE));
+    }
+
+    Ok(hits)
+}
+
+pub(crate) fn apply_config(_config: &FileSearchConfig) -> Result<(), String> {
+    // The scope of the database is configured in `/etc/updatedb.conf`, which
+    // requires root to modify, and it covers the whole file system by default.
+    // The config is applied by filtering the hits.
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extension::built_in::file_search::config::{LinuxBackend, SearchBy};
+    use tempfile::TempDir;
+
+    /// Index `root` into a database at `database`.
+    fn updatedb(updatedb_path: &Path, root: &Path, database: &Path) {
+        let status = std::process::Command::new(updatedb_path)
+            // Don't require root, and don't use the settings in
+            // `/etc/updatedb.conf`, which usually prune `/tmp`.
+            .args(["--require-visibility", "no"])
+            .args(["--prunepaths", ""])
+            .args(["--prunefs", ""])
+            .args(["--prunenames", ""])
+            .arg("--database-root")
+            .arg(root)
+            .arg("--output")
+            .arg(database)
+            .status()
+            .expect("failed to run updatedb");
+        assert!(status.success(), "updatedb failed with [{}]", status);
+    }
+
+    #[test]
+    fn test_build_locate_query() {
+        assert_eq!(
+            build_locate_query(
+                Path::new("/usr/bin/plocate"),
+                Some(Path::new("/tmp/test.db")),
+                "a*b[1]",
+                &["/home/user/".into(), "/data/[x]".into()]
+            ),
+            vec![
+                "/usr/bin/plocate",
+                "--ignore-case",
+                "--existing",
+                "--null",
+                "--database",
+                "/tmp/test.db",
+                "--",
+                r"/home/user/*a\*b\[1\]*",
+                r"/data/\[x\]/*a\*b\[1\]*",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_locate() {
+        // Requires plocate or mlocate, and updatedb
+        let (Some(tool_path), Ok(updatedb_path)) = (cli_tool_lookup(), which::which("updatedb"))
+        else {
+            eprintln!("skipping test_locate, locate or updatedb is not installed");
+            return;
+        };
+
+        let dir = TempDir::new().unwrap();
+        let root = dir.path().join("root");
+        for file in [
+            "Documents/Notes.md",
+            "Documents/notes_dir/readme.md",
+            "Documents/notes.txt",
+            "Documents/cache/notes.txt",
+            "Downloads/notes.md",
+            "Documents/removed_notes.md",
+        ] {
+            let path = root.join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, "notes").unwrap();
+        }
+        let database = dir.path().join("locate.db");
+        updatedb(&updatedb_path, &root, &database);
+        std::fs::remove_file(root.join("Documents/removed_notes.md")).unwrap();
+
+        let root = root.to_str().unwrap();
+        let config = FileSearchConfig {
+            search_paths: vec![format!("{}/Documents", root)],
+            exclude_paths: vec!["cache".into()],
+            file_types: vec![],
+            search_by: SearchBy::Name,
+            linux_backend: LinuxBackend::Locate,
+        };
+        let locate = async |filters: &QueryFilters, from: usize, size: usize| {
+            let mut paths = locate(
+                &tool_path,
+                Some(&database),
+                "NOTES",
+                from,
+                size,
+                &config,
+                filters,
+            )
+            .await
+            .unwrap();
+            paths.sort();
+            paths
+        };
+
+        assert_eq!(
+            locate(&QueryFilters::default(), 0, 10).await,
+            vec![
+                format!("{}/Documents/Notes.md", root),
+                format!("{}/Documents/notes.txt", root),
+                // Directories are in the database as well
+                format!("{}/Documents/notes_dir", root),
+            ]
+        );
+        assert_eq!(locate(&QueryFilters::default(), 1, 10).await.len(), 2);
+        assert_eq!(locate(&QueryFilters::default(), 0, 1).await.len(), 1);
+        assert_eq!(
+            locate(
+                &QueryFilters {
+                    kinds: vec!["txt".into()],
+                    ..Default::default()
+                },
+                0,
+                10
+            )
+            .await,
+            vec![format!("{}/Documents/notes.txt", root)]
+        );
+    }
+}
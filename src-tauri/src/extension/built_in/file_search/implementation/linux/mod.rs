@@ -1,15 +1,60 @@
 mod gnome;
+mod indexer;
 mod kde;
+mod locate;
 
-use super::super::config::FileSearchConfig;
+use super::super::config::{FileSearchConfig, LinuxBackend};
+use super::super::filter::QueryFilters;
+use crate::GLOBAL_TAURI_APP_HANDLE;
 use crate::common::document::Document;
 use crate::util::LinuxDesktopEnvironment;
 use crate::util::get_linux_desktop_environment;
-use std::ops::Deref;
 use std::sync::LazyLock;
+use tauri_plugin_store::StoreExt;
 
-static DESKTOP_ENVIRONMENT: LazyLock<Option<LinuxDesktopEnvironment>> =
-    LazyLock::new(|| get_linux_desktop_environment());
+/// Tauri store of the items that we added to the exclusion lists of the system
+/// indexers, keyed by the backend and the list.
+const TAURI_STORE_ADDED_EXCLUSIONS: &str = "file_search_added_exclusions";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Backend {
+    Gnome,
+    Kde,
+    Locate,
+    BuiltInIndexer,
+}
+
+/// The backend used by `LinuxBackend::Auto`.  Checked only once so that we
+/// won't switch between backends, e.g., between the locate database and the
+/// built-in indexer after the latter is started.
+static AUTO_BACKEND: LazyLock<Backend> = LazyLock::new(|| {
+    // The system indexer of the desktop environment may not be installed or
+    // running, so it is probed before being chosen.
+    let opt_system_indexer = match get_linux_desktop_environment() {
+        Some(LinuxDesktopEnvironment::Gnome) if gnome::is_available() => Some(Backend::Gnome),
+        Some(LinuxDesktopEnvironment::Kde) if kde::is_available() => Some(Backend::Kde),
+        _ => None,
+    };
+    // Otherwise, use the locate database, or our own indexer
+    let backend = opt_system_indexer.unwrap_or_else(|| {
+        if locate::is_available() {
+            Backend::Locate
+        } else {
+            Backend::BuiltInIndexer
+        }
+    });
+    log::info!("file search backend: {:?}", backend);
+
+    backend
+});
+
+fn backend(config: &FileSearchConfig) -> Backend {
+    match config.linux_backend {
+        LinuxBackend::Locate => Backend::Locate,
+        LinuxBackend::BuiltInIndexer => Backend::BuiltInIndexer,
+        LinuxBackend::Auto => *AUTO_BACKEND,
+    }
+}
 
 /// Dispatch to implementations powered by different backends.
 pub(crate) async fn hits(
@@ -17,34 +62,121 @@ pub(crate) async fn hits(
     from: usize,
     size: usize,
     config: &FileSearchConfig,
+    filters: &QueryFilters,
 ) -> Result<Vec<(Document, f64)>, String> {
-    let de = DESKTOP_ENVIRONMENT.deref();
-    match de {
-        Some(LinuxDesktopEnvironment::Gnome) => gnome::hits(query_string, from, size, config).await,
-        Some(LinuxDesktopEnvironment::Kde) => kde::hits(query_string, from, size, config).await,
-        Some(LinuxDesktopEnvironment::Unsupported {
-            xdg_current_desktop: _,
-        }) => {
-            return Err("file search is not supported on this desktop environment".into());
-        }
-        None => {
-            return Err("could not determine Linux desktop environment".into());
-        }
+    match backend(config) {
+        Backend::Gnome => gnome::hits(query_string, from, size, config, filters).await,
+        Backend::Kde => kde::hits(query_string, from, size, config, filters).await,
+        Backend::Locate => locate::hits(query_string, from, size, config, filters).await,
+        Backend::BuiltInIndexer => indexer::hits(query_string, from, size, config, filters).await,
     }
 }
 
 pub(crate) fn apply_config(config: &FileSearchConfig) -> Result<(), String> {
-    let de = DESKTOP_ENVIRONMENT.deref();
-    match de {
-        Some(LinuxDesktopEnvironment::Gnome) => gnome::apply_config(config),
-        Some(LinuxDesktopEnvironment::Kde) => kde::apply_config(config),
-        Some(LinuxDesktopEnvironment::Unsupported {
-            xdg_current_desktop: _,
-        }) => {
-            return Err("file search is not supported on this desktop environment".into());
-        }
-        None => {
-            return Err("could not determine Linux desktop environment".into());
+    let backend = backend(config);
+    // The built-in indexer could be chosen by the previous config
+    if backend != Backend::BuiltInIndexer {
+        indexer::stop();
+    }
+
+    match backend {
+        Backend::Gnome => gnome::apply_config(config),
+        Backend::Kde => kde::apply_config(config),
+        Backend::Locate => locate::apply_config(config),
+        Backend::BuiltInIndexer => indexer::apply_config(config),
+    }
+}
+
+/// Stop the built-in indexer, if it is running.  System indexers keep running
+/// as they are not managed by us.
+pub(crate) fn shutdown() {
+    indexer::stop();
+}
+
+/// Add the `excluded` items to `list`, an exclusion list of a system indexer
+/// that is shared with the whole desktop, and remove the items that we added
+/// before, `previously_added`, but are no longer excluded.  Items added by
+/// others are left untouched.
+///
+/// Return the items that are in `list` because of us.
+fn sync_excluded_items(
+    list: &mut Vec<String>,
+    previously_added: &[String],
+    excluded: &[String],
+) -> Result<Vec<String>, String> {
+    if let Some(item) = excluded.iter().find(|item| item.contains('\0')) {
+        return Err(format!(
+            "exclude pattern [{}] contains a NUL character",
+            item
+        ));
+    }
+
+    list.retain(|item| !previously_added.contains(item) || excluded.contains(item));
+
+    let mut added = Vec::new();
+    for item in excluded {
+        if !list.contains(item) {
+            list.push(item.clone());
+            added.push(item.clone());
+        } else if previously_added.contains(item) {
+            added.push(item.clone());
         }
     }
+
+    Ok(added)
+}
+
+/// The items that we added to the exclusion list `key`.
+fn load_added_items(key: &str) -> Vec<String> {
+    GLOBAL_TAURI_APP_HANDLE
+        .get()
+        .expect("global tauri app handle not set")
+        .store(TAURI_STORE_ADDED_EXCLUSIONS)
+        .ok()
+        .and_then(|store| store.get(key))
+        .and_then(|json| serde_json::from_value(json).ok())
+        .unwrap_or_default()
+}
+
+fn save_added_items(key: &str, items: &[String]) -> Result<(), String> {
+    GLOBAL_TAURI_APP_HANDLE
+        .get()
+        .expect("global tauri app handle not set")
+        .store(TAURI_STORE_ADDED_EXCLUSIONS)
+        .map_err(|e| e.to_string())?
+        .set(key, items);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sync_excluded_items() {
+        let strings = |items: &[&str]| {
+            items
+                .iter()
+                .map(|item| item.to_string())
+                .collect::<Vec<_>>()
+        };
+
+        let mut list = strings(&["po", "*.o"]);
+        let added =
+            sync_excluded_items(&mut list, &[], &strings(&["node_modules", "*.o"])).unwrap();
+        assert_eq!(list, strings(&["po", "*.o", "node_modules"]));
+        assert_eq!(added, strings(&["node_modules"]));
+
+        // "*.o" was not added by us
+        let added = sync_excluded_items(&mut list, &added, &strings(&["*.tmp"])).unwrap();
+        assert_eq!(list, strings(&["po", "*.o", "*.tmp"]));
+        assert_eq!(added, strings(&["*.tmp"]));
+
+        let added = sync_excluded_items(&mut list, &added, &strings(&["*.tmp"])).unwrap();
+        assert_eq!(list, strings(&["po", "*.o", "*.tmp"]));
+        assert_eq!(added, strings(&["*.tmp"]));
+
+        assert!(sync_excluded_items(&mut list, &added, &strings(&["a\0b"])).is_err());
+    }
 }
@@ -1,6 +1,7 @@
 use super::super::EXTENSION_ID;
 use super::super::config::FileSearchConfig;
 use super::super::config::SearchBy;
+use super::super::filter::{FileMetadata, QueryFilters};
 use super::should_be_filtered_out;
 use crate::common::document::{DataSourceReference, Document};
 use crate::extension::LOCAL_QUERY_SOURCE_TYPE;
@@ -24,9 +25,10 @@ pub(crate) async fn hits(
     from: usize,
     size: usize,
     config: &FileSearchConfig,
+    filters: &QueryFilters,
 ) -> Result<Vec<(Document, f64)>, String> {
     let (mut iter, _mdfind_child_process) =
-        execute_mdfind_query(&query_string, from, size, &config)?;
+        execute_mdfind_query(&query_string, from, size, &config, filters)?;
 
     // Convert results to documents
     let mut hits: Vec<(Document, f64)> = Vec::new();
@@ -55,7 +57,7 @@ pub(crate) async fn hits(
             url: file_path.clone(),
         };
 
-        let doc = Document {
+        let mut doc = Document {
             id: file_path.clone(),
             title: Some(file_name.to_string()),
             source: Some(DataSourceReference {
@@ -70,6 +72,9 @@ pub(crate) async fn hits(
             icon: Some(icon.to_string()),
             ..Default::default()
         };
+        if let Some(metadata) = FileMetadata::read(&doc.id) {
+            metadata.fill_document(&mut doc);
+        }
 
         hits.push((doc, SCORE));
     }
@@ -82,6 +87,10 @@ fn build_mdfind_query(query_string: &str, config: &FileSearchConfig) -> Vec<Stri
     let mut args = vec!["mdfind".to_string()];
 
     match config.search_by {
+        // Query filters only, match all the files
+        _ if query_string.is_empty() => {
+            args.push("kMDItemFSName == '*'".into());
+        }
         SearchBy::Name => {
             // The tailing char 'c' makes the search case-insensitive.
             //
@@ -132,6 +141,7 @@ fn execute_mdfind_query(
     from: usize,
     size: usize,
     config: &FileSearchConfig,
+    filters: &QueryFilters,
 ) -> Result<(impl Stream<Item = std::io::Result<String>>, Child), String> {
     let args = build_mdfind_query(query_string, &config);
     let (rx, tx) = std::io::pipe().unwrap();
@@ -148,11 +158,16 @@ fn execute_mdfind_query(
         .spawn()
         .map_err(|e| format!("Failed to spawn mdfind: {}", e))?;
     let config_clone = config.clone();
+    // Query filters are not translated to the mdfind query
+    let filters_clone = filters.clone();
     let iter = lines
         .filter(move |res_path| {
             std::future::ready({
                 match res_path {
-                    Ok(path) => !should_be_filtered_out(&config_clone, path, false, true, true),
+                    Ok(path) => {
+                        !should_be_filtered_out(&config_clone, path, false, true, true)
+                            && filters_clone.matches_file(path)
+                    }
                     Err(_) => {
                         // Don't filter out Err() values
                         true
@@ -186,5 +201,8 @@ pub(crate) fn apply_config(_: &FileSearchConfig) -> Result<(), String> {
     //       since macOS Mojave)
     //
     // There is nothing we can do.
+    //
+    // Exclude patterns and file types cannot be applied to Spotlight either,
+    // they are post-filtered in `hits()`.
     Ok(())
 }
@@ -1,15 +1,23 @@
 use cfg_if::cfg_if;
 
-// * hits: the implementation of search
+// * hits: the implementation of search.  Query filters should be pushed
+//   into the backend query where possible, and checked against the hits
+//   otherwise.  The `size` and `updated` fields of the hit documents should be
+//   filled in.  An empty query string, i.e., the query has filters only,
+//   matches all the files.
 //
 // * apply_config: Routines that should be performed to keep "other things"
 //   synchronous with the passed configuration.
-//   Currently, "other things" only include system indexer's setting entries.
+//   Currently, "other things" include system indexer's setting entries, and
+//   the built-in indexer on Linux.
+//
+// * shutdown (Linux only): stop the built-in indexer
 cfg_if! {
     if #[cfg(target_os = "linux")] {
         mod linux;
         pub(crate) use linux::hits;
         pub(crate) use linux::apply_config;
+        pub(crate) use linux::shutdown;
     } else if #[cfg(target_os = "macos")] {
         mod macos;
         pub(crate) use macos::hits;
@@ -21,16 +29,15 @@ cfg_if! {
     }
 }
 
-cfg_if! {
-    if #[cfg(not(target_os = "windows"))] {
-        use super::config::FileSearchConfig;
-        use camino::Utf8Path;
-    }
-}
+use super::config::FileSearchConfig;
+use super::pattern::{ExcludeMatcher, matches_file_types};
+use camino::Utf8Path;
 
 /// If `file_path` should be removed from the search results given the filter
 /// conditions specified in `config`.
-#[cfg(not(target_os = "windows"))] // Not used on Windows
+///
+/// `exclude_paths` and `file_types` can be patterns and groups, see the
+/// [`pattern`](super::pattern) module.
 pub(crate) fn should_be_filtered_out(
     config: &FileSearchConfig,
     file_path: &str,
@@ -51,12 +58,9 @@ pub(crate) fn should_be_filtered_out(
         }
     }
 
-    if check_exclude_paths {
+    if check_exclude_paths && !config.exclude_paths.is_empty() {
         // exclude path
-        let is_excluded = config
-            .exclude_paths
-            .iter()
-            .any(|exclude_path| file_path.starts_with(exclude_path));
+        let is_excluded = ExcludeMatcher::get(config).is_excluded(file_path.as_str());
         if is_excluded {
             return true;
         }
@@ -69,10 +73,7 @@ pub(crate) fn should_be_filtered_out(
         } else {
             let path_obj = camino::Utf8Path::new(&file_path);
             if let Some(extension) = path_obj.extension() {
-                config
-                    .file_types
-                    .iter()
-                    .any(|file_type| file_type == extension)
+                matches_file_types(&config.file_types, extension)
             } else {
                 // `config.file_types` is not empty, the hit files should have extensions.
                 false
@@ -87,10 +88,10 @@ pub(crate) fn should_be_filtered_out(
     false
 }
 
-// should_be_filtered_out() is not defined for Windows
+// Paths in the tests are Unix paths
 #[cfg(all(test, not(target_os = "windows")))]
 mod tests {
-    use super::super::config::SearchBy;
+    use super::super::config::{LinuxBackend, SearchBy};
     use super::*;
 
     #[test]
@@ -100,6 +101,7 @@ mod tests {
             exclude_paths: vec![],
             file_types: vec!["fffffff".into()],
             search_by: SearchBy::Name,
+            linux_backend: LinuxBackend::Auto,
         };
 
         assert!(!should_be_filtered_out(
@@ -117,6 +119,7 @@ mod tests {
             exclude_paths: vec![],
             file_types: vec![],
             search_by: SearchBy::Name,
+            linux_backend: LinuxBackend::Auto,
         };
 
         // Files in search paths should not be filtered
@@ -176,6 +179,7 @@ mod tests {
             ],
             file_types: vec![],
             search_by: SearchBy::Name,
+            linux_backend: LinuxBackend::Auto,
         };
 
         // Files in search paths but not excluded should not be filtered
@@ -225,6 +229,7 @@ mod tests {
             exclude_paths: vec![],
             file_types: vec!["txt".to_string(), "md".to_string()],
             search_by: SearchBy::Name,
+            linux_backend: LinuxBackend::Auto,
         };
 
         // Files with allowed extensions should not be filtered
@@ -283,6 +288,7 @@ mod tests {
             exclude_paths: vec![],
             file_types: vec![],
             search_by: SearchBy::Name,
+            linux_backend: LinuxBackend::Auto,
         };
 
         // When file_types is empty, all file types should be allowed
@@ -323,6 +329,7 @@ mod tests {
             exclude_paths: vec!["/home/user/Trash".to_string()],
             file_types: vec!["txt".to_string()],
             search_by: SearchBy::Name,
+            linux_backend: LinuxBackend::Auto,
         };
 
         // Should pass all filters: in search path, not excluded, and correct file type
@@ -369,6 +376,7 @@ mod tests {
             exclude_paths: vec![],
             file_types: vec!["txt".to_string()],
             search_by: SearchBy::Name,
+            linux_backend: LinuxBackend::Auto,
         };
 
         // Empty path
@@ -393,4 +401,55 @@ mod tests {
             true
         ));
     }
+
+    #[test]
+    fn test_should_be_filtered_out_patterns_and_groups() {
+        let config = FileSearchConfig {
+            search_paths: vec!["/home/user".to_string()],
+            exclude_paths: vec!["**/node_modules".to_string(), "*.tmp".to_string()],
+            file_types: vec!["images".to_string(), "PDF".to_string()],
+            search_by: SearchBy::Name,
+            linux_backend: LinuxBackend::Auto,
+        };
+
+        assert!(!should_be_filtered_out(
+            &config,
+            "/home/user/photo.JPG",
+            true,
+            true,
+            true
+        ));
+        assert!(!should_be_filtered_out(
+            &config,
+            "/home/user/paper.pdf",
+            true,
+            true,
+            true
+        ));
+
+        // Excluded by patterns
+        assert!(should_be_filtered_out(
+            &config,
+            "/home/user/app/node_modules/logo.png",
+            true,
+            true,
+            true
+        ));
+        assert!(should_be_filtered_out(
+            &config,
+            "/home/user/cache.tmp/logo.png",
+            true,
+            true,
+            true
+        ));
+
+        // Not in the groups
+        assert!(should_be_filtered_out(
+            &config,
+            "/home/user/main.rs",
+            true,
+            true,
+            true
+        ));
+    }
 }
@@ -8,10 +8,14 @@ mod crawl_scope_manager;
 use super::super::EXTENSION_ID;
 use super::super::config::FileSearchConfig;
 use super::super::config::SearchBy;
+use super::super::filter::{FileMetadata, QueryFilters};
+use super::super::pattern::{ExcludePattern, expand_file_types};
+use super::should_be_filtered_out;
 use crate::common::document::{DataSourceReference, Document};
 use crate::extension::LOCAL_QUERY_SOURCE_TYPE;
 use crate::extension::OnOpened;
 use crate::util::file::sync_get_file_icon;
+use chrono::DateTime;
 use std::borrow::Borrow;
 use std::path::PathBuf;
 use windows::{
@@ -76,56 +80,113 @@ fn query_string_cleanup(old: &str) -> String {
     chars.into_iter().collect()
 }
 
+/// If the hits need to be post-filtered, this many times `from + size` hits are
+/// fetched, and the factor by which it grows if a page is still short.
+const POST_FILTERING_OVER_FETCH: usize = 4;
+/// Fetching grows up to this many hits, pages can be short beyond it.
+const POST_FILTERING_MAX_TOP_N: usize = 10_000;
+
+/// Return true if some exclude patterns in `config` cannot be expressed in
+/// Windows Search SQL, the hits need to be post-filtered then.
+fn needs_post_filtering(config: &FileSearchConfig) -> bool {
+    config.exclude_paths.iter().any(|exclude_path| {
+        !matches!(
+            ExcludePattern::parse(exclude_path),
+            Ok(ExcludePattern::Path(_))
+        )
+    })
+}
+
+/// Return `{n}` of the `TOP` keyword for the page at `from`, see `query_sql()`.
+fn top_n(from: usize, size: usize, config: &FileSearchConfig) -> usize {
+    let top_n = from
+        .checked_add(size)
+        .expect("[from + size] cannot fit into an [usize]");
+
+    if needs_post_filtering(config) {
+        top_n
+            .saturating_mul(POST_FILTERING_OVER_FETCH)
+            .min(POST_FILTERING_MAX_TOP_N.max(top_n))
+    } else {
+        top_n
+    }
+}
+
 /// Helper function to construct the Windows Search SQL.
 ///
 /// Paging is not natively supported by windows Search SQL, it only supports `size`
 /// via the `TOP` keyword ("SELECT TOP {n} {columns}").  The SQL returned by this
 /// function will have `{n}` set to `from + size`, then we will manually implement
-/// paging.
-fn query_sql(query_string: &str, from: usize, size: usize, config: &FileSearchConfig) -> String {
-    let top_n = from
-        .checked_add(size)
-        .expect("[from + size] cannot fit into an [usize]");
+/// paging.  If the hits need to be post-filtered, `{n}` is a few times larger,
+/// and `hits()` queries again with [`query_sql_with_top_n`] if the page is
+/// still short.
+///
+/// An empty `query_string` matches all the files, the query filters are all
+/// translated to SQL.
+fn query_sql(
+    query_string: &str,
+    from: usize,
+    size: usize,
+    config: &FileSearchConfig,
+    filters: &QueryFilters,
+) -> String {
+    query_sql_with_top_n(query_string, top_n(from, size, config), config, filters)
+}
 
+/// [`query_sql`] with `{n}` of the `TOP` keyword set to `top_n`.
+fn query_sql_with_top_n(
+    query_string: &str,
+    top_n: usize,
+    config: &FileSearchConfig,
+    filters: &QueryFilters,
+) -> String {
     // System.ItemUrl is a column that contains the file path
     // example: "file:C:/Users/desktop.ini"
     //
     // System.Search.Rank is the relevance score
     let mut sql = format!(
-        "SELECT TOP {} System.ItemUrl, System.Search.Rank FROM SystemIndex WHERE",
+        "SELECT TOP {} System.ItemUrl, System.Search.Rank FROM SystemIndex",
         top_n
     );
 
     let query_string = query_string_cleanup(query_string);
 
-    let search_by_predicate = match config.search_by {
-        SearchBy::Name => {
-            // `contains(System.FileName, '{query_string}')` would be faster
-            // because it uses inverted index, but that's not what we want
-            // due to the limitation of tokenization. For example, suppose "Coco AI.rs"
-            // will be tokenized to `["Coco", "AI", "rs"]`, then if users search
-            // via `Co`, this file won't be returned because term `Co` does not
-            // exist in the index.
-            //
-            // So we use wildcard instead even though it is slower.
-            format!("(System.FileName LIKE '%{query_string}%')")
-        }
-        SearchBy::NameAndContents => {
-            // Windows File Search does not support searching by file content.
-            //
-            // `CONTAINS('query_string')` would search all columns for `query_string`,
-            // this is the closest solution we have.
-            format!("((System.FileName LIKE '%{query_string}%') OR CONTAINS('{query_string}'))")
+    let search_by_predicate: Option<String> = if query_string.is_empty() {
+        None
+    } else {
+        match config.search_by {
+            SearchBy::Name => {
+                // `contains(System.FileName, '{query_string}')` would be faster
+                // because it uses inverted index, but that's not what we want
+                // due to the limitation of tokenization. For example, suppose "Coco AI.rs"
+                // will be tokenized to `["Coco", "AI", "rs"]`, then if users search
+                // via `Co`, this file won't be returned because term `Co` does not
+                // exist in the index.
+                //
+                // So we use wildcard instead even though it is slower.
+                Some(format!("(System.FileName LIKE '%{query_string}%')"))
+            }
+            SearchBy::NameAndContents => {
+                // Windows File Search does not support searching by file content.
+                //
+                // `CONTAINS('query_string')` would search all columns for `query_string`,
+                // this is the closest solution we have.
+                Some(format!(
+                    "((System.FileName LIKE '%{query_string}%') OR CONTAINS('{query_string}'))"
+                ))
+            }
         }
     };
 
+    // `in:` filters narrow the search paths
+    let search_paths = filters.narrow_search_paths(&config.search_paths);
     let search_paths_predicate: Option<String> = {
-        if config.search_paths.is_empty() {
+        if search_paths.is_empty() {
             None
         } else {
             let mut output = String::from("(");
 
-            for (idx, search_path) in config.search_paths.iter().enumerate() {
+            for (idx, search_path) in search_paths.iter().enumerate() {
                 if idx != 0 {
                     output.push_str(" OR ");
                 }
@@ -141,13 +202,25 @@ fn query_sql(query_string: &str, from: usize, size: usize, config: &FileSearchCo
         }
     };
 
+    // Only plain paths can be expressed with SCOPE, other exclude patterns are
+    // post-filtered in `hits()`.
+    let excluded_scopes: Vec<&String> = config
+        .exclude_paths
+        .iter()
+        .filter(|exclude_path| {
+            matches!(
+                ExcludePattern::parse(exclude_path),
+                Ok(ExcludePattern::Path(_))
+            )
+        })
+        .collect();
     let exclude_paths_predicate: Option<String> = {
-        if config.exclude_paths.is_empty() {
+        if excluded_scopes.is_empty() {
             None
         } else {
             let mut output = String::from("(");
 
-            for (idx, exclude_path) in config.exclude_paths.iter().enumerate() {
+            for (idx, exclude_path) in excluded_scopes.into_iter().enumerate() {
                 if idx != 0 {
                     output.push_str(" AND ");
                 }
@@ -164,45 +237,83 @@ fn query_sql(query_string: &str, from: usize, size: usize, config: &FileSearchCo
         }
     };
 
-    let file_types_predicate: Option<String> = {
-        if config.file_types.is_empty() {
-            None
-        } else {
-            let mut output = String::from("(");
+    let file_types_predicate = file_extension_predicate(&expand_file_types(&config.file_types));
+    let kinds_predicate = file_extension_predicate(&expand_file_types(&filters.kinds));
 
-            for (idx, file_type) in config.file_types.iter().enumerate() {
-                if idx != 0 {
-                    output.push_str(" OR ");
-                }
+    let modified_predicate: Option<String> = filters.modified.map(|modified| {
+        // Dates are compared in UTC
+        fn date_time(timestamp: i64) -> String {
+            DateTime::from_timestamp(timestamp, 0)
+                .unwrap_or_default()
+                .format("%Y/%m/%d %H:%M:%S")
+                .to_string()
+        }
 
-                // NOTE that this column contains a starting dot
-                output.push_str("System.FileExtension = '.");
-                output.push_str(&file_type);
-                output.push('\'');
-            }
+        let mut conditions = Vec::new();
+        if let Some(min) = modified.min {
+            conditions.push(format!("System.DateModified >= '{}'", date_time(min)));
+        }
+        if let Some(max) = modified.max {
+            conditions.push(format!("System.DateModified <= '{}'", date_time(max)));
+        }
+        format!("({})", conditions.join(" AND "))
+    });
+
+    // Directories have no System.Size, they never match size filters
+    let size_predicate: Option<String> = filters.size.map(|size| {
+        let mut conditions = Vec::new();
+        if let Some(min) = size.min {
+            conditions.push(format!("System.Size >= {}", min));
+        }
+        if let Some(max) = size.max {
+            conditions.push(format!("System.Size <= {}", max));
+        }
+        format!("({})", conditions.join(" AND "))
+    });
+
+    let predicates: Vec<String> = [
+        search_by_predicate,
+        search_paths_predicate,
+        exclude_paths_predicate,
+        file_types_predicate,
+        kinds_predicate,
+        modified_predicate,
+        size_predicate,
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    if !predicates.is_empty() {
+        sql.push_str(" WHERE ");
+        sql.push_str(&predicates.join(" AND "));
+    }
 
-            output.push(')');
+    sql
+}
 
-            Some(output)
+/// Helper function to construct the predicate that matches any of the
+/// `extensions`.
+fn file_extension_predicate(extensions: &[String]) -> Option<String> {
+    if extensions.is_empty() {
+        return None;
+    }
+
+    let mut output = String::from("(");
+
+    for (idx, extension) in extensions.iter().enumerate() {
+        if idx != 0 {
+            output.push_str(" OR ");
         }
-    };
 
-    sql.push(' ');
-    sql.push_str(search_by_predicate.as_str());
-    if let Some(search_paths_predicate) = search_paths_predicate {
-        sql.push_str(" AND ");
-        sql.push_str(search_paths_predicate.as_str());
-    }
-    if let Some(exclude_paths_predicate) = exclude_paths_predicate {
-        sql.push_str(" AND ");
-        sql.push_str(exclude_paths_predicate.as_str());
-    }
-    if let Some(file_types_predicate) = file_types_predicate {
-        sql.push_str(" AND ");
-        sql.push_str(file_types_predicate.as_str());
+        // NOTE that this column contains a starting dot
+        output.push_str("System.FileExtension = '.");
+        output.push_str(extension);
+        output.push('\'');
     }
 
-    sql
+    output.push(')');
+
+    Some(output)
 }
 
 /// Default GUID for Search.CollatorDSO.1
@@ -406,13 +517,45 @@ pub(crate) async fn hits(
     from: usize,
     size: usize,
     config: &FileSearchConfig,
+    filters: &QueryFilters,
 ) -> Result<Vec<(Document, f64)>, String> {
-    let sql = query_sql(query_string, from, size, config);
-    unsafe { OleInitialize(None).map_err(|e| e.to_string())? };
-    let result = execute_windows_search_sql(&sql)?;
-    unsafe { OleUninitialize() };
-    // .take(size) is not needed as `result` will contain `from+size` files at most
-    let result_with_paging = result.into_iter().skip(from);
+    // The `in:` filters and the search paths have nothing in common
+    if !filters.paths.is_empty() && filters.narrow_search_paths(&config.search_paths).is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let n_needed = from + size;
+    let mut top_n = top_n(from, size, config);
+    let mut sql = query_sql(query_string, from, size, config, filters);
+    let result = loop {
+        unsafe { OleInitialize(None).map_err(|e| e.to_string())? };
+        let result = execute_windows_search_sql(&sql);
+        unsafe { OleUninitialize() };
+        let result = result?;
+        let n_fetched = result.len();
+
+        // Exclude patterns that cannot be expressed in SQL are applied here,
+        // before paging.  Query filters are all in the SQL.
+        let filtered: Vec<(String, String)> = result
+            .into_iter()
+            .filter(|(item_url, _score_str)| {
+                let file_path = item_url.strip_prefix("file:").unwrap_or(item_url);
+                !should_be_filtered_out(config, file_path, false, true, false)
+            })
+            .collect();
+
+        // The page is short, and there could be more hits
+        if filtered.len() < n_needed && n_fetched == top_n && top_n < POST_FILTERING_MAX_TOP_N {
+            top_n = top_n
+                .saturating_mul(POST_FILTERING_OVER_FETCH)
+                .min(POST_FILTERING_MAX_TOP_N);
+            sql = query_sql_with_top_n(query_string, top_n, config, filters);
+            continue;
+        }
+
+        break filtered;
+    };
+    let result_with_paging = result.into_iter().skip(from).take(size);
     // result_with_paging won't contain more than `size` entries
     let mut hits = Vec::with_capacity(size);
 
@@ -446,7 +589,7 @@ pub(crate) async fn hits(
             url: file_path.to_string(),
         };
 
-        let doc = Document {
+        let mut doc = Document {
             id: file_path.to_string(),
             title: Some(file_name.to_string()),
             source: Some(DataSourceReference {
@@ -461,6 +604,9 @@ pub(crate) async fn hits(
             icon: Some(icon.to_string()),
             ..Default::default()
         };
+        if let Some(metadata) = FileMetadata::read(file_path) {
+            metadata.fill_document(&mut doc);
+        }
 
         let score: f64 = score_str.parse().expect(
             "System.Search.Rank should be in range [0, 1000], which should be valid for [f64]",
@@ -478,7 +624,7 @@ pub(crate) fn apply_config(config: &FileSearchConfig) -> Result<(), String> {
     //
     // 1. Add an inclusion rule for every search path to ensure indexer index
     //    them
-    // 2. For the exclude paths, we exclude them from the crawl scope if they
+    // 2. For the exclude paths (patterns are skipped), we exclude them from the crawl scope if they
     //    were not included in the scope before we update the scope. Otherwise,
     //    we cannot exclude them as doing that could potentially break other
     //    apps (by removing the indexes they rely on).
@@ -513,6 +659,14 @@ pub(crate) fn apply_config(config: &FileSearchConfig) -> Result<(), String> {
     // indexes to `exclude_paths` of the paths we need to exclude
     let mut paths_to_exclude: Vec<usize> = Vec::new();
     for (idx, exclude_path) in exclude_paths.into_iter().enumerate() {
+        // Crawl scope rules cannot express patterns
+        if !matches!(
+            ExcludePattern::parse(exclude_path),
+            Ok(ExcludePattern::Path(_))
+        ) {
+            continue;
+        }
+
         let exclude_path = add_tailing_backslash(&exclude_path);
         let exclude_path: &str = exclude_path.borrow();
 
@@ -557,6 +711,8 @@ pub(crate) fn apply_config(config: &FileSearchConfig) -> Result<(), String> {
 // I have no idea about the underlying root cause
 #[cfg(all(test, not(ci)))]
 mod test_windows_search {
+    use super::super::super::config::LinuxBackend;
+    use super::super::super::filter::Bounds;
     use super::*;
 
     /// Helper function for ensuring `sql` is valid SQL by actually executing it.
@@ -573,8 +729,9 @@ mod test_windows_search {
             exclude_paths: Vec::new(),
             file_types: Vec::new(),
             search_by: SearchBy::Name,
+            linux_backend: LinuxBackend::Auto,
         };
-        let sql = query_sql("coco", 0, 10, &config);
+        let sql = query_sql("coco", 0, 10, &config, &QueryFilters::default());
 
         assert_eq!(
             sql,
@@ -590,8 +747,9 @@ mod test_windows_search {
             exclude_paths: Vec::new(),
             file_types: Vec::new(),
             search_by: SearchBy::NameAndContents,
+            linux_backend: LinuxBackend::Auto,
         };
-        let sql = query_sql("coco", 0, 10, &config);
+        let sql = query_sql("coco", 0, 10, &config, &QueryFilters::default());
 
         assert_eq!(
             sql,
@@ -607,8 +765,9 @@ mod test_windows_search {
             exclude_paths: Vec::new(),
             file_types: Vec::new(),
             search_by: SearchBy::Name,
+            linux_backend: LinuxBackend::Auto,
         };
-        let sql = query_sql("coco", 0, 10, &config);
+        let sql = query_sql("coco", 0, 10, &config, &QueryFilters::default());
 
         assert_eq!(
             sql,
@@ -628,8 +787,9 @@ mod test_windows_search {
             exclude_paths: Vec::new(),
             file_types: Vec::new(),
             search_by: SearchBy::Name,
+            linux_backend: LinuxBackend::Auto,
         };
-        let sql = query_sql("test", 0, 5, &config);
+        let sql = query_sql("test", 0, 5, &config, &QueryFilters::default());
 
         assert_eq!(
             sql,
@@ -645,8 +805,9 @@ mod test_windows_search {
             exclude_paths: vec!["C:/Windows/".into()],
             file_types: Vec::new(),
             search_by: SearchBy::Name,
+            linux_backend: LinuxBackend::Auto,
         };
-        let sql = query_sql("file", 0, 20, &config);
+        let sql = query_sql("file", 0, 20, &config, &QueryFilters::default());
 
         assert_eq!(
             sql,
@@ -662,8 +823,9 @@ mod test_windows_search {
             exclude_paths: vec!["C:/Windows/".into(), "C:/System/".into(), "C:/Temp/".into()],
             file_types: Vec::new(),
             search_by: SearchBy::Name,
+            linux_backend: LinuxBackend::Auto,
         };
-        let sql = query_sql("data", 5, 15, &config);
+        let sql = query_sql("data", 5, 15, &config, &QueryFilters::default());
 
         assert_eq!(
             sql,
@@ -679,8 +841,9 @@ mod test_windows_search {
             exclude_paths: Vec::new(),
             file_types: vec!["txt".into()],
             search_by: SearchBy::Name,
+            linux_backend: LinuxBackend::Auto,
         };
-        let sql = query_sql("readme", 0, 10, &config);
+        let sql = query_sql("readme", 0, 10, &config, &QueryFilters::default());
 
         assert_eq!(
             sql,
@@ -696,8 +859,9 @@ mod test_windows_search {
             exclude_paths: Vec::new(),
             file_types: vec!["rs".into(), "toml".into(), "md".into(), "json".into()],
             search_by: SearchBy::Name,
+            linux_backend: LinuxBackend::Auto,
         };
-        let sql = query_sql("config", 0, 50, &config);
+        let sql = query_sql("config", 0, 50, &config, &QueryFilters::default());
 
         assert_eq!(
             sql,
@@ -713,8 +877,9 @@ mod test_windows_search {
             exclude_paths: vec!["C:/Projects/temp/".into()],
             file_types: vec!["rs".into(), "ts".into()],
             search_by: SearchBy::Name,
+            linux_backend: LinuxBackend::Auto,
         };
-        let sql = query_sql("main", 10, 25, &config);
+        let sql = query_sql("main", 10, 25, &config, &QueryFilters::default());
 
         assert_eq!(
             sql,
@@ -730,8 +895,9 @@ mod test_windows_search {
             exclude_paths: Vec::new(),
             file_types: vec!["c++".into()],
             search_by: SearchBy::Name,
+            linux_backend: LinuxBackend::Auto,
         };
-        let sql = query_sql("hello-world", 0, 10, &config);
+        let sql = query_sql("hello-world", 0, 10, &config, &QueryFilters::default());
 
         assert_eq!(
             sql,
@@ -747,8 +913,9 @@ mod test_windows_search {
             exclude_paths: Vec::new(),
             file_types: Vec::new(),
             search_by: SearchBy::Name,
+            linux_backend: LinuxBackend::Auto,
         };
-        let sql = query_sql("test", 100, 50, &config);
+        let sql = query_sql("test", 100, 50, &config, &QueryFilters::default());
 
         assert_eq!(
             sql,
@@ -756,6 +923,56 @@ mod test_windows_search {
         );
         ensure_it_is_valid_sql(&sql);
     }
+
+    #[test]
+    fn test_query_sql_with_query_filters() {
+        let config = FileSearchConfig {
+            search_paths: vec!["C:/Users/".into()],
+            exclude_paths: Vec::new(),
+            file_types: Vec::new(),
+            search_by: SearchBy::Name,
+            linux_backend: LinuxBackend::Auto,
+        };
+        let filters = QueryFilters {
+            modified: Some(Bounds {
+                min: Some(1_704_067_200),
+                max: None,
+            }),
+            size: Some(Bounds {
+                min: None,
+                max: Some(1024),
+            }),
+            kinds: vec!["pdf".into()],
+            paths: vec!["C:/Users/coco/".into()],
+        };
+        let sql = query_sql("", 0, 10, &config, &filters);
+
+        assert_eq!(
+            sql,
+            "SELECT TOP 10 System.ItemUrl, System.Search.Rank FROM SystemIndex WHERE (SCOPE = 'file:C:/Users/coco/') AND (System.FileExtension = '.pdf') AND (System.DateModified >= '2024/01/01 00:00:00') AND (System.Size <= 1024)"
+        );
+        ensure_it_is_valid_sql(&sql);
+    }
+
+    #[test]
+    fn test_query_sql_with_exclude_patterns_over_fetches() {
+        let config = FileSearchConfig {
+            search_paths: Vec::new(),
+            exclude_paths: vec!["**/node_modules".into()],
+            file_types: Vec::new(),
+            search_by: SearchBy::Name,
+            linux_backend: LinuxBackend::Auto,
+        };
+        let sql = query_sql("coco", 0, 10, &config, &QueryFilters::default());
+
+        assert_eq!(
+            sql,
+            "SELECT TOP 40 System.ItemUrl, System.Search.Rank FROM SystemIndex WHERE (System.FileName LIKE '%coco%')"
+        );
+        ensure_it_is_valid_sql(&sql);
+
+        assert_eq!(top_n(0, 10_000, &config), 10_000);
+    }
 }
 
 #[cfg(test)]
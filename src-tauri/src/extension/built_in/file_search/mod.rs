@@ -1,15 +1,23 @@
 pub(crate) mod config;
+pub(crate) mod filter;
 pub(crate) mod implementation;
+pub(crate) mod pattern;
 
 use super::super::LOCAL_QUERY_SOURCE_TYPE;
+use crate::common::document::Document;
+use crate::common::document_action::file_actions;
 use crate::common::{
     error::SearchError,
     search::{QueryResponse, QuerySource, SearchQuery},
     traits::SearchSource,
 };
+use crate::search::fuzzy::fuzzy_match;
+use crate::search::preview::{DocumentPreview, preview_file};
 use async_trait::async_trait;
 use config::FileSearchConfig;
+use filter::parse_query;
 use hostname;
+use std::path::Path;
 use tauri::AppHandle;
 
 pub(crate) const EXTENSION_ID: &str = "File Search";
@@ -41,6 +49,20 @@ impl SearchSource for FileSearchExtensionSearchSource {
         }
     }
 
+    #[cfg(target_os = "linux")]
+    async fn shutdown(&self, _tauri_app_handle: &AppHandle) {
+        implementation::shutdown();
+    }
+
+    async fn preview(
+        &self,
+        _tauri_app_handle: &AppHandle,
+        document: &Document,
+    ) -> Result<Option<DocumentPreview>, SearchError> {
+        // Document ID is the file path
+        preview_file(Path::new(&document.id)).await
+    }
+
     async fn search(
         &self,
         tauri_app_handle: AppHandle,
@@ -58,8 +80,10 @@ impl SearchSource for FileSearchExtensionSearchSource {
         let from = usize::try_from(query.from).expect("from too big");
         let size = usize::try_from(query.size).expect("size too big");
 
-        let query_string = query_string.trim();
-        if query_string.is_empty() {
+        let parsed_query = parse_query(query_string);
+        let query_string = parsed_query.text.as_str();
+        // A query containing only filters matches all the files that pass them
+        if query_string.is_empty() && parsed_query.filters.is_empty() {
             return Ok(QueryResponse {
                 source: self.get_type(),
                 hits: Vec::new(),
@@ -89,9 +113,27 @@ impl SearchSource for FileSearchExtensionSearchSource {
         // Execute search in a blocking task
         let query_source = self.get_type();
 
-        let hits = implementation::hits(&query_string, from, size, &config)
-            .await
-            .map_err(|e| SearchError::InternalError { error: e })?;
+        let mut hits =
+            implementation::hits(query_string, from, size, &config, &parsed_query.filters)
+                .await
+                .map_err(|e| SearchError::InternalError { error: e })?;
+
+        // Files are matched by the platform search engine, we only need the
+        // match ranges of the file names for highlighting.
+        for (document, _score) in hits.iter_mut() {
+            if !query_string.is_empty()
+                && let Some(ref file_name) = document.title
+            {
+                document.title_match_ranges =
+                    fuzzy_match(query_string, file_name).map(|name_match| name_match.ranges);
+            }
+
+            // Document ID is the file path
+            document.actions = Some(file_actions(
+                &document.id,
+                document.title.as_deref().unwrap_or(&document.id),
+            ));
+        }
 
         let total_hits = hits.len();
         Ok(QueryResponse {
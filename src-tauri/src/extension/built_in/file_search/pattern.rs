@@ -0,0 +1,516 @@
+//! Exclude patterns and file type groups of [`FileSearchConfig`].
+//!
+//! Entries of `exclude_paths` are gitignore-style patterns:
+//!
+//! * An absolute path, e.g., `/home/user/Trash`, excludes itself and its
+//!   descendants.  A leading `~` is expanded to the home directory.  If the
+//!   path exists, it is taken literally even if it contains wildcards, e.g.,
+//!   `/home/user/[draft]`.
+//! * A pattern without `/`, e.g., `node_modules` or `*.tmp`, is matched
+//!   against the name of every file and directory below the search paths,
+//!   but not the search paths or their ancestors.  `**/node_modules` is the
+//!   same as `node_modules`.
+//! * Other relative patterns, e.g., `build/*.o`, are relative to the search
+//!   paths.
+//!
+//! `*` and `?` don't match `/`, `**` matches any number of directories.  A
+//! matched directory excludes all its descendants, so a trailing `/` makes no
+//! difference.  Negation (`!pattern`) is not supported.
+//!
+//! Entries of `file_types` are extensions, e.g., `pdf`, or names of the groups
+//! in [`FILE_TYPE_GROUPS`], e.g., `images`.
+
+use super::config::FileSearchConfig;
+use camino::Utf8Path;
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use serde::Serialize;
+use std::borrow::Cow;
+use std::sync::{Arc, Mutex};
+
+/// Named groups of file types, and the extensions they expand to.
+pub(crate) const FILE_TYPE_GROUPS: &[(&str, &[&str])] = &[
+    (
+        "images",
+        &[
+            "jpg", "jpeg", "png", "gif", "bmp", "webp", "svg", "tif", "tiff", "heic", "heif",
+            "ico", "avif", "raw",
+        ],
+    ),
+    (
+        "documents",
+        &[
+            "pdf", "doc", "docx", "odt", "rtf", "txt", "md", "xls", "xlsx", "ods", "csv", "ppt",
+            "pptx", "odp", "pages", "numbers", "key", "epub",
+        ],
+    ),
+    (
+        "code",
+        &[
+            "rs", "go", "c", "h", "cc", "cpp", "hpp", "cs", "java", "kt", "swift", "py", "rb",
+            "php", "js", "jsx", "mjs", "ts", "tsx", "vue", "svelte", "html", "css", "scss", "sh",
+            "sql", "lua", "dart", "scala", "json", "yaml", "yml", "toml", "xml",
+        ],
+    ),
+    (
+        "archives",
+        &[
+            "zip", "tar", "gz", "tgz", "bz2", "xz", "zst", "7z", "rar", "dmg", "iso", "jar",
+        ],
+    ),
+    (
+        "audio",
+        &[
+            "mp3", "wav", "flac", "aac", "ogg", "opus", "m4a", "wma", "aiff",
+        ],
+    ),
+    (
+        "video",
+        &[
+            "mp4", "mkv", "mov", "avi", "webm", "wmv", "flv", "m4v", "mpeg", "mpg",
+        ],
+    ),
+];
+
+fn file_type_group(name: &str) -> Option<&'static [&'static str]> {
+    FILE_TYPE_GROUPS
+        .iter()
+        .find(|(group_name, _extensions)| group_name.eq_ignore_ascii_case(name))
+        .map(|(_group_name, extensions)| *extensions)
+}
+
+/// Users may type the leading dot of an extension.
+fn strip_dot(file_type: &str) -> &str {
+    file_type.trim().trim_start_matches('.')
+}
+
+/// Expand the groups in `file_types` into extensions, without duplicates.
+pub(crate) fn expand_file_types(file_types: &[String]) -> Vec<String> {
+    let mut extensions: Vec<String> = Vec::new();
+    for file_type in file_types {
+        let expanded = match file_type_group(file_type) {
+            Some(group) => group
+                .iter()
+                .map(|extension| extension.to_string())
+                .collect(),
+            None => vec![strip_dot(file_type).to_string()],
+        };
+        for extension in expanded {
+            if !extensions.contains(&extension) {
+                extensions.push(extension);
+            }
+        }
+    }
+
+    extensions
+}
+
+/// Return true if `extension` is one of the `file_types`, or in one of
+/// their groups, case-insensitively.
+pub(crate) fn matches_file_types(file_types: &[String], extension: &str) -> bool {
+    file_types
+        .iter()
+        .any(|file_type| match file_type_group(file_type) {
+            Some(group) => group
+                .iter()
+                .any(|group_extension| group_extension.eq_ignore_ascii_case(extension)),
+            None => strip_dot(file_type).eq_ignore_ascii_case(extension),
+        })
+}
+
+/// An entry of `exclude_paths`, see the module documentation.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum ExcludePattern {
+    /// An absolute path without wildcards, or an existing absolute path
+    Path(String),
+    /// A pattern without `/`, matched against file names
+    Name(String),
+    /// An absolute pattern with wildcards
+    Glob(String),
+    /// A relative pattern containing `/`, anchored at the search paths
+    RelativeGlob(String),
+}
+
+fn has_wildcard(pattern: &str) -> bool {
+    pattern.contains(['*', '?', '[', ']', '{', '}'])
+}
+
+/// Windows paths may use `\` as the separator, but globs always use `/`.
+fn normalize_separators(path: &str) -> Cow<'_, str> {
+    if cfg!(target_os = "windows") && path.contains('\\') {
+        Cow::Owned(path.replace('\\', "/"))
+    } else {
+        Cow::Borrowed(path)
+    }
+}
+
+fn build_glob(pattern: &str) -> Result<globset::Glob, String> {
+    GlobBuilder::new(pattern)
+        .literal_separator(true)
+        .build()
+        .map_err(|e| e.to_string())
+}
+
+impl ExcludePattern {
+    pub(crate) fn parse(pattern: &str) -> Result<Self, String> {
+        let pattern = pattern.trim();
+        if pattern.is_empty() {
+            return Err("pattern should not be empty".into());
+        }
+        if pattern.starts_with('!') {
+            return Err("negation is not supported".into());
+        }
+        if pattern.contains('\0') {
+            return Err("pattern should not contain NUL characters".into());
+        }
+
+        let mut pattern = normalize_separators(pattern).into_owned();
+        if pattern == "~" || pattern.starts_with("~/") {
+            let home = dirs::home_dir().ok_or("home directory not found")?;
+            let home = home.to_str().ok_or("home directory is not UTF-8 encoded")?;
+            pattern = format!("{}{}", normalize_separators(home), &pattern[1..]);
+        }
+        // Keep the root path
+        while pattern.len() > 1 && pattern.ends_with('/') {
+            pattern.pop();
+        }
+
+        let is_absolute = pattern.starts_with('/') || Utf8Path::new(&pattern).is_absolute();
+        if is_absolute && Utf8Path::new(&pattern).exists() {
+            return Ok(Self::Path(pattern));
+        }
+        build_glob(&pattern)?;
+
+        let excluded = if is_absolute {
+            if has_wildcard(&pattern) {
+                Self::Glob(pattern)
+            } else {
+                Self::Path(pattern)
+            }
+        } else {
+            let name = pattern.strip_prefix("**/").unwrap_or(&pattern);
+            if name.contains('/') {
+                Self::RelativeGlob(pattern)
+            } else {
+                Self::Name(name.to_string())
+            }
+        };
+
+        Ok(excluded)
+    }
+
+    /// Globs matching the excluded paths, but not their descendants.
+    fn globs(&self, search_paths: &[String]) -> Vec<String> {
+        match self {
+            Self::Path(path) => vec![globset::escape(path)],
+            Self::Name(name) => vec![format!("**/{}", name)],
+            Self::Glob(glob) => vec![glob.clone()],
+            Self::RelativeGlob(glob) => search_paths
+                .iter()
+                .map(|search_path| {
+                    let search_path = normalize_separators(search_path);
+                    format!(
+                        "{}/{}",
+                        globset::escape(search_path.trim_end_matches('/')),
+                        glob
+                    )
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Matches the paths excluded by a configuration.
+pub(crate) struct ExcludeMatcher {
+    search_paths: Vec<String>,
+    exclude_paths: Vec<String>,
+    glob_set: GlobSet,
+    /// Globs of [`ExcludePattern::Name`], which only match below the search
+    /// paths.
+    name_glob_set: GlobSet,
+}
+
+/// Compiling the patterns is expensive, cache the matcher of the last used
+/// configuration.
+static EXCLUDE_MATCHER: Mutex<Option<Arc<ExcludeMatcher>>> = Mutex::new(None);
+
+impl ExcludeMatcher {
+    fn new(config: &FileSearchConfig) -> Self {
+        let mut builder = GlobSetBuilder::new();
+        let mut name_builder = GlobSetBuilder::new();
+        for exclude_path in config.exclude_paths.iter() {
+            let pattern = match ExcludePattern::parse(exclude_path) {
+                Ok(pattern) => pattern,
+                Err(e) => {
+                    // They are validated before being saved, but the store
+                    // file could be edited by hand.
+                    log::warn!(
+                        "ignoring invalid exclude pattern [{}], error [{}]",
+                        exclude_path,
+                        e
+                    );
+                    continue;
+                }
+            };
+            let builder = match pattern {
+                ExcludePattern::Name(_) => &mut name_builder,
+                _ => &mut builder,
+            };
+            for glob in pattern.globs(&config.search_paths) {
+                match build_glob(&glob) {
+                    Ok(glob) => {
+                        builder.add(glob);
+                    }
+                    Err(e) => log::warn!("ignoring invalid glob [{}], error [{}]", glob, e),
+                }
+            }
+        }
+
+        let build = |builder: GlobSetBuilder| {
+            builder.build().unwrap_or_else(|e| {
+                log::warn!("failed to build exclude patterns, error [{}]", e);
+                GlobSet::empty()
+            })
+        };
+
+        Self {
+            search_paths: config.search_paths.clone(),
+            exclude_paths: config.exclude_paths.clone(),
+            glob_set: build(builder),
+            name_glob_set: build(name_builder),
+        }
+    }
+
+    /// Return the matcher of `config`.
+    pub(crate) fn get(config: &FileSearchConfig) -> Arc<Self> {
+        let mut opt_cached = EXCLUDE_MATCHER.lock().unwrap();
+        if let Some(cached) = opt_cached.as_ref()
+            && cached.search_paths == config.search_paths
+            && cached.exclude_paths == config.exclude_paths
+        {
+            return Arc::clone(cached);
+        }
+
+        let matcher = Arc::new(Self::new(config));
+        *opt_cached = Some(Arc::clone(&matcher));
+        matcher
+    }
+
+    /// Return true if `path` or any of its ancestors is excluded.
+    ///
+    /// Like gitignore, file name patterns only match the ancestors below the
+    /// search path containing `path`.
+    pub(crate) fn is_excluded(&self, path: &str) -> bool {
+        let path = normalize_separators(path);
+        let path = Utf8Path::new(path.as_ref());
+        let opt_search_path = self
+            .search_paths
+            .iter()
+            .map(|search_path| normalize_separators(search_path))
+            .filter(|search_path| path.starts_with(search_path.as_ref()))
+            .max_by_key(|search_path| search_path.len());
+
+        path.ancestors().any(|ancestor| {
+            if self.glob_set.is_match(ancestor.as_str()) {
+                return true;
+            }
+
+            let is_below_search_path = match opt_search_path {
+                Some(ref search_path) => {
+                    let search_path = Utf8Path::new(search_path.as_ref());
+                    ancestor != search_path && ancestor.starts_with(search_path)
+                }
+                // Not in the search paths, all the ancestors count
+                None => true,
+            };
+            is_below_search_path && self.name_glob_set.is_match(ancestor.as_str())
+        })
+    }
+}
+
+/// A configuration entry that is invalid.
+#[derive(Debug, Serialize, PartialEq)]
+pub struct InvalidPattern {
+    pub pattern: String,
+    pub error: String,
+}
+
+fn validate_file_type(file_type: &str) -> Result<(), String> {
+    if file_type_group(file_type).is_some() {
+        return Ok(());
+    }
+
+    let extension = strip_dot(file_type);
+    if extension.is_empty() {
+        return Err("file type should not be empty".into());
+    }
+    if extension.contains(|c: char| c == '.' || c == '/' || c == '\\' || c.is_whitespace())
+        || has_wildcard(extension)
+    {
+        return Err(format!(
+            "should be an extension, or one of the groups: {}",
+            FILE_TYPE_GROUPS
+                .iter()
+                .map(|(group_name, _extensions)| *group_name)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+    }
+
+    Ok(())
+}
+
+/// Return the invalid entries of `exclude_paths` and `file_types`.
+pub(crate) fn validate(exclude_paths: &[String], file_types: &[String]) -> Vec<InvalidPattern> {
+    let invalid_exclude_paths = exclude_paths.iter().filter_map(|exclude_path| {
+        ExcludePattern::parse(exclude_path)
+            .err()
+            .map(|error| InvalidPattern {
+                pattern: exclude_path.clone(),
+                error,
+            })
+    });
+    let invalid_file_types = file_types.iter().filter_map(|file_type| {
+        validate_file_type(file_type)
+            .err()
+            .map(|error| InvalidPattern {
+                pattern: file_type.clone(),
+                error,
+            })
+    });
+
+    invalid_exclude_paths.chain(invalid_file_types).collect()
+}
+
+// Patterns in the tests are Unix paths
+#[cfg(all(test, not(target_os = "windows")))]
+mod tests {
+    use super::super::config::{LinuxBackend, SearchBy};
+    use super::*;
+
+    fn matcher(exclude_paths: &[&str]) -> ExcludeMatcher {
+        ExcludeMatcher::new(&FileSearchConfig {
+            search_paths: vec!["/home/user".into(), "/data/".into()],
+            exclude_paths: exclude_paths.iter().map(|p| p.to_string()).collect(),
+            file_types: vec![],
+            search_by: SearchBy::Name,
+            linux_backend: LinuxBackend::Auto,
+        })
+    }
+
+    #[test]
+    fn test_parse_exclude_pattern() {
+        assert_eq!(
+            ExcludePattern::parse("/home/user/Trash/"),
+            Ok(ExcludePattern::Path("/home/user/Trash".into()))
+        );
+        assert_eq!(
+            ExcludePattern::parse("**/node_modules"),
+            Ok(ExcludePattern::Name("node_modules".into()))
+        );
+        assert_eq!(
+            ExcludePattern::parse("*.tmp"),
+            Ok(ExcludePattern::Name("*.tmp".into()))
+        );
+        assert_eq!(
+            ExcludePattern::parse("/home/*/.cache"),
+            Ok(ExcludePattern::Glob("/home/*/.cache".into()))
+        );
+        assert_eq!(
+            ExcludePattern::parse("build/**/*.o"),
+            Ok(ExcludePattern::RelativeGlob("build/**/*.o".into()))
+        );
+        assert!(ExcludePattern::parse("  ").is_err());
+        assert!(ExcludePattern::parse("!keep.txt").is_err());
+        assert!(ExcludePattern::parse("/home/user/[abc").is_err());
+        assert!(ExcludePattern::parse("a\0b").is_err());
+
+        // Existing paths are taken literally
+        let dir = tempfile::tempdir().unwrap();
+        let existing = format!("{}/[draft", dir.path().to_str().unwrap());
+        std::fs::create_dir(&existing).unwrap();
+        assert_eq!(
+            ExcludePattern::parse(&existing),
+            Ok(ExcludePattern::Path(existing.clone()))
+        );
+        assert!(validate(&[existing], &[]).is_empty());
+
+        let home = dirs::home_dir().unwrap();
+        assert_eq!(
+            ExcludePattern::parse("~/Trash"),
+            Ok(ExcludePattern::Path(format!(
+                "{}/Trash",
+                home.to_str().unwrap()
+            )))
+        );
+    }
+
+    #[test]
+    fn test_exclude_matcher() {
+        let matcher = matcher(&[
+            "/home/user/Trash",
+            "**/node_modules",
+            "*.tmp",
+            "build/*.o",
+            "/home/user/[draft]",
+        ]);
+
+        assert!(matcher.is_excluded("/home/user/Trash"));
+        assert!(matcher.is_excluded("/home/user/Trash/a/b.txt"));
+        assert!(!matcher.is_excluded("/home/user/Trash2/b.txt"));
+
+        assert!(matcher.is_excluded("/home/user/app/node_modules/x/index.js"));
+        assert!(matcher.is_excluded("/data/node_modules"));
+
+        assert!(matcher.is_excluded("/home/user/a.tmp"));
+        assert!(matcher.is_excluded("/home/user/cache.tmp/file"));
+        assert!(!matcher.is_excluded("/home/user/a.tmpl"));
+
+        assert!(matcher.is_excluded("/home/user/build/main.o"));
+        assert!(matcher.is_excluded("/data/build/main.o"));
+        assert!(!matcher.is_excluded("/home/user/src/build/main.o"));
+
+        // Brackets are character classes
+        assert!(matcher.is_excluded("/home/user/d/a.txt"));
+        assert!(!matcher.is_excluded("/home/user/draft/a.txt"));
+
+        // File name patterns don't match the search paths or their ancestors
+        let matcher = ExcludeMatcher::new(&FileSearchConfig {
+            search_paths: vec!["/home/user/build/proj".into()],
+            exclude_paths: vec!["build".into(), "proj".into()],
+            file_types: vec![],
+            search_by: SearchBy::Name,
+            linux_backend: LinuxBackend::Auto,
+        });
+        assert!(!matcher.is_excluded("/home/user/build/proj/src/main.rs"));
+        assert!(matcher.is_excluded("/home/user/build/proj/src/build/main.o"));
+        assert!(matcher.is_excluded("/home/user/build/proj/proj"));
+        assert!(matcher.is_excluded("/tmp/build/main.o"));
+    }
+
+    #[test]
+    fn test_file_types() {
+        let file_types = vec!["images".to_string(), ".PDF".to_string()];
+        assert!(matches_file_types(&file_types, "png"));
+        assert!(matches_file_types(&file_types, "JPG"));
+        assert!(matches_file_types(&file_types, "pdf"));
+        assert!(!matches_file_types(&file_types, "rs"));
+
+        let expanded = expand_file_types(&["pdf".into(), "documents".into()]);
+        assert_eq!(expanded[0], "pdf");
+        assert_eq!(expanded.iter().filter(|ext| *ext == "pdf").count(), 1);
+        assert!(expanded.contains(&"docx".to_string()));
+    }
+
+    #[test]
+    fn test_validate() {
+        let invalid = validate(
+            &["**/node_modules".into(), "/a/[b".into(), "".into()],
+            &["code".into(), "md".into(), ".md".into(), "*.md".into()],
+        );
+        let invalid_patterns: Vec<&str> = invalid
+            .iter()
+            .map(|invalid| invalid.pattern.as_str())
+            .collect();
+        assert_eq!(invalid_patterns, vec!["/a/[b", "", "*.md"]);
+    }
+}
@@ -2,18 +2,21 @@
 
 pub mod ai_overview;
 pub mod application;
+#[cfg(target_os = "linux")]
+pub mod browser;
 pub mod calculator;
+pub mod clipboard_history;
 pub mod file_search;
 pub mod open_camera;
 pub mod pizza_engine_runtime;
 pub mod quick_ai_access;
+pub mod snippets;
 #[cfg(target_os = "macos")]
 pub mod window_management;
 
 use super::Extension;
 use crate::SearchSourceRegistry;
 use crate::common::error::{ReportErrorStyle, report_error};
-use crate::extension::built_in::application::{set_apps_hotkey, unset_apps_hotkey};
 use crate::extension::{
     ExtensionBundleIdBorrowed, PLUGIN_JSON_FILE_NAME, alter_extension_json_file,
 };
@@ -196,6 +199,24 @@ pub(crate) async fn list_built_in_extensions(
         .await?,
     );
 
+    #[cfg(target_os = "linux")]
+    built_in_extensions.push(
+        load_built_in_extension(&dir, browser::EXTENSION_ID, browser::PLUGIN_JSON_FILE).await?,
+    );
+
+    built_in_extensions.push(
+        load_built_in_extension(
+            &dir,
+            clipboard_history::EXTENSION_ID,
+            clipboard_history::PLUGIN_JSON_FILE,
+        )
+        .await?,
+    );
+
+    built_in_extensions.push(
+        load_built_in_extension(&dir, snippets::EXTENSION_ID, snippets::PLUGIN_JSON_FILE).await?,
+    );
+
     cfg_if::cfg_if! {
       if #[cfg(target_os = "macos")] {
           built_in_extensions.push(
@@ -221,25 +242,27 @@ pub(super) async fn init_built_in_extension(
 
     if extension.id == application::QUERYSOURCE_ID_DATASOURCE_ID_DATASOURCE_NAME {
         search_source_registry
-            .register_source(application::ApplicationSearchSource)
-            .await;
-        set_apps_hotkey(&tauri_app_handle)?;
+            .register_source(tauri_app_handle, application::ApplicationSearchSource)
+            .await
+            .map_err(|e| report_error(&e, ReportErrorStyle::SingleLine))?;
         log::debug!("built-in extension [{}] initialized", extension.id);
     }
 
     if extension.id == calculator::DATA_SOURCE_ID {
         let calculator_search = calculator::CalculatorSource::new(2000f64);
         search_source_registry
-            .register_source(calculator_search)
-            .await;
+            .register_source(tauri_app_handle, calculator_search)
+            .await
+            .map_err(|e| report_error(&e, ReportErrorStyle::SingleLine))?;
         log::debug!("built-in extension [{}] initialized", extension.id);
     }
 
     if extension.id == file_search::EXTENSION_ID {
         let file_system_search = file_search::FileSearchExtensionSearchSource;
         search_source_registry
-            .register_source(file_system_search)
-            .await;
+            .register_source(tauri_app_handle, file_system_search)
+            .await
+            .map_err(|e| report_error(&e, ReportErrorStyle::SingleLine))?;
         let file_search_config = FileSearchConfig::get(tauri_app_handle);
         file_search_apply_config(&file_search_config)?;
         log::debug!("built-in extension [{}] initialized", extension.id);
@@ -247,7 +270,39 @@ pub(super) async fn init_built_in_extension(
 
     if extension.id == open_camera::EXTENSION_ID {
         let camera_search = open_camera::OpenCameraSearchSource::new(1500f64);
-        search_source_registry.register_source(camera_search).await;
+        search_source_registry
+            .register_source(tauri_app_handle, camera_search)
+            .await
+            .map_err(|e| report_error(&e, ReportErrorStyle::SingleLine))?;
+        log::debug!("built-in extension [{}] initialized", extension.id);
+    }
+
+    #[cfg(target_os = "linux")]
+    if extension.id == browser::EXTENSION_ID {
+        let browser_search = browser::BrowserSearchSource::new(1000f64);
+        search_source_registry
+            .register_source(tauri_app_handle, browser_search)
+            .await
+            .map_err(|e| report_error(&e, ReportErrorStyle::SingleLine))?;
+        log::debug!("built-in extension [{}] initialized", extension.id);
+    }
+
+    if extension.id == clipboard_history::EXTENSION_ID {
+        let clipboard_history_search =
+            clipboard_history::ClipboardHistorySearchSource::new(1000f64);
+        search_source_registry
+            .register_source(tauri_app_handle, clipboard_history_search)
+            .await
+            .map_err(|e| report_error(&e, ReportErrorStyle::SingleLine))?;
+        log::debug!("built-in extension [{}] initialized", extension.id);
+    }
+
+    if extension.id == snippets::EXTENSION_ID {
+        let snippets_search = snippets::SnippetsSearchSource::new(1000f64);
+        search_source_registry
+            .register_source(tauri_app_handle, snippets_search)
+            .await
+            .map_err(|e| report_error(&e, ReportErrorStyle::SingleLine))?;
         log::debug!("built-in extension [{}] initialized", extension.id);
     }
 
@@ -256,8 +311,9 @@ pub(super) async fn init_built_in_extension(
           if extension.id == window_management::EXTENSION_ID {
               let file_system_search = window_management::search_source::WindowManagementSearchSource;
               search_source_registry
-                  .register_source(file_system_search)
-                  .await;
+                  .register_source(tauri_app_handle, file_system_search)
+                  .await
+                  .map_err(|e| report_error(&e, ReportErrorStyle::SingleLine))?;
 
               window_management::set_up_commands_hotkeys(tauri_app_handle, extension)?;
               log::debug!("built-in extension [{}] initialized", extension.id);
@@ -287,9 +343,9 @@ pub(crate) async fn enable_built_in_extension(
         && bundle_id.sub_extension_id.is_none()
     {
         search_source_registry_tauri_state
-            .register_source(application::ApplicationSearchSource)
-            .await;
-        set_apps_hotkey(tauri_app_handle)?;
+            .register_source(tauri_app_handle, application::ApplicationSearchSource)
+            .await
+            .map_err(|e| report_error(&e, ReportErrorStyle::SingleLine))?;
 
         alter_extension_json_file(
             &get_built_in_extension_directory(tauri_app_handle),
@@ -312,8 +368,9 @@ pub(crate) async fn enable_built_in_extension(
     if bundle_id.extension_id == calculator::DATA_SOURCE_ID {
         let calculator_search = calculator::CalculatorSource::new(2000f64);
         search_source_registry_tauri_state
-            .register_source(calculator_search)
-            .await;
+            .register_source(tauri_app_handle, calculator_search)
+            .await
+            .map_err(|e| report_error(&e, ReportErrorStyle::SingleLine))?;
         alter_extension_json_file(
             &get_built_in_extension_directory(tauri_app_handle),
             bundle_id,
@@ -343,8 +400,9 @@ pub(crate) async fn enable_built_in_extension(
     if bundle_id.extension_id == file_search::EXTENSION_ID {
         let file_system_search = file_search::FileSearchExtensionSearchSource;
         search_source_registry_tauri_state
-            .register_source(file_system_search)
-            .await;
+            .register_source(tauri_app_handle, file_system_search)
+            .await
+            .map_err(|e| report_error(&e, ReportErrorStyle::SingleLine))?;
         alter_extension_json_file(
             &get_built_in_extension_directory(tauri_app_handle),
             bundle_id,
@@ -358,8 +416,53 @@ pub(crate) async fn enable_built_in_extension(
     if bundle_id.extension_id == open_camera::EXTENSION_ID {
         let camera_search = open_camera::OpenCameraSearchSource::new(1500f64);
         search_source_registry_tauri_state
-            .register_source(camera_search)
-            .await;
+            .register_source(tauri_app_handle, camera_search)
+            .await
+            .map_err(|e| report_error(&e, ReportErrorStyle::SingleLine))?;
+        alter_extension_json_file(
+            &get_built_in_extension_directory(tauri_app_handle),
+            bundle_id,
+            update_extension,
+        )?;
+        return Ok(());
+    }
+
+    #[cfg(target_os = "linux")]
+    if bundle_id.extension_id == browser::EXTENSION_ID {
+        let browser_search = browser::BrowserSearchSource::new(1000f64);
+        search_source_registry_tauri_state
+            .register_source(tauri_app_handle, browser_search)
+            .await
+            .map_err(|e| report_error(&e, ReportErrorStyle::SingleLine))?;
+        alter_extension_json_file(
+            &get_built_in_extension_directory(tauri_app_handle),
+            bundle_id,
+            update_extension,
+        )?;
+        return Ok(());
+    }
+
+    if bundle_id.extension_id == clipboard_history::EXTENSION_ID {
+        let clipboard_history_search =
+            clipboard_history::ClipboardHistorySearchSource::new(1000f64);
+        search_source_registry_tauri_state
+            .register_source(tauri_app_handle, clipboard_history_search)
+            .await
+            .map_err(|e| report_error(&e, ReportErrorStyle::SingleLine))?;
+        alter_extension_json_file(
+            &get_built_in_extension_directory(tauri_app_handle),
+            bundle_id,
+            update_extension,
+        )?;
+        return Ok(());
+    }
+
+    if bundle_id.extension_id == snippets::EXTENSION_ID {
+        let snippets_search = snippets::SnippetsSearchSource::new(1000f64);
+        search_source_registry_tauri_state
+            .register_source(tauri_app_handle, snippets_search)
+            .await
+            .map_err(|e| report_error(&e, ReportErrorStyle::SingleLine))?;
         alter_extension_json_file(
             &get_built_in_extension_directory(tauri_app_handle),
             bundle_id,
@@ -377,8 +480,9 @@ pub(crate) async fn enable_built_in_extension(
 
                 let file_system_search = window_management::search_source::WindowManagementSearchSource;
                 search_source_registry_tauri_state
-                    .register_source(file_system_search)
-                    .await;
+                    .register_source(tauri_app_handle, file_system_search)
+                    .await
+                    .map_err(|e| report_error(&e, ReportErrorStyle::SingleLine))?;
 
                 let extension =
                     load_extension_from_json_file(&built_in_extension_dir, bundle_id.extension_id)?;
@@ -420,9 +524,8 @@ pub(crate) async fn disable_built_in_extension(
         && bundle_id.sub_extension_id.is_none()
     {
         search_source_registry_tauri_state
-            .remove_source(bundle_id.extension_id)
+            .remove_source(tauri_app_handle, bundle_id.extension_id)
             .await;
-        unset_apps_hotkey(tauri_app_handle)?;
 
         alter_extension_json_file(
             &get_built_in_extension_directory(tauri_app_handle),
@@ -443,7 +546,7 @@ pub(crate) async fn disable_built_in_extension(
 
     if bundle_id.extension_id == calculator::DATA_SOURCE_ID {
         search_source_registry_tauri_state
-            .remove_source(bundle_id.extension_id)
+            .remove_source(tauri_app_handle, bundle_id.extension_id)
             .await;
         alter_extension_json_file(
             &get_built_in_extension_directory(tauri_app_handle),
@@ -475,7 +578,7 @@ pub(crate) async fn disable_built_in_extension(
 
     if bundle_id.extension_id == file_search::EXTENSION_ID {
         search_source_registry_tauri_state
-            .remove_source(bundle_id.extension_id)
+            .remove_source(tauri_app_handle, bundle_id.extension_id)
             .await;
         alter_extension_json_file(
             &get_built_in_extension_directory(tauri_app_handle),
@@ -487,7 +590,44 @@ pub(crate) async fn disable_built_in_extension(
 
     if bundle_id.extension_id == open_camera::EXTENSION_ID {
         search_source_registry_tauri_state
-            .remove_source(bundle_id.extension_id)
+            .remove_source(tauri_app_handle, bundle_id.extension_id)
+            .await;
+        alter_extension_json_file(
+            &get_built_in_extension_directory(tauri_app_handle),
+            bundle_id,
+            update_extension,
+        )?;
+        return Ok(());
+    }
+
+    #[cfg(target_os = "linux")]
+    if bundle_id.extension_id == browser::EXTENSION_ID {
+        search_source_registry_tauri_state
+            .remove_source(tauri_app_handle, bundle_id.extension_id)
+            .await;
+        alter_extension_json_file(
+            &get_built_in_extension_directory(tauri_app_handle),
+            bundle_id,
+            update_extension,
+        )?;
+        return Ok(());
+    }
+
+    if bundle_id.extension_id == clipboard_history::EXTENSION_ID {
+        search_source_registry_tauri_state
+            .remove_source(tauri_app_handle, bundle_id.extension_id)
+            .await;
+        alter_extension_json_file(
+            &get_built_in_extension_directory(tauri_app_handle),
+            bundle_id,
+            update_extension,
+        )?;
+        return Ok(());
+    }
+
+    if bundle_id.extension_id == snippets::EXTENSION_ID {
+        search_source_registry_tauri_state
+            .remove_source(tauri_app_handle, bundle_id.extension_id)
             .await;
         alter_extension_json_file(
             &get_built_in_extension_directory(tauri_app_handle),
@@ -505,7 +645,7 @@ pub(crate) async fn disable_built_in_extension(
                 let built_in_extension_dir = get_built_in_extension_directory(tauri_app_handle);
 
                 search_source_registry_tauri_state
-                    .remove_source(bundle_id.extension_id)
+                    .remove_source(tauri_app_handle, bundle_id.extension_id)
                     .await;
                 alter_extension_json_file(&built_in_extension_dir, bundle_id, update_extension)?;
 
@@ -731,6 +871,28 @@ pub(crate) async fn is_built_in_extension_enabled(
             .is_some());
     }
 
+    #[cfg(target_os = "linux")]
+    if bundle_id.extension_id == browser::EXTENSION_ID {
+        return Ok(search_source_registry_tauri_state
+            .get_source(bundle_id.extension_id)
+            .await
+            .is_some());
+    }
+
+    if bundle_id.extension_id == clipboard_history::EXTENSION_ID {
+        return Ok(search_source_registry_tauri_state
+            .get_source(bundle_id.extension_id)
+            .await
+            .is_some());
+    }
+
+    if bundle_id.extension_id == snippets::EXTENSION_ID {
+        return Ok(search_source_registry_tauri_state
+            .get_source(bundle_id.extension_id)
+            .await
+            .is_some());
+    }
+
     cfg_if::cfg_if! {
         if #[cfg(target_os = "macos")] {
             // Window Management
@@ -5,13 +5,12 @@ use crate::common::{
     search::{QueryResponse, QuerySource, SearchQuery},
     traits::SearchSource,
 };
-use crate::extension::calculate_text_similarity;
+use crate::search::fuzzy::{fuzzy_match, fuzzy_score};
 use async_trait::async_trait;
 use tauri::AppHandle;
 
 pub(crate) const EXTENSION_ID: &str = "OpenCamera";
 const EXTENSION_NAME: &str = "Open Camera";
-const EXTENSION_NAME_LOWERCASE: &str = "open camera";
 
 /// JSON file for this extension.
 pub(crate) const PLUGIN_JSON_FILE: &str = r#"
@@ -51,6 +50,11 @@ impl SearchSource for OpenCameraSearchSource {
         }
     }
 
+    /// This extension itself is the only hit.
+    fn supports_pagination(&self) -> bool {
+        false
+    }
+
     async fn search(
         &self,
         _tauri_app_handle: AppHandle,
@@ -75,11 +79,9 @@ impl SearchSource for OpenCameraSearchSource {
             });
         }
 
-        let query_string_lowercase = query_string.to_lowercase();
-
         // Match against the extension name and common aliases
-        let search_terms = [
-            EXTENSION_NAME_LOWERCASE,
+        let opt_name_match = fuzzy_match(query_string, EXTENSION_NAME);
+        let aliases = [
             "camera",
             "webcam",
             "selfie",
@@ -89,12 +91,12 @@ impl SearchSource for OpenCameraSearchSource {
             "相机",
         ];
 
-        let mut best_score = 0_f64;
-        for term in &search_terms {
-            if let Some(score) = calculate_text_similarity(&query_string_lowercase, term) {
-                if score > best_score {
-                    best_score = score;
-                }
+        let mut best_score = opt_name_match.as_ref().map_or(0.0, |m| m.score);
+        for alias in &aliases {
+            if let Some(score) = fuzzy_score(query_string, alias)
+                && score > best_score
+            {
+                best_score = score;
             }
         }
 
@@ -110,6 +112,7 @@ impl SearchSource for OpenCameraSearchSource {
         let doc = Document {
             id: EXTENSION_ID.to_string(),
             title: Some(EXTENSION_NAME.to_string()),
+            title_match_ranges: opt_name_match.map(|name_match| name_match.ranges),
             category: Some("View".to_string()),
             icon: Some("font_camera".to_string()),
             source: Some(DataSourceReference {
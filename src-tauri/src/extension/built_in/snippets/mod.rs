@@ -0,0 +1,535 @@
+//! Snippets extension, it lets users search the text snippets they defined,
+//! and paste them with the placeholders filled in.
+//!
+//! A snippet can have a keyword, typing the exact keyword ranks the snippet
+//! first.  For the supported placeholders, see [`template`].
+
+pub(crate) mod template;
+
+use super::super::LOCAL_QUERY_SOURCE_TYPE;
+use crate::common::document::{DataSourceReference, Document, OnOpened};
+use crate::common::document_action::DocumentAction;
+use crate::common::error::SearchError;
+use crate::common::search::{QueryResponse, QuerySource, SearchQuery};
+use crate::common::traits::SearchSource;
+use crate::search::fuzzy::fuzzy_match;
+use crate::search::preview::{DocumentPreview, truncated_text_preview};
+use async_trait::async_trait;
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as Json;
+use std::collections::HashMap;
+use std::ops::Range;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use tauri::AppHandle;
+use tauri_plugin_clipboard_manager::ClipboardExt;
+use tauri_plugin_store::StoreExt;
+use template::{RenderContext, RenderedSnippet, SnippetTemplate};
+
+pub(crate) const EXTENSION_ID: &str = "Snippets";
+
+/// JSON file for this extension.
+pub(crate) const PLUGIN_JSON_FILE: &str = r#"
+{
+  "id": "Snippets",
+  "name": "Snippets",
+  "platforms": ["macos", "linux", "windows"],
+  "description": "Search and paste your text snippets",
+  "icon": "font_file_txt",
+  "type": "extension",
+  "enabled": true
+}
+"#;
+
+// Tauri store keys for the snippets
+const TAURI_STORE_SNIPPETS: &str = "snippets";
+const TAURI_STORE_KEY_SNIPPETS: &str = "snippets";
+
+/// Coco should be hidden, and the previous application focused, before
+/// sending the paste shortcut.
+const PASTE_DELAY: Duration = Duration::from_millis(200);
+
+const KEYWORD_MATCH_SCORE: f64 = 2.0;
+/// The text is matched by substring only, fuzzy matching long texts produces
+/// noise.
+const TEXT_MATCH_SCORE: f64 = 0.3;
+const SUMMARY_MAX_CHARS: usize = 200;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub(crate) struct Snippet {
+    /// Left empty when creating a snippet, one will be generated.
+    #[serde(default)]
+    pub(crate) id: String,
+    pub(crate) name: String,
+    /// Typing this exactly ranks this snippet first.
+    pub(crate) keyword: Option<String>,
+    /// Text with placeholders.
+    pub(crate) text: String,
+}
+
+impl Snippet {
+    fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("snippet name should not be empty".into());
+        }
+        if let Some(ref keyword) = self.keyword
+            && (keyword.is_empty() || keyword.contains(char::is_whitespace))
+        {
+            return Err(format!(
+                "snippet keyword [{}] should be non-empty and contain no whitespace",
+                keyword
+            ));
+        }
+        SnippetTemplate::parse(&self.text)
+            .map_err(|e| format!("snippet [{}] has invalid text, {}", self.name, e))?;
+
+        Ok(())
+    }
+
+    fn template(&self) -> SnippetTemplate {
+        SnippetTemplate::parse(&self.text).expect("snippets are validated when saved")
+    }
+}
+
+fn generate_id() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    format!(
+        "{}-{}",
+        chrono::Utc::now().timestamp_millis(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    )
+}
+
+fn load_snippets(tauri_app_handle: &AppHandle) -> Vec<Snippet> {
+    let store = tauri_app_handle
+        .store(TAURI_STORE_SNIPPETS)
+        .unwrap_or_else(|e| {
+            panic!(
+                "store [{}] not found/loaded, error [{}]",
+                TAURI_STORE_SNIPPETS, e
+            )
+        });
+
+    match store.get(TAURI_STORE_KEY_SNIPPETS) {
+        Some(json) => serde_json::from_value(json.clone()).unwrap_or_else(|e| {
+            panic!(
+                "failed to deserialize JSON [{}] to [Vec<Snippet>], error [{}], store [{}] key [{}] is possibly corrupted!",
+                json, e, TAURI_STORE_SNIPPETS, TAURI_STORE_KEY_SNIPPETS
+            )
+        }),
+        None => Vec::new(),
+    }
+}
+
+fn persist_snippets(tauri_app_handle: &AppHandle, snippets: &[Snippet]) {
+    tauri_app_handle
+        .store(TAURI_STORE_SNIPPETS)
+        .expect("create or load a store should not fail")
+        .set(
+            TAURI_STORE_KEY_SNIPPETS,
+            serde_json::to_value(snippets).expect("serializing snippets should not fail"),
+        );
+}
+
+/// Validate `new_snippets` and merge them into `snippets`.  A snippet with an
+/// empty ID is added with a generated ID, others replace the existing
+/// snippets with the same ID.
+fn merge_snippets(snippets: &mut Vec<Snippet>, new_snippets: Vec<Snippet>) -> Result<(), String> {
+    let mut merged = snippets.clone();
+    for mut snippet in new_snippets {
+        snippet.validate()?;
+        if snippet.id.is_empty() {
+            snippet.id = generate_id();
+        }
+
+        match merged.iter_mut().find(|s| s.id == snippet.id) {
+            Some(existing) => *existing = snippet,
+            None => merged.push(snippet),
+        }
+    }
+
+    for (idx, snippet) in merged.iter().enumerate() {
+        if let Some(ref keyword) = snippet.keyword
+            && merged[..idx]
+                .iter()
+                .any(|s| s.keyword.as_ref() == Some(keyword))
+        {
+            return Err(format!(
+                "snippet keyword [{}] is used by more than one snippet",
+                keyword
+            ));
+        }
+    }
+
+    *snippets = merged;
+    Ok(())
+}
+
+fn find_snippet(tauri_app_handle: &AppHandle, id: &str) -> Result<Snippet, String> {
+    load_snippets(tauri_app_handle)
+        .into_iter()
+        .find(|snippet| snippet.id == id)
+        .ok_or_else(|| format!("snippet [{}] does not exist", id))
+}
+
+fn render_snippet(
+    tauri_app_handle: &AppHandle,
+    snippet: &Snippet,
+    user_supplied_args: &Option<HashMap<String, Json>>,
+) -> RenderedSnippet {
+    let template = snippet.template();
+    let opt_clipboard = if template.uses_clipboard() {
+        tauri_app_handle.clipboard().read_text().ok()
+    } else {
+        None
+    };
+    let context = RenderContext {
+        now: chrono::Local::now(),
+        clipboard: opt_clipboard.as_deref(),
+    };
+
+    template.render(user_supplied_args, &context)
+}
+
+/// Copy the rendered text of snippet `id` to the clipboard.
+pub(crate) fn copy_snippet(
+    tauri_app_handle: &AppHandle,
+    id: &str,
+    user_supplied_args: &Option<HashMap<String, Json>>,
+) -> Result<(), String> {
+    let snippet = find_snippet(tauri_app_handle, id)?;
+    let rendered = render_snippet(tauri_app_handle, &snippet, user_supplied_args);
+    tauri_app_handle
+        .clipboard()
+        .write_text(rendered.text)
+        .map_err(|e| e.to_string())
+}
+
+/// Paste the rendered text of snippet `id` into the frontmost application,
+/// called when a snippet is opened.
+pub(crate) async fn paste_snippet(
+    tauri_app_handle: &AppHandle,
+    id: &str,
+    user_supplied_args: &Option<HashMap<String, Json>>,
+) -> Result<(), String> {
+    let snippet = find_snippet(tauri_app_handle, id)?;
+    let RenderedSnippet {
+        text,
+        chars_after_cursor: opt_chars_after_cursor,
+    } = render_snippet(tauri_app_handle, &snippet, user_supplied_args);
+    tauri_app_handle
+        .clipboard()
+        .write_text(text)
+        .map_err(|e| e.to_string())?;
+    crate::hide_coco(tauri_app_handle.clone()).await;
+
+    // Don't block opening, it has a timeout
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(PASTE_DELAY).await;
+        let mut result = crate::util::keyboard::simulate_paste().await;
+        if result.is_ok()
+            && let Some(chars_after_cursor) = opt_chars_after_cursor
+        {
+            result = crate::util::keyboard::press_left_arrow(chars_after_cursor).await;
+        }
+        if let Err(e) = result {
+            log::warn!("failed to paste snippet, error [{}]", e);
+        }
+    });
+
+    Ok(())
+}
+
+/// Return how well `query_string` matches `snippet`, and the matched ranges
+/// of its name.
+fn match_snippet(
+    query_string: &str,
+    snippet: &Snippet,
+) -> Option<(f64, Option<Vec<Range<usize>>>)> {
+    if snippet.keyword.as_deref() == Some(query_string) {
+        return Some((KEYWORD_MATCH_SCORE, None));
+    }
+
+    let opt_name_match =
+        fuzzy_match(query_string, &snippet.name).filter(|m| m.score >= TEXT_MATCH_SCORE);
+    if let Some(name_match) = opt_name_match {
+        return Some((name_match.score, Some(name_match.ranges)));
+    }
+
+    if snippet
+        .text
+        .to_lowercase()
+        .contains(&query_string.to_lowercase())
+    {
+        return Some((TEXT_MATCH_SCORE, None));
+    }
+
+    fuzzy_match(query_string, &snippet.name).map(|m| (m.score, Some(m.ranges)))
+}
+
+fn snippet_to_document(
+    snippet: &Snippet,
+    title_match_ranges: Option<Vec<Range<usize>>>,
+) -> Document {
+    Document {
+        id: snippet.id.clone(),
+        title: Some(snippet.name.clone()),
+        title_match_ranges,
+        summary: Some(snippet.text.chars().take(SUMMARY_MAX_CHARS).collect()),
+        category: Some(EXTENSION_ID.into()),
+        tags: snippet.keyword.clone().map(|keyword| vec![keyword]),
+        icon: Some("font_file_txt".into()),
+        on_opened: Some(OnOpened::Snippet {
+            id: snippet.id.clone(),
+        }),
+        actions: Some(vec![DocumentAction::copy_snippet(&snippet.id)]),
+        source: Some(DataSourceReference {
+            r#type: Some(EXTENSION_ID.into()),
+            name: Some(EXTENSION_ID.into()),
+            id: Some(EXTENSION_ID.into()),
+            icon: None,
+        }),
+        ..Default::default()
+    }
+}
+
+pub(crate) struct SnippetsSearchSource {
+    base_score: f64,
+}
+
+impl SnippetsSearchSource {
+    pub(crate) fn new(base_score: f64) -> Self {
+        Self { base_score }
+    }
+}
+
+#[async_trait]
+impl SearchSource for SnippetsSearchSource {
+    fn get_type(&self) -> QuerySource {
+        QuerySource {
+            r#type: LOCAL_QUERY_SOURCE_TYPE.into(),
+            name: hostname::get()
+                .unwrap_or(EXTENSION_ID.into())
+                .to_string_lossy()
+                .into(),
+            id: EXTENSION_ID.into(),
+        }
+    }
+
+    async fn preview(
+        &self,
+        tauri_app_handle: &AppHandle,
+        document: &Document,
+    ) -> Result<Option<DocumentPreview>, SearchError> {
+        let opt_preview = load_snippets(tauri_app_handle)
+            .into_iter()
+            .find(|snippet| snippet.id == document.id)
+            .map(|snippet| {
+                let rendered = render_snippet(tauri_app_handle, &snippet, &None);
+                truncated_text_preview(&rendered.text)
+            });
+
+        Ok(opt_preview)
+    }
+
+    async fn search(
+        &self,
+        tauri_app_handle: AppHandle,
+        query: SearchQuery,
+    ) -> Result<QueryResponse, SearchError> {
+        let query_string = query
+            .query_strings
+            .get("query")
+            .map(|query_string| query_string.trim())
+            .unwrap_or_default();
+        if query_string.is_empty() {
+            return Ok(QueryResponse {
+                source: self.get_type(),
+                hits: Vec::new(),
+                total_hits: 0,
+                aggregations: None,
+            });
+        }
+
+        let mut hits: Vec<(Document, f64)> = load_snippets(&tauri_app_handle)
+            .iter()
+            .filter_map(|snippet| {
+                let (score, ranges) = match_snippet(query_string, snippet)?;
+                Some((
+                    snippet_to_document(snippet, ranges),
+                    self.base_score * score,
+                ))
+            })
+            .collect();
+        hits.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+
+        let total_hits = hits.len();
+        let hits = hits
+            .into_iter()
+            .skip(query.from as usize)
+            .take(query.size as usize)
+            .collect();
+
+        Ok(QueryResponse {
+            source: self.get_type(),
+            hits,
+            total_hits,
+            aggregations: None,
+        })
+    }
+}
+
+#[tauri::command]
+pub(crate) async fn list_snippets(tauri_app_handle: AppHandle) -> Vec<Snippet> {
+    load_snippets(&tauri_app_handle)
+}
+
+/// Add a new snippet, or update the snippet with the same ID.  Return the
+/// saved snippet, with its ID filled in.
+#[tauri::command]
+pub(crate) async fn save_snippet(
+    tauri_app_handle: AppHandle,
+    mut snippet: Snippet,
+) -> Result<Snippet, String> {
+    if snippet.id.is_empty() {
+        snippet.id = generate_id();
+    }
+
+    let mut snippets = load_snippets(&tauri_app_handle);
+    merge_snippets(&mut snippets, vec![snippet.clone()])?;
+    persist_snippets(&tauri_app_handle, &snippets);
+
+    Ok(snippet)
+}
+
+#[tauri::command]
+pub(crate) async fn remove_snippet(tauri_app_handle: AppHandle, id: String) -> Result<(), String> {
+    let mut snippets = load_snippets(&tauri_app_handle);
+    let n_snippets = snippets.len();
+    snippets.retain(|snippet| snippet.id != id);
+    if snippets.len() == n_snippets {
+        return Err(format!("snippet [{}] does not exist", id));
+    }
+    persist_snippets(&tauri_app_handle, &snippets);
+
+    Ok(())
+}
+
+/// Return name and optional default value of the arguments that the user
+/// should supply, passed as `extra_args` when opening the snippet.
+#[tauri::command]
+pub(crate) async fn snippet_arguments(
+    tauri_app_handle: AppHandle,
+    id: String,
+) -> Result<IndexMap<String, Option<String>>, String> {
+    let snippet = find_snippet(&tauri_app_handle, &id)?;
+    Ok(snippet.template().arguments())
+}
+
+/// Import snippets from a JSON array, as produced by `export_snippets`.
+/// Snippets with the same ID are overwritten.  Nothing is imported if any
+/// of them is invalid.  Return the number of imported snippets.
+#[tauri::command]
+pub(crate) async fn import_snippets(
+    tauri_app_handle: AppHandle,
+    json: String,
+) -> Result<usize, String> {
+    let new_snippets: Vec<Snippet> =
+        serde_json::from_str(&json).map_err(|e| format!("invalid snippets JSON, error [{}]", e))?;
+    let n_new_snippets = new_snippets.len();
+
+    let mut snippets = load_snippets(&tauri_app_handle);
+    merge_snippets(&mut snippets, new_snippets)?;
+    persist_snippets(&tauri_app_handle, &snippets);
+
+    Ok(n_new_snippets)
+}
+
+/// Export all the snippets as a JSON array.
+#[tauri::command]
+pub(crate) async fn export_snippets(tauri_app_handle: AppHandle) -> String {
+    serde_json::to_string_pretty(&load_snippets(&tauri_app_handle))
+        .expect("serializing snippets should not fail")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snippet(id: &str, name: &str, keyword: Option<&str>, text: &str) -> Snippet {
+        Snippet {
+            id: id.into(),
+            name: name.into(),
+            keyword: keyword.map(str::to_string),
+            text: text.into(),
+        }
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(
+            snippet("1", "Greeting", Some(";hi"), "Hi {name}")
+                .validate()
+                .is_ok()
+        );
+        assert!(snippet("1", " ", None, "Hi").validate().is_err());
+        assert!(
+            snippet("1", "Greeting", Some("; hi"), "Hi")
+                .validate()
+                .is_err()
+        );
+        assert!(
+            snippet("1", "Greeting", None, "Hi {name")
+                .validate()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_merge_snippets() {
+        let mut snippets = vec![snippet("1", "Greeting", Some(";hi"), "Hi")];
+
+        merge_snippets(
+            &mut snippets,
+            vec![
+                snippet("1", "Greeting", Some(";hi"), "Hello"),
+                snippet("", "Signature", Some(";sig"), "Regards"),
+            ],
+        )
+        .unwrap();
+        assert_eq!(snippets.len(), 2);
+        assert_eq!(snippets[0].text, "Hello");
+        assert!(!snippets[1].id.is_empty());
+
+        // Duplicate keyword, nothing is merged
+        let result = merge_snippets(
+            &mut snippets,
+            vec![
+                snippet("", "Address", None, "Somewhere"),
+                snippet("", "Another signature", Some(";sig"), "Bye"),
+            ],
+        );
+        assert!(result.is_err());
+        assert_eq!(snippets.len(), 2);
+    }
+
+    #[test]
+    fn test_match_snippet() {
+        let signature = snippet("1", "Email signature", Some(";sig"), "Best regards");
+
+        let (score, ranges) = match_snippet(";sig", &signature).unwrap();
+        assert_eq!(score, KEYWORD_MATCH_SCORE);
+        assert!(ranges.is_none());
+
+        let (score, ranges) = match_snippet("email", &signature).unwrap();
+        assert!(score > TEXT_MATCH_SCORE);
+        assert_eq!(ranges, Some(vec![Range { start: 0, end: 5 }]));
+
+        let (score, ranges) = match_snippet("REGARDS", &signature).unwrap();
+        assert_eq!(score, TEXT_MATCH_SCORE);
+        assert!(ranges.is_none());
+
+        assert!(match_snippet("address", &signature).is_none());
+    }
+}
@@ -0,0 +1,291 @@
+//! Snippet text with placeholders, parsed with the quicklink placeholder
+//! parser.
+//!
+//! Besides user-supplied arguments, e.g., `{name}` or
+//! `{argument_name: "name", default: "Coco"}`, the following placeholders are
+//! filled in automatically:
+//!
+//! * `{date}`: current date, e.g., "2025-01-31"
+//! * `{time}`: current time, e.g., "13:45"
+//! * `{datetime}`: current date and time
+//! * `{clipboard}`: text on the clipboard
+//! * `{cursor}`: empty, the text cursor is moved here after pasting
+//!
+//! `{{` and `}}` are literal braces, unlike in quicklinks.
+
+use crate::extension::{QuicklinkLinkComponent, parse_quicklink_components};
+use chrono::{DateTime, Local};
+use indexmap::IndexMap;
+use serde_json::Value as Json;
+use std::collections::HashMap;
+
+const PLACEHOLDER_DATE: &str = "date";
+const PLACEHOLDER_TIME: &str = "time";
+const PLACEHOLDER_DATETIME: &str = "datetime";
+const PLACEHOLDER_CLIPBOARD: &str = "clipboard";
+const PLACEHOLDER_CURSOR: &str = "cursor";
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+const TIME_FORMAT: &str = "%H:%M";
+
+fn is_builtin_placeholder(name: &str) -> bool {
+    matches!(
+        name,
+        PLACEHOLDER_DATE
+            | PLACEHOLDER_TIME
+            | PLACEHOLDER_DATETIME
+            | PLACEHOLDER_CLIPBOARD
+            | PLACEHOLDER_CURSOR
+    )
+}
+
+/// Push `component`, merging adjacent static strings.
+fn push_component(components: &mut Vec<QuicklinkLinkComponent>, component: QuicklinkLinkComponent) {
+    if let QuicklinkLinkComponent::StaticStr(ref str) = component
+        && let Some(QuicklinkLinkComponent::StaticStr(last)) = components.last_mut()
+    {
+        last.push_str(str);
+        return;
+    }
+    components.push(component);
+}
+
+/// Parse `text` with the quicklink placeholder parser, except for `{{` and
+/// `}}` outside of placeholders, which are literal braces.
+fn parse_components(text: &str) -> Result<Vec<QuicklinkLinkComponent>, String> {
+    let mut components = Vec::new();
+    let mut segment = String::new();
+    // Nesting level of the braces of the current placeholder
+    let mut depth = 0_usize;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' | '}' if depth == 0 && chars.peek() == Some(&c) => {
+                chars.next();
+                for component in parse_quicklink_components(&std::mem::take(&mut segment))? {
+                    push_component(&mut components, component);
+                }
+                push_component(
+                    &mut components,
+                    QuicklinkLinkComponent::StaticStr(c.to_string()),
+                );
+                continue;
+            }
+            '{' => depth += 1,
+            '}' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+        segment.push(c);
+    }
+    for component in parse_quicklink_components(&segment)? {
+        push_component(&mut components, component);
+    }
+
+    Ok(components)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct SnippetTemplate {
+    components: Vec<QuicklinkLinkComponent>,
+}
+
+/// Values of the placeholders that are not supplied by the user.
+pub(crate) struct RenderContext<'a> {
+    pub(crate) now: DateTime<Local>,
+    /// `None` if the clipboard does not contain text.
+    pub(crate) clipboard: Option<&'a str>,
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) struct RenderedSnippet {
+    pub(crate) text: String,
+    /// Number of characters after the `{cursor}` placeholder, i.e., how many
+    /// times the cursor should be moved left after pasting.  `None` if there
+    /// is no such placeholder.
+    pub(crate) chars_after_cursor: Option<usize>,
+}
+
+impl SnippetTemplate {
+    pub(crate) fn parse(text: &str) -> Result<Self, String> {
+        let components = parse_components(text)?;
+        for component in components.iter() {
+            if let QuicklinkLinkComponent::DynamicPlaceholder { argument_name, .. } = component
+                && argument_name.is_empty()
+            {
+                return Err("placeholder name should not be empty".into());
+            }
+        }
+
+        Ok(Self { components })
+    }
+
+    /// Return name and optional default value of the arguments that should
+    /// be supplied by the user.
+    pub(crate) fn arguments(&self) -> IndexMap<String, Option<String>> {
+        let mut arguments = IndexMap::new();
+        for component in self.components.iter() {
+            if let QuicklinkLinkComponent::DynamicPlaceholder {
+                argument_name,
+                default,
+            } = component
+                && !is_builtin_placeholder(argument_name)
+            {
+                arguments.insert(argument_name.clone(), default.clone());
+            }
+        }
+
+        arguments
+    }
+
+    /// Fill in the placeholders, missing arguments are replaced with their
+    /// default values, or empty strings if they have no default value.
+    pub(crate) fn render(
+        &self,
+        user_supplied_args: &Option<HashMap<String, Json>>,
+        context: &RenderContext<'_>,
+    ) -> RenderedSnippet {
+        let mut text = String::new();
+        let mut opt_cursor_pos = None;
+
+        for component in self.components.iter() {
+            match component {
+                QuicklinkLinkComponent::StaticStr(str) => text.push_str(str),
+                QuicklinkLinkComponent::DynamicPlaceholder {
+                    argument_name,
+                    default,
+                } => match argument_name.as_str() {
+                    PLACEHOLDER_DATE => text.push_str(&context.now.format(DATE_FORMAT).to_string()),
+                    PLACEHOLDER_TIME => text.push_str(&context.now.format(TIME_FORMAT).to_string()),
+                    PLACEHOLDER_DATETIME => text.push_str(
+                        &context
+                            .now
+                            .format(&format!("{} {}", DATE_FORMAT, TIME_FORMAT))
+                            .to_string(),
+                    ),
+                    PLACEHOLDER_CLIPBOARD => text.push_str(context.clipboard.unwrap_or_default()),
+                    // Only the first one takes effect
+                    PLACEHOLDER_CURSOR => {
+                        opt_cursor_pos.get_or_insert(text.len());
+                    }
+                    _ => {
+                        let opt_value = user_supplied_args
+                            .as_ref()
+                            .and_then(|args| args.get(argument_name.as_str()))
+                            .and_then(Json::as_str)
+                            .or(default.as_deref());
+                        text.push_str(opt_value.unwrap_or_default());
+                    }
+                },
+            }
+        }
+
+        let chars_after_cursor = opt_cursor_pos.map(|pos| text[pos..].chars().count());
+        RenderedSnippet {
+            text,
+            chars_after_cursor,
+        }
+    }
+
+    /// Return true if rendering reads the clipboard.
+    pub(crate) fn uses_clipboard(&self) -> bool {
+        self.components.iter().any(|component| {
+            matches!(
+                component,
+                QuicklinkLinkComponent::DynamicPlaceholder { argument_name, .. }
+                    if argument_name == PLACEHOLDER_CLIPBOARD
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn context(clipboard: Option<&str>) -> RenderContext<'_> {
+        RenderContext {
+            now: Local.with_ymd_and_hms(2025, 1, 31, 13, 45, 0).unwrap(),
+            clipboard,
+        }
+    }
+
+    #[test]
+    fn test_render_builtin_placeholders() {
+        let template = SnippetTemplate::parse("{date} {time} | {datetime} | {clipboard}").unwrap();
+        assert!(template.arguments().is_empty());
+        assert!(template.uses_clipboard());
+
+        let rendered = template.render(&None, &context(Some("copied")));
+        assert_eq!(
+            rendered.text,
+            "2025-01-31 13:45 | 2025-01-31 13:45 | copied"
+        );
+        assert_eq!(rendered.chars_after_cursor, None);
+
+        let rendered = template.render(&None, &context(None));
+        assert_eq!(rendered.text, "2025-01-31 13:45 | 2025-01-31 13:45 | ");
+    }
+
+    #[test]
+    fn test_render_arguments() {
+        let template = SnippetTemplate::parse(
+            r#"Hi {name}, regards, {argument_name: "sender", default: "Coco"}"#,
+        )
+        .unwrap();
+        assert!(!template.uses_clipboard());
+
+        let arguments = template.arguments();
+        assert_eq!(arguments.len(), 2);
+        assert_eq!(arguments["name"], None);
+        assert_eq!(arguments["sender"], Some("Coco".into()));
+
+        let rendered = template.render(&None, &context(None));
+        assert_eq!(rendered.text, "Hi , regards, Coco");
+
+        let args = HashMap::from([
+            ("name".to_string(), Json::from("Alice")),
+            ("sender".to_string(), Json::from("Bob")),
+        ]);
+        let rendered = template.render(&Some(args), &context(None));
+        assert_eq!(rendered.text, "Hi Alice, regards, Bob");
+    }
+
+    #[test]
+    fn test_render_cursor() {
+        let template = SnippetTemplate::parse("<b>{cursor}</b>{cursor}").unwrap();
+        let rendered = template.render(&None, &context(None));
+        assert_eq!(rendered.text, "<b></b>");
+        assert_eq!(rendered.chars_after_cursor, Some(4));
+
+        // Counted in characters, not bytes
+        let template = SnippetTemplate::parse("「{cursor}」").unwrap();
+        let rendered = template.render(&None, &context(None));
+        assert_eq!(rendered.chars_after_cursor, Some(1));
+    }
+
+    #[test]
+    fn test_render_escaped_braces() {
+        let template = SnippetTemplate::parse(r#"{{"date": "{date}", "name": "{name}"}}"#).unwrap();
+        assert_eq!(template.arguments().len(), 1);
+
+        let args = HashMap::from([("name".to_string(), Json::from("Alice"))]);
+        let rendered = template.render(&Some(args), &context(None));
+        assert_eq!(rendered.text, r#"{"date": "2025-01-31", "name": "Alice"}"#);
+
+        // Braces in placeholders are not escapes
+        let template =
+            SnippetTemplate::parse(r#"{{{argument_name: "json", default: "{}"}}}"#).unwrap();
+        let rendered = template.render(&None, &context(None));
+        assert_eq!(rendered.text, "{{}}");
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(SnippetTemplate::parse("fn main() {").is_err());
+        assert!(SnippetTemplate::parse("fn main() {{ }}").is_ok());
+        assert!(SnippetTemplate::parse("{}").is_err());
+        assert!(SnippetTemplate::parse("plain text").is_ok());
+    }
+}
@@ -801,7 +801,7 @@ pub(crate) fn set_up_commands_hotkeys(
 
                     if event.state() == ShortcutState::Pressed {
                         async_runtime::spawn(async move {
-                            let result = open(app_handle_clone, on_opened_clone, None).await;
+                            let result = open(app_handle_clone, on_opened_clone, None, None).await;
                             if let Err(msg) = result {
                                 log::warn!(
                                     "failed to open extension [{}], error [{}]",
@@ -870,7 +870,7 @@ pub(crate) fn set_up_command_hotkey(
 
                 if event.state() == ShortcutState::Pressed {
                     async_runtime::spawn(async move {
-                        let result = open(app_handle_clone, on_opened_clone, None).await;
+                        let result = open(app_handle_clone, on_opened_clone, None, None).await;
                         if let Err(msg) = result {
                             log::warn!(
                                 "failed to open extension [{}], error [{}]",
@@ -930,7 +930,7 @@ pub(crate) fn register_command_hotkey(
 
             if event.state() == ShortcutState::Pressed {
                 async_runtime::spawn(async move {
-                    let result = open(app_handle_clone, on_opened_clone, None).await;
+                    let result = open(app_handle_clone, on_opened_clone, None, None).await;
                     if let Err(msg) = result {
                         log::warn!(
                             "failed to open extension [{}], error [{}]",
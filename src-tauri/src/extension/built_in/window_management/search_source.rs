@@ -7,7 +7,8 @@ use crate::common::{
     traits::SearchSource,
 };
 use crate::extension::built_in::{get_built_in_extension_directory, load_extension_from_json_file};
-use crate::extension::{ExtensionType, LOCAL_QUERY_SOURCE_TYPE, calculate_text_similarity};
+use crate::extension::{ExtensionType, LOCAL_QUERY_SOURCE_TYPE};
+use crate::search::fuzzy::{fuzzy_match, fuzzy_score};
 use async_trait::async_trait;
 use hostname;
 use tauri::AppHandle;
@@ -55,8 +56,6 @@ impl SearchSource for WindowManagementSearchSource {
                 aggregations: None,
             });
         }
-        let query_string_lowercase = query_string.to_lowercase();
-
         let extension = load_extension_from_json_file(
             &get_built_in_extension_directory(&tauri_app_handle),
             super::EXTENSION_ID,
@@ -69,19 +68,16 @@ impl SearchSource for WindowManagementSearchSource {
         // We know they are all commands
         let command_type_string = ExtensionType::Command.to_string();
         for command in commands.iter().filter(|ext| ext.enabled) {
+            let opt_name_match = fuzzy_match(query_string, &command.name);
             let score = {
                 let mut score = 0_f64;
 
-                if let Some(name_score) =
-                    calculate_text_similarity(&query_string_lowercase, &command.name.to_lowercase())
-                {
-                    score += name_score;
+                if let Some(ref name_match) = opt_name_match {
+                    score += name_match.score;
                 }
 
                 if let Some(ref alias) = command.alias {
-                    if let Some(alias_score) =
-                        calculate_text_similarity(&query_string_lowercase, &alias.to_lowercase())
-                    {
+                    if let Some(alias_score) = fuzzy_score(query_string, alias) {
                         score += alias_score;
                     }
                 }
@@ -91,7 +87,7 @@ impl SearchSource for WindowManagementSearchSource {
                 // To do this, we score the extension name and take that
                 // into account.
                 if let Some(main_extension_score) =
-                    calculate_text_similarity(&query_string_lowercase, &EXTENSION_NAME_LOWERCASE)
+                    fuzzy_score(query_string, EXTENSION_NAME_LOWERCASE)
                 {
                     score += main_extension_score;
                 }
@@ -106,6 +102,7 @@ impl SearchSource for WindowManagementSearchSource {
                 let document = Document {
                     id: command.id.clone(),
                     title: Some(command.name.clone()),
+                    title_match_ranges: opt_name_match.map(|name_match| name_match.ranges),
                     icon: Some(command.icon.clone()),
                     on_opened: Some(on_opened),
                     url: Some(url),
@@ -1,5 +1,6 @@
 pub(crate) mod api;
 pub(crate) mod built_in;
+pub(crate) mod setting;
 pub(crate) mod third_party;
 pub(crate) mod view_extension;
 
@@ -21,6 +22,7 @@ use semver::Version as SemVer;
 use serde::Deserialize;
 use serde::Serialize;
 use serde_json::Value as Json;
+use setting::Setting;
 use std::collections::HashMap;
 use std::collections::HashSet;
 use std::io;
@@ -82,11 +84,17 @@ pub struct Extension {
     r#type: ExtensionType,
     /// If this is a Command extension, then action defines the operation to execute
     /// when the it is triggered.
+    ///
+    /// If this is a Script extension, then action defines the executable that
+    /// provides the search results, see `third_party::script`.
     #[serde(skip_serializing_if = "Option::is_none")]
     action: Option<CommandAction>,
     /// The link to open if this is a Quicklink extension.
     #[serde(skip_serializing_if = "Option::is_none")]
     quicklink: Option<Quicklink>,
+    /// The settings tab or panel to open if this is a Setting extension.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    setting: Option<Setting>,
 
     /*
      * If this extension is of type Group or Extension, then it behaves like a
@@ -96,6 +104,7 @@ pub struct Extension {
     scripts: Option<Vec<Extension>>,
     quicklinks: Option<Vec<Extension>>,
     views: Option<Vec<Extension>>,
+    setting_entries: Option<Vec<Extension>>,
 
     /// The alias of the extension.
     ///
@@ -314,8 +323,24 @@ pub(crate) fn _extension_on_opened(extension: &Extension) -> Option<OnOpened> {
 
             Some(OnOpened::Extension(extension_on_opened))
         }
-        ExtensionType::Script => todo!("not supported yet"),
-        ExtensionType::Setting => todo!("not supported yet"),
+        // Script extensions are not opened, they provide search results, which
+        // are opened instead, see `third_party::script`.
+        ExtensionType::Script => return None,
+        ExtensionType::Setting => {
+            let setting = extension.setting.clone().unwrap_or_else(|| {
+              panic!(
+                "Setting extension [{}]'s [setting] field is not set, something wrong with your extension validity check", extension.id
+              )
+            });
+
+            let extension_on_opened = ExtensionOnOpened {
+                ty: ExtensionOnOpenedType::Setting { setting },
+                settings,
+                permission,
+            };
+
+            Some(OnOpened::Extension(extension_on_opened))
+        }
         ExtensionType::View => {
             let name = extension.name.clone();
             let icon = extension.icon.clone();
@@ -376,6 +401,14 @@ impl Extension {
                 return Some(sub_ext);
             }
         }
+        if let Some(ref setting_entries) = self.setting_entries {
+            if let Some(sub_ext) = setting_entries
+                .iter()
+                .find(|entry| entry.id == sub_extension_id)
+            {
+                return Some(sub_ext);
+            }
+        }
 
         None
     }
@@ -411,6 +444,14 @@ impl Extension {
                 return Some(sub_ext);
             }
         }
+        if let Some(ref mut setting_entries) = self.setting_entries {
+            if let Some(sub_ext) = setting_entries
+                .iter_mut()
+                .find(|entry| entry.id == sub_extension_id)
+            {
+                return Some(sub_ext);
+            }
+        }
 
         None
     }
@@ -418,7 +459,8 @@ impl Extension {
     pub(crate) fn supports_alias_hotkey(&self) -> bool {
         let ty = self.r#type;
 
-        ty != ExtensionType::Group && ty != ExtensionType::Extension
+        // Script extensions cannot be opened, so no hotkey
+        ty != ExtensionType::Group && ty != ExtensionType::Extension && ty != ExtensionType::Script
     }
 }
 
@@ -718,6 +760,9 @@ fn filter_out_extensions(
                 if let Some(ref mut views) = extension.views {
                     views.retain(|link| link.enabled);
                 }
+                if let Some(ref mut setting_entries) = extension.setting_entries {
+                    setting_entries.retain(|entry| entry.enabled);
+                }
             }
         }
     }
@@ -772,6 +817,9 @@ fn filter_out_extensions(
                 if let Some(ref mut views) = extension.views {
                     views.retain(|link| link.r#type == extension_type);
                 }
+                if let Some(ref mut setting_entries) = extension.setting_entries {
+                    setting_entries.retain(|entry| entry.r#type == extension_type);
+                }
             }
         }
     }
@@ -827,6 +875,9 @@ fn filter_out_extensions(
                 if let Some(ref mut views) = extension.views {
                     views.retain(&match_closure);
                 }
+                if let Some(ref mut setting_entries) = extension.setting_entries {
+                    setting_entries.retain(&match_closure);
+                }
             }
         }
     }
@@ -900,7 +951,12 @@ pub(crate) async fn list_extensions(
                 .as_ref()
                 .map_or(false, |quicklinks| !quicklinks.is_empty());
 
-            has_commands || has_scripts || has_quicklinks
+            let has_setting_entries = ext
+                .setting_entries
+                .as_ref()
+                .map_or(false, |setting_entries| !setting_entries.is_empty());
+
+            has_commands || has_scripts || has_quicklinks || has_setting_entries
         });
     }
 
@@ -915,8 +971,7 @@ pub(crate) async fn init_extensions(tauri_app_handle: &AppHandle) -> Result<(),
     // Third-party extensions
     //
     // 1. Init the global search source variable
-    // 2. Init the extensions in search source
-    // 3. Register the search source
+    // 2. Register the search source, which initializes the extensions in it
     let third_party_dir = third_party::get_third_party_extension_directory(&tauri_app_handle);
     if !third_party_dir.try_exists().map_err(|e| e.to_string())? {
         tokio::fs::create_dir_all(&third_party_dir)
@@ -926,7 +981,6 @@ pub(crate) async fn init_extensions(tauri_app_handle: &AppHandle) -> Result<(),
     let extensions =
         third_party::load_third_party_extensions_from_directory(&third_party_dir).await?;
     let search_source = third_party::ThirdPartyExtensionsSearchSource::new(extensions);
-    search_source.init(&tauri_app_handle).await.unwrap();
     THIRD_PARTY_EXTENSIONS_SEARCH_SOURCE
         .set(search_source.clone())
         .unwrap_or_else(|_already_set| {
@@ -936,13 +990,18 @@ pub(crate) async fn init_extensions(tauri_app_handle: &AppHandle) -> Result<(),
             )
         });
     search_source_registry_tauri_state
-        .register_source(search_source)
-        .await;
+        .register_source(tauri_app_handle, search_source)
+        .await
+        .map_err(|e| report_error(&e, ReportErrorStyle::SingleLine))?;
 
     // Extension store
     search_source_registry_tauri_state
-        .register_source(third_party::install::store::ExtensionStore)
-        .await;
+        .register_source(
+            tauri_app_handle,
+            third_party::install::store::ExtensionStore,
+        )
+        .await
+        .map_err(|e| report_error(&e, ReportErrorStyle::SingleLine))?;
 
     // Built-in extensions
 
@@ -1140,6 +1199,12 @@ pub(crate) fn canonicalize_relative_icon_path(
         }
     }
 
+    if let Some(setting_entries) = &mut extension.setting_entries {
+        for setting_entry in setting_entries {
+            _canonicalize_relative_icon_path(extension_dir, setting_entry)?;
+        }
+    }
+
     Ok(())
 }
 
@@ -1251,6 +1316,17 @@ fn alter_extension_json_file(
             }
         }
 
+        // Search in setting entries
+        if let Some(ref mut setting_entries) = root_extension.setting_entries {
+            if let Some(entry) = setting_entries
+                .iter_mut()
+                .find(|entry| entry.id == sub_extension_id)
+            {
+                how(entry)?;
+                return Ok(());
+            }
+        }
+
         Err(format!(
             "extension [{:?}] not found in {:?}",
             bundle_id, root_extension
@@ -1297,10 +1373,13 @@ fn alter_extension_json_file(
     Ok(())
 }
 
-/// Helper function to impl Deserialize for `QuicklinkLink`.
+/// Helper function to impl Deserialize for `QuicklinkLink`, also used to parse
+/// the placeholders of snippets.
 ///
 /// Parse a quicklink string into components, handling dynamic placeholders
-fn parse_quicklink_components(input: &str) -> Result<Vec<QuicklinkLinkComponent>, String> {
+pub(crate) fn parse_quicklink_components(
+    input: &str,
+) -> Result<Vec<QuicklinkLinkComponent>, String> {
     let mut components = Vec::new();
     let mut current_pos = 0;
     let chars: Vec<char> = input.chars().collect();
@@ -1497,52 +1576,6 @@ pub(crate) struct ExtensionHttpPermission {
     pub(crate) host: String,
 }
 
-/// Calculates a similarity score between a query and a text, aiming for a [0, 1] range.
-/// Assumes query and text are already lowercased.
-///
-/// Used in extension_to_hit().
-fn calculate_text_similarity(query: &str, text: &str) -> Option<f64> {
-    if query.is_empty() || text.is_empty() {
-        return None;
-    }
-
-    if text == query {
-        return Some(1.0); // Perfect match
-    }
-
-    let query_len = query.len() as f64;
-    let text_len = text.len() as f64;
-    let ratio = query_len / text_len;
-    let mut score: f64 = 0.0;
-
-    // Case 1: Text starts with the query (prefix match)
-    // Score: base 0.5, bonus up to 0.4 for how much of `text` is covered by `query`. Max 0.9.
-    if text.starts_with(query) {
-        score = score.max(0.5 + 0.4 * ratio);
-    }
-
-    // Case 2: Text contains the query (substring match, not necessarily prefix)
-    // Score: base 0.3, bonus up to 0.3. Max 0.6.
-    // `score.max` ensures that if it's both a prefix and contains, the higher score (prefix) is taken.
-    if text.contains(query) {
-        score = score.max(0.3 + 0.3 * ratio);
-    }
-
-    // Case 3: Fallback for "all query characters exist in text" (order-independent)
-    if score < 0.2 {
-        if query.chars().all(|c_q| text.contains(c_q)) {
-            score = score.max(0.15); // Fixed low score for this weaker match type
-        }
-    }
-
-    if score > 0.0 {
-        // Cap non-perfect matches slightly below 1.0 to make perfect (1.0) distinct.
-        Some(score.min(0.95))
-    } else {
-        None
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -2014,98 +2047,6 @@ mod tests {
         assert_eq!(result, "");
     }
 
-    // Helper function for approximate floating point comparison
-    fn approx_eq(a: f64, b: f64) -> bool {
-        (a - b).abs() < 1e-10
-    }
-
-    #[test]
-    fn test_empty_strings() {
-        assert_eq!(calculate_text_similarity("", "text"), None);
-        assert_eq!(calculate_text_similarity("query", ""), None);
-        assert_eq!(calculate_text_similarity("", ""), None);
-    }
-
-    #[test]
-    fn test_perfect_match() {
-        assert_eq!(calculate_text_similarity("text", "text"), Some(1.0));
-        assert_eq!(calculate_text_similarity("a", "a"), Some(1.0));
-    }
-
-    #[test]
-    fn test_prefix_match() {
-        // For "te" and "text":
-        // score = 0.5 + 0.4 * (2/4) = 0.5 + 0.2 = 0.7
-        let score = calculate_text_similarity("te", "text").unwrap();
-        assert!(approx_eq(score, 0.7));
-
-        // For "tex" and "text":
-        // score = 0.5 + 0.4 * (3/4) = 0.5 + 0.3 = 0.8
-        let score = calculate_text_similarity("tex", "text").unwrap();
-        assert!(approx_eq(score, 0.8));
-    }
-
-    #[test]
-    fn test_substring_match() {
-        // For "ex" and "text":
-        // score = 0.3 + 0.3 * (2/4) = 0.3 + 0.15 = 0.45
-        let score = calculate_text_similarity("ex", "text").unwrap();
-        assert!(approx_eq(score, 0.45));
-
-        // Prefix should score higher than substring
-        assert!(
-            calculate_text_similarity("te", "text").unwrap()
-                > calculate_text_similarity("ex", "text").unwrap()
-        );
-    }
-
-    #[test]
-    fn test_character_presence() {
-        // Characters present but not in sequence
-        // "tac" in "contact" - not a substring, but all chars exist
-        let score = calculate_text_similarity("tac", "contact").unwrap();
-        assert!(approx_eq(0.3 + 0.3 * (3.0 / 7.0), score));
-
-        assert!(calculate_text_similarity("ac", "contact").is_some());
-
-        // Should not apply if some characters are missing
-        assert_eq!(calculate_text_similarity("xyz", "contact"), None);
-    }
-
-    #[test]
-    fn test_combined_scenarios() {
-        // Test that character presence fallback doesn't override higher scores
-        // "tex" is a prefix of "text" with score 0.8
-        let score = calculate_text_similarity("tex", "text").unwrap();
-        assert!(approx_eq(score, 0.8));
-
-        // Test a case where the characters exist but it's already a substring
-        // "act" is a substring of "contact" with score > 0.2, so fallback won't apply
-        let expected_score = 0.3 + 0.3 * (3.0 / 7.0);
-        let actual_score = calculate_text_similarity("act", "contact").unwrap();
-        assert!(approx_eq(actual_score, expected_score));
-    }
-
-    #[test]
-    fn test_no_similarity() {
-        assert_eq!(calculate_text_similarity("xyz", "test"), None);
-    }
-
-    #[test]
-    fn test_score_capping() {
-        // Use a long query that's a prefix of a slightly longer text
-        let long_text = "abcdefghijklmnopqrstuvwxyz";
-        let long_prefix = "abcdefghijklmnopqrstuvwxy"; // All but last letter
-
-        // Expected score would be 0.5 + 0.4 * (25/26) = 0.5 + 0.385 = 0.885
-        let expected_score = 0.5 + 0.4 * (25.0 / 26.0);
-        let actual_score = calculate_text_similarity(long_prefix, long_text).unwrap();
-        assert!(approx_eq(actual_score, expected_score));
-
-        // Verify that non-perfect matches are capped at 0.95
-        assert!(calculate_text_similarity("almost", "almost perfect").unwrap() <= 0.95);
-    }
-
     #[test]
     fn test_filesystem_access_serialize_empty() {
         let access = FileSystemAccess::empty();
@@ -0,0 +1,168 @@
+//! Setting extensions, which deep-link into a tab of Coco's settings window,
+//! or a panel of the system settings.
+
+use crate::util::LinuxDesktopEnvironment;
+use crate::util::get_linux_desktop_environment;
+use crate::util::platform::Platform;
+use derive_more::Display;
+use serde::Deserialize;
+use serde::Serialize;
+use tauri::AppHandle;
+use tauri::Emitter;
+
+/// The frontend opens the settings window on this event, the payload is the
+/// tab to show.
+const OPEN_SETTINGS_EVENT: &str = "open_settings";
+
+/// Where a Setting extension links to.
+///
+/// In `plugin.json`:
+///
+/// ```json
+/// { "target": "coco", "tab": "extensions" }
+/// { "target": "system", "macos": "x-apple.systempreferences:com.apple.Displays-Settings.extension", "windows": "ms-settings:display", "gnome": "display", "kde": "kcm_kscreen" }
+/// ```
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(tag = "target", rename_all = "snake_case")]
+pub(crate) enum Setting {
+    /// A tab of Coco's settings window.
+    Coco { tab: CocoSettingsTab },
+    /// A panel of the system settings.
+    System(SystemSettingsPanel),
+}
+
+/// Tabs of Coco's settings window.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Display)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum CocoSettingsTab {
+    #[display("general")]
+    General,
+    #[display("extensions")]
+    Extensions,
+    #[display("connect")]
+    Connect,
+    #[display("advanced")]
+    Advanced,
+    #[display("about")]
+    About,
+}
+
+/// A system settings panel, specified for every platform.  A platform that
+/// is not specified cannot open it.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub(crate) struct SystemSettingsPanel {
+    /// A "x-apple.systempreferences:" URL.
+    pub(crate) macos: Option<String>,
+    /// A "ms-settings:" URI.
+    pub(crate) windows: Option<String>,
+    /// A GNOME Control Center panel, e.g., "display".
+    pub(crate) gnome: Option<String>,
+    /// A KDE System Settings module, e.g., "kcm_kscreen".
+    pub(crate) kde: Option<String>,
+}
+
+impl SystemSettingsPanel {
+    /// Return true if this panel can be opened on `platform`.
+    pub(crate) fn supports(&self, platform: Platform) -> bool {
+        match platform {
+            Platform::Macos => self.macos.is_some(),
+            Platform::Windows => self.windows.is_some(),
+            Platform::Linux => self.gnome.is_some() || self.kde.is_some(),
+        }
+    }
+}
+
+/// Open the settings tab or panel `setting` links to.
+pub(crate) async fn open_setting(
+    tauri_app_handle: &AppHandle,
+    setting: &Setting,
+) -> Result<(), String> {
+    match setting {
+        Setting::Coco { tab } => tauri_app_handle
+            .emit(OPEN_SETTINGS_EVENT, tab.to_string())
+            .map_err(|e| e.to_string()),
+        Setting::System(panel) => open_system_settings_panel(tauri_app_handle, panel).await,
+    }
+}
+
+async fn open_system_settings_panel(
+    tauri_app_handle: &AppHandle,
+    panel: &SystemSettingsPanel,
+) -> Result<(), String> {
+    let current_platform = Platform::current();
+    let unsupported = || {
+        format!(
+            "this settings panel cannot be opened on [{}]",
+            current_platform
+        )
+    };
+
+    match current_platform {
+        Platform::Macos => {
+            let url = panel.macos.clone().ok_or_else(unsupported)?;
+            crate::util::open(tauri_app_handle.clone(), url).await
+        }
+        Platform::Windows => {
+            let uri = panel.windows.clone().ok_or_else(unsupported)?;
+            crate::util::open(tauri_app_handle.clone(), uri).await
+        }
+        Platform::Linux => {
+            let (program, panel_name) = match get_linux_desktop_environment() {
+                Some(LinuxDesktopEnvironment::Gnome) => (
+                    "gnome-control-center",
+                    panel.gnome.as_deref().ok_or_else(unsupported)?,
+                ),
+                Some(LinuxDesktopEnvironment::Kde) => (
+                    "systemsettings",
+                    panel.kde.as_deref().ok_or_else(unsupported)?,
+                ),
+                Some(LinuxDesktopEnvironment::Unsupported {
+                    xdg_current_desktop,
+                }) => {
+                    return Err(format!(
+                        "Cannot open settings panels as this Linux desktop environment [{}] is not supported",
+                        xdg_current_desktop
+                    ));
+                }
+                None => return Err("no desktop environment found".into()),
+            };
+
+            // The settings app keeps running, don't wait for it
+            tokio::process::Command::new(program)
+                .arg(panel_name)
+                .spawn()
+                .map(|_child| ())
+                .map_err(|e| format!("failed to spawn [{}] due to error [{}]", program, e))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deserialize_setting() {
+        let setting: Setting =
+            serde_json::from_str(r#"{ "target": "coco", "tab": "extensions" }"#).unwrap();
+        assert_eq!(
+            setting,
+            Setting::Coco {
+                tab: CocoSettingsTab::Extensions
+            }
+        );
+
+        let setting: Setting =
+            serde_json::from_str(r#"{ "target": "system", "gnome": "display" }"#).unwrap();
+        let Setting::System(panel) = setting else {
+            panic!("expect a system settings panel");
+        };
+        assert!(panel.supports(Platform::Linux));
+        assert!(!panel.supports(Platform::Macos));
+
+        assert!(
+            serde_json::from_str::<Setting>(r#"{ "target": "coco", "tab": "nonexistent" }"#)
+                .is_err()
+        );
+    }
+}
@@ -14,6 +14,7 @@
 
 use crate::extension::Extension;
 use crate::extension::ExtensionType;
+use crate::extension::setting::Setting;
 use crate::util::platform::Platform;
 use derive_more::Display;
 use serde::Serialize;
@@ -64,6 +65,11 @@ pub(crate) enum InvalidPluginJsonErrorKind {
         extra_platforms
     )]
     SubExtensionHasMoreSupportedPlatforms { extra_platforms: Vec<String> },
+    #[display(
+        "it supports platforms {:?} that its settings panel cannot be opened on",
+        platforms
+    )]
+    SettingPanelMissingForPlatforms { platforms: Vec<String> },
     #[display("an extensions of type '{}' should have field '{}' set", ty, field)]
     FieldRequired {
         field: &'static str,
@@ -75,6 +81,7 @@ pub(crate) fn general_check(extension: &Extension) -> Result<(), InvalidPluginJs
     // Check main extension
     check_main_extension_only(extension)?;
     check_main_extension_or_sub_extension(extension, false)?;
+    check_setting(extension, None, false)?;
 
     // `None` if `extension` is compatible with all the platforms. Otherwise `Some(limited_platforms)`
     let limited_supported_platforms = match extension.platforms.as_ref() {
@@ -105,12 +112,17 @@ pub(crate) fn general_check(extension: &Extension) -> Result<(), InvalidPluginJs
         Some(ref v) => v.as_slice(),
         None => &[],
     };
-    let sub_extensions = [commands, scripts, quicklinks, views].concat();
+    let setting_entries = match extension.setting_entries {
+        Some(ref v) => v.as_slice(),
+        None => &[],
+    };
+    let sub_extensions = [commands, scripts, quicklinks, views, setting_entries].concat();
     let mut sub_extension_ids = HashSet::new();
 
     for sub_extension in sub_extensions.iter() {
         check_sub_extension_only(sub_extension, limited_supported_platforms)?;
         check_main_extension_or_sub_extension(extension, true)?;
+        check_setting(sub_extension, limited_supported_platforms, true)?;
 
         if !sub_extension_ids.insert(sub_extension.id.as_str()) {
             // extension ID already exists
@@ -160,11 +172,18 @@ fn check_main_extension_only(extension: &Extension) -> Result<(), InvalidPluginJ
         || extension.scripts.is_some()
         || extension.quicklinks.is_some()
         || extension.views.is_some()
+        || extension.setting_entries.is_some()
     {
         if extension.r#type != ExtensionType::Group && extension.r#type != ExtensionType::Extension
         {
             return Err(err(InvalidPluginJsonErrorKind::FieldsNotAllowed {
-                fields: &["commands", "scripts", "quicklinks", "views"],
+                fields: &[
+                    "commands",
+                    "scripts",
+                    "quicklinks",
+                    "views",
+                    "setting_entries",
+                ],
                 ty: extension.r#type,
             }));
         }
@@ -206,10 +225,17 @@ fn check_sub_extension_only(
         || sub_extension.scripts.is_some()
         || sub_extension.quicklinks.is_some()
         || sub_extension.views.is_some()
+        || sub_extension.setting_entries.is_some()
     {
         return Err(err(
             InvalidPluginJsonErrorKind::FieldsNotAllowedForSubExtension {
-                fields: &["commands", "scripts", "quicklinks", "views"],
+                fields: &[
+                    "commands",
+                    "scripts",
+                    "quicklinks",
+                    "views",
+                    "setting_entries",
+                ],
             },
         ));
     }
@@ -266,15 +292,17 @@ fn check_main_extension_or_sub_extension(
         sub_extension_id: is_sub_extension.then(|| extension.id.clone()),
     };
 
-    // If field `action` is Some, then it should be a Command
-    if extension.action.is_some() && extension.r#type != ExtensionType::Command {
+    // If field `action` is Some, then it should be a Command or Script
+    let action_type =
+        extension.r#type == ExtensionType::Command || extension.r#type == ExtensionType::Script;
+    if extension.action.is_some() && !action_type {
         return Err(err(InvalidPluginJsonErrorKind::FieldsNotAllowed {
             fields: &["action"],
             ty: extension.r#type,
         }));
     }
 
-    if extension.r#type == ExtensionType::Command && extension.action.is_none() {
+    if action_type && extension.action.is_none() {
         return Err(err(InvalidPluginJsonErrorKind::FieldRequired {
             field: "action",
             ty: extension.r#type,
@@ -322,9 +350,68 @@ fn check_main_extension_or_sub_extension(
     Ok(())
 }
 
+/// Check field `setting` of a main extension or a sub-extension.
+///
+/// Argument `main_extension_limited_platforms`: the platforms supported by the
+/// main extension if `extension` is a sub-extension and the main extension does
+/// not support all the platforms. Otherwise, None.
+fn check_setting(
+    extension: &Extension,
+    main_extension_limited_platforms: Option<&HashSet<Platform>>,
+    is_sub_extension: bool,
+) -> Result<(), InvalidPluginJsonError> {
+    let err = |kind| InvalidPluginJsonError {
+        kind,
+        sub_extension_id: is_sub_extension.then(|| extension.id.clone()),
+    };
+
+    // If field `setting` is Some, then it should be a Setting
+    if extension.setting.is_some() && extension.r#type != ExtensionType::Setting {
+        return Err(err(InvalidPluginJsonErrorKind::FieldsNotAllowed {
+            fields: &["setting"],
+            ty: extension.r#type,
+        }));
+    }
+
+    if extension.r#type == ExtensionType::Setting {
+        let Some(ref setting) = extension.setting else {
+            return Err(err(InvalidPluginJsonErrorKind::FieldRequired {
+                field: "setting",
+                ty: extension.r#type,
+            }));
+        };
+
+        // A system settings panel should be specified for all the supported platforms
+        if let Setting::System(panel) = setting {
+            let supported_platforms = extension
+                .platforms
+                .clone()
+                .or_else(|| main_extension_limited_platforms.cloned())
+                .unwrap_or_else(Platform::all);
+            let mut missing_platforms = supported_platforms
+                .into_iter()
+                .filter(|platform| !panel.supports(*platform))
+                .map(|platform| platform.to_string())
+                .collect::<Vec<String>>();
+            missing_platforms.sort();
+
+            if !missing_platforms.is_empty() {
+                return Err(err(
+                    InvalidPluginJsonErrorKind::SettingPanelMissingForPlatforms {
+                        platforms: missing_platforms,
+                    },
+                ));
+            }
+        }
+    }
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::extension::setting::{CocoSettingsTab, SystemSettingsPanel};
     use crate::extension::{
         CommandAction, ExtensionSettings, Quicklink, QuicklinkLink, QuicklinkLinkComponent,
     };
@@ -347,10 +434,12 @@ mod tests {
             r#type: extension_type,
             action: None,
             quicklink: None,
+            setting: None,
             commands: None,
             scripts: None,
             quicklinks: None,
             views: None,
+            setting_entries: None,
             alias: None,
             hotkey: None,
             enabled: true,
@@ -463,7 +552,13 @@ mod tests {
         expect_error_kind(
             general_check(&extension),
             InvalidPluginJsonErrorKind::FieldsNotAllowed {
-                fields: &["commands", "scripts", "quicklinks", "views"],
+                fields: &[
+                    "commands",
+                    "scripts",
+                    "quicklinks",
+                    "views",
+                    "setting_entries",
+                ],
                 ty: ExtensionType::Command,
             },
         );
@@ -512,19 +607,40 @@ mod tests {
     }
 
     #[test]
-    fn test_non_command_cannot_have_action() {
-        let mut extension = create_basic_extension("test-script", ExtensionType::Script);
+    fn test_script_must_have_action() {
+        let extension = create_basic_extension("test-script", ExtensionType::Script);
+
+        expect_error_kind(
+            general_check(&extension),
+            InvalidPluginJsonErrorKind::FieldRequired {
+                field: "action",
+                ty: ExtensionType::Script,
+            },
+        );
+    }
+
+    #[test]
+    fn test_non_command_or_script_cannot_have_action() {
+        let mut extension = create_basic_extension("test-quicklink", ExtensionType::Quicklink);
         extension.action = Some(create_command_action());
 
         expect_error_kind(
             general_check(&extension),
             InvalidPluginJsonErrorKind::FieldsNotAllowed {
                 fields: &["action"],
-                ty: ExtensionType::Script,
+                ty: ExtensionType::Quicklink,
             },
         );
     }
 
+    #[test]
+    fn test_valid_script_extension() {
+        let mut extension = create_basic_extension("test-script", ExtensionType::Script);
+        extension.action = Some(create_command_action());
+
+        assert!(general_check(&extension).is_ok());
+    }
+
     #[test]
     fn test_quicklink_must_have_quicklink_field() {
         let extension = create_basic_extension("test-quicklink", ExtensionType::Quicklink);
@@ -645,7 +761,13 @@ mod tests {
         expect_error_kind(
             general_check(&extension),
             InvalidPluginJsonErrorKind::FieldsNotAllowedForSubExtension {
-                fields: &["commands", "scripts", "quicklinks", "views"],
+                fields: &[
+                    "commands",
+                    "scripts",
+                    "quicklinks",
+                    "views",
+                    "setting_entries",
+                ],
             },
         );
     }
@@ -733,6 +855,80 @@ mod tests {
         assert!(general_check(&extension).is_ok());
     }
 
+    #[test]
+    fn test_setting_must_have_setting_field() {
+        let extension = create_basic_extension("test-setting", ExtensionType::Setting);
+
+        expect_error_kind(
+            general_check(&extension),
+            InvalidPluginJsonErrorKind::FieldRequired {
+                field: "setting",
+                ty: ExtensionType::Setting,
+            },
+        );
+    }
+
+    #[test]
+    fn test_non_setting_cannot_have_setting_field() {
+        let mut extension = create_basic_extension("test-cmd", ExtensionType::Command);
+        extension.action = Some(create_command_action());
+        extension.setting = Some(Setting::Coco {
+            tab: CocoSettingsTab::General,
+        });
+
+        expect_error_kind(
+            general_check(&extension),
+            InvalidPluginJsonErrorKind::FieldsNotAllowed {
+                fields: &["setting"],
+                ty: ExtensionType::Command,
+            },
+        );
+    }
+
+    #[test]
+    fn test_setting_panel_should_cover_supported_platforms() {
+        let linux_only_panel = Setting::System(SystemSettingsPanel {
+            macos: None,
+            windows: None,
+            gnome: Some("display".into()),
+            kde: None,
+        });
+
+        // Supports all the platforms
+        let mut extension = create_basic_extension("test-setting", ExtensionType::Setting);
+        extension.setting = Some(linux_only_panel.clone());
+        expect_error_kind(
+            general_check(&extension),
+            InvalidPluginJsonErrorKind::SettingPanelMissingForPlatforms {
+                platforms: vec!["macOS".to_string(), "windows".to_string()],
+            },
+        );
+
+        // Only Linux is supported
+        extension.platforms = Some(HashSet::from([Platform::Linux]));
+        assert!(general_check(&extension).is_ok());
+
+        // A sub-extension supports the platforms of its main extension
+        let mut main_extension = create_basic_extension("test-group", ExtensionType::Group);
+        main_extension.platforms = Some(HashSet::from([Platform::Linux]));
+        let mut entry = create_basic_extension("display", ExtensionType::Setting);
+        entry.setting = Some(linux_only_panel);
+        main_extension.setting_entries = Some(vec![entry]);
+        assert!(general_check(&main_extension).is_ok());
+    }
+
+    #[test]
+    fn test_valid_setting_extension() {
+        let mut extension = create_basic_extension("test-group", ExtensionType::Group);
+        let mut entry = create_basic_extension("coco-extensions", ExtensionType::Setting);
+        entry.setting = Some(Setting::Coco {
+            tab: CocoSettingsTab::Extensions,
+        });
+        extension.setting_entries = Some(vec![entry]);
+
+        assert!(general_check(&extension).is_ok());
+    }
+
     #[test]
     fn test_valid_quicklink_extension() {
         let mut extension = create_basic_extension("test-quicklink", ExtensionType::Quicklink);
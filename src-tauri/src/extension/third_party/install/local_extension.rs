@@ -13,6 +13,7 @@ use crate::extension::third_party::install::error::ParseMinimumCocoVersionSnafu;
 use crate::extension::third_party::install::{
     filter_out_incompatible_sub_extensions, is_extension_installed,
 };
+use crate::extension::third_party::script::sync_script_search_sources;
 use crate::extension::third_party::{
     THIRD_PARTY_EXTENSIONS_SEARCH_SOURCE, get_third_party_extension_directory,
 };
@@ -236,6 +237,7 @@ pub(crate) async fn install_local_extension(
     canonicalize_relative_page_path(&dest_dir, &mut extension).context(IoSnafu)?;
 
     // Add extension to the search source
+    sync_script_search_sources(&tauri_app_handle, &extension).await;
     third_party_ext_list_write_lock.push(extension);
 
     Ok(())
@@ -123,6 +123,17 @@ pub(crate) fn filter_out_incompatible_sub_extensions(
             }
         });
     }
+
+    // Filter setting entries
+    if let Some(ref mut setting_entries) = extension.setting_entries {
+        setting_entries.retain(|sub_ext| {
+            if let Some(ref platforms) = sub_ext.platforms {
+                platforms.contains(&current_platform)
+            } else {
+                main_extension_supported_platforms.contains(&current_platform)
+            }
+        });
+    }
 }
 
 #[derive(Debug, Snafu, Serialize)]
@@ -174,10 +185,12 @@ mod tests {
             r#type: extension_type,
             action: None,
             quicklink: None,
+            setting: None,
             commands: None,
             scripts: None,
             quicklinks: None,
             views: None,
+            setting_entries: None,
             alias: None,
             hotkey: None,
             enabled: true,
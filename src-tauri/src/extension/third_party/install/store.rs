@@ -29,6 +29,7 @@ use crate::extension::third_party::install::error::IoSnafu;
 use crate::extension::third_party::install::error::ParseMinimumCocoVersionSnafu;
 use crate::extension::third_party::install::error::ZipArchiveDecodingSnafu;
 use crate::extension::third_party::install::filter_out_incompatible_sub_extensions;
+use crate::extension::third_party::script::sync_script_search_sources;
 use crate::server::http_client::DecodeResponseSnafu;
 use crate::server::http_client::HttpClient;
 use crate::util::platform::Platform;
@@ -57,6 +58,11 @@ impl SearchSource for ExtensionStore {
         }
     }
 
+    /// The extension store itself is the only hit.
+    fn supports_pagination(&self) -> bool {
+        false
+    }
+
     async fn search(
         &self,
         _tauri_app_handle: AppHandle,
@@ -462,6 +468,7 @@ pub(crate) async fn install_extension_from_store(
     canonicalize_relative_icon_path(&extension_directory, &mut extension).context(IoSnafu)?;
     canonicalize_relative_page_path(&extension_directory, &mut extension).context(IoSnafu)?;
 
+    sync_script_search_sources(&tauri_app_handle, &extension).await;
     third_party_ext_list_write_lock.push(extension);
 
     Ok(())
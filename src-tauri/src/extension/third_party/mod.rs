@@ -1,5 +1,6 @@
 pub(crate) mod check;
 pub(crate) mod install;
+pub(crate) mod script;
 
 use super::Extension;
 use super::LOCAL_QUERY_SOURCE_TYPE;
@@ -8,7 +9,11 @@ use super::alter_extension_json_file;
 use super::canonicalize_relative_icon_path;
 use crate::common::document::DataSourceReference;
 use crate::common::document::Document;
+use crate::common::document::ExtensionOnOpened;
+use crate::common::document::ExtensionOnOpenedType;
+use crate::common::document::OnOpened;
 use crate::common::document::open;
+use crate::common::document_action::DocumentAction;
 use crate::common::error::ReportErrorStyle;
 use crate::common::error::SearchError;
 use crate::common::error::report_error;
@@ -21,9 +26,9 @@ use crate::extension::ExtensionBundleId;
 use crate::extension::ExtensionBundleIdBorrowed;
 use crate::extension::ExtensionType;
 use crate::extension::PLUGIN_JSON_FIELD_MINIMUM_COCO_VERSION;
-use crate::extension::calculate_text_similarity;
 use crate::extension::canonicalize_relative_page_path;
 use crate::extension::is_extension_compatible;
+use crate::search::fuzzy::{fuzzy_match, fuzzy_score};
 use crate::util::platform::Platform;
 use crate::util::version::COCO_VERSION;
 use crate::util::version::parse_coco_semver;
@@ -259,10 +264,12 @@ pub(crate) async fn load_third_party_extensions_from_directory(
                     platforms: None,
                     action: None,
                     quicklink: None,
+                    setting: None,
                     commands: None,
                     scripts: None,
                     quicklinks: None,
                     views: None,
+                    setting_entries: None,
                     alias: None,
                     hotkey: None,
                     settings: None,
@@ -418,6 +425,21 @@ impl ThirdPartyExtensionsSearchSource {
         extension.get_sub_extension(sub_extension_id)
     }
 
+    /// Register or remove the search sources of the Script extensions in the
+    /// main extension of `bundle_id`, according to their enabled states.
+    async fn sync_script_search_sources(
+        tauri_app_handle: &AppHandle,
+        extensions: &[Extension],
+        bundle_id: &ExtensionBundleIdBorrowed<'_>,
+    ) {
+        let opt_main_extension = extensions.iter().find(|ext| {
+            ext.id == bundle_id.extension_id && ext.developer.as_deref() == bundle_id.developer
+        });
+        if let Some(main_extension) = opt_main_extension {
+            script::sync_script_search_sources(tauri_app_handle, main_extension).await;
+        }
+    }
+
     /// Difference between this function and `enable_extension()`
     ///
     /// This function does the actual job, i.e., to enable/activate the extension.
@@ -444,7 +466,8 @@ impl ThirdPartyExtensionsSearchSource {
 
                         if event.state() == ShortcutState::Pressed {
                             async_runtime::spawn(async move {
-                                let result = open(app_handle_clone, on_opened_clone, None).await;
+                                let result =
+                                    open(app_handle_clone, on_opened_clone, None, None).await;
                                 if let Err(msg) = result {
                                     log::warn!(
                                         "failed to open extension [{}], error [{}]",
@@ -483,6 +506,11 @@ impl ThirdPartyExtensionsSearchSource {
                     Self::_enable_extension(&tauri_app_handle, view).await?;
                 }
             }
+            if let Some(setting_entries) = &extension.setting_entries {
+                for setting_entry in setting_entries.iter().filter(|ext| ext.enabled) {
+                    Self::_enable_extension(&tauri_app_handle, setting_entry).await?;
+                }
+            }
         }
 
         Ok(())
@@ -530,6 +558,11 @@ impl ThirdPartyExtensionsSearchSource {
                     Self::_disable_extension(tauri_app_handle, view).await?;
                 }
             }
+            if let Some(setting_entries) = &extension.setting_entries {
+                for setting_entry in setting_entries.iter().filter(|ext| ext.enabled) {
+                    Self::_disable_extension(tauri_app_handle, setting_entry).await?;
+                }
+            }
         }
 
         Ok(())
@@ -584,6 +617,7 @@ impl ThirdPartyExtensionsSearchSource {
             update_extension,
         )?;
         Self::_enable_extension(tauri_app_handle, extension).await?;
+        Self::sync_script_search_sources(tauri_app_handle, &extensions_write_lock, bundle_id).await;
 
         Ok(())
     }
@@ -624,6 +658,7 @@ impl ThirdPartyExtensionsSearchSource {
             update_extension,
         )?;
         Self::_disable_extension(tauri_app_handle, extension).await?;
+        Self::sync_script_search_sources(tauri_app_handle, &extensions_write_lock, bundle_id).await;
 
         Ok(())
     }
@@ -660,18 +695,6 @@ impl ThirdPartyExtensionsSearchSource {
         Ok(())
     }
 
-    /// Initialize the third-party extensions, which literally means
-    /// enabling/activating the enabled extensions.
-    pub(crate) async fn init(&self, tauri_app_handle: &AppHandle) -> Result<(), String> {
-        let extensions_read_lock = self.inner.extensions.read().await;
-
-        for extension in extensions_read_lock.iter().filter(|ext| ext.enabled) {
-            Self::_enable_extension(tauri_app_handle, extension).await?;
-        }
-
-        Ok(())
-    }
-
     #[named]
     pub(super) async fn register_extension_hotkey(
         &self,
@@ -720,7 +743,7 @@ impl ThirdPartyExtensionsSearchSource {
 
                 if event.state() == ShortcutState::Pressed {
                     async_runtime::spawn(async move {
-                        let result = open(app_handle_clone, on_opened_clone, None).await;
+                        let result = open(app_handle_clone, on_opened_clone, None, None).await;
                         if let Err(msg) = result {
                             log::warn!(
                                 "failed to open extension [{:?}], error [{}]",
@@ -884,6 +907,7 @@ impl ThirdPartyExtensionsSearchSource {
         // an extension, so we directly use this function here even though "disabling"
         // the extension that one is trying to uninstall does not make too much sense.
         Self::_disable_extension(&tauri_app_handle, &deleted_extension).await?;
+        script::remove_script_search_sources(tauri_app_handle, &deleted_extension).await;
 
         Ok(())
     }
@@ -917,7 +941,7 @@ impl ThirdPartyExtensionsSearchSource {
             });
         };
 
-        crate::common::document::open(tauri_app_handle, on_opened, None)
+        crate::common::document::open(tauri_app_handle, on_opened, None, None)
             .await
             .map_err(|err_msg| OpenThirdPartyExtensionError::OnOpenedOpenError { msg: err_msg })?;
 
@@ -947,6 +971,9 @@ pub(crate) async fn open_third_party_extension(
         .await
 }
 
+/// ID of the query source of third-party extensions.
+pub(crate) const THIRD_PARTY_EXTENSIONS_QUERY_SOURCE_ID: &str = "extensions";
+
 pub(crate) static THIRD_PARTY_EXTENSIONS_SEARCH_SOURCE: OnceLock<ThirdPartyExtensionsSearchSource> =
     OnceLock::new();
 
@@ -964,10 +991,61 @@ impl SearchSource for ThirdPartyExtensionsSearchSource {
                 .unwrap_or("My Computer".into())
                 .to_string_lossy()
                 .into(),
-            id: "extensions".into(),
+            id: THIRD_PARTY_EXTENSIONS_QUERY_SOURCE_ID.into(),
         }
     }
 
+    /// All the matched extensions are returned, there is no pagination.
+    fn supports_pagination(&self) -> bool {
+        false
+    }
+
+    /// Initialize the third-party extensions, which literally means
+    /// enabling/activating the enabled extensions.
+    async fn init(&self, tauri_app_handle: &AppHandle) -> Result<(), SearchError> {
+        let extensions_read_lock = self.inner.extensions.read().await;
+
+        for extension in extensions_read_lock.iter().filter(|ext| ext.enabled) {
+            Self::_enable_extension(tauri_app_handle, extension)
+                .await
+                .map_err(|error| SearchError::InternalError { error })?;
+        }
+        for extension in extensions_read_lock.iter() {
+            script::sync_script_search_sources(tauri_app_handle, extension).await;
+        }
+
+        Ok(())
+    }
+
+    /// Complete `prefix` with the names of the enabled extensions.
+    async fn suggest(
+        &self,
+        _tauri_app_handle: &AppHandle,
+        prefix: &str,
+        size: usize,
+    ) -> Result<Vec<String>, SearchError> {
+        let lowercase_prefix = prefix.to_lowercase();
+        let extensions_read_lock = self.inner.extensions.read().await;
+
+        let suggestions = extensions_read_lock
+            .iter()
+            .filter(|ext| ext.enabled)
+            .flat_map(|ext| {
+                std::iter::once(ext)
+                    .chain(ext.commands.iter().flatten())
+                    .chain(ext.scripts.iter().flatten())
+                    .chain(ext.quicklinks.iter().flatten())
+                    .chain(ext.views.iter().flatten())
+                    .chain(ext.setting_entries.iter().flatten())
+            })
+            .filter(|ext| ext.enabled && ext.name.to_lowercase().starts_with(&lowercase_prefix))
+            .map(|ext| ext.name.clone())
+            .take(size)
+            .collect();
+
+        Ok(suggestions)
+    }
+
     // query main_extension_id querysource
     // main_extension_id querysource
     // query querysource datasource
@@ -1083,6 +1161,19 @@ impl SearchSource for ThirdPartyExtensionsSearchSource {
                             }
                         }
                     }
+
+                    if let Some(ref setting_entries) = extension.setting_entries {
+                        for setting_entry in setting_entries.iter().filter(|entry| entry.enabled) {
+                            if let Some(hit) = extension_to_hit(
+                                setting_entry,
+                                opt_lowercase_query_string.as_deref(),
+                                opt_data_source.as_deref(),
+                                opt_main_extension_lowercase_name.as_deref(),
+                            ) {
+                                hits.push(hit);
+                            }
+                        }
+                    }
                 } else {
                     if let Some(hit) = extension_to_hit(
                         extension,
@@ -1157,20 +1248,19 @@ fn extension_to_hit(
     }
 
     let mut total_score = 0.0;
+    let mut opt_title_match_ranges = None;
     if let Some(query_lower) = opt_lowercase_query_string {
         // Score based on title match
         // Title is considered more important, so it gets a higher weight.
-        if let Some(title_score) =
-            calculate_text_similarity(query_lower, &extension.name.to_lowercase())
-        {
-            total_score += title_score;
+        if let Some(title_match) = fuzzy_match(query_lower, &extension.name) {
+            total_score += title_match.score;
+            opt_title_match_ranges = Some(title_match.ranges);
         }
 
         // Score based on alias match if available
         // Alias is considered less important than title, so it gets a lower weight.
         if let Some(alias) = &extension.alias {
-            if let Some(alias_score) = calculate_text_similarity(query_lower, &alias.to_lowercase())
-            {
+            if let Some(alias_score) = fuzzy_score(query_lower, alias) {
                 total_score += alias_score;
             }
         }
@@ -1181,7 +1271,7 @@ fn extension_to_hit(
         // into account.
         if let Some(main_extension_lowercase_id) = opt_main_extension_lowercase_name {
             if let Some(main_extension_score) =
-                calculate_text_similarity(query_lower, main_extension_lowercase_id)
+                fuzzy_score(query_lower, main_extension_lowercase_id)
             {
                 total_score += main_extension_score;
             }
@@ -1200,12 +1290,21 @@ fn extension_to_hit(
         )
     });
     let url = on_opened.url();
+    let opt_actions = match on_opened {
+        OnOpened::Extension(ExtensionOnOpened {
+            ty: ExtensionOnOpenedType::Quicklink { ref link, .. },
+            ..
+        }) => Some(vec![DocumentAction::copy_quicklink_url(link.clone())]),
+        _ => None,
+    };
 
     let document = Document {
         id: extension.id.clone(),
         title: Some(extension.name.clone()),
+        title_match_ranges: opt_title_match_ranges,
         icon: Some(extension.icon.clone()),
         on_opened: Some(on_opened),
+        actions: opt_actions,
         url: Some(url),
         category: Some(extension_type_string.clone()),
         source: Some(DataSourceReference {
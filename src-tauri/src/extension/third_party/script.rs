@@ -0,0 +1,747 @@
+//! Script extensions, which provide search results dynamically.
+//!
+//! Like Alfred's script filters, Coco runs the executable declared in the
+//! `action` field of a Script extension, with the query string appended to its
+//! arguments, and reads the results from its stdout:
+//!
+//! ```json
+//! {
+//!   "items": [
+//!     {
+//!       "id": "optional, defaults to the index of the item",
+//!       "title": "Coco AI",
+//!       "subtitle": "optional",
+//!       "url": "optional, the URL or path to open",
+//!       "icon": "optional, a path relative to the extension directory or a font code"
+//!     }
+//!   ]
+//! }
+//! ```
+//!
+//! Every enabled Script extension is registered as a search source.
+//!
+//! Permission checks, against the `permission` field of the Script extension:
+//!
+//! * The executable should be in the extension directory, or be granted with
+//!   `read` access in `permission.fs`.  Executables specified by name, e.g.,
+//!   "python3", are looked up in `PATH`, and the found path is checked as
+//!   well, e.g., `/usr/bin` should be granted to run `/usr/bin/python3`.
+//! * An item can only open HTTP(S) URLs whose host is in `permission.http`,
+//!   or files in the extension directory or granted with `read` access in
+//!   `permission.fs`. Other items are dropped.
+
+use super::get_third_party_extension_directory;
+use crate::common::document::DataSourceReference;
+use crate::common::document::Document;
+use crate::common::document::OnOpened;
+use crate::common::document_action::url_actions;
+use crate::common::error::SearchError;
+use crate::common::register::SearchSourceRegistry;
+use crate::common::search::QueryResponse;
+use crate::common::search::QuerySource;
+use crate::common::search::SearchQuery;
+use crate::common::traits::SearchSource;
+use crate::extension::Extension;
+use crate::extension::ExtensionPermission;
+use crate::extension::ExtensionType;
+use crate::extension::FileSystemAccess;
+use crate::extension::LOCAL_QUERY_SOURCE_TYPE;
+use crate::extension::is_extension_compatible;
+use async_trait::async_trait;
+use serde::Deserialize;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::time::Duration;
+use tauri::AppHandle;
+use tauri::Manager;
+use tokio::io::AsyncReadExt;
+use tokio::process::Command;
+
+/// A script gets killed if it does not exit within this duration.
+const SCRIPT_TIMEOUT: Duration = Duration::from_secs(5);
+/// A script gets killed if it writes more than this many bytes to stdout.
+const MAX_SCRIPT_OUTPUT_BYTES: usize = 1024 * 1024;
+/// At most this many bytes of stderr are logged.
+const MAX_SCRIPT_STDERR_BYTES: usize = 4 * 1024;
+
+#[derive(Debug, Deserialize)]
+struct ScriptOutput {
+    items: Vec<ScriptItem>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ScriptItem {
+    id: Option<String>,
+    title: String,
+    subtitle: Option<String>,
+    url: Option<String>,
+    icon: Option<String>,
+}
+
+/// Search source of a Script extension.
+#[derive(Debug)]
+pub(crate) struct ScriptSearchSource {
+    query_source_id: String,
+    name: String,
+    icon: String,
+    extension_dir: PathBuf,
+    /// Canonicalized if it is a path.
+    exec: PathBuf,
+    args: Vec<String>,
+    permission: Option<ExtensionPermission>,
+}
+
+impl ScriptSearchSource {
+    /// Return an error if the executable of `script` does not exist or is not
+    /// permitted.
+    fn new(
+        query_source_id: String,
+        extension_dir: &Path,
+        script: &Extension,
+    ) -> Result<Self, String> {
+        let Some(ref action) = script.action else {
+            return Err(format!(
+                "Script extension [{}]'s [action] field is not set",
+                script.id
+            ));
+        };
+
+        let extension_dir = extension_dir.canonicalize().map_err(|e| {
+            format!(
+                "failed to canonicalize extension directory [{}] due to error [{}]",
+                extension_dir.display(),
+                e
+            )
+        })?;
+        let exec = resolve_exec(&extension_dir, &action.exec)?;
+        if !exec_permitted(&extension_dir, &exec, script.permission.as_ref()) {
+            return Err(format!(
+                "Script extension [{}] has no permission to execute [{}], it should be granted with [read] access in [permission.fs]",
+                script.id,
+                exec.display()
+            ));
+        }
+
+        Ok(Self {
+            query_source_id,
+            name: script.name.clone(),
+            icon: script.icon.clone(),
+            extension_dir,
+            exec,
+            args: action.args.clone().unwrap_or_default(),
+            permission: script.permission.clone(),
+        })
+    }
+
+    fn item_to_hit(&self, index: usize, n_items: usize, item: ScriptItem) -> (Document, f64) {
+        let on_opened = item
+            .url
+            .as_ref()
+            .map(|url| OnOpened::Document { url: url.clone() });
+        let actions = item
+            .url
+            .as_deref()
+            .map(|url| url_actions(Some(&item.title), url));
+        let icon = item
+            .icon
+            .and_then(|icon| resolve_item_icon(&self.extension_dir, &icon))
+            .unwrap_or_else(|| self.icon.clone());
+        let script_type = ExtensionType::Script.to_string();
+
+        let document = Document {
+            id: item.id.unwrap_or_else(|| index.to_string()),
+            title: Some(item.title),
+            summary: item.subtitle,
+            icon: Some(icon),
+            on_opened,
+            actions,
+            url: item.url,
+            category: Some(script_type.clone()),
+            source: Some(DataSourceReference {
+                id: Some(self.query_source_id.clone()),
+                name: Some(self.name.clone()),
+                icon: Some(self.icon.clone()),
+                r#type: Some(script_type),
+            }),
+            ..Default::default()
+        };
+        // The script sorts its items, keep the order.
+        let score = (n_items - index) as f64 / n_items as f64;
+
+        (document, score)
+    }
+}
+
+#[async_trait]
+impl SearchSource for ScriptSearchSource {
+    fn get_type(&self) -> QuerySource {
+        QuerySource {
+            r#type: LOCAL_QUERY_SOURCE_TYPE.into(),
+            name: self.name.clone(),
+            id: self.query_source_id.clone(),
+        }
+    }
+
+    async fn search(
+        &self,
+        _tauri_app_handle: AppHandle,
+        query: SearchQuery,
+    ) -> Result<QueryResponse, SearchError> {
+        let opt_query_string = query
+            .query_strings
+            .get("query")
+            .filter(|query_string| !query_string.is_empty());
+
+        let hits = match opt_query_string {
+            // Scripts are expensive, don't run them without a query
+            None => Vec::new(),
+            Some(query_string) => {
+                let stdout = run_script(
+                    &self.exec,
+                    &self.args,
+                    &self.extension_dir,
+                    query_string,
+                    SCRIPT_TIMEOUT,
+                    MAX_SCRIPT_OUTPUT_BYTES,
+                )
+                .await?;
+                let output: ScriptOutput =
+                    serde_json::from_slice(&stdout).map_err(|e| SearchError::InternalError {
+                        error: format!(
+                            "script [{}] wrote invalid output, error [{}]",
+                            self.query_source_id, e
+                        ),
+                    })?;
+
+                let items: Vec<ScriptItem> = output
+                    .items
+                    .into_iter()
+                    .filter(|item| {
+                        let Some(ref url) = item.url else {
+                            return true;
+                        };
+                        let permitted =
+                            url_permitted(&self.extension_dir, url, self.permission.as_ref());
+                        if !permitted {
+                            log::warn!(
+                                "script [{}] has no permission to open [{}], item dropped",
+                                self.query_source_id,
+                                url
+                            );
+                        }
+                        permitted
+                    })
+                    .collect();
+                let n_items = items.len();
+
+                items
+                    .into_iter()
+                    .enumerate()
+                    .map(|(index, item)| self.item_to_hit(index, n_items, item))
+                    .skip(query.from as usize)
+                    .take(query.size as usize)
+                    .collect()
+            }
+        };
+        let total_hits = hits.len();
+
+        Ok(QueryResponse {
+            source: self.get_type(),
+            hits,
+            total_hits,
+            // Local search source does not support aggregations
+            aggregations: None,
+        })
+    }
+}
+
+/// Resolve `exec`, the executable of a Script extension, to an absolute path.
+///
+/// Paths are relative to `extension_dir` and get canonicalized, names are
+/// looked up in `PATH`.
+fn resolve_exec(extension_dir: &Path, exec: &str) -> Result<PathBuf, String> {
+    let exec_path = Path::new(exec);
+    if exec_path.components().count() == 1 && !exec_path.is_absolute() && !exec.starts_with('.') {
+        return which::which(exec_path).map_err(|e| {
+            format!(
+                "failed to find script executable [{}] in PATH due to error [{}]",
+                exec, e
+            )
+        });
+    }
+
+    extension_dir.join(exec_path).canonicalize().map_err(|e| {
+        format!(
+            "failed to resolve script executable [{}] due to error [{}]",
+            exec, e
+        )
+    })
+}
+
+/// Return true if the extension has `read` access to `path`.
+fn fs_read_permitted(
+    extension_dir: &Path,
+    path: &Path,
+    opt_permission: Option<&ExtensionPermission>,
+) -> bool {
+    if path.starts_with(extension_dir) {
+        return true;
+    }
+
+    opt_permission
+        .and_then(|permission| permission.fs.as_ref())
+        .into_iter()
+        .flatten()
+        .any(|fs_permission| {
+            fs_permission.access.contains(FileSystemAccess::READ)
+                && path.starts_with(&fs_permission.path)
+        })
+}
+
+/// Return true if the extension can execute `exec`, which is resolved by
+/// `resolve_exec()`.  Executables found in `PATH` (e.g., `python3`) need the
+/// permission as well.
+fn exec_permitted(
+    extension_dir: &Path,
+    exec: &Path,
+    opt_permission: Option<&ExtensionPermission>,
+) -> bool {
+    fs_read_permitted(extension_dir, exec, opt_permission)
+}
+
+/// Resolve the `icon` of a script item, a path relative to `extension_dir` or
+/// a font code.
+///
+/// Return `None` if it is a path outside of `extension_dir`, or a path that
+/// does not exist.
+fn resolve_item_icon(extension_dir: &Path, icon: &str) -> Option<String> {
+    let icon_path = Path::new(icon);
+    match extension_dir.join(icon_path).canonicalize() {
+        Ok(path) if path.is_file() => path
+            .starts_with(extension_dir)
+            .then(|| path.to_string_lossy().into_owned()),
+        // A font code
+        _ if icon_path.components().count() == 1 && !icon_path.is_absolute() && icon != ".." => {
+            Some(icon.to_string())
+        }
+        _ => None,
+    }
+}
+
+/// Return true if an item of the script can open `url`.
+fn url_permitted(
+    extension_dir: &Path,
+    url: &str,
+    opt_permission: Option<&ExtensionPermission>,
+) -> bool {
+    let path = match url::Url::parse(url) {
+        Ok(parsed_url) if ["http", "https"].contains(&parsed_url.scheme()) => {
+            let Some(host) = parsed_url.host_str() else {
+                return false;
+            };
+            return opt_permission
+                .and_then(|permission| permission.http.as_ref())
+                .into_iter()
+                .flatten()
+                .any(|http_permission| http_permission.host == host);
+        }
+        Ok(parsed_url) if parsed_url.scheme() == "file" => match parsed_url.to_file_path() {
+            Ok(path) => path,
+            Err(()) => return false,
+        },
+        // A Windows path like "C:\foo" parses as a URL with scheme "c"
+        _ if Path::new(url).is_absolute() => PathBuf::from(url),
+        _ => return false,
+    };
+
+    // Resolve ".." so that it cannot escape
+    let Ok(path) = path.canonicalize() else {
+        return false;
+    };
+    fs_read_permitted(extension_dir, &path, opt_permission)
+}
+
+/// Run `exec` with `args` and `query` in `working_dir`, return its stdout.
+///
+/// The script gets killed if it runs longer than `timeout`, or writes more
+/// than `max_output_bytes` bytes to stdout.
+async fn run_script(
+    exec: &Path,
+    args: &[String],
+    working_dir: &Path,
+    query: &str,
+    timeout: Duration,
+    max_output_bytes: usize,
+) -> Result<Vec<u8>, SearchError> {
+    let internal_error = |error: String| SearchError::InternalError { error };
+
+    let mut child = Command::new(exec)
+        .args(args)
+        .arg(query)
+        .current_dir(working_dir)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| {
+            internal_error(format!(
+                "failed to spawn script [{}] due to error [{}]",
+                exec.display(),
+                e
+            ))
+        })?;
+    let mut stdout = child.stdout.take().expect("stdout is piped");
+    let mut stderr = child.stderr.take().expect("stderr is piped");
+
+    // Read stderr in another task, it won't end until the script exits
+    let stderr_task = tokio::spawn(async move {
+        let mut stderr_bytes = Vec::new();
+        let mut limited_stderr = (&mut stderr).take(MAX_SCRIPT_STDERR_BYTES as u64);
+        let _ = limited_stderr.read_to_end(&mut stderr_bytes).await;
+        // Drain the rest, or the script would block on writing it
+        let _ = tokio::io::copy(&mut stderr, &mut tokio::io::sink()).await;
+
+        stderr_bytes
+    });
+
+    let run = async move {
+        // Read one more byte to tell if the output exceeds the limit
+        let mut stdout_bytes = Vec::new();
+        let mut limited_stdout = (&mut stdout).take(max_output_bytes as u64 + 1);
+        limited_stdout
+            .read_to_end(&mut stdout_bytes)
+            .await
+            .map_err(|e| internal_error(e.to_string()))?;
+        if stdout_bytes.len() > max_output_bytes {
+            // `child` gets killed when dropped
+            return Err(internal_error(format!(
+                "script [{}] wrote more than [{}] bytes to stdout",
+                exec.display(),
+                max_output_bytes
+            )));
+        }
+
+        let status = child
+            .wait()
+            .await
+            .map_err(|e| internal_error(e.to_string()))?;
+        if !status.success() {
+            let stderr_bytes = stderr_task.await.unwrap_or_default();
+            return Err(internal_error(format!(
+                "script [{}] failed, exit code: [{}], stderr: [{}]",
+                exec.display(),
+                status,
+                String::from_utf8_lossy(&stderr_bytes)
+            )));
+        }
+
+        Ok(stdout_bytes)
+    };
+
+    tokio::time::timeout(timeout, run)
+        .await
+        .map_err(|_elapsed| SearchError::SearchTimeout)?
+}
+
+/// Query source ID of a Script extension.
+fn script_query_source_id(main_extension: &Extension, script: &Extension) -> String {
+    let developer = main_extension.developer.as_deref().unwrap_or_default();
+    if main_extension.id == script.id {
+        format!("script:{}/{}", developer, main_extension.id)
+    } else {
+        format!("script:{}/{}/{}", developer, main_extension.id, script.id)
+    }
+}
+
+/// Return the Script extensions in `main_extension`, it could be one itself.
+fn scripts_of(main_extension: &Extension) -> Vec<&Extension> {
+    if main_extension.r#type == ExtensionType::Script {
+        vec![main_extension]
+    } else {
+        main_extension.scripts.iter().flatten().collect()
+    }
+}
+
+/// Register the enabled Script extensions in `main_extension` as search
+/// sources, and remove the disabled ones.
+pub(crate) async fn sync_script_search_sources(
+    tauri_app_handle: &AppHandle,
+    main_extension: &Extension,
+) {
+    let registry = tauri_app_handle.state::<SearchSourceRegistry>();
+    let extension_dir = get_third_party_extension_directory(tauri_app_handle)
+        .join(main_extension.developer.as_deref().unwrap_or_default())
+        .join(&main_extension.id);
+    let main_extension_searchable =
+        main_extension.enabled && is_extension_compatible(main_extension.clone());
+
+    for script in scripts_of(main_extension) {
+        let query_source_id = script_query_source_id(main_extension, script);
+        if !(main_extension_searchable && script.enabled) {
+            registry
+                .remove_source(tauri_app_handle, &query_source_id)
+                .await;
+            continue;
+        }
+
+        match ScriptSearchSource::new(query_source_id.clone(), &extension_dir, script) {
+            Ok(search_source) => {
+                if let Err(e) = registry
+                    .register_source(tauri_app_handle, search_source)
+                    .await
+                {
+                    log::warn!(
+                        "Script extension [{}] won't be searched, error [{}]",
+                        query_source_id,
+                        e
+                    );
+                }
+            }
+            Err(e) => {
+                log::warn!(
+                    "Script extension [{}] won't be searched, error [{}]",
+                    query_source_id,
+                    e
+                );
+                registry
+                    .remove_source(tauri_app_handle, &query_source_id)
+                    .await;
+            }
+        }
+    }
+}
+
+/// Remove the search sources of the Script extensions in `main_extension`.
+pub(crate) async fn remove_script_search_sources(
+    tauri_app_handle: &AppHandle,
+    main_extension: &Extension,
+) {
+    let registry = tauri_app_handle.state::<SearchSourceRegistry>();
+    for script in scripts_of(main_extension) {
+        registry
+            .remove_source(
+                tauri_app_handle,
+                &script_query_source_id(main_extension, script),
+            )
+            .await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extension::ExtensionFileSystemPermission;
+    use crate::extension::ExtensionHttpPermission;
+
+    fn permission(fs_paths: &[&Path], http_hosts: &[&str]) -> ExtensionPermission {
+        ExtensionPermission {
+            fs: Some(
+                fs_paths
+                    .iter()
+                    .map(|path| ExtensionFileSystemPermission {
+                        path: path.to_string_lossy().into_owned(),
+                        access: FileSystemAccess::READ,
+                    })
+                    .collect(),
+            ),
+            http: Some(
+                http_hosts
+                    .iter()
+                    .map(|host| ExtensionHttpPermission {
+                        host: host.to_string(),
+                    })
+                    .collect(),
+            ),
+            api: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_script_output() {
+        let output: ScriptOutput = serde_json::from_str(
+            r#"{"items": [{"title": "a", "url": "https://coco.rs"}, {"id": "b", "title": "b", "subtitle": "sub"}]}"#,
+        )
+        .unwrap();
+        assert_eq!(output.items.len(), 2);
+        assert_eq!(output.items[0].url.as_deref(), Some("https://coco.rs"));
+        assert_eq!(output.items[1].id.as_deref(), Some("b"));
+
+        // `title` is required
+        assert!(serde_json::from_str::<ScriptOutput>(r#"{"items": [{"id": "a"}]}"#).is_err());
+    }
+
+    #[test]
+    fn test_exec_permission() {
+        let extension_dir = tempfile::tempdir().unwrap();
+        let granted_dir = tempfile::tempdir().unwrap();
+        let extension_dir = extension_dir.path().canonicalize().unwrap();
+        let granted_dir = granted_dir.path().canonicalize().unwrap();
+        std::fs::write(extension_dir.join("filter.sh"), "").unwrap();
+        std::fs::write(granted_dir.join("filter.sh"), "").unwrap();
+
+        let exec = resolve_exec(&extension_dir, "./filter.sh").unwrap();
+        assert!(exec_permitted(&extension_dir, &exec, None));
+        // Looked up in `PATH`, which needs the permission as well
+        #[cfg(unix)]
+        {
+            let exec = resolve_exec(&extension_dir, "sh").unwrap();
+            assert!(exec.is_absolute());
+            assert!(!exec_permitted(&extension_dir, &exec, None));
+            assert!(exec_permitted(
+                &extension_dir,
+                &exec,
+                Some(&permission(&[exec.parent().unwrap()], &[]))
+            ));
+        }
+        assert!(resolve_exec(&extension_dir, "coco-missing-executable").is_err());
+
+        let exec = resolve_exec(
+            &extension_dir,
+            granted_dir.join("filter.sh").to_str().unwrap(),
+        )
+        .unwrap();
+        assert!(!exec_permitted(&extension_dir, &exec, None));
+        assert!(exec_permitted(
+            &extension_dir,
+            &exec,
+            Some(&permission(&[granted_dir.as_path()], &[]))
+        ));
+
+        // Escaping the extension directory
+        let escaping_exec = format!(
+            "../{}/filter.sh",
+            granted_dir.file_name().unwrap().to_str().unwrap()
+        );
+        if let Ok(exec) = resolve_exec(&extension_dir, &escaping_exec) {
+            assert!(!exec_permitted(&extension_dir, &exec, None));
+        }
+        assert!(resolve_exec(&extension_dir, "./missing.sh").is_err());
+    }
+
+    #[test]
+    fn test_url_permission() {
+        let extension_dir = tempfile::tempdir().unwrap();
+        let extension_dir = extension_dir.path().canonicalize().unwrap();
+        std::fs::write(extension_dir.join("result.txt"), "").unwrap();
+        let permission = permission(&[], &["coco.rs"]);
+
+        assert!(url_permitted(
+            &extension_dir,
+            "https://coco.rs/docs",
+            Some(&permission)
+        ));
+        assert!(!url_permitted(
+            &extension_dir,
+            "https://example.com",
+            Some(&permission)
+        ));
+        assert!(!url_permitted(&extension_dir, "https://coco.rs", None));
+
+        let file = extension_dir.join("result.txt");
+        assert!(url_permitted(&extension_dir, file.to_str().unwrap(), None));
+        assert!(!url_permitted(&extension_dir, "mailto:a@coco.rs", None));
+    }
+
+    #[test]
+    fn test_resolve_item_icon() {
+        let extension_dir = tempfile::tempdir().unwrap();
+        let outside_dir = tempfile::tempdir().unwrap();
+        let extension_dir = extension_dir.path().canonicalize().unwrap();
+        let outside_dir = outside_dir.path().canonicalize().unwrap();
+        std::fs::create_dir(extension_dir.join("assets")).unwrap();
+        std::fs::write(extension_dir.join("assets").join("icon.png"), "").unwrap();
+        std::fs::write(outside_dir.join("icon.png"), "").unwrap();
+
+        assert_eq!(
+            resolve_item_icon(&extension_dir, "assets/icon.png"),
+            Some(
+                extension_dir
+                    .join("assets")
+                    .join("icon.png")
+                    .to_string_lossy()
+                    .into_owned()
+            )
+        );
+        assert_eq!(
+            resolve_item_icon(&extension_dir, "font_coco"),
+            Some("font_coco".to_string())
+        );
+
+        // Escaping the extension directory
+        let escaping_icon = format!(
+            "../{}/icon.png",
+            outside_dir.file_name().unwrap().to_str().unwrap()
+        );
+        assert_eq!(resolve_item_icon(&extension_dir, &escaping_icon), None);
+        assert_eq!(
+            resolve_item_icon(
+                &extension_dir,
+                outside_dir.join("icon.png").to_str().unwrap()
+            ),
+            None
+        );
+        assert_eq!(
+            resolve_item_icon(&extension_dir, "assets/missing.png"),
+            None
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_run_script() {
+        let working_dir = std::env::temp_dir();
+        let sh = Path::new("/bin/sh");
+        let args = |script: &str| vec!["-c".to_string(), script.to_string(), "sh".to_string()];
+
+        // The query is the last argument
+        let stdout = run_script(
+            sh,
+            &args(r#"printf '{"items": [{"title": "%s"}]}' "$1""#),
+            &working_dir,
+            "coco",
+            Duration::from_secs(5),
+            1024,
+        )
+        .await
+        .unwrap();
+        let output: ScriptOutput = serde_json::from_slice(&stdout).unwrap();
+        assert_eq!(output.items[0].title, "coco");
+
+        let result = run_script(
+            sh,
+            &args("sleep 10"),
+            &working_dir,
+            "coco",
+            Duration::from_millis(100),
+            1024,
+        )
+        .await;
+        assert!(matches!(result, Err(SearchError::SearchTimeout)));
+
+        let result = run_script(
+            sh,
+            &args("yes"),
+            &working_dir,
+            "coco",
+            Duration::from_secs(5),
+            1024,
+        )
+        .await;
+        assert!(matches!(result, Err(SearchError::InternalError { .. })));
+
+        let result = run_script(
+            sh,
+            &args("echo oops >&2; exit 1"),
+            &working_dir,
+            "coco",
+            Duration::from_secs(5),
+            1024,
+        )
+        .await;
+        assert!(
+            matches!(result, Err(SearchError::InternalError { error }) if error.contains("oops"))
+        );
+    }
+}
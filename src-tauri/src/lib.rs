@@ -189,6 +189,16 @@ pub fn run() {
             server::datasource::mcp_server_search,
             server::connector::get_connectors_by_server,
             search::query_coco_fusion,
+            search::query_coco_fusion_stream,
+            search::session::cancel_query_session,
+            search::prefix::get_query_prefixes,
+            search::diagnostics::query_coco_fusion_debug,
+            search::health::get_search_source_health,
+            search::preview::preview_document,
+            search::elasticsearch::get_elasticsearch_sources,
+            search::elasticsearch::save_elasticsearch_source,
+            search::elasticsearch::remove_elasticsearch_source,
+            search::suggest::query_suggestions,
             assistant::chat_history,
             assistant::chat_create,
             assistant::chat_chat,
@@ -241,10 +251,33 @@ pub fn run() {
             settings::get_allow_self_signature,
             settings::set_local_query_source_weight,
             settings::get_local_query_source_weight,
+            settings::set_fusion_strategy,
+            settings::get_fusion_strategy,
+            settings::set_frecency_enabled,
+            settings::get_frecency_enabled,
+            search::frecency::get_usage_history,
+            search::frecency::clear_usage_history,
             assistant::ask_ai,
             crate::common::document::open,
+            crate::common::document_action::execute_document_action,
             extension::built_in::file_search::config::get_file_system_config,
             extension::built_in::file_search::config::set_file_system_config,
+            extension::built_in::file_search::config::validate_file_search_patterns,
+            extension::built_in::clipboard_history::get_clipboard_history_config,
+            extension::built_in::clipboard_history::set_clipboard_history_config,
+            extension::built_in::clipboard_history::list_clipboard_history,
+            extension::built_in::clipboard_history::pin_clipboard_entry,
+            extension::built_in::clipboard_history::delete_clipboard_entry,
+            extension::built_in::clipboard_history::clear_clipboard_history,
+            extension::built_in::clipboard_history::paste_clipboard_entry,
+            extension::built_in::snippets::list_snippets,
+            extension::built_in::snippets::save_snippet,
+            extension::built_in::snippets::remove_snippet,
+            extension::built_in::snippets::snippet_arguments,
+            extension::built_in::snippets::import_snippets,
+            extension::built_in::snippets::export_snippets,
+            extension::built_in::calculator::currency::get_currency_rates,
+            extension::built_in::calculator::currency::refresh_currency_rates,
             server::synthesize::synthesize,
             util::file::get_file_icon,
             setup::backend_setup,
@@ -330,6 +363,8 @@ pub async fn init(app_handle: &AppHandle) {
             .await;
     }
 
+    search::elasticsearch::init(app_handle).await;
+
     /*
      * Start the background heartbeat worker here after setting up Coco server
      * storage and SearchSourceRegistry.
@@ -0,0 +1,393 @@
+//! Cursor-based pagination of `query_coco_fusion()`.
+//!
+//! Sending the same `from`/`size` to every query source does not work for a
+//! fused result: a page contains a different number of hits from every query
+//! source, and the hits dropped by the fusion strategy would never show up on
+//! the later pages.  Instead, the response carries a cursor that records how
+//! many hits of every query source have been returned so far, and the next
+//! page continues every query source from there.
+//!
+//! The cursor is opaque to the frontend. It is the JSON of `PaginationCursor`,
+//! encoded in URL-safe base64.
+
+use crate::common::error::SearchError;
+use crate::common::search::{QueryHits, QuerySource};
+use crate::search::dedup::DedupedHits;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub(crate) struct PaginationCursor {
+    /// Query source ID => offset of its next hit to return.
+    offsets: BTreeMap<String, u64>,
+    /// IDs of the query sources that have no more hits.
+    exhausted: BTreeSet<String>,
+}
+
+impl PaginationCursor {
+    pub(crate) fn decode(cursor: &str) -> Result<Self, SearchError> {
+        let invalid_cursor = || SearchError::InvalidQuery {
+            reason: format!("invalid pagination cursor [{}]", cursor),
+        };
+
+        let json =
+            base64::decode_config(cursor, base64::URL_SAFE_NO_PAD).map_err(|_| invalid_cursor())?;
+        serde_json::from_slice(&json).map_err(|_| invalid_cursor())
+    }
+
+    pub(crate) fn encode(&self) -> String {
+        let json = serde_json::to_vec(self).expect("PaginationCursor should be serializable");
+        base64::encode_config(json, base64::URL_SAFE_NO_PAD)
+    }
+
+    /// Return the offset to search query source `source_id` from, or `None`
+    /// if it has no more hits.
+    ///
+    /// `default_offset` is used if this query source is not recorded in the
+    /// cursor, i.e., this is the first page, or the query source was added
+    /// after the first page.
+    pub(crate) fn offset(&self, source_id: &str, default_offset: u64) -> Option<u64> {
+        if self.exhausted.contains(source_id) {
+            return None;
+        }
+
+        Some(
+            self.offsets
+                .get(source_id)
+                .copied()
+                .unwrap_or(default_offset),
+        )
+    }
+
+    /// Record that query source `source_id`, searched from `offset` with
+    /// `size`, returned `n_returned` hits, of which the first `n_consumed`
+    /// are on the current page.
+    pub(crate) fn advance(
+        &mut self,
+        source_id: &str,
+        offset: u64,
+        size: u64,
+        n_returned: usize,
+        n_consumed: usize,
+    ) {
+        self.offsets
+            .insert(source_id.to_string(), offset + n_consumed as u64);
+        if (n_returned as u64) < size && n_consumed == n_returned {
+            self.exhausted.insert(source_id.to_string());
+        }
+    }
+
+    /// Stop searching query source `source_id` on the later pages, e.g., it
+    /// failed or timed out.
+    pub(crate) fn exhaust(&mut self, source_id: &str) {
+        self.exhausted.insert(source_id.to_string());
+    }
+
+    /// Return the encoded cursor of the next page, or `None` if all the query
+    /// sources in `source_ids` have no more hits.
+    pub(crate) fn next_page<'id>(
+        &self,
+        source_ids: impl IntoIterator<Item = &'id str>,
+    ) -> Option<String> {
+        let mut source_ids = source_ids.into_iter();
+        if source_ids.all(|source_id| self.exhausted.contains(source_id)) {
+            None
+        } else {
+            Some(self.encode())
+        }
+    }
+}
+
+/// Return the `from` and `size` to search a query source with, to get `size`
+/// hits starting at `offset`.
+///
+/// A query source that does not support pagination ignores `from`, so it is
+/// searched for all the hits up to this page, and `skip_previous_pages()`
+/// drops the ones before `offset`.
+pub(crate) fn search_range(offset: u64, size: u64, supports_pagination: bool) -> (u64, u64) {
+    if supports_pagination {
+        (offset, size)
+    } else {
+        (0, offset + size)
+    }
+}
+
+/// Drop the `hits` of a query source that does not support pagination that
+/// are before `offset`, and keep at most `size` of the rest.  See
+/// `search_range()`.
+pub(crate) fn skip_previous_pages<T>(
+    hits: &mut Vec<T>,
+    offset: u64,
+    size: u64,
+    supports_pagination: bool,
+) {
+    if supports_pagination {
+        return;
+    }
+
+    let offset = usize::try_from(offset)
+        .unwrap_or(usize::MAX)
+        .min(hits.len());
+    hits.drain(..offset);
+    hits.truncate(usize::try_from(size).unwrap_or(usize::MAX));
+}
+
+/// Take a page of at most `size` hits from `fused_hits`, in which the hits of
+/// every query source are a prefix of the hits it returned, `hits_by_source`.
+///
+/// This is required by pagination, as the next page continues every query
+/// source right after its last hit on this page.  But a fusion strategy can
+/// drop a hit and keep a later one from the same query source, so a hit pulls
+/// the dropped hits before it onto the page, placed right before it with the
+/// same score.  A hit that does not fit, together with the hits it pulls, is
+/// left to the next page.
+///
+/// Duplicates are merged, see `crate::search::dedup`, a hit merged into
+/// another one still counts as a hit of its query source on this page.
+///
+/// Return the page and the number of hits of every query source on it.
+pub(crate) fn take_page(
+    fused_hits: Vec<QueryHits>,
+    hits_by_source: &HashMap<QuerySource, Vec<QueryHits>>,
+    size: usize,
+) -> (Vec<QueryHits>, HashMap<String, usize>) {
+    let mut page = DedupedHits::default();
+    let mut consumed: HashMap<String, usize> = HashMap::new();
+
+    for hit in fused_hits {
+        if page.len() >= size {
+            break;
+        }
+
+        let opt_source_hits = hit
+            .source
+            .as_ref()
+            .and_then(|source| hits_by_source.get(source));
+        let opt_position = opt_source_hits.and_then(|source_hits| {
+            source_hits
+                .iter()
+                .position(|source_hit| source_hit.document.id == hit.document.id)
+        });
+        let (Some(source_hits), Some(position)) = (opt_source_hits, opt_position) else {
+            // Not from any query source, nothing to track.
+            page.push(hit);
+            continue;
+        };
+
+        let source_id = &hit.source.as_ref().expect("checked above").id;
+        let n_consumed = consumed.get(source_id).copied().unwrap_or(0);
+        if position < n_consumed {
+            // Already pulled onto the page by a later hit of this query source
+            continue;
+        }
+
+        let n_needed = position + 1 - n_consumed;
+        if !page.is_empty() && page.len() + n_needed > size {
+            continue;
+        }
+
+        for dropped_hit in &source_hits[n_consumed..position] {
+            let mut dropped_hit = dropped_hit.clone();
+            dropped_hit.score = hit.score;
+            page.push(dropped_hit);
+        }
+        consumed.insert(source_id.clone(), position + 1);
+        page.push(hit);
+    }
+
+    (page.into_hits(), consumed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_fixtures::{hit, source};
+    use super::*;
+
+    fn ids(hits: &[QueryHits]) -> Vec<&str> {
+        hits.iter().map(|hit| hit.document.id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_encode_decode() {
+        let mut cursor = PaginationCursor::default();
+        cursor.advance("apps", 0, 10, 10, 4);
+        cursor.advance("files", 5, 10, 3, 3);
+
+        let decoded = PaginationCursor::decode(&cursor.encode()).unwrap();
+        assert_eq!(decoded, cursor);
+        assert_eq!(decoded.offset("apps", 0), Some(4));
+        // Exhausted
+        assert_eq!(decoded.offset("files", 0), None);
+        // Not recorded
+        assert_eq!(decoded.offset("server", 7), Some(7));
+    }
+
+    #[test]
+    fn test_decode_invalid_cursor() {
+        assert!(matches!(
+            PaginationCursor::decode("not a cursor"),
+            Err(SearchError::InvalidQuery { .. })
+        ));
+        // Valid base64, invalid JSON
+        let cursor = base64::encode_config("[]", base64::URL_SAFE_NO_PAD);
+        assert!(PaginationCursor::decode(&cursor).is_err());
+    }
+
+    #[test]
+    fn test_next_page() {
+        let mut cursor = PaginationCursor::default();
+        cursor.advance("apps", 0, 10, 10, 10);
+        cursor.advance("files", 0, 10, 2, 2);
+        assert!(cursor.next_page(["apps", "files"]).is_some());
+
+        // Returned fewer than `size`, but not all of them are consumed
+        cursor.advance("apps", 10, 10, 5, 3);
+        assert!(cursor.next_page(["apps", "files"]).is_some());
+
+        cursor.advance("apps", 13, 10, 2, 2);
+        assert_eq!(cursor.next_page(["apps", "files"]), None);
+    }
+
+    #[test]
+    fn test_exhaust() {
+        let mut cursor = PaginationCursor::default();
+        cursor.advance("apps", 0, 10, 10, 10);
+        // Timed out
+        cursor.exhaust("server");
+
+        assert_eq!(cursor.offset("server", 0), None);
+        assert!(cursor.next_page(["apps", "server"]).is_some());
+        cursor.exhaust("apps");
+        assert_eq!(cursor.next_page(["apps", "server"]), None);
+    }
+
+    #[test]
+    fn test_page_through_source_without_pagination() {
+        // A query source that ignores `from`, it always returns its hits from
+        // the first one.
+        let all_hits = ["a0", "a1", "a2", "a3", "a4"];
+        let search = |_from: u64, size: u64| -> Vec<&str> {
+            all_hits.iter().copied().take(size as usize).collect()
+        };
+        let size = 2;
+
+        let mut cursor = PaginationCursor::default();
+        let mut pages = Vec::new();
+        loop {
+            let offset = cursor.offset("apps", 0).unwrap();
+            let (from, search_size) = search_range(offset, size, false);
+            let mut hits = search(from, search_size);
+            skip_previous_pages(&mut hits, offset, size, false);
+            cursor.advance("apps", offset, size, hits.len(), hits.len());
+            pages.push(hits);
+
+            let Some(next_cursor) = cursor.next_page(["apps"]) else {
+                break;
+            };
+            cursor = PaginationCursor::decode(&next_cursor).unwrap();
+        }
+
+        assert_eq!(pages, vec![vec!["a0", "a1"], vec!["a2", "a3"], vec!["a4"]]);
+    }
+
+    #[test]
+    fn test_search_range() {
+        assert_eq!(search_range(20, 10, true), (20, 10));
+        assert_eq!(search_range(20, 10, false), (0, 30));
+
+        // Hits of a query source that supports pagination are kept as is
+        let mut hits = vec![1, 2, 3];
+        skip_previous_pages(&mut hits, 20, 2, true);
+        assert_eq!(hits, vec![1, 2, 3]);
+
+        let mut hits = vec![1, 2, 3];
+        skip_previous_pages(&mut hits, 5, 2, false);
+        assert!(hits.is_empty());
+    }
+
+    #[test]
+    fn test_take_page_pulls_dropped_hits() {
+        let mut hits_by_source = HashMap::new();
+        hits_by_source.insert(
+            source("a"),
+            vec![
+                hit("a", "a0", 9.0),
+                hit("a", "a1", 8.0),
+                hit("a", "a2", 7.0),
+            ],
+        );
+        hits_by_source.insert(source("b"), vec![hit("b", "b0", 1.0), hit("b", "b1", 0.5)]);
+
+        // The fusion strategy dropped "a0"
+        let fused_hits = vec![
+            hit("b", "b0", 0.9),
+            hit("a", "a1", 0.8),
+            hit("b", "b1", 0.7),
+        ];
+        let (page, consumed) = take_page(fused_hits, &hits_by_source, 4);
+
+        assert_eq!(ids(&page), vec!["b0", "a0", "a1", "b1"]);
+        assert_eq!(page[1].score, 0.8);
+        assert_eq!(consumed["a"], 2);
+        assert_eq!(consumed["b"], 2);
+    }
+
+    #[test]
+    fn test_take_page_merges_duplicates() {
+        let with_url = |mut hit: QueryHits| {
+            hit.document.url = Some("https://example.com".into());
+            hit
+        };
+        let mut hits_by_source = HashMap::new();
+        hits_by_source.insert(
+            source("a"),
+            vec![with_url(hit("a", "a0", 9.0)), hit("a", "a1", 8.0)],
+        );
+        hits_by_source.insert(
+            source("b"),
+            vec![with_url(hit("b", "b0", 1.0)), hit("b", "b1", 0.5)],
+        );
+
+        let fused_hits = vec![
+            with_url(hit("a", "a0", 0.9)),
+            with_url(hit("b", "b0", 0.8)),
+            hit("a", "a1", 0.7),
+            hit("b", "b1", 0.6),
+        ];
+        let (page, consumed) = take_page(fused_hits, &hits_by_source, 2);
+
+        // The page is still full
+        assert_eq!(ids(&page), vec!["a0", "a1"]);
+        assert_eq!(page[0].also_found_in, vec![source("b")]);
+        assert_eq!(consumed["a"], 2);
+        // "b0" won't be returned on the next page
+        assert_eq!(consumed["b"], 1);
+    }
+
+    #[test]
+    fn test_take_page_defers_hits_that_do_not_fit() {
+        let mut hits_by_source = HashMap::new();
+        hits_by_source.insert(
+            source("a"),
+            vec![
+                hit("a", "a0", 9.0),
+                hit("a", "a1", 8.0),
+                hit("a", "a2", 7.0),
+            ],
+        );
+        hits_by_source.insert(source("b"), vec![hit("b", "b0", 1.0)]);
+
+        // Taking "a2" also takes the skipped "a1", so it needs 2 slots, only 1
+        // is left, so it goes to the next page
+        let fused_hits = vec![
+            hit("a", "a0", 0.9),
+            hit("a", "a2", 0.8),
+            hit("b", "b0", 0.7),
+        ];
+        let (page, consumed) = take_page(fused_hits, &hits_by_source, 2);
+
+        assert_eq!(ids(&page), vec!["a0", "b0"]);
+        assert_eq!(consumed["a"], 1);
+        assert_eq!(consumed["b"], 1);
+    }
+}
@@ -0,0 +1,291 @@
+//! Cross-source deduplication of search hits.
+//!
+//! The same web page or file can be returned by multiple query sources, e.g.,
+//! several Coco servers indexing the same site, or a file found by both a Coco
+//! server connector and the local file search.  Such hits are merged into one,
+//! which keeps the best score and records the other query sources in
+//! `QueryHits.also_found_in`.
+//!
+//! Duplicates are merged while taking a page from the fused hits, see
+//! `crate::search::cursor::take_page()`, so that a page is not left short.
+
+use crate::common::search::QueryHits;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::collections::hash_map::Entry;
+use url::Url;
+
+/// Return the key used to detect duplicate documents, documents with the same
+/// key are considered the same.
+///
+/// In order of preference, the key is derived from:
+///
+/// 1. `Document.url`
+/// 2. `OnOpened::url()`
+/// 3. The query source ID and `Document.id`, as IDs are only unique within a
+///    query source
+fn dedup_key(hit: &QueryHits) -> String {
+    let document = &hit.document;
+    if let Some(url) = document.url.as_deref()
+        && !url.trim().is_empty()
+    {
+        return format!("url:{}", normalize_url(url));
+    }
+
+    if let Some(on_opened) = document.on_opened.as_ref() {
+        let url = on_opened.url();
+        // "N/A" means this `OnOpened` has no URL
+        if url != "N/A" && !url.trim().is_empty() {
+            return format!("url:{}", normalize_url(&url));
+        }
+    }
+
+    let source_id = hit
+        .source
+        .as_ref()
+        .map(|source| source.id.as_str())
+        .unwrap_or_default();
+    format!("id:{}:{}", source_id, document.id)
+}
+
+/// Normalize `url` so that trivially different URLs pointing to the same
+/// resource are equal:
+///
+/// * Local paths and `file://` URLs become plain paths
+/// * Scheme (http/https), "www." prefix, default ports and fragments are
+///   removed from web URLs
+/// * Trailing slashes are removed
+fn normalize_url(url: &str) -> String {
+    let url = url.trim();
+
+    match Url::parse(url) {
+        // A single-letter "scheme" is a Windows drive letter, e.g., "C:\foo"
+        Ok(parsed) if parsed.scheme().len() > 1 => match parsed.scheme() {
+            "file" => match parsed.to_file_path() {
+                Ok(path) => normalize_path(&path.to_string_lossy()),
+                Err(()) => parsed.to_string(),
+            },
+            "http" | "https" => {
+                let host = parsed.host_str().unwrap_or_default();
+                let host = host.strip_prefix("www.").unwrap_or(host);
+                // `Url` has removed the default port for us
+                let port = parsed
+                    .port()
+                    .map(|port| format!(":{}", port))
+                    .unwrap_or_default();
+                let path = parsed.path().trim_end_matches('/');
+                let query = parsed
+                    .query()
+                    .map(|query| format!("?{}", query))
+                    .unwrap_or_default();
+
+                format!("{}{}{}{}", host, port, path, query)
+            }
+            _ => parsed.to_string(),
+        },
+        _ => normalize_path(url),
+    }
+}
+
+fn normalize_path(path: &str) -> String {
+    let trimmed = path.trim_end_matches(['/', '\\']);
+    if trimmed.is_empty() {
+        // The root directory
+        path.to_string()
+    } else {
+        trimmed.to_string()
+    }
+}
+
+/// Hits without duplicates, a hit that duplicates a hit pushed before is
+/// merged into it.
+///
+/// For each group of duplicates, the hit with the highest score is kept, and
+/// the query sources of the others are recorded in its `also_found_in`.  The
+/// relative order of the kept hits is retained, so if the hits are pushed in
+/// descending order of score, they remain sorted.
+#[derive(Default)]
+pub(crate) struct DedupedHits {
+    hits: Vec<QueryHits>,
+    key_to_index: HashMap<String, usize>,
+}
+
+impl DedupedHits {
+    /// Push `hit`, return false if it is merged into a duplicate.
+    pub(crate) fn push(&mut self, hit: QueryHits) -> bool {
+        match self.key_to_index.entry(dedup_key(&hit)) {
+            Entry::Vacant(vacant) => {
+                vacant.insert(self.hits.len());
+                self.hits.push(hit);
+                true
+            }
+            Entry::Occupied(occupied) => {
+                let kept = &mut self.hits[*occupied.get()];
+                log::debug!(
+                    "merging duplicate document [{}] from query source [{:?}] into [{}] from [{:?}]",
+                    hit.document.id,
+                    hit.source.as_ref().map(|s| &s.id),
+                    kept.document.id,
+                    kept.source.as_ref().map(|s| &s.id),
+                );
+                merge_hit(kept, hit);
+                false
+            }
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.hits.len()
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.hits.is_empty()
+    }
+
+    pub(crate) fn into_hits(self) -> Vec<QueryHits> {
+        self.hits
+    }
+}
+
+/// Return the number of hits that duplicate the hits before them.
+pub(crate) fn count_duplicates<'hits>(hits: impl IntoIterator<Item = &'hits QueryHits>) -> usize {
+    let mut keys = HashSet::new();
+    hits.into_iter()
+        .filter(|hit| !keys.insert(dedup_key(hit)))
+        .count()
+}
+
+/// Merge `other` into `kept`.
+fn merge_hit(kept: &mut QueryHits, mut other: QueryHits) {
+    if other.score > kept.score {
+        std::mem::swap(kept, &mut other);
+    }
+
+    let mut sources = std::mem::take(&mut kept.also_found_in);
+    sources.extend(other.source);
+    sources.extend(other.also_found_in);
+
+    for source in sources {
+        let is_kept_source = kept.source.as_ref() == Some(&source);
+        if !is_kept_source && !kept.also_found_in.contains(&source) {
+            kept.also_found_in.push(source);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_fixtures::source;
+    use super::*;
+    use crate::common::document::OnOpened;
+
+    fn hit(source_id: &str, doc_id: &str, url: Option<&str>, score: f64) -> QueryHits {
+        let mut hit = super::super::test_fixtures::hit(source_id, doc_id, score);
+        hit.document.url = url.map(str::to_string);
+        hit
+    }
+
+    #[test]
+    fn test_normalize_url() {
+        assert_eq!(
+            normalize_url("https://www.Example.com:443/docs/#intro"),
+            "example.com/docs"
+        );
+        assert_eq!(
+            normalize_url("http://example.com/docs/"),
+            "example.com/docs"
+        );
+        assert_eq!(
+            normalize_url("http://example.com:8080/a?b=c"),
+            "example.com:8080/a?b=c"
+        );
+        assert_eq!(
+            normalize_url("/home/user/report.pdf/"),
+            "/home/user/report.pdf"
+        );
+        assert_eq!(normalize_url("/"), "/");
+        #[cfg(not(target_os = "windows"))]
+        assert_eq!(
+            normalize_url("file:///home/user/report.pdf"),
+            "/home/user/report.pdf"
+        );
+        assert_eq!(
+            normalize_url(r"C:\Users\user\report.pdf"),
+            r"C:\Users\user\report.pdf"
+        );
+    }
+
+    fn dedup_hits(hits: Vec<QueryHits>) -> Vec<QueryHits> {
+        let mut deduped = DedupedHits::default();
+        for hit in hits {
+            deduped.push(hit);
+        }
+        deduped.into_hits()
+    }
+
+    #[test]
+    fn test_dedup_key_fallbacks() {
+        let mut query_hit = hit("server-a", "doc-1", None, 1.0);
+        assert_eq!(dedup_key(&query_hit), "id:server-a:doc-1");
+
+        query_hit.document.on_opened = Some(OnOpened::Document {
+            url: "https://example.com/".into(),
+        });
+        assert_eq!(dedup_key(&query_hit), "url:example.com");
+
+        query_hit.document.url = Some("https://example.com/page".into());
+        assert_eq!(dedup_key(&query_hit), "url:example.com/page");
+    }
+
+    #[test]
+    fn test_same_id_from_different_sources_is_not_duplicate() {
+        let hits = vec![
+            hit("server-a", "1", None, 0.9),
+            hit("server-b", "1", None, 0.8),
+            hit("server-a", "1", None, 0.7),
+        ];
+
+        assert_eq!(count_duplicates(&hits), 1);
+        assert_eq!(dedup_hits(hits).len(), 2);
+    }
+
+    #[test]
+    fn test_dedup_hits_keeps_best_score_and_records_sources() {
+        let hits = vec![
+            hit("server-a", "a1", Some("https://example.com/page"), 0.9),
+            hit("files", "/tmp/x", Some("/tmp/x"), 0.8),
+            hit("server-b", "b1", Some("http://www.example.com/page/"), 0.95),
+            hit("server-c", "c1", Some("https://example.com/page#top"), 0.1),
+            hit("server-b", "b2", Some("file:///tmp/x"), 0.5),
+        ];
+
+        let deduped = dedup_hits(hits);
+
+        assert_eq!(deduped.len(), 2);
+
+        assert_eq!(deduped[0].document.id, "b1");
+        assert_eq!(deduped[0].score, 0.95);
+        let also_found_in: Vec<&str> = deduped[0]
+            .also_found_in
+            .iter()
+            .map(|s| s.id.as_str())
+            .collect();
+        assert_eq!(also_found_in, vec!["server-a", "server-c"]);
+
+        assert_eq!(deduped[1].document.id, "/tmp/x");
+        assert_eq!(deduped[1].also_found_in, vec![source("server-b")]);
+    }
+
+    #[test]
+    fn test_dedup_hits_same_source_is_not_recorded() {
+        let hits = vec![
+            hit("server-a", "1", Some("https://example.com"), 0.9),
+            hit("server-a", "2", Some("https://example.com/"), 0.3),
+        ];
+
+        let deduped = dedup_hits(hits);
+
+        assert_eq!(deduped.len(), 1);
+        assert!(deduped[0].also_found_in.is_empty());
+    }
+}
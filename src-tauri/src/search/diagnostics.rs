@@ -0,0 +1,229 @@
+//! Search diagnostics: per-source timing and ranking debug information.
+
+use super::_query_coco_fusion;
+use crate::common::error::{SearchError, SearchErrorKind};
+use crate::common::search::{
+    HitScoreDebug, MultiSourceQueryResponse, QueryHits, QueryResponse, QuerySource,
+    SourceDiagnostics,
+};
+use crate::search::fusion::FusionStrategyKind;
+use crate::settings::get_fusion_strategy;
+use function_name::named;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::time::Duration;
+use tauri::AppHandle;
+use tokio::time::error::Elapsed;
+
+/// Build the `SourceDiagnostics` of `source` from its search result.
+pub(crate) fn source_diagnostics(
+    source: QuerySource,
+    elapsed: Duration,
+    timeout_result: &Result<Result<QueryResponse, SearchError>, Elapsed>,
+) -> SourceDiagnostics {
+    let (hit_count, timed_out, error_kind) = match timeout_result {
+        Err(_timeout) => (0, true, Some(SearchErrorKind::SearchTimeout)),
+        Ok(Ok(response)) => (response.hits.len(), false, None),
+        Ok(Err(search_error)) => (0, false, Some(search_error.kind())),
+    };
+
+    SourceDiagnostics {
+        source,
+        elapsed_ms: elapsed.as_millis().try_into().unwrap_or(u64::MAX),
+        hit_count,
+        timed_out,
+        error_kind,
+    }
+}
+
+/// Records the scores of hits at every ranking stage.
+#[derive(Debug, Default)]
+pub(crate) struct RankingDebug {
+    entries: Vec<HitScoreDebug>,
+    /// (query source ID, document ID) => index into `entries`
+    indexes: HashMap<(String, String), usize>,
+}
+
+impl RankingDebug {
+    fn index_of(&self, hit: &QueryHits) -> Option<usize> {
+        let source = hit.source.as_ref()?;
+        self.indexes
+            .get(&(source.id.clone(), hit.document.id.clone()))
+            .copied()
+    }
+
+    /// Record the scores returned by the query sources.
+    pub(crate) fn record_raw_scores<'hit>(
+        &mut self,
+        hits: impl IntoIterator<Item = &'hit QueryHits>,
+    ) {
+        for hit in hits {
+            let Some(ref source) = hit.source else {
+                continue;
+            };
+
+            self.indexes.insert(
+                (source.id.clone(), hit.document.id.clone()),
+                self.entries.len(),
+            );
+            self.entries.push(HitScoreDebug {
+                source: source.clone(),
+                document_id: hit.document.id.clone(),
+                title: hit.document.title.clone(),
+                raw_score: hit.score,
+                fused_score: None,
+                final_score: None,
+                final_rank: None,
+            });
+        }
+    }
+
+    /// Record the scores after rank fusion.
+    pub(crate) fn record_fused_scores(&mut self, hits: &[QueryHits]) {
+        for hit in hits {
+            if let Some(index) = self.index_of(hit) {
+                self.entries[index].fused_score = Some(hit.score);
+            }
+        }
+    }
+
+    /// Record the scores of the final result.
+    pub(crate) fn record_final_scores(&mut self, hits: &[QueryHits]) {
+        for (rank, hit) in hits.iter().enumerate() {
+            if let Some(index) = self.index_of(hit) {
+                self.entries[index].final_score = Some(hit.score);
+                self.entries[index].final_rank = Some(rank);
+            }
+        }
+    }
+
+    /// Return the entries, hits in the final result come first, in their final
+    /// order, followed by the dropped ones.
+    pub(crate) fn into_entries(mut self) -> Vec<HitScoreDebug> {
+        self.entries
+            .sort_by_key(|entry| entry.final_rank.unwrap_or(usize::MAX));
+        self.entries
+    }
+}
+
+/// Return value of `query_coco_fusion_debug()`.
+#[derive(Debug, Serialize)]
+pub(crate) struct QueryCocoFusionDebugResponse {
+    /// The fusion strategy in use.
+    fusion_strategy: FusionStrategyKind,
+    response: MultiSourceQueryResponse,
+    hit_scores: Vec<HitScoreDebug>,
+}
+
+/// Debug version of `query_coco_fusion()`, it additionally returns the scores of
+/// every hit before and after re-ranking, which helps tune the ranking.
+///
+/// This is not used by the search window, it is meant to be invoked manually.
+#[named]
+#[tauri::command]
+pub(crate) async fn query_coco_fusion_debug(
+    tauri_app_handle: AppHandle,
+    from: u64,
+    size: u64,
+    query_strings: HashMap<String, String>,
+    query_timeout: u64,
+) -> Result<QueryCocoFusionDebugResponse, SearchError> {
+    log::debug!(
+        "{}() invoked with parameters: from: [{}], size: [{}], query_strings: [{:?}], timeout: [{:?}]",
+        function_name!(),
+        from,
+        size,
+        query_strings,
+        query_timeout,
+    );
+
+    let mut ranking_debug = RankingDebug::default();
+    let response = _query_coco_fusion(
+        tauri_app_handle.clone(),
+        from,
+        size,
+        query_strings,
+        query_timeout,
+        None,
+        None,
+        None,
+        Some(&mut ranking_debug),
+    )
+    .await?;
+
+    Ok(QueryCocoFusionDebugResponse {
+        fusion_strategy: get_fusion_strategy(tauri_app_handle),
+        response,
+        hit_scores: ranking_debug.into_entries(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_fixtures::hit;
+    use super::*;
+    use crate::common::document::Document;
+
+    #[test]
+    fn test_source_diagnostics() {
+        let source = QuerySource {
+            r#type: "local".into(),
+            id: "apps".into(),
+            name: "apps".into(),
+        };
+
+        let ok: Result<Result<QueryResponse, SearchError>, Elapsed> = Ok(Ok(QueryResponse {
+            source: source.clone(),
+            hits: vec![(Document::default(), 1.0), (Document::default(), 0.5)],
+            total_hits: 2,
+            aggregations: None,
+        }));
+        let diagnostics = source_diagnostics(source.clone(), Duration::from_millis(42), &ok);
+        assert_eq!(diagnostics.elapsed_ms, 42);
+        assert_eq!(diagnostics.hit_count, 2);
+        assert!(!diagnostics.timed_out);
+        assert_eq!(diagnostics.error_kind, None);
+
+        let failed: Result<Result<QueryResponse, SearchError>, Elapsed> =
+            Ok(Err(SearchError::InternalError {
+                error: "boom".into(),
+            }));
+        let diagnostics = source_diagnostics(source, Duration::from_millis(1), &failed);
+        assert_eq!(diagnostics.hit_count, 0);
+        assert_eq!(diagnostics.error_kind, Some(SearchErrorKind::InternalError));
+    }
+
+    #[test]
+    fn test_ranking_debug() {
+        let raw_hits = vec![hit("a", "1", 10.0), hit("a", "2", 5.0), hit("b", "3", 0.7)];
+
+        let mut ranking_debug = RankingDebug::default();
+        ranking_debug.record_raw_scores(&raw_hits);
+        // Fusion re-scores and drops the hit "2"
+        ranking_debug.record_fused_scores(&[hit("b", "3", 0.9), hit("a", "1", 0.8)]);
+        ranking_debug.record_final_scores(&[hit("a", "1", 1.2), hit("b", "3", 0.9)]);
+
+        let entries = ranking_debug.into_entries();
+        let summary: Vec<_> = entries
+            .iter()
+            .map(|entry| {
+                (
+                    entry.document_id.as_str(),
+                    entry.raw_score,
+                    entry.fused_score,
+                    entry.final_score,
+                    entry.final_rank,
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            summary,
+            vec![
+                ("1", 10.0, Some(0.8), Some(1.2), Some(0)),
+                ("3", 0.7, Some(0.9), Some(0.9), Some(1)),
+                ("2", 5.0, None, None, None),
+            ]
+        );
+    }
+}
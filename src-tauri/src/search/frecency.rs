@@ -0,0 +1,403 @@
+//! Frecency (frequency + recency) based boosting.
+//!
+//! Every time a search result is opened, we record which item it was and the
+//! query that found it.  Later searches whose query shares a prefix with a
+//! recorded query boost that item, the more often and the more recently it was
+//! opened, the higher the boost.  So an app launched ten times a day ranks
+//! first after typing its first 2 characters.
+
+use crate::common::search::QueryHits;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as Json;
+use std::collections::HashMap;
+use std::sync::LazyLock;
+use tauri::AppHandle;
+use tauri_plugin_store::StoreExt;
+use tokio::sync::RwLock;
+
+/// Usage history is stored in its own store so that recording it does not
+/// rewrite the main store.
+const COCO_USAGE_STORE: &str = "coco_usage_store";
+/// `USAGE_HISTORY_CACHE` will be stored in KV store COCO_USAGE_STORE, under this key.
+const USAGE_HISTORY: &str = "usage_history";
+
+/// Only the latest `MAX_VISITS_PER_ITEM` visits of an item are kept.
+const MAX_VISITS_PER_ITEM: usize = 20;
+/// Only the `MAX_ITEMS` most recently used items are kept.
+const MAX_ITEMS: usize = 500;
+/// The recorded query is truncated to this many characters, longer queries
+/// would not be a prefix of what users usually type.
+const MAX_QUERY_PREFIX_CHARS: usize = 32;
+
+/// `frecency / (frecency + FRECENCY_HALF_BOOST)` of the top score is the boost
+/// added to the fused score, an item with frecency `FRECENCY_HALF_BOOST` gets
+/// half of the top score.  Scores of different fusion strategies differ by
+/// orders of magnitude, e.g., RRF scores are around 0.016, so the boost is
+/// relative to them.
+const FRECENCY_HALF_BOOST: f64 = 200.0;
+
+/// In-memory copy of the usage history, `None` if it has not been loaded
+/// from the store yet.
+static USAGE_HISTORY_CACHE: LazyLock<RwLock<Option<UsageHistory>>> =
+    LazyLock::new(|| RwLock::new(None));
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub(crate) struct UsageHistory {
+    /// Item key => usage
+    items: HashMap<String, ItemUsage>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub(crate) struct ItemUsage {
+    /// Sorted by timestamp, oldest first.
+    visits: Vec<Visit>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub(crate) struct Visit {
+    /// Lowercased query that found the item.
+    query_prefix: String,
+    /// Unix timestamp in seconds.
+    timestamp: i64,
+}
+
+/// Entry returned by `get_usage_history()`.
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct UsageHistoryEntry {
+    key: String,
+    visits: Vec<Visit>,
+    /// Frecency of this item for an empty query.
+    frecency: f64,
+}
+
+/// Identifies the search result that gets opened.
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct OpenedSearchResult {
+    /// The query that was typed when the result was opened.
+    pub(crate) query: String,
+    /// ID of the query source that returned this result.
+    pub(crate) query_source_id: String,
+    pub(crate) document_id: String,
+}
+
+/// Key used to identify an item in the usage history.
+///
+/// Document IDs are only unique within a query source, so both are included.
+pub(crate) fn item_key(query_source_id: &str, document_id: &str) -> String {
+    format!("{}/{}", query_source_id, document_id)
+}
+
+fn normalize_query(query: &str) -> String {
+    query
+        .trim()
+        .to_lowercase()
+        .chars()
+        .take(MAX_QUERY_PREFIX_CHARS)
+        .collect()
+}
+
+/// Weight of a visit that happened `age_secs` ago, similar to the buckets
+/// that Firefox uses.
+fn recency_weight(age_secs: i64) -> f64 {
+    const DAY: i64 = 24 * 60 * 60;
+
+    if age_secs < 4 * DAY {
+        100.0
+    } else if age_secs < 14 * DAY {
+        70.0
+    } else if age_secs < 31 * DAY {
+        50.0
+    } else if age_secs < 90 * DAY {
+        30.0
+    } else {
+        10.0
+    }
+}
+
+/// Does a visit recorded with `recorded` query count for `query`?
+///
+/// Both should be normalized.  An empty query matches everything.
+fn query_matches(recorded: &str, query: &str) -> bool {
+    recorded.starts_with(query) || query.starts_with(recorded)
+}
+
+impl UsageHistory {
+    pub(crate) fn record(&mut self, key: String, query: &str, now: i64) {
+        let item = self.items.entry(key).or_default();
+        item.visits.push(Visit {
+            query_prefix: normalize_query(query),
+            timestamp: now,
+        });
+        if item.visits.len() > MAX_VISITS_PER_ITEM {
+            let n_to_remove = item.visits.len() - MAX_VISITS_PER_ITEM;
+            item.visits.drain(0..n_to_remove);
+        }
+
+        if self.items.len() > MAX_ITEMS {
+            self.evict_least_recently_used(self.items.len() - MAX_ITEMS);
+        }
+    }
+
+    fn evict_least_recently_used(&mut self, n: usize) {
+        let mut last_used: Vec<(i64, String)> = self
+            .items
+            .iter()
+            .map(|(key, item)| {
+                let last_visit = item.visits.last().map_or(i64::MIN, |v| v.timestamp);
+                (last_visit, key.clone())
+            })
+            .collect();
+        last_used.sort();
+
+        for (_last_visit, key) in last_used.into_iter().take(n) {
+            self.items.remove(&key);
+        }
+    }
+
+    pub(crate) fn frecency(&self, key: &str, query: &str, now: i64) -> f64 {
+        let Some(item) = self.items.get(key) else {
+            return 0.0;
+        };
+        let query = normalize_query(query);
+
+        item.visits
+            .iter()
+            .filter(|visit| query_matches(&visit.query_prefix, &query))
+            .map(|visit| recency_weight(now - visit.timestamp))
+            .sum()
+    }
+
+    /// Add the frecency boost to the scores of `hits`, then re-sort them.
+    pub(crate) fn boost(&self, hits: &mut [QueryHits], query: &str, now: i64) {
+        if self.items.is_empty() {
+            return;
+        }
+
+        let top_score = hits.iter().map(|hit| hit.score).fold(0.0, f64::max);
+        if top_score <= 0.0 {
+            return;
+        }
+
+        let mut boosted = false;
+        for hit in hits.iter_mut() {
+            let Some(ref source) = hit.source else {
+                continue;
+            };
+            let frecency = self.frecency(&item_key(&source.id, &hit.document.id), query, now);
+            if frecency > 0.0 {
+                hit.score += top_score * frecency / (frecency + FRECENCY_HALF_BOOST);
+                boosted = true;
+            }
+        }
+
+        if boosted {
+            hits.sort_by(|a, b| {
+                b.score
+                    .partial_cmp(&a.score)
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            });
+        }
+    }
+
+    fn entries(&self, now: i64) -> Vec<UsageHistoryEntry> {
+        let mut entries: Vec<UsageHistoryEntry> = self
+            .items
+            .iter()
+            .map(|(key, item)| UsageHistoryEntry {
+                key: key.clone(),
+                visits: item.visits.clone(),
+                frecency: self.frecency(key, "", now),
+            })
+            .collect();
+        entries.sort_by(|a, b| {
+            b.frecency
+                .partial_cmp(&a.frecency)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| a.key.cmp(&b.key))
+        });
+
+        entries
+    }
+}
+
+fn now() -> i64 {
+    chrono::Utc::now().timestamp()
+}
+
+/// Load the usage history from the store into `USAGE_HISTORY_CACHE` if that
+/// has not been done.
+async fn ensure_loaded(tauri_app_handle: &AppHandle) {
+    if USAGE_HISTORY_CACHE.read().await.is_some() {
+        return;
+    }
+
+    let mut cache = USAGE_HISTORY_CACHE.write().await;
+    // Re-check, someone else may have loaded it.
+    if cache.is_some() {
+        return;
+    }
+
+    let store = tauri_app_handle
+        .store(COCO_USAGE_STORE)
+        .expect("create or load a store should never fail");
+    let history = match store.get(USAGE_HISTORY) {
+        Some(json) => serde_json::from_value(json).unwrap_or_else(|e| {
+            log::warn!("usage history is broken, error [{}], starting over", e);
+            UsageHistory::default()
+        }),
+        None => UsageHistory::default(),
+    };
+
+    *cache = Some(history);
+}
+
+fn persist(tauri_app_handle: &AppHandle, history: &UsageHistory) {
+    let json: Json = serde_json::to_value(history).expect("UsageHistory should be serializable");
+
+    tauri_app_handle
+        .store(COCO_USAGE_STORE)
+        .expect("create or load a store should never fail")
+        .set(USAGE_HISTORY, json);
+}
+
+/// Record that `opened` was opened, no-op if frecency is disabled in settings.
+pub(crate) async fn record_opened_search_result(
+    tauri_app_handle: &AppHandle,
+    opened: OpenedSearchResult,
+) {
+    if !crate::settings::get_frecency_enabled(tauri_app_handle.clone()) {
+        return;
+    }
+
+    ensure_loaded(tauri_app_handle).await;
+    let mut cache = USAGE_HISTORY_CACHE.write().await;
+    let history = cache.as_mut().expect("loaded by ensure_loaded()");
+
+    history.record(
+        item_key(&opened.query_source_id, &opened.document_id),
+        &opened.query,
+        now(),
+    );
+    persist(tauri_app_handle, history);
+}
+
+/// Boost `hits` with frecency, no-op if frecency is disabled in settings.
+pub(crate) async fn boost_hits_by_frecency(
+    tauri_app_handle: &AppHandle,
+    hits: &mut [QueryHits],
+    query: &str,
+) {
+    if !crate::settings::get_frecency_enabled(tauri_app_handle.clone()) {
+        return;
+    }
+
+    ensure_loaded(tauri_app_handle).await;
+    let cache = USAGE_HISTORY_CACHE.read().await;
+    let history = cache.as_ref().expect("loaded by ensure_loaded()");
+
+    history.boost(hits, query, now());
+}
+
+/// Return the usage history, most used items first.
+#[tauri::command]
+pub(crate) async fn get_usage_history(tauri_app_handle: AppHandle) -> Vec<UsageHistoryEntry> {
+    ensure_loaded(&tauri_app_handle).await;
+    let cache = USAGE_HISTORY_CACHE.read().await;
+
+    cache
+        .as_ref()
+        .expect("loaded by ensure_loaded()")
+        .entries(now())
+}
+
+#[tauri::command]
+pub(crate) async fn clear_usage_history(tauri_app_handle: AppHandle) {
+    let mut cache = USAGE_HISTORY_CACHE.write().await;
+    let history = UsageHistory::default();
+    persist(&tauri_app_handle, &history);
+    *cache = Some(history);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_fixtures::hit;
+    use super::*;
+
+    const DAY: i64 = 24 * 60 * 60;
+    const NOW: i64 = 1_700_000_000;
+
+    #[test]
+    fn frecency_matches_query_prefixes() {
+        let mut history = UsageHistory::default();
+        let key = item_key("application", "/Applications/Slack.app");
+        history.record(key.clone(), "Slack", NOW);
+
+        assert_eq!(history.frecency(&key, "sl", NOW), 100.0);
+        assert_eq!(history.frecency(&key, "SLACK", NOW), 100.0);
+        assert_eq!(history.frecency(&key, "", NOW), 100.0);
+        assert_eq!(history.frecency(&key, "sk", NOW), 0.0);
+        assert_eq!(history.frecency("unknown", "sl", NOW), 0.0);
+    }
+
+    #[test]
+    fn frecency_decays_with_age() {
+        let mut history = UsageHistory::default();
+        history.record("a".into(), "x", NOW - 10 * DAY);
+        history.record("b".into(), "x", NOW - 100 * DAY);
+
+        assert_eq!(history.frecency("a", "x", NOW), 70.0);
+        assert_eq!(history.frecency("b", "x", NOW), 10.0);
+    }
+
+    #[test]
+    fn visits_and_items_are_bounded() {
+        let mut history = UsageHistory::default();
+        for i in 0..(MAX_VISITS_PER_ITEM as i64 + 5) {
+            history.record("a".into(), "x", NOW + i);
+        }
+        assert_eq!(history.items["a"].visits.len(), MAX_VISITS_PER_ITEM);
+        assert_eq!(history.items["a"].visits[0].timestamp, NOW + 5);
+
+        for i in 0..MAX_ITEMS as i64 {
+            history.record(format!("item-{}", i), "x", NOW + 100 + i);
+        }
+        assert_eq!(history.items.len(), MAX_ITEMS);
+        // "a" is the least recently used one
+        assert!(!history.items.contains_key("a"));
+    }
+
+    #[test]
+    fn boost_reorders_hits() {
+        let mut history = UsageHistory::default();
+        for _ in 0..10 {
+            history.record(item_key("application", "slack"), "sla", NOW);
+        }
+
+        let mut hits = vec![
+            hit("application", "slides", 0.9),
+            hit("application", "slack", 0.6),
+        ];
+        history.boost(&mut hits, "sl", NOW);
+
+        assert_eq!(hits[0].document.id, "slack");
+        assert_eq!(hits[1].score, 0.9);
+    }
+
+    #[test]
+    fn boost_is_relative_to_the_scores() {
+        let mut history = UsageHistory::default();
+        history.record(item_key("application", "slack"), "sla", NOW);
+
+        // RRF scores
+        let mut hits = vec![
+            hit("application", "slides", 0.0164),
+            hit("application", "slack", 0.0161),
+            hit("application", "slate", 0.0159),
+        ];
+        history.boost(&mut hits, "sl", NOW);
+
+        assert_eq!(hits[0].document.id, "slack");
+        // A single visit does not dwarf the scores
+        assert!(hits[0].score < 0.0164 * 1.5);
+    }
+}
@@ -0,0 +1,643 @@
+//! Rank fusion, i.e., merging the hits from multiple query sources into a
+//! single ranked list.
+//!
+//! Scores returned by different query sources are on incomparable scales, e.g.,
+//! Coco server returns BM25-like scores, Tracker returns `fts:rank`, and the
+//! extensions use `fuzzy_match()`, so we cannot simply sort the
+//! hits by their raw scores.  A `FusionStrategy` defines how these hits should
+//! be merged, it is selectable in settings, see `FusionStrategyKind`.
+
+use crate::common::search::{QueryHits, QuerySource};
+use crate::extension::LOCAL_QUERY_SOURCE_TYPE;
+use crate::extension::built_in::calculator::DATA_SOURCE_ID as CALCULATOR_DATA_SOURCE_ID;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use strsim::levenshtein;
+
+/// Parameters shared by all the fusion strategies.
+#[derive(Debug, Clone)]
+pub(crate) struct FusionContext<'query> {
+    /// The search keyword.
+    pub(crate) query: &'query str,
+    /// Maximum number of hits to return.
+    pub(crate) size: usize,
+    /// Settings "local search results weight", scores of the hits from local
+    /// query sources will be multiplied by it.
+    pub(crate) local_query_source_weight: f64,
+}
+
+pub(crate) trait FusionStrategy: Send + Sync {
+    /// Merge the hits from all the query sources into a ranked list that
+    /// contains at most `ctx.size` hits, sorted by score in descending order.
+    fn fuse(
+        &self,
+        hits_grouped_by_query_source: HashMap<QuerySource, Vec<QueryHits>>,
+        ctx: &FusionContext<'_>,
+    ) -> Vec<QueryHits>;
+}
+
+/// Fusion strategies that users can choose in settings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum FusionStrategyKind {
+    /// Even per-source quotas, then re-rank the titles with a Levenshtein &
+    /// Jaccard hybrid score.
+    #[default]
+    QuotaRerank,
+    /// Reciprocal Rank Fusion, which only looks at the ranks.
+    ReciprocalRankFusion,
+    /// Min-max normalize the scores of each query source.
+    MinMaxNormalization,
+    /// Z-score normalize the scores of each query source.
+    ZScoreNormalization,
+}
+
+impl FusionStrategyKind {
+    pub(crate) fn strategy(self) -> Box<dyn FusionStrategy> {
+        match self {
+            Self::QuotaRerank => Box::new(QuotaRerankFusion),
+            Self::ReciprocalRankFusion => Box::new(ReciprocalRankFusion::default()),
+            Self::MinMaxNormalization => Box::new(ScoreNormalizationFusion {
+                method: ScoreNormalization::MinMax,
+            }),
+            Self::ZScoreNormalization => Box::new(ScoreNormalizationFusion {
+                method: ScoreNormalization::ZScore,
+            }),
+        }
+    }
+}
+
+/// The calculator result is an exact answer rather than a ranked guess, no
+/// matter which strategy is used, it should not be re-scored.
+fn is_pinned_source(query_source: &QuerySource) -> bool {
+    query_source.id == CALCULATOR_DATA_SOURCE_ID
+}
+
+fn apply_local_query_source_weight(
+    query_source: &QuerySource,
+    hits: &mut [QueryHits],
+    local_query_source_weight: f64,
+) {
+    // Scores remain unchanged if it is 1.0
+    if query_source.r#type == LOCAL_QUERY_SOURCE_TYPE && local_query_source_weight != 1.0 {
+        hits.iter_mut()
+            .for_each(|hit| hit.score *= local_query_source_weight);
+    }
+}
+
+/// Sort hits by score in descending order.
+///
+/// Ties are broken by query source ID and document ID so that the result is
+/// deterministic regardless of the `HashMap` iteration order.
+fn sort_hits_by_score(hits: &mut [QueryHits]) {
+    hits.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| {
+                let a_source = a.source.as_ref().map(|s| s.id.as_str());
+                let b_source = b.source.as_ref().map(|s| s.id.as_str());
+                a_source.cmp(&b_source)
+            })
+            .then_with(|| a.document.id.cmp(&b.document.id))
+    });
+}
+
+/// Sort the hits of every query source by the raw score (descending) in case
+/// query sources do not sort them.
+fn sort_hits_within_query_sources(
+    hits_grouped_by_query_source: &mut HashMap<QuerySource, Vec<QueryHits>>,
+) {
+    for hits in hits_grouped_by_query_source.values_mut() {
+        hits.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(Ordering::Equal));
+    }
+}
+
+/// Put the hits from pinned sources in front of `fused_hits`.
+///
+/// `fused_hits` should be sorted.
+fn prepend_pinned_hits(mut pinned_hits: Vec<QueryHits>, fused_hits: &mut Vec<QueryHits>) {
+    if pinned_hits.is_empty() {
+        return;
+    }
+
+    let top_score = fused_hits.first().map_or(0.0, |hit| hit.score);
+    for hit in pinned_hits.iter_mut() {
+        hit.score = hit.score.max(top_score);
+    }
+    pinned_hits.append(fused_hits);
+    *fused_hits = pinned_hits;
+}
+
+/// The original strategy.
+///
+/// 1. Collect hits evenly across sources so that every query source has hits
+///    returned and query sources with many hits won't dominate
+/// 2. Re-score the titles with `boosted_levenshtein_rerank()`
+/// 3. Apply the local query source weight
+pub(crate) struct QuotaRerankFusion;
+
+impl FusionStrategy for QuotaRerankFusion {
+    fn fuse(
+        &self,
+        mut all_hits_grouped_by_query_source: HashMap<QuerySource, Vec<QueryHits>>,
+        ctx: &FusionContext<'_>,
+    ) -> Vec<QueryHits> {
+        let n_sources = all_hits_grouped_by_query_source.len();
+        if n_sources == 0 {
+            return Vec::new();
+        }
+        let size = ctx.size;
+
+        sort_hits_within_query_sources(&mut all_hits_grouped_by_query_source);
+
+        let mut final_hits_grouped_by_query_source: HashMap<QuerySource, Vec<QueryHits>> =
+            HashMap::new();
+        let mut pruned: HashMap<&QuerySource, &[QueryHits]> = HashMap::new();
+
+        // Include at least 2 hits from each query source
+        let max_hits_per_source = (size / n_sources).max(2);
+        for (query_source, hits) in all_hits_grouped_by_query_source.iter() {
+            let hits_taken = if hits.len() > max_hits_per_source {
+                pruned.insert(query_source, &hits[max_hits_per_source..]);
+                hits[0..max_hits_per_source].to_vec()
+            } else {
+                hits.clone()
+            };
+
+            final_hits_grouped_by_query_source.insert(query_source.clone(), hits_taken);
+        }
+
+        let final_hits_len = final_hits_grouped_by_query_source
+            .values()
+            .fold(0, |acc: usize, hits| acc + hits.len());
+        let pruned_len = pruned.values().fold(0, |acc: usize, hits| acc + hits.len());
+
+        /*
+         * If we still need more hits, take the highest-scoring from `pruned`
+         *
+         * `pruned` contains sorted arrays, we scan it in a way similar to
+         * how n-way-merge-sort extracts the element with the greatest value.
+         */
+        if final_hits_len < size {
+            let n_take = pruned_len.min(size - final_hits_len);
+
+            for _ in 0..n_take {
+                let (source, sorted_hits) = pruned
+                    .iter_mut()
+                    .filter(|(_source, sorted_hits)| !sorted_hits.is_empty())
+                    .max_by(|(a_source, a_hits), (b_source, b_hits)| {
+                        a_hits[0]
+                            .score
+                            .partial_cmp(&b_hits[0].score)
+                            .unwrap_or(Ordering::Equal)
+                            // Prefer the smaller source ID on ties
+                            .then_with(|| b_source.id.cmp(&a_source.id))
+                    })
+                    .expect("`pruned` should contain at least `n_take` elements");
+
+                let hit = sorted_hits[0].clone();
+                *sorted_hits = &sorted_hits[1..];
+
+                final_hits_grouped_by_query_source
+                    .get_mut(*source)
+                    .expect("all the source_ids stored in `pruned` come from `final_hits_grouped_by_query_source`, so it should exist")
+                    .push(hit);
+            }
+        }
+
+        /*
+         * Re-rank (re-score) the final hits
+         */
+        if n_sources > 1 {
+            boosted_levenshtein_rerank(ctx.query, &mut final_hits_grouped_by_query_source);
+        }
+
+        let mut final_hits = Vec::new();
+        for (query_source, mut hits) in final_hits_grouped_by_query_source {
+            apply_local_query_source_weight(
+                &query_source,
+                &mut hits,
+                ctx.local_query_source_weight,
+            );
+            final_hits.extend(hits);
+        }
+
+        sort_hits_by_score(&mut final_hits);
+        final_hits.truncate(size);
+
+        final_hits
+    }
+}
+
+fn boosted_levenshtein_rerank(
+    query: &str,
+    final_hits_grouped_by_query_source: &mut HashMap<QuerySource, Vec<QueryHits>>,
+) {
+    let query_lower = query.to_lowercase();
+
+    for (source, hits) in final_hits_grouped_by_query_source.iter_mut() {
+        // Skip special sources like calculator
+        if is_pinned_source(source) {
+            continue;
+        }
+
+        for hit in hits.iter_mut() {
+            let document_title = hit.document.title.as_deref().unwrap_or("");
+            let document_title_lowercase = document_title.to_lowercase();
+
+            let new_score = {
+                let mut score = 0.0;
+
+                // --- Exact or substring boost ---
+                if document_title.contains(query) {
+                    score += 0.4;
+                } else if document_title_lowercase.contains(&query_lower) {
+                    score += 0.2;
+                }
+
+                // --- Levenshtein distance (character similarity) ---
+                let dist = levenshtein(&query_lower, &document_title_lowercase);
+                let max_len = query_lower.len().max(document_title.len());
+                let levenshtein_score = if max_len > 0 {
+                    (1.0 - (dist as f64 / max_len as f64)) as f32
+                } else {
+                    0.0
+                };
+
+                // --- Jaccard similarity (token overlap) ---
+                let jaccard_score = jaccard_similarity(&query_lower, &document_title_lowercase);
+
+                // --- Combine scores (weights adjustable) ---
+                // Levenshtein emphasizes surface similarity
+                // Jaccard emphasizes term overlap (semantic hint)
+                let hybrid_score = 0.7 * levenshtein_score + 0.3 * jaccard_score;
+
+                // --- Apply hybrid score ---
+                score += hybrid_score;
+
+                // --- Limit score range ---
+                score.min(1.0) as f64
+            };
+
+            hit.score = new_score;
+        }
+    }
+}
+
+/// Compute token-based Jaccard similarity
+fn jaccard_similarity(a: &str, b: &str) -> f32 {
+    let a_tokens: HashSet<_> = tokenize(a).into_iter().collect();
+    let b_tokens: HashSet<_> = tokenize(b).into_iter().collect();
+
+    if a_tokens.is_empty() || b_tokens.is_empty() {
+        return 0.0;
+    }
+
+    let intersection = a_tokens.intersection(&b_tokens).count() as f32;
+    let union = a_tokens.union(&b_tokens).count() as f32;
+
+    intersection / union
+}
+
+/// Basic tokenizer (case-insensitive, alphanumeric words only)
+fn tokenize(text: &str) -> Vec<String> {
+    text.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|s| !s.is_empty())
+        .map(|s| s.to_string())
+        .collect()
+}
+
+/// Reciprocal Rank Fusion[1].
+///
+/// A hit ranked `r` (starting from 1) in its query source gets score
+/// `1 / (k + r)`.  Raw scores are only used to rank hits within the same
+/// source, so the scales do not matter.
+///
+/// [1]: https://plg.uwaterloo.ca/~gvcormac/cormacksigir09-rrf.pdf
+pub(crate) struct ReciprocalRankFusion {
+    k: f64,
+}
+
+impl Default for ReciprocalRankFusion {
+    fn default() -> Self {
+        // The value recommended by the paper
+        Self { k: 60.0 }
+    }
+}
+
+impl FusionStrategy for ReciprocalRankFusion {
+    fn fuse(
+        &self,
+        mut hits_grouped_by_query_source: HashMap<QuerySource, Vec<QueryHits>>,
+        ctx: &FusionContext<'_>,
+    ) -> Vec<QueryHits> {
+        sort_hits_within_query_sources(&mut hits_grouped_by_query_source);
+
+        let mut pinned_hits = Vec::new();
+        let mut fused_hits = Vec::new();
+        for (query_source, mut hits) in hits_grouped_by_query_source {
+            if is_pinned_source(&query_source) {
+                pinned_hits.extend(hits);
+                continue;
+            }
+
+            for (idx, hit) in hits.iter_mut().enumerate() {
+                let rank = (idx + 1) as f64;
+                hit.score = 1.0 / (self.k + rank);
+            }
+            apply_local_query_source_weight(
+                &query_source,
+                &mut hits,
+                ctx.local_query_source_weight,
+            );
+            fused_hits.extend(hits);
+        }
+
+        sort_hits_by_score(&mut fused_hits);
+        prepend_pinned_hits(pinned_hits, &mut fused_hits);
+        fused_hits.truncate(ctx.size);
+
+        fused_hits
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum ScoreNormalization {
+    /// `(score - min) / (max - min)`, maps scores to [0, 1].
+    MinMax,
+    /// `(score - mean) / std_dev`, then mapped to (0, 1) by the logistic
+    /// function so that the local query source weight (a multiplier) does
+    /// not flip the order of negative scores.
+    ZScore,
+}
+
+impl ScoreNormalization {
+    /// Normalize `scores` in place.
+    fn normalize(self, scores: &mut [f64]) {
+        if scores.is_empty() {
+            return;
+        }
+        let n = scores.len() as f64;
+
+        match self {
+            Self::MinMax => {
+                let min = scores.iter().copied().fold(f64::INFINITY, f64::min);
+                let max = scores.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+                let range = max - min;
+
+                for score in scores.iter_mut() {
+                    // All the hits are equally good
+                    *score = if range > 0.0 {
+                        (*score - min) / range
+                    } else {
+                        1.0
+                    };
+                }
+            }
+            Self::ZScore => {
+                let mean = scores.iter().sum::<f64>() / n;
+                let variance = scores.iter().map(|s| (s - mean).powi(2)).sum::<f64>() / n;
+                let std_dev = variance.sqrt();
+
+                for score in scores.iter_mut() {
+                    let z = if std_dev > 0.0 {
+                        (*score - mean) / std_dev
+                    } else {
+                        0.0
+                    };
+                    *score = 1.0 / (1.0 + (-z).exp());
+                }
+            }
+        }
+    }
+}
+
+/// Normalize the scores of each query source so that they become comparable,
+/// then merge them.
+pub(crate) struct ScoreNormalizationFusion {
+    method: ScoreNormalization,
+}
+
+impl FusionStrategy for ScoreNormalizationFusion {
+    fn fuse(
+        &self,
+        hits_grouped_by_query_source: HashMap<QuerySource, Vec<QueryHits>>,
+        ctx: &FusionContext<'_>,
+    ) -> Vec<QueryHits> {
+        let mut pinned_hits = Vec::new();
+        let mut fused_hits = Vec::new();
+        for (query_source, mut hits) in hits_grouped_by_query_source {
+            if is_pinned_source(&query_source) {
+                pinned_hits.extend(hits);
+                continue;
+            }
+
+            let mut scores: Vec<f64> = hits.iter().map(|hit| hit.score).collect();
+            self.method.normalize(&mut scores);
+            for (hit, score) in hits.iter_mut().zip(scores) {
+                hit.score = score;
+            }
+            apply_local_query_source_weight(
+                &query_source,
+                &mut hits,
+                ctx.local_query_source_weight,
+            );
+            fused_hits.extend(hits);
+        }
+
+        sort_hits_by_score(&mut fused_hits);
+        prepend_pinned_hits(pinned_hits, &mut fused_hits);
+        fused_hits.truncate(ctx.size);
+
+        fused_hits
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_fixtures::hit_of;
+    use super::*;
+
+    fn source(id: &str, ty: &str) -> QuerySource {
+        QuerySource {
+            r#type: ty.to_string(),
+            id: id.to_string(),
+            name: id.to_string(),
+        }
+    }
+
+    fn hit(source: &QuerySource, doc_id: &str, title: &str, score: f64) -> QueryHits {
+        let mut hit = hit_of(source, doc_id, score);
+        hit.document.title = Some(title.to_string());
+        hit
+    }
+
+    fn ctx(size: usize) -> FusionContext<'static> {
+        FusionContext {
+            query: "rust",
+            size,
+            local_query_source_weight: 1.0,
+        }
+    }
+
+    fn doc_ids(hits: &[QueryHits]) -> Vec<&str> {
+        hits.iter().map(|hit| hit.document.id.as_str()).collect()
+    }
+
+    /// A server with BM25-like scores, and a local source with scores in [0, 1].
+    fn server_and_local_hits() -> HashMap<QuerySource, Vec<QueryHits>> {
+        let server = source("server", "coco-servers");
+        let local = source("apps", LOCAL_QUERY_SOURCE_TYPE);
+
+        HashMap::from([
+            (
+                server.clone(),
+                vec![
+                    hit(&server, "s1", "Rust book", 42.0),
+                    hit(&server, "s2", "Rust by example", 30.0),
+                    hit(&server, "s3", "Rustonomicon", 12.0),
+                ],
+            ),
+            (
+                local.clone(),
+                vec![
+                    hit(&local, "l2", "RustRover", 0.6),
+                    hit(&local, "l1", "Rust", 0.9),
+                ],
+            ),
+        ])
+    }
+
+    #[test]
+    fn rrf_interleaves_sources_by_rank() {
+        let fused = ReciprocalRankFusion::default().fuse(server_and_local_hits(), &ctx(10));
+
+        // Rank 1 of both sources tie, broken by source ID ("apps" < "server")
+        assert_eq!(doc_ids(&fused), vec!["l1", "s1", "l2", "s2", "s3"]);
+        assert!((fused[0].score - 1.0 / 61.0).abs() < 1e-12);
+        assert!((fused[4].score - 1.0 / 63.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn rrf_respects_local_weight_and_size() {
+        let ctx = FusionContext {
+            query: "rust",
+            size: 3,
+            local_query_source_weight: 0.5,
+        };
+        let fused = ReciprocalRankFusion::default().fuse(server_and_local_hits(), &ctx);
+
+        assert_eq!(doc_ids(&fused), vec!["s1", "s2", "s3"]);
+    }
+
+    #[test]
+    fn min_max_normalization() {
+        let mut scores = vec![10.0, 20.0, 15.0];
+        ScoreNormalization::MinMax.normalize(&mut scores);
+        assert_eq!(scores, vec![0.0, 1.0, 0.5]);
+
+        let mut same = vec![3.0, 3.0];
+        ScoreNormalization::MinMax.normalize(&mut same);
+        assert_eq!(same, vec![1.0, 1.0]);
+    }
+
+    #[test]
+    fn z_score_normalization() {
+        let mut scores = vec![1.0, 2.0, 3.0];
+        ScoreNormalization::ZScore.normalize(&mut scores);
+
+        assert!((scores[1] - 0.5).abs() < 1e-12);
+        assert!(scores[0] < scores[1] && scores[1] < scores[2]);
+        assert!((scores[0] + scores[2] - 1.0).abs() < 1e-12);
+        assert!(scores.iter().all(|s| *s > 0.0 && *s < 1.0));
+    }
+
+    #[test]
+    fn min_max_fusion_makes_scales_comparable() {
+        let strategy = FusionStrategyKind::MinMaxNormalization.strategy();
+        let fused = strategy.fuse(server_and_local_hits(), &ctx(10));
+
+        // l1 and s1 are both normalized to 1.0, and the raw 42.0 no longer
+        // dominates.
+        assert_eq!(doc_ids(&fused), vec!["l1", "s1", "s2", "l2", "s3"]);
+        assert_eq!(fused[0].score, 1.0);
+        assert_eq!(fused[4].score, 0.0);
+    }
+
+    #[test]
+    fn calculator_hits_are_pinned() {
+        let calculator = source(CALCULATOR_DATA_SOURCE_ID, LOCAL_QUERY_SOURCE_TYPE);
+        let mut grouped = server_and_local_hits();
+        grouped.insert(
+            calculator.clone(),
+            vec![hit(&calculator, "calc", "1 + 1 = 2", 0.01)],
+        );
+
+        for kind in [
+            FusionStrategyKind::ReciprocalRankFusion,
+            FusionStrategyKind::MinMaxNormalization,
+            FusionStrategyKind::ZScoreNormalization,
+        ] {
+            let fused = kind.strategy().fuse(grouped.clone(), &ctx(10));
+            assert_eq!(fused[0].document.id, "calc", "strategy {:?}", kind);
+            assert!(fused[0].score >= fused[1].score);
+        }
+    }
+
+    #[test]
+    fn quota_rerank_keeps_every_source() {
+        let server = source("server", "coco-servers");
+        let local = source("apps", LOCAL_QUERY_SOURCE_TYPE);
+        let grouped = HashMap::from([
+            (
+                server.clone(),
+                (0..10)
+                    .map(|i| hit(&server, &format!("s{}", i), "rust", 100.0 - i as f64))
+                    .collect(),
+            ),
+            (local.clone(), vec![hit(&local, "l0", "Rust", 0.1)]),
+        ]);
+
+        let fused = QuotaRerankFusion.fuse(grouped, &ctx(4));
+
+        assert_eq!(fused.len(), 4);
+        assert!(fused.iter().any(|hit| hit.document.id == "l0"));
+        // The remaining slots are filled with the best pruned server hits
+        let mut server_ids: Vec<&str> = fused
+            .iter()
+            .filter(|hit| hit.source.as_ref().unwrap().id == "server")
+            .map(|hit| hit.document.id.as_str())
+            .collect();
+        server_ids.sort();
+        assert_eq!(server_ids, vec!["s0", "s1", "s2"]);
+    }
+
+    #[test]
+    fn quota_rerank_single_source_keeps_raw_scores() {
+        let local = source("apps", LOCAL_QUERY_SOURCE_TYPE);
+        let grouped = HashMap::from([(
+            local.clone(),
+            vec![hit(&local, "b", "Rust", 0.2), hit(&local, "a", "Rust", 0.8)],
+        )]);
+
+        let fused = QuotaRerankFusion.fuse(grouped, &ctx(10));
+        assert_eq!(doc_ids(&fused), vec!["a", "b"]);
+        assert_eq!(fused[0].score, 0.8);
+    }
+
+    #[test]
+    fn fusion_strategy_kind_serde() {
+        assert_eq!(
+            serde_json::to_string(&FusionStrategyKind::ReciprocalRankFusion).unwrap(),
+            "\"reciprocal_rank_fusion\""
+        );
+        assert_eq!(
+            serde_json::from_str::<FusionStrategyKind>("\"quota_rerank\"").unwrap(),
+            FusionStrategyKind::QuotaRerank
+        );
+    }
+}
@@ -0,0 +1,596 @@
+//! Fuzzy matching shared by the local query sources.
+//!
+//! `fuzzy_match()` scores how well a query matches a piece of text (e.g., an
+//! application or extension name), it tries the following in order and takes
+//! the best score:
+//!
+//! 1. Exact match (case-insensitive)
+//! 2. Prefix and substring match
+//! 3. Subsequence match, with bonuses for matching at word boundaries and
+//!    camelCase humps, so that acronyms like "vsc" match "Visual Studio Code"
+//! 4. If none of the above matches, typo-tolerant match with a bounded edit
+//!    distance, e.g., "visaul" matches "Visual"
+//! 5. If the score is still low, order-independent match, all the query chars
+//!    exist in the text, e.g., "tca" matches "contact"
+//!
+//! For texts containing Chinese characters, the above are applied to their
+//! pinyin as well, so that "weixin" and "wx" match "微信", see
+//! `transliteration.rs`.
+//!
+//! Besides the score, the matched ranges of the text are returned so that the
+//! frontend can highlight them.
+
+use super::transliteration::{contains_han, transliteration_keys};
+use std::ops::Range;
+
+/// Result of `fuzzy_match()`.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct FuzzyMatch {
+    /// In range (0.0, 1.0], 1.0 means a perfect match.
+    pub(crate) score: f64,
+    /// Matched ranges of the text, in char (not byte) indices, sorted and
+    /// non-overlapping.
+    pub(crate) ranges: Vec<Range<usize>>,
+}
+
+const PERFECT_MATCH_SCORE: f64 = 1.0;
+/// Cap non-perfect matches slightly below 1.0 to make perfect (1.0) distinct.
+const MAX_IMPERFECT_MATCH_SCORE: f64 = 0.95;
+
+/// Subsequence scores are mapped to [MIN, MAX].
+const SUBSEQUENCE_MIN_SCORE: f64 = 0.15;
+const SUBSEQUENCE_MAX_SCORE: f64 = 0.6;
+/// Typo-tolerant scores are mapped to [MIN, MAX].
+const TYPO_MIN_SCORE: f64 = 0.05;
+const TYPO_MAX_SCORE: f64 = 0.15;
+/// Fixed low score for the order-independent match, a weaker match type.
+const ORDER_INDEPENDENT_SCORE: f64 = 0.15;
+
+/// Score of every matched char in a subsequence match.
+const MATCH_SCORE: f64 = 1.0;
+/// Bonus for a char matched at a word boundary or a camelCase hump.
+const BOUNDARY_BONUS: f64 = 2.0;
+/// Bonus for a char matched right after the previous matched char.
+const CONSECUTIVE_BONUS: f64 = 1.5;
+/// Penalty of every unmatched char between 2 matched chars.
+const GAP_PENALTY: f64 = 0.1;
+/// Subsequence matching is O(query length * text length), skip it for
+/// extremely long texts.
+const SUBSEQUENCE_MAX_TEXT_LEN: usize = 1024;
+
+/// Match `query` against `text`, case-insensitively.
+///
+/// Return `None` if they do not match at all or either of them is empty.
+pub(crate) fn fuzzy_match(query: &str, text: &str) -> Option<FuzzyMatch> {
+    let mut best = fuzzy_match_without_transliteration(query, text);
+    if best
+        .as_ref()
+        .is_some_and(|best| best.score == PERFECT_MATCH_SCORE)
+    {
+        return best;
+    }
+
+    if !contains_han(query) && contains_han(text) {
+        for key in transliteration_keys(text) {
+            let Some(key_match) = fuzzy_match_without_transliteration(query, &key.key) else {
+                continue;
+            };
+
+            // Map the ranges of the key back to the original text
+            let mut matched_indexes: Vec<usize> = key_match
+                .ranges
+                .into_iter()
+                .flatten()
+                .map(|index| key.char_indexes[index])
+                .collect();
+            matched_indexes.dedup();
+
+            // Only typing the original text is a perfect match
+            let score = key_match.score.min(MAX_IMPERFECT_MATCH_SCORE);
+            keep_better(&mut best, score, indexes_to_ranges(&matched_indexes));
+        }
+    }
+
+    best
+}
+
+fn fuzzy_match_without_transliteration(query: &str, text: &str) -> Option<FuzzyMatch> {
+    let query = lowercase_chars(query);
+    let original_text: Vec<char> = text.chars().collect();
+    let text = lowercase_chars(text);
+
+    if query.is_empty() || text.is_empty() {
+        return None;
+    }
+
+    if query == text {
+        return Some(FuzzyMatch {
+            score: PERFECT_MATCH_SCORE,
+            ranges: std::iter::once(0..text.len()).collect(),
+        });
+    }
+
+    let mut best: Option<FuzzyMatch> = None;
+
+    let ratio = query.len() as f64 / text.len() as f64;
+    if let Some(start) = find(&text, &query) {
+        let score = if start == 0 {
+            // Prefix match: base 0.5, bonus up to 0.4 for how much of `text`
+            // is covered by `query`. Max 0.9.
+            0.5 + 0.4 * ratio
+        } else {
+            // Substring match: base 0.3, bonus up to 0.3. Max 0.6.
+            0.3 + 0.3 * ratio
+        };
+        let range = start..start + query.len();
+        keep_better(&mut best, score, std::iter::once(range).collect());
+    }
+
+    if let Some((normalized_score, ranges)) = subsequence_match(&query, &text, &original_text) {
+        let score = SUBSEQUENCE_MIN_SCORE
+            + (SUBSEQUENCE_MAX_SCORE - SUBSEQUENCE_MIN_SCORE) * normalized_score;
+        keep_better(&mut best, score, ranges);
+    }
+
+    if best.is_none()
+        && let Some((normalized_score, range)) = typo_tolerant_match(&query, &text)
+    {
+        let score = TYPO_MIN_SCORE + (TYPO_MAX_SCORE - TYPO_MIN_SCORE) * normalized_score;
+        keep_better(&mut best, score, std::iter::once(range).collect());
+    }
+
+    if best
+        .as_ref()
+        .is_none_or(|best| best.score < ORDER_INDEPENDENT_SCORE)
+        && let Some(ranges) = order_independent_match(&query, &text)
+    {
+        keep_better(&mut best, ORDER_INDEPENDENT_SCORE, ranges);
+    }
+
+    best.map(|mut fuzzy_match| {
+        fuzzy_match.score = fuzzy_match.score.min(MAX_IMPERFECT_MATCH_SCORE);
+        fuzzy_match
+    })
+}
+
+fn keep_better(best: &mut Option<FuzzyMatch>, score: f64, ranges: Vec<Range<usize>>) {
+    if best.as_ref().is_none_or(|best| score > best.score) {
+        *best = Some(FuzzyMatch { score, ranges });
+    }
+}
+
+/// Shorthand for `fuzzy_match(query, text).map(|m| m.score)`.
+pub(crate) fn fuzzy_score(query: &str, text: &str) -> Option<f64> {
+    fuzzy_match(query, text).map(|fuzzy_match| fuzzy_match.score)
+}
+
+/// Lowercase `str` char by char, so that the char indices of the result map
+/// 1:1 to the original string.
+fn lowercase_chars(str: &str) -> Vec<char> {
+    str.chars()
+        .map(|c| c.to_lowercase().next().unwrap_or(c))
+        .collect()
+}
+
+/// Return the index of the first occurrence of `needle` in `haystack`.
+fn find(haystack: &[char], needle: &[char]) -> Option<usize> {
+    if needle.len() > haystack.len() {
+        return None;
+    }
+
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+/// Is `text[index]` the start of a word?
+fn is_word_boundary(original_text: &[char], index: usize) -> bool {
+    if index == 0 {
+        return true;
+    }
+
+    let prev = original_text[index - 1];
+    let current = original_text[index];
+
+    !prev.is_alphanumeric()
+        // camelCase
+        || (prev.is_lowercase() && current.is_uppercase())
+        // "mp3", "Win11"
+        || (prev.is_alphabetic() && current.is_numeric())
+}
+
+/// Find the best way to match `query` as a subsequence of `text`.
+///
+/// Return the score normalized to [0.0, 1.0], and the matched ranges.
+fn subsequence_match(
+    query: &[char],
+    text: &[char],
+    original_text: &[char],
+) -> Option<(f64, Vec<Range<usize>>)> {
+    let m = query.len();
+    let n = text.len();
+    if m > n || n > SUBSEQUENCE_MAX_TEXT_LEN {
+        return None;
+    }
+
+    let char_score = |j: usize| -> f64 {
+        if is_word_boundary(original_text, j) {
+            MATCH_SCORE + BOUNDARY_BONUS
+        } else {
+            MATCH_SCORE
+        }
+    };
+
+    // scores[i][j]: best score of matching `query[..=i]` with `query[i]`
+    // matched to `text[j]`.
+    let mut scores = vec![vec![f64::NEG_INFINITY; n]; m];
+    // parents[i][j]: where `query[i - 1]` is matched in the best match of
+    // scores[i][j].
+    let mut parents = vec![vec![usize::MAX; n]; m];
+
+    for (j, c) in text.iter().enumerate() {
+        if *c == query[0] {
+            scores[0][j] = char_score(j);
+        }
+    }
+
+    for i in 1..m {
+        // max(scores[i - 1][k] + GAP_PENALTY * k) for k < j - 1, and its k
+        let mut best_gapped: Option<(f64, usize)> = None;
+
+        for j in i..n {
+            if j >= 2 {
+                let k = j - 2;
+                if scores[i - 1][k].is_finite() {
+                    let candidate = scores[i - 1][k] + GAP_PENALTY * k as f64;
+                    if best_gapped.is_none_or(|(best, _)| candidate > best) {
+                        best_gapped = Some((candidate, k));
+                    }
+                }
+            }
+
+            if text[j] != query[i] {
+                continue;
+            }
+
+            let consecutive = scores[i - 1][j - 1] + CONSECUTIVE_BONUS;
+            // Gap penalty: GAP_PENALTY * (j - k - 1)
+            let gapped = best_gapped
+                .map(|(best, k)| (best - GAP_PENALTY * (j - 1) as f64, k))
+                .unwrap_or((f64::NEG_INFINITY, usize::MAX));
+
+            let (score, parent) = if consecutive >= gapped.0 {
+                (consecutive, j - 1)
+            } else {
+                gapped
+            };
+            if score.is_finite() {
+                scores[i][j] = score + char_score(j);
+                parents[i][j] = parent;
+            }
+        }
+    }
+
+    let (last_matched, best_score) = scores[m - 1]
+        .iter()
+        .copied()
+        .enumerate()
+        .filter(|(_, score)| score.is_finite())
+        .max_by(|(_, a), (_, b)| a.total_cmp(b))?;
+
+    let mut matched_indexes = vec![last_matched];
+    let mut j = last_matched;
+    for i in (1..m).rev() {
+        j = parents[i][j];
+        matched_indexes.push(j);
+    }
+    matched_indexes.reverse();
+
+    let max_score = m as f64 * (MATCH_SCORE + BOUNDARY_BONUS) + (m - 1) as f64 * CONSECUTIVE_BONUS;
+    let normalized_score = (best_score / max_score).clamp(0.0, 1.0);
+
+    Some((normalized_score, indexes_to_ranges(&matched_indexes)))
+}
+
+/// Merge sorted char indexes into ranges.
+fn indexes_to_ranges(indexes: &[usize]) -> Vec<Range<usize>> {
+    let mut ranges: Vec<Range<usize>> = Vec::new();
+    for &index in indexes {
+        match ranges.last_mut() {
+            Some(last) if last.end == index => last.end += 1,
+            _ => ranges.push(index..index + 1),
+        }
+    }
+
+    ranges
+}
+
+/// Match if all the chars of `query` exist in `text`, regardless of their
+/// order.
+///
+/// Return the matched ranges, the first occurrence of every query char.
+fn order_independent_match(query: &[char], text: &[char]) -> Option<Vec<Range<usize>>> {
+    let mut matched_indexes = query
+        .iter()
+        .map(|query_char| text.iter().position(|c| c == query_char))
+        .collect::<Option<Vec<usize>>>()?;
+    matched_indexes.sort_unstable();
+    matched_indexes.dedup();
+
+    Some(indexes_to_ranges(&matched_indexes))
+}
+
+/// The maximum number of typos allowed in a query of length `query_len`.
+fn max_typos(query_len: usize) -> usize {
+    match query_len {
+        0..=3 => 0,
+        4..=7 => 1,
+        _ => 2,
+    }
+}
+
+/// Match `query` against the words of `text`, and the beginning of `text`,
+/// allowing `max_typos()` typos.
+///
+/// Return the score normalized to [0.0, 1.0] and the matched range.
+fn typo_tolerant_match(query: &[char], text: &[char]) -> Option<(f64, Range<usize>)> {
+    let max_typos = max_typos(query.len());
+    if max_typos == 0 {
+        return None;
+    }
+
+    // Candidate ranges: every word, and the beginning of the text, which
+    // allows multi-word queries.
+    let mut candidates: Vec<Range<usize>> = Vec::new();
+    let mut word_start = None;
+    for (index, c) in text.iter().enumerate() {
+        match (c.is_alphanumeric(), word_start) {
+            (true, None) => word_start = Some(index),
+            (false, Some(start)) => {
+                candidates.push(start..index);
+                word_start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(start) = word_start {
+        candidates.push(start..text.len());
+    }
+    candidates.push(0..text.len());
+
+    let mut best: Option<(usize, Range<usize>)> = None;
+    for candidate in candidates {
+        // Users may have only typed the beginning of the word, compare with
+        // the prefix of the same length as well.
+        let prefix_end = candidate.end.min(candidate.start + query.len());
+        for range in [candidate.clone(), candidate.start..prefix_end] {
+            let distance = edit_distance(query, &text[range.clone()]);
+            if distance <= max_typos && best.as_ref().is_none_or(|(best, _)| distance < *best) {
+                best = Some((distance, range));
+            }
+        }
+    }
+
+    best.map(|(distance, range)| {
+        let normalized_score = 1.0 - distance as f64 / (max_typos + 1) as f64;
+        (normalized_score, range)
+    })
+}
+
+/// Optimal string alignment distance, i.e., Levenshtein distance plus
+/// transpositions of 2 adjacent chars.
+fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let mut distances = vec![vec![0_usize; b.len() + 1]; a.len() + 1];
+    for (i, row) in distances.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    distances[0] = (0..=b.len()).collect();
+
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut distance = (distances[i - 1][j] + 1)
+                .min(distances[i][j - 1] + 1)
+                .min(distances[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distance = distance.min(distances[i - 2][j - 2] + 1);
+            }
+            distances[i][j] = distance;
+        }
+    }
+
+    distances[a.len()][b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ranges(fuzzy_match: &FuzzyMatch) -> Vec<(usize, usize)> {
+        fuzzy_match
+            .ranges
+            .iter()
+            .map(|range| (range.start, range.end))
+            .collect()
+    }
+
+    // Helper function for approximate floating point comparison
+    fn approx_eq(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-10
+    }
+
+    #[test]
+    fn test_empty_strings() {
+        assert_eq!(fuzzy_score("", "text"), None);
+        assert_eq!(fuzzy_score("query", ""), None);
+        assert_eq!(fuzzy_score("", ""), None);
+    }
+
+    #[test]
+    fn test_perfect_match() {
+        assert_eq!(fuzzy_score("text", "text"), Some(1.0));
+        assert_eq!(fuzzy_score("a", "a"), Some(1.0));
+        assert_eq!(fuzzy_score("slack", "Slack"), Some(1.0));
+    }
+
+    #[test]
+    fn test_prefix_match() {
+        // For "te" and "text":
+        // score = 0.5 + 0.4 * (2/4) = 0.5 + 0.2 = 0.7
+        let score = fuzzy_score("te", "text").unwrap();
+        assert!(approx_eq(score, 0.7));
+
+        // For "tex" and "text":
+        // score = 0.5 + 0.4 * (3/4) = 0.5 + 0.3 = 0.8
+        let score = fuzzy_score("tex", "text").unwrap();
+        assert!(approx_eq(score, 0.8));
+    }
+
+    #[test]
+    fn test_substring_match() {
+        // For "ex" and "text":
+        // score = 0.3 + 0.3 * (2/4) = 0.3 + 0.15 = 0.45
+        let score = fuzzy_score("ex", "text").unwrap();
+        assert!(approx_eq(score, 0.45));
+
+        // Prefix should score higher than substring
+        assert!(fuzzy_score("te", "text").unwrap() > fuzzy_score("ex", "text").unwrap());
+
+        // "tac" is a substring of "contact"
+        let score = fuzzy_score("tac", "contact").unwrap();
+        assert!(approx_eq(0.3 + 0.3 * (3.0 / 7.0), score));
+        assert!(fuzzy_score("ac", "contact").is_some());
+    }
+
+    #[test]
+    fn test_character_presence() {
+        // Characters present but not in sequence
+        let fuzzy_match = fuzzy_match("tca", "contact").unwrap();
+        assert!(approx_eq(fuzzy_match.score, 0.15));
+        assert_eq!(ranges(&fuzzy_match), vec![(0, 1), (3, 5)]);
+
+        // Should not apply if some characters are missing
+        assert_eq!(fuzzy_score("tcx", "contact"), None);
+    }
+
+    #[test]
+    fn test_combined_scenarios() {
+        // Character presence fallback doesn't override higher scores
+        // "tex" is a prefix of "text" with score 0.8
+        let score = fuzzy_score("tex", "text").unwrap();
+        assert!(approx_eq(score, 0.8));
+
+        // "act" is a substring of "contact" with score > 0.15, so fallback won't apply
+        let expected_score = 0.3 + 0.3 * (3.0 / 7.0);
+        let actual_score = fuzzy_score("act", "contact").unwrap();
+        assert!(approx_eq(actual_score, expected_score));
+    }
+
+    #[test]
+    fn test_no_similarity() {
+        assert_eq!(fuzzy_score("xyz", "test"), None);
+        assert_eq!(fuzzy_score("xyz", "contact"), None);
+    }
+
+    #[test]
+    fn test_score_capping() {
+        // Use a long query that's a prefix of a slightly longer text
+        let long_text = "abcdefghijklmnopqrstuvwxyz";
+        let long_prefix = "abcdefghijklmnopqrstuvwxy"; // All but last letter
+
+        // Expected score would be 0.5 + 0.4 * (25/26) = 0.5 + 0.385 = 0.885
+        let expected_score = 0.5 + 0.4 * (25.0 / 26.0);
+        let actual_score = fuzzy_score(long_prefix, long_text).unwrap();
+        assert!(approx_eq(actual_score, expected_score));
+
+        // Verify that non-perfect matches are capped at 0.95
+        assert!(fuzzy_score("almost", "almost perfect").unwrap() <= 0.95);
+    }
+
+    #[test]
+    fn test_acronym() {
+        let fuzzy_match = fuzzy_match("vsc", "Visual Studio Code").unwrap();
+        assert_eq!(ranges(&fuzzy_match), vec![(0, 1), (7, 8), (14, 15)]);
+
+        // Matching word starts beats matching scattered chars
+        assert!(fuzzy_match.score > fuzzy_score("vsc", "visual basic").unwrap());
+    }
+
+    #[test]
+    fn test_camel_case() {
+        let fuzzy_match = fuzzy_match("gc", "GoogleChrome").unwrap();
+        assert_eq!(ranges(&fuzzy_match), vec![(0, 1), (6, 7)]);
+
+        assert!(fuzzy_match.score > fuzzy_score("gc", "Goodluck").unwrap());
+    }
+
+    #[test]
+    fn test_subsequence_prefers_consecutive_chars() {
+        let fuzzy_match = fuzzy_match("code", "Visual Studio Code").unwrap();
+        assert_eq!(ranges(&fuzzy_match), vec![(14, 18)]);
+    }
+
+    #[test]
+    fn test_typo_tolerance() {
+        // Transposition
+        let fuzzy_match = fuzzy_match("visaul", "Visual Studio Code").unwrap();
+        assert_eq!(ranges(&fuzzy_match), vec![(0, 6)]);
+        assert!(fuzzy_match.score < fuzzy_score("vsc", "Visual Studio Code").unwrap());
+
+        // Substitution in the 2nd word
+        assert!(fuzzy_score("stodio", "Visual Studio Code").is_some());
+
+        assert!(fuzzy_score("cdoe", "Code").is_some());
+
+        // Short queries allow no typos
+        assert_eq!(fuzzy_score("xde", "Code"), None);
+
+        // Too many typos
+        assert_eq!(fuzzy_score("vxsxal", "Visual"), None);
+    }
+
+    #[test]
+    fn test_ranges_are_char_indexes() {
+        let fuzzy_match = fuzzy_match("拍照", "快速拍照").unwrap();
+        assert_eq!(ranges(&fuzzy_match), vec![(2, 4)]);
+    }
+
+    #[test]
+    fn test_pinyin() {
+        let matched = fuzzy_match("weixin", "微信").unwrap();
+        assert_eq!(ranges(&matched), vec![(0, 2)]);
+        assert!(matched.score < 1.0);
+
+        let matched = fuzzy_match("wx", "微信").unwrap();
+        assert_eq!(ranges(&matched), vec![(0, 2)]);
+
+        let matched = fuzzy_match("wei", "微信").unwrap();
+        assert_eq!(ranges(&matched), vec![(0, 1)]);
+
+        // Typing the Chinese characters is still the best
+        assert_eq!(fuzzy_score("微信", "微信"), Some(1.0));
+        assert_eq!(fuzzy_score("xyz", "微信"), None);
+    }
+
+    #[test]
+    fn test_pinyin_polyphonic_characters() {
+        // 重: chong/zhong
+        assert!(fuzzy_score("cq", "重庆").is_some());
+        assert!(fuzzy_score("chongqing", "重庆").is_some());
+        assert!(fuzzy_score("zhongyao", "重要").is_some());
+
+        // 乐: yue/le
+        let fuzzy_match = fuzzy_match("yinyue", "QQ音乐").unwrap();
+        assert_eq!(ranges(&fuzzy_match), vec![(2, 4)]);
+        assert!(fuzzy_score("kuaile", "快乐").is_some());
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        let chars = |s: &str| s.chars().collect::<Vec<char>>();
+
+        assert_eq!(edit_distance(&chars("kitten"), &chars("sitting")), 3);
+        assert_eq!(edit_distance(&chars("ab"), &chars("ba")), 1);
+        assert_eq!(edit_distance(&chars(""), &chars("abc")), 3);
+    }
+}
@@ -0,0 +1,35 @@
+//! Health of the registered search sources.
+
+use crate::common::health::SourceHealth;
+use crate::common::register::SearchSourceRegistry;
+use crate::common::search::QuerySource;
+use futures::future::join_all;
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+
+#[derive(Debug, Serialize)]
+pub(crate) struct SearchSourceHealth {
+    source: QuerySource,
+    health: SourceHealth,
+}
+
+/// Report the health of every registered search source.
+#[tauri::command]
+pub(crate) async fn get_search_source_health(
+    tauri_app_handle: AppHandle,
+) -> Vec<SearchSourceHealth> {
+    let registry = tauri_app_handle.state::<SearchSourceRegistry>();
+    let search_sources = registry.get_sources().await;
+
+    let tauri_app_handle = &tauri_app_handle;
+    let health_futures = search_sources.iter().map(|search_source| async move {
+        SearchSourceHealth {
+            source: search_source.get_type(),
+            health: search_source.health(tauri_app_handle).await,
+        }
+    });
+
+    let mut health_of_sources = join_all(health_futures).await;
+    health_of_sources.sort_by(|a, b| a.source.id.cmp(&b.source.id));
+    health_of_sources
+}
@@ -1,22 +1,43 @@
+pub(crate) mod cursor;
+pub(crate) mod dedup;
+pub(crate) mod diagnostics;
+pub(crate) mod elasticsearch;
+pub(crate) mod frecency;
+pub(crate) mod fusion;
+pub(crate) mod fuzzy;
+pub(crate) mod health;
+pub(crate) mod prefix;
+pub(crate) mod preview;
+pub(crate) mod session;
+pub(crate) mod suggest;
+pub(crate) mod transliteration;
+
 use crate::common::error::{ReportErrorStyle, SearchError, report_error};
 use crate::common::register::SearchSourceRegistry;
 use crate::common::search::{
-    FailedRequest, MultiSourceQueryResponse, QueryHits, QuerySource, SearchQuery,
-    merge_aggregations,
+    FailedRequest, MultiSourceQueryResponse, QueryFusionStreamEvent, QueryFusionStreamPayload,
+    QueryHits, QuerySource, SearchQuery, merge_aggregations,
 };
 use crate::common::traits::SearchSource;
-use crate::extension::LOCAL_QUERY_SOURCE_TYPE;
 use crate::server::http_client::HttpRequestError;
 use crate::server::servers::logout_coco_server;
 use crate::server::servers::mark_server_as_offline;
-use crate::settings::get_local_query_source_weight;
+use crate::settings::{get_fusion_strategy, get_local_query_source_weight};
+use cursor::{PaginationCursor, search_range, skip_previous_pages, take_page};
+use dedup::count_duplicates;
+use diagnostics::{RankingDebug, source_diagnostics};
+use frecency::boost_hits_by_frecency;
 use function_name::named;
+use fusion::FusionContext;
 use futures::StreamExt;
 use futures::stream::FuturesUnordered;
+use prefix::parse_query_prefix;
 use reqwest::StatusCode;
+use session::QuerySessionGuard;
 use std::collections::HashMap;
 use std::sync::Arc;
-use tauri::{AppHandle, Manager};
+use std::time::Instant;
+use tauri::{AppHandle, Emitter, Manager};
 use tokio::time::{Duration, timeout};
 
 /// Available `query_strings`:
@@ -35,34 +56,262 @@ use tokio::time::{Duration, timeout};
 ///
 /// * Some query string that are exclusive to Coco server, see `convert_query_string()`
 ///   in `src-tauri/src/server/search.rs`
+///
+/// If "querysource" is not provided, "query" can start with a prefix that sets
+/// the above query strings, e.g., "app: slack", see `prefix.rs` for the syntax.
+/// An invalid prefix results in `SearchError::InvalidQuery`.
+///
+/// If `session_id` and `sequence` are provided, this search belongs to a query
+/// session, it cancels the in-flight search of the same session with a smaller
+/// sequence number, and gets cancelled by a later search.  A cancelled search
+/// returns `SearchError::Cancelled`. See `session.rs` for more info.
+///
+/// To get the next page, pass the `next_cursor` of the response as `cursor`,
+/// every query source then continues from where it left off, and `from` only
+/// applies to the query sources that are not recorded in the cursor. An
+/// invalid cursor results in `SearchError::InvalidQuery`. See `cursor.rs` for
+/// more info.
 #[named]
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn query_coco_fusion(
     tauri_app_handle: AppHandle,
     from: u64,
     size: u64,
     query_strings: HashMap<String, String>,
     query_timeout: u64,
+    session_id: Option<String>,
+    sequence: Option<u64>,
+    cursor: Option<String>,
 ) -> Result<MultiSourceQueryResponse, SearchError> {
-    if query_strings.contains_key("datasource") && !query_strings.contains_key("querysource") {
-        panic!("[querysource] has to be provided if [datasource] is set")
-    }
+    log::debug!(
+        "{}() invoked with parameters: from: [{}], size: [{}], query_strings: [{:?}], timeout: [{:?}], session: [{:?}], sequence: [{:?}], cursor: [{:?}]",
+        function_name!(),
+        from,
+        size,
+        query_strings,
+        query_timeout,
+        session_id,
+        sequence,
+        cursor
+    );
 
-    let opt_query_source_id = query_strings.get("querysource");
-    let search_sources = tauri_app_handle.state::<SearchSourceRegistry>();
-    let query_source_list = search_sources.get_sources().await;
-    let timeout_duration = Duration::from_millis(query_timeout);
-    let search_query = SearchQuery::new(from, size, query_strings.clone());
+    _query_coco_fusion(
+        tauri_app_handle,
+        from,
+        size,
+        query_strings,
+        query_timeout,
+        cursor,
+        session_id.zip(sequence),
+        None,
+        None,
+    )
+    .await
+}
+
+/// Name of the event emitted by `query_coco_fusion_stream()`.
+const QUERY_FUSION_STREAM_EVENT: &str = "query-coco-fusion-stream";
 
+/// Streaming version of `query_coco_fusion()`, it accepts the same parameters.
+///
+/// Rather than waiting for the slowest query source, every query source's
+/// hits are emitted to the frontend as soon as they arrive, as event
+/// `QUERY_FUSION_STREAM_EVENT` with a `QueryFusionStreamEvent` payload.  Once
+/// all the query sources have responded or timed out, the re-ranked result is
+/// emitted as the final event, which is also the return value.
+///
+/// `query_id` is generated by the frontend and echoed in every event so that
+/// events of an outdated query can be told apart.
+#[named]
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn query_coco_fusion_stream(
+    tauri_app_handle: AppHandle,
+    query_id: String,
+    from: u64,
+    size: u64,
+    query_strings: HashMap<String, String>,
+    query_timeout: u64,
+    session_id: Option<String>,
+    sequence: Option<u64>,
+    cursor: Option<String>,
+) -> Result<MultiSourceQueryResponse, SearchError> {
     log::debug!(
-        "{}() invoked with parameters: from: [{}], size: [{}], query_strings: [{:?}], timeout: [{:?}]",
+        "{}() invoked with parameters: query_id: [{}], from: [{}], size: [{}], query_strings: [{:?}], timeout: [{:?}], session: [{:?}], sequence: [{:?}], cursor: [{:?}]",
         function_name!(),
+        query_id,
         from,
         size,
         query_strings,
-        timeout_duration
+        query_timeout,
+        session_id,
+        sequence,
+        cursor
     );
 
+    let mut emitter = QueryFusionStreamEmitter::new(tauri_app_handle.clone(), query_id);
+    let response = _query_coco_fusion(
+        tauri_app_handle,
+        from,
+        size,
+        query_strings,
+        query_timeout,
+        cursor,
+        session_id.zip(sequence),
+        Some(&mut emitter),
+        None,
+    )
+    .await?;
+
+    emitter.emit(QueryFusionStreamPayload::Final {
+        response: response.clone(),
+    });
+
+    Ok(response)
+}
+
+/// Helper type to emit the events of a streaming query, it tracks the revision
+/// number.
+struct QueryFusionStreamEmitter {
+    tauri_app_handle: AppHandle,
+    query_id: String,
+    next_revision: u32,
+}
+
+impl QueryFusionStreamEmitter {
+    fn new(tauri_app_handle: AppHandle, query_id: String) -> Self {
+        Self {
+            tauri_app_handle,
+            query_id,
+            next_revision: 0,
+        }
+    }
+
+    fn emit(&mut self, payload: QueryFusionStreamPayload) {
+        let event = QueryFusionStreamEvent {
+            query_id: self.query_id.clone(),
+            revision: self.next_revision,
+            payload,
+        };
+        self.next_revision += 1;
+
+        if let Err(e) = self.tauri_app_handle.emit(QUERY_FUSION_STREAM_EVENT, event) {
+            log::error!(
+                "failed to emit event [{}] for query [{}], error [{}]",
+                QUERY_FUSION_STREAM_EVENT,
+                self.query_id,
+                e
+            );
+        }
+    }
+}
+
+/// Shared implementation of `query_coco_fusion()` and `query_coco_fusion_stream()`.
+///
+/// `opt_cursor` is the pagination cursor returned by the previous page.
+///
+/// If `opt_session` (session ID, sequence) is Some, the search will be aborted
+/// once a newer search of this session arrives.
+///
+/// If `opt_emitter` is Some, per-source results will be emitted through it.
+///
+/// If `opt_ranking_debug` is Some, the scores of every ranking stage will be
+/// recorded in it.
+#[allow(clippy::too_many_arguments)]
+async fn _query_coco_fusion(
+    tauri_app_handle: AppHandle,
+    from: u64,
+    size: u64,
+    query_strings: HashMap<String, String>,
+    query_timeout: u64,
+    opt_cursor: Option<String>,
+    opt_session: Option<(String, u64)>,
+    opt_emitter: Option<&mut QueryFusionStreamEmitter>,
+    opt_ranking_debug: Option<&mut RankingDebug>,
+) -> Result<MultiSourceQueryResponse, SearchError> {
+    let Some((session_id, sequence)) = opt_session else {
+        return query_coco_fusion_dispatch(
+            tauri_app_handle,
+            from,
+            size,
+            query_strings,
+            query_timeout,
+            opt_cursor,
+            opt_emitter,
+            opt_ranking_debug,
+        )
+        .await;
+    };
+
+    let Some(session_guard) = QuerySessionGuard::begin(session_id, sequence) else {
+        // A newer search of this session has arrived before us.
+        return Err(SearchError::Cancelled);
+    };
+
+    // Dropping the search future on cancellation drops all the pending
+    // `SearchSource::search()` futures, including the underlying HTTP
+    // requests, so nothing keeps running in the background.
+    tokio::select! {
+        _ = session_guard.cancelled() => {
+            log::debug!("search [sequence {}] cancelled, a newer search arrived", sequence);
+            Err(SearchError::Cancelled)
+        }
+        res = query_coco_fusion_dispatch(
+            tauri_app_handle,
+            from,
+            size,
+            query_strings,
+            query_timeout,
+            opt_cursor,
+            opt_emitter,
+            opt_ranking_debug,
+        ) => res,
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn query_coco_fusion_dispatch(
+    tauri_app_handle: AppHandle,
+    from: u64,
+    size: u64,
+    mut query_strings: HashMap<String, String>,
+    query_timeout: u64,
+    opt_cursor: Option<String>,
+    opt_emitter: Option<&mut QueryFusionStreamEmitter>,
+    opt_ranking_debug: Option<&mut RankingDebug>,
+) -> Result<MultiSourceQueryResponse, SearchError> {
+    let search_sources = tauri_app_handle.state::<SearchSourceRegistry>();
+    let query_source_list = search_sources.get_sources().await;
+
+    if !query_strings.contains_key("querysource")
+        && let Some(query) = query_strings.get("query")
+    {
+        let query_sources: Vec<QuerySource> = query_source_list
+            .iter()
+            .map(|search_source| search_source.get_type())
+            .collect();
+        if let Some(scoped_query) = parse_query_prefix(query, &query_sources)? {
+            log::debug!("query prefix parsed: {:?}", scoped_query);
+            scoped_query.apply_to(&mut query_strings);
+        }
+    }
+
+    if query_strings.contains_key("datasource") && !query_strings.contains_key("querysource") {
+        return Err(SearchError::InvalidQuery {
+            reason: "[querysource] has to be provided if [datasource] is set".into(),
+        });
+    }
+
+    let cursor = match opt_cursor {
+        Some(ref cursor) => PaginationCursor::decode(cursor)?,
+        None => PaginationCursor::default(),
+    };
+
+    let opt_query_source_id = query_strings.get("querysource");
+    let timeout_duration = Duration::from_millis(query_timeout);
+    let search_query = SearchQuery::new(from, size, query_strings.clone());
+
     // Dispatch to different `query_coco_fusion_xxx()` functions.
     let mut res_response = if let Some(query_source_id) = opt_query_source_id {
         query_coco_fusion_single_query_source(
@@ -71,6 +320,8 @@ pub async fn query_coco_fusion(
             query_source_id.clone(),
             timeout_duration,
             search_query,
+            cursor,
+            opt_ranking_debug,
         )
         .await
     } else {
@@ -79,6 +330,9 @@ pub async fn query_coco_fusion(
             query_source_list,
             timeout_duration,
             search_query,
+            cursor,
+            opt_emitter,
+            opt_ranking_debug,
         )
         .await
     };
@@ -100,7 +354,9 @@ async fn query_coco_fusion_single_query_source(
     mut query_source_list: Vec<Arc<dyn SearchSource>>,
     id_of_query_source_to_query: String,
     timeout_duration: Duration,
-    search_query: SearchQuery,
+    mut search_query: SearchQuery,
+    mut cursor: PaginationCursor,
+    mut opt_ranking_debug: Option<&mut RankingDebug>,
 ) -> Result<MultiSourceQueryResponse, SearchError> {
     // If this query source ID is specified, we only query this query source.
     log::debug!(
@@ -127,18 +383,32 @@ async fn query_coco_fusion_single_query_source(
         // The application query source is not in the source list because the extension
         // was disabled and thus removed from the query sources, but the last
         // search is indeed invoked with parameter `querysource=application`.
-        return Ok(MultiSourceQueryResponse {
-            failed: Vec::new(),
-            hits: Vec::new(),
-            total_hits: 0,
-            aggregations: None,
-        });
+        return Ok(MultiSourceQueryResponse::default());
     };
 
+    let Some(offset) = cursor.offset(&id_of_query_source_to_query, search_query.from) else {
+        // No more hits
+        return Ok(MultiSourceQueryResponse::default());
+    };
+    let size = search_query.size;
     let query_source_trait_object = query_source_list.remove(query_source_trait_object_index);
+    let supports_pagination = query_source_trait_object.supports_pagination();
+    (search_query.from, search_query.size) = search_range(offset, size, supports_pagination);
+
+    let query_keyword = search_query
+        .query_strings
+        .get("query")
+        .cloned()
+        .unwrap_or_default();
     let query_source = query_source_trait_object.get_type();
     let search_fut = query_source_trait_object.search(tauri_app_handle.clone(), search_query);
+    let started_at = Instant::now();
     let timeout_result = timeout(timeout_duration, search_fut).await;
+    let diagnostics = vec![source_diagnostics(
+        query_source.clone(),
+        started_at.elapsed(),
+        &timeout_result,
+    )];
 
     let mut failed_requests: Vec<FailedRequest> = Vec::new();
     let mut hits = Vec::new();
@@ -152,12 +422,12 @@ async fn query_coco_fusion_single_query_source(
                 "searching query source [{}] timed out, skip this request",
                 query_source.id
             );
+            cursor.exhaust(&query_source.id);
         }
         Ok(query_result) => match query_result {
             Ok(response) => {
                 total_hits = response.total_hits;
                 aggregations = response.aggregations;
-
                 for (document, score) in response.hits {
                     log::debug!(
                         "document from query source [{}]: ID [{}], title [{:?}], score [{}]",
@@ -171,12 +441,29 @@ async fn query_coco_fusion_single_query_source(
                         source: Some(response.source.clone()),
                         score,
                         document,
+                        also_found_in: Vec::new(),
                     };
 
                     hits.push(query_hit);
                 }
+
+                // There is no rank fusion with only 1 query source, the fused
+                // scores are the raw scores.
+                if let Some(ref mut ranking_debug) = opt_ranking_debug {
+                    ranking_debug.record_raw_scores(&hits);
+                    ranking_debug.record_fused_scores(&hits);
+                }
+
+                // Boost before skipping the previous pages, so that a result
+                // that users frequently open makes it to the first page.
+                boost_hits_by_frecency(&tauri_app_handle, &mut hits, &query_keyword).await;
+                skip_previous_pages(&mut hits, offset, size, supports_pagination);
+                // There is no rank fusion, all the hits are on this page.
+                let n_hits = hits.len();
+                cursor.advance(&query_source.id, offset, size, n_hits, n_hits);
             }
             Err(search_error) => {
+                cursor.exhaust(&query_source.id);
                 query_coco_fusion_handle_failed_request(
                     tauri_app_handle.clone(),
                     &mut failed_requests,
@@ -188,11 +475,17 @@ async fn query_coco_fusion_single_query_source(
         },
     }
 
+    if let Some(ranking_debug) = opt_ranking_debug {
+        ranking_debug.record_final_scores(&hits);
+    }
+
     Ok(MultiSourceQueryResponse {
         failed: failed_requests,
         hits,
         total_hits,
         aggregations,
+        diagnostics,
+        next_cursor: cursor.next_page([id_of_query_source_to_query.as_str()]),
     })
 }
 
@@ -201,6 +494,9 @@ async fn query_coco_fusion_multi_query_sources(
     query_source_trait_object_list: Vec<Arc<dyn SearchSource>>,
     timeout_duration: Duration,
     search_query: SearchQuery,
+    mut cursor: PaginationCursor,
+    mut opt_emitter: Option<&mut QueryFusionStreamEmitter>,
+    mut opt_ranking_debug: Option<&mut RankingDebug>,
 ) -> Result<MultiSourceQueryResponse, SearchError> {
     log::debug!(
         "will query query sources {:?}",
@@ -218,22 +514,37 @@ async fn query_coco_fusion_multi_query_sources(
     let size = search_query.size;
 
     let mut futures = FuturesUnordered::new();
+    // Query source ID => offset it is searched from
+    let mut offsets: HashMap<String, u64> = HashMap::new();
 
     for query_source_trait_object in query_source_trait_object_list {
         let query_source = query_source_trait_object.get_type().clone();
+        let Some(offset) = cursor.offset(&query_source.id, search_query.from) else {
+            // No more hits
+            continue;
+        };
+        offsets.insert(query_source.id.clone(), offset);
+        let supports_pagination = query_source_trait_object.supports_pagination();
         let tauri_app_handle_clone = tauri_app_handle.clone();
-        let search_query_clone = search_query.clone();
+        let mut search_query_clone = search_query.clone();
+        (search_query_clone.from, search_query_clone.size) =
+            search_range(offset, size, supports_pagination);
 
         futures.push(async move {
+            let started_at = Instant::now();
+            let timeout_result = timeout(timeout_duration, async {
+                query_source_trait_object
+                    .search(tauri_app_handle_clone, search_query_clone)
+                    .await
+            })
+            .await;
+
             (
                 // Store `query_source` as part of future for debugging purposes.
                 query_source,
-                timeout(timeout_duration, async {
-                    query_source_trait_object
-                        .search(tauri_app_handle_clone, search_query_clone)
-                        .await
-                })
-                .await,
+                supports_pagination,
+                started_at.elapsed(),
+                timeout_result,
             )
         });
     }
@@ -242,8 +553,19 @@ async fn query_coco_fusion_multi_query_sources(
     let mut failed_requests = Vec::new();
     let mut all_hits_grouped_by_query_source: HashMap<QuerySource, Vec<QueryHits>> = HashMap::new();
     let mut aggregations = None;
+    let mut diagnostics = Vec::new();
+    // Query source ID => number of hits it returned
+    let mut n_hits_by_query_source: HashMap<String, usize> = HashMap::new();
+
+    while let Some((query_source, supports_pagination, elapsed, timeout_result)) =
+        futures.next().await
+    {
+        diagnostics.push(source_diagnostics(
+            query_source.clone(),
+            elapsed,
+            &timeout_result,
+        ));
 
-    while let Some((query_source, timeout_result)) = futures.next().await {
         match timeout_result {
             // Ignore the `_timeout` variable as it won't provide any useful debugging information.
             Err(_timeout) => {
@@ -251,14 +573,23 @@ async fn query_coco_fusion_multi_query_sources(
                     "searching query source [{}] timed out, skip this request",
                     query_source.id
                 );
+                cursor.exhaust(&query_source.id);
             }
             Ok(query_result) => match query_result {
-                Ok(response) => {
+                Ok(mut response) => {
+                    skip_previous_pages(
+                        &mut response.hits,
+                        offsets[&query_source.id],
+                        size,
+                        supports_pagination,
+                    );
                     total_hits += response.total_hits;
+                    n_hits_by_query_source.insert(query_source.id.clone(), response.hits.len());
                     if let Some(from) = response.aggregations {
                         merge_aggregations(&mut aggregations, from);
                     }
 
+                    let mut source_hits = Vec::with_capacity(response.hits.len());
                     for (document, score) in response.hits {
                         log::debug!(
                             "document from query source [{}]: ID [{}], title [{:?}], score [{}]",
@@ -272,15 +603,29 @@ async fn query_coco_fusion_multi_query_sources(
                             source: Some(response.source.clone()),
                             score,
                             document,
+                            also_found_in: Vec::new(),
                         };
 
+                        source_hits.push(query_hit);
+                    }
+
+                    if let Some(ref mut emitter) = opt_emitter {
+                        emitter.emit(QueryFusionStreamPayload::SourceHits {
+                            source: query_source.clone(),
+                            hits: source_hits.clone(),
+                            total_hits: response.total_hits,
+                        });
+                    }
+
+                    if !source_hits.is_empty() {
                         all_hits_grouped_by_query_source
                             .entry(query_source.clone())
                             .or_insert_with(Vec::new)
-                            .push(query_hit);
+                            .extend(source_hits);
                     }
                 }
                 Err(search_error) => {
+                    cursor.exhaust(&query_source.id);
                     query_coco_fusion_handle_failed_request(
                         tauri_app_handle.clone(),
                         &mut failed_requests,
@@ -288,148 +633,90 @@ async fn query_coco_fusion_multi_query_sources(
                         search_error,
                     )
                     .await;
+
+                    if let Some(ref mut emitter) = opt_emitter {
+                        let failed = failed_requests
+                            .last()
+                            .expect("query_coco_fusion_handle_failed_request() should push a failed request")
+                            .clone();
+                        emitter.emit(QueryFusionStreamPayload::SourceFailed { failed });
+                    }
                 }
             },
         }
     }
 
-    let n_sources = all_hits_grouped_by_query_source.len();
+    if all_hits_grouped_by_query_source.is_empty() {
+        for (query_source_id, n_hits) in n_hits_by_query_source {
+            cursor.advance(&query_source_id, offsets[&query_source_id], size, n_hits, 0);
+        }
 
-    if n_sources == 0 {
         return Ok(MultiSourceQueryResponse {
-            failed: Vec::new(),
-            hits: Vec::new(),
-            total_hits: 0,
-            aggregations: None,
+            next_cursor: cursor.next_page(offsets.keys().map(String::as_str)),
+            diagnostics,
+            ..Default::default()
         });
     }
 
-    /*
-     * Sort hits within each source by score (descending) in case data sources
-     * do not sort them
-     */
-    for hits in all_hits_grouped_by_query_source.values_mut() {
-        hits.sort_by(|a, b| {
-            b.score
-                .partial_cmp(&a.score)
-                .unwrap_or(std::cmp::Ordering::Greater)
-        });
-    }
-
-    /*
-     * Collect hits evenly across sources, to ensure:
-     *
-     * 1. All sources have hits returned
-     * 2. Query sources with many hits won't dominate
-     */
-    let mut final_hits_grouped_by_query_source: HashMap<QuerySource, Vec<QueryHits>> =
-        HashMap::new();
-    let mut pruned: HashMap<&QuerySource, &[QueryHits]> = HashMap::new();
-
-    // Include at least 2 hits from each query source
-    let max_hits_per_source = (size as usize / n_sources).max(2);
-    for (query_source, hits) in all_hits_grouped_by_query_source.iter() {
-        let hits_taken = if hits.len() > max_hits_per_source {
-            pruned.insert(&query_source, &hits[max_hits_per_source..]);
-            hits[0..max_hits_per_source].to_vec()
-        } else {
-            hits.clone()
-        };
-
-        final_hits_grouped_by_query_source.insert(query_source.clone(), hits_taken);
+    if let Some(ref mut ranking_debug) = opt_ranking_debug {
+        ranking_debug.record_raw_scores(all_hits_grouped_by_query_source.values().flatten());
     }
 
-    let final_hits_len = final_hits_grouped_by_query_source
-        .iter()
-        .fold(0, |acc: usize, (_source, hits)| acc + hits.len());
-    let pruned_len = pruned
-        .iter()
-        .fold(0, |acc: usize, (_source, hits)| acc + hits.len());
-
     /*
-     * If we still need more hits, take the highest-scoring from `pruned`
-     *
-     * `pruned` contains sorted arrays, we scan it in a way similar to
-     * how n-way-merge-sort extracts the element with the greatest value.
+     * Merge the hits from all the query sources with the fusion strategy
+     * chosen in settings.
      */
-    if final_hits_len < size as usize {
-        let n_need = size as usize - final_hits_len;
-        let n_have = pruned_len;
-        let n_take = n_have.min(n_need);
-
-        for _ in 0..n_take {
-            let mut highest_score_hit: Option<(&QuerySource, &QueryHits)> = None;
-            for (source, sorted_hits) in pruned.iter_mut() {
-                if sorted_hits.is_empty() {
-                    continue;
-                }
-
-                let hit = &sorted_hits[0];
-
-                let have_higher_score_hit = match highest_score_hit {
-                    Some((_, current_highest_score_hit)) => {
-                        hit.score > current_highest_score_hit.score
-                    }
-                    None => true,
-                };
-
-                if have_higher_score_hit {
-                    highest_score_hit = Some((*source, hit));
-
-                    // Advance sorted_hits by 1 element, if have
-                    if sorted_hits.len() == 1 {
-                        *sorted_hits = &[];
-                    } else {
-                        *sorted_hits = &sorted_hits[1..];
-                    }
-                }
-            }
-
-            let (source, hit) = highest_score_hit.expect("`pruned` should contain at least `n_take` elements so `highest_score_hit` should be set");
-
-            final_hits_grouped_by_query_source
-                .get_mut(source)
-                .expect("all the source_ids stored in `pruned` come from `final_hits_grouped_by_query_source`, so it should exist")
-                .push(hit.clone());
-        }
+    let n_duplicates = count_duplicates(all_hits_grouped_by_query_source.values().flatten());
+    let fusion_context = FusionContext {
+        query: &query_keyword,
+        // Duplicates will be merged, fuse more hits so that the page won't be
+        // short.
+        size: size as usize + n_duplicates,
+        local_query_source_weight: get_local_query_source_weight(tauri_app_handle.clone()),
+    };
+    let fusion_strategy = get_fusion_strategy(tauri_app_handle.clone());
+    log::debug!("fusing hits with strategy [{:?}]", fusion_strategy);
+    let hits_by_query_source = all_hits_grouped_by_query_source.clone();
+    let mut fused_hits = fusion_strategy
+        .strategy()
+        .fuse(all_hits_grouped_by_query_source, &fusion_context);
+    if let Some(ref mut ranking_debug) = opt_ranking_debug {
+        ranking_debug.record_fused_scores(&fused_hits);
     }
 
     /*
-     * Re-rank (re-score) the final hits
+     * Boost the results that users frequently open, before taking the page so
+     * that they can make it to this page.
      */
-    if n_sources > 1 {
-        boosted_levenshtein_rerank(&query_keyword, &mut final_hits_grouped_by_query_source);
-    }
+    boost_hits_by_frecency(&tauri_app_handle, &mut fused_hits, &query_keyword).await;
 
     /*
-     * Apply settings "local search results weight" to the scores
+     * Every query source should continue from its last hit on this page, see
+     * `cursor.rs`.
+     *
+     * The same document can be returned by multiple query sources, they are
+     * merged while taking the page.
      */
-    let local_query_source_weight: f64 = get_local_query_source_weight(tauri_app_handle);
-    // Scores remain unchanged if it is 1.0
-    if local_query_source_weight != 1.0 {
-        for (query_source, hits) in final_hits_grouped_by_query_source.iter_mut() {
-            if query_source.r#type == LOCAL_QUERY_SOURCE_TYPE {
-                hits.iter_mut()
-                    .for_each(|hit| hit.score = hit.score * local_query_source_weight);
-            }
-        }
+    let (final_hits, n_consumed_by_query_source) =
+        take_page(fused_hits, &hits_by_query_source, size as usize);
+    for (query_source_id, n_hits) in n_hits_by_query_source {
+        let n_consumed = n_consumed_by_query_source
+            .get(&query_source_id)
+            .copied()
+            .unwrap_or(0);
+        cursor.advance(
+            &query_source_id,
+            offsets[&query_source_id],
+            size,
+            n_hits,
+            n_consumed,
+        );
     }
 
-    let mut final_hits = Vec::new();
-    for (_source, hits) in final_hits_grouped_by_query_source {
-        final_hits.extend(hits);
+    if let Some(ranking_debug) = opt_ranking_debug {
+        ranking_debug.record_final_scores(&final_hits);
     }
 
-    // **Sort final hits by score descending**
-    final_hits.sort_by(|a, b| {
-        b.score
-            .partial_cmp(&a.score)
-            .unwrap_or(std::cmp::Ordering::Equal)
-    });
-
-    // Truncate `final_hits` in case it contains more than `size` hits
-    final_hits.truncate(size as usize);
-
     if final_hits.len() < 5 {
         //TODO: Add a recommendation system to suggest more sources
         log::info!(
@@ -444,91 +731,11 @@ async fn query_coco_fusion_multi_query_sources(
         hits: final_hits,
         total_hits,
         aggregations,
+        diagnostics,
+        next_cursor: cursor.next_page(offsets.keys().map(String::as_str)),
     })
 }
 
-use std::collections::HashSet;
-use strsim::levenshtein;
-
-fn boosted_levenshtein_rerank(
-    query: &str,
-    final_hits_grouped_by_query_source: &mut HashMap<QuerySource, Vec<QueryHits>>,
-) {
-    let query_lower = query.to_lowercase();
-
-    for (source, hits) in final_hits_grouped_by_query_source.iter_mut() {
-        // Skip special sources like calculator
-        if source.id == crate::extension::built_in::calculator::DATA_SOURCE_ID {
-            continue;
-        }
-
-        for hit in hits.iter_mut() {
-            let document_title = hit.document.title.as_deref().unwrap_or("");
-            let document_title_lowercase = document_title.to_lowercase();
-
-            let new_score = {
-                let mut score = 0.0;
-
-                // --- Exact or substring boost ---
-                if document_title.contains(query) {
-                    score += 0.4;
-                } else if document_title_lowercase.contains(&query_lower) {
-                    score += 0.2;
-                }
-
-                // --- Levenshtein distance (character similarity) ---
-                let dist = levenshtein(&query_lower, &document_title_lowercase);
-                let max_len = query_lower.len().max(document_title.len());
-                let levenshtein_score = if max_len > 0 {
-                    (1.0 - (dist as f64 / max_len as f64)) as f32
-                } else {
-                    0.0
-                };
-
-                // --- Jaccard similarity (token overlap) ---
-                let jaccard_score = jaccard_similarity(&query_lower, &document_title_lowercase);
-
-                // --- Combine scores (weights adjustable) ---
-                // Levenshtein emphasizes surface similarity
-                // Jaccard emphasizes term overlap (semantic hint)
-                let hybrid_score = 0.7 * levenshtein_score + 0.3 * jaccard_score;
-
-                // --- Apply hybrid score ---
-                score += hybrid_score;
-
-                // --- Limit score range ---
-                score.min(1.0) as f64
-            };
-
-            hit.score = new_score;
-        }
-    }
-}
-
-/// Compute token-based Jaccard similarity
-fn jaccard_similarity(a: &str, b: &str) -> f32 {
-    let a_tokens: HashSet<_> = tokenize(a).into_iter().collect();
-    let b_tokens: HashSet<_> = tokenize(b).into_iter().collect();
-
-    if a_tokens.is_empty() || b_tokens.is_empty() {
-        return 0.0;
-    }
-
-    let intersection = a_tokens.intersection(&b_tokens).count() as f32;
-    let union = a_tokens.union(&b_tokens).count() as f32;
-
-    intersection / union
-}
-
-/// Basic tokenizer (case-insensitive, alphanumeric words only)
-fn tokenize(text: &str) -> Vec<String> {
-    text.to_lowercase()
-        .split(|c: char| !c.is_alphanumeric())
-        .filter(|s| !s.is_empty())
-        .map(|s| s.to_string())
-        .collect()
-}
-
 /// Helper function to handle a failed request.
 ///
 /// Extracted as a function because `query_coco_fusion_single_query_source()` and
@@ -584,3 +791,38 @@ async fn query_coco_fusion_handle_failed_request(
         reason: None,
     });
 }
+
+/// Fixtures shared by the tests of the submodules.
+#[cfg(test)]
+pub(crate) mod test_fixtures {
+    use crate::common::document::Document;
+    use crate::common::search::{QueryHits, QuerySource};
+
+    /// A local query source.
+    pub(crate) fn source(id: &str) -> QuerySource {
+        QuerySource {
+            r#type: "local".into(),
+            id: id.into(),
+            name: id.into(),
+        }
+    }
+
+    /// A hit of document `doc_id` returned by `source`.
+    pub(crate) fn hit_of(source: &QuerySource, doc_id: &str, score: f64) -> QueryHits {
+        QueryHits {
+            source: Some(source.clone()),
+            score,
+            document: Document {
+                id: doc_id.into(),
+                ..Default::default()
+            },
+            also_found_in: Vec::new(),
+        }
+    }
+
+    /// A hit of document `doc_id` returned by the local query source
+    /// `source_id`.
+    pub(crate) fn hit(source_id: &str, doc_id: &str, score: f64) -> QueryHits {
+        hit_of(&source(source_id), doc_id, score)
+    }
+}
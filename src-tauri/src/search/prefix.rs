@@ -0,0 +1,385 @@
+//! Query prefix syntax.
+//!
+//! Users can scope a search to a specific query source by prefixing the query:
+//!
+//! * `app: slack`: search applications
+//! * `file: report.pdf`: search local files
+//! * `ext: translate`: search extensions, `ext:<extension ID> term` searches
+//!   only the extension with that ID
+//! * `@<server> term`: search the Coco server whose name or ID is `<server>`
+//!   (whitespace in the name omitted), `@<server>/<datasource> term` further
+//!   limits the search to a datasource of this server.  If `<server>` is not
+//!   an enabled Coco server, the query is not scoped
+//!
+//! The prefix is translated to the `querysource`, `datasource` and
+//! `main_extension_id` query strings that `query_coco_fusion()` understands.
+
+use crate::common::error::SearchError;
+use crate::common::register::SearchSourceRegistry;
+use crate::common::search::QuerySource;
+use crate::extension::built_in::application::QUERYSOURCE_ID_DATASOURCE_ID_DATASOURCE_NAME as APPLICATION_QUERY_SOURCE_ID;
+use crate::extension::built_in::file_search::EXTENSION_ID as FILE_SEARCH_QUERY_SOURCE_ID;
+use crate::extension::third_party::THIRD_PARTY_EXTENSIONS_QUERY_SOURCE_ID;
+use crate::server::datasource::get_datasources_from_cache;
+use crate::server::search::COCO_SERVERS;
+use serde::Serialize;
+use std::collections::HashMap;
+use tauri::{AppHandle, Manager};
+
+/// Keyword prefixes and the IDs of the query sources they route to.
+const KEYWORD_PREFIXES: &[(&str, QueryPrefixKind, &str)] = &[
+    (
+        "app:",
+        QueryPrefixKind::Application,
+        APPLICATION_QUERY_SOURCE_ID,
+    ),
+    ("file:", QueryPrefixKind::File, FILE_SEARCH_QUERY_SOURCE_ID),
+    (
+        "ext:",
+        QueryPrefixKind::Extension,
+        THIRD_PARTY_EXTENSIONS_QUERY_SOURCE_ID,
+    ),
+];
+
+const SERVER_PREFIX: char = '@';
+const SERVER_DATASOURCE_SEPARATOR: char = '/';
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum QueryPrefixKind {
+    Application,
+    File,
+    Extension,
+    CocoServer,
+}
+
+/// A query prefix that is available to users, returned by `get_query_prefixes()`.
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct QueryPrefix {
+    /// What users type, e.g., "app:" or "@MyServer".
+    prefix: String,
+    kind: QueryPrefixKind,
+    /// The query source this prefix routes to.
+    query_source: QuerySource,
+}
+
+/// A query with its prefix parsed.
+#[derive(Debug, PartialEq)]
+pub(crate) struct ScopedQuery {
+    query_source_id: String,
+    datasource: Option<String>,
+    main_extension_id: Option<String>,
+    /// The query with the prefix stripped.
+    query: String,
+}
+
+impl ScopedQuery {
+    /// Write this query to `query_strings`.
+    pub(crate) fn apply_to(self, query_strings: &mut HashMap<String, String>) {
+        query_strings.insert("query".into(), self.query);
+
+        if let Some(datasource) = self.datasource {
+            let datasource = resolve_datasource_id(&self.query_source_id, datasource);
+            query_strings.insert("datasource".into(), datasource);
+        }
+        if let Some(main_extension_id) = self.main_extension_id {
+            query_strings.insert("main_extension_id".into(), main_extension_id);
+        }
+        query_strings.insert("querysource".into(), self.query_source_id);
+    }
+}
+
+/// Users can refer to a datasource by either name or ID, translate it to ID if
+/// the datasources of this server have been cached.
+fn resolve_datasource_id(server_id: &str, name_or_id: String) -> String {
+    let Some(datasources) = get_datasources_from_cache(server_id) else {
+        return name_or_id;
+    };
+
+    if datasources.contains_key(&name_or_id) {
+        return name_or_id;
+    }
+
+    datasources
+        .values()
+        .find(|datasource| {
+            datasource
+                .name
+                .as_deref()
+                .is_some_and(|name| name.eq_ignore_ascii_case(&name_or_id))
+        })
+        .map(|datasource| datasource.id.clone())
+        .unwrap_or(name_or_id)
+}
+
+/// Parse the prefix of `query`.
+///
+/// Return `Ok(None)` if `query` has no prefix, or its `@` prefix does not name
+/// a Coco server in `query_sources`, and an error if the keyword prefix refers
+/// to a query source that is not in `query_sources`.
+pub(crate) fn parse_query_prefix(
+    query: &str,
+    query_sources: &[QuerySource],
+) -> Result<Option<ScopedQuery>, SearchError> {
+    let query = query.trim_start();
+
+    if let Some(after_prefix) = query.strip_prefix(SERVER_PREFIX) {
+        return parse_server_prefix(after_prefix, query_sources);
+    }
+
+    for (keyword, kind, query_source_id) in KEYWORD_PREFIXES {
+        let Some(after_prefix) = strip_prefix_ignore_ascii_case(query, keyword) else {
+            continue;
+        };
+
+        if !query_sources.iter().any(|qs| qs.id == *query_source_id) {
+            return Err(SearchError::InvalidQuery {
+                reason: format!(
+                    "prefix [{}] is unavailable as query source [{}] is disabled",
+                    keyword, query_source_id
+                ),
+            });
+        }
+
+        // `ext:<extension ID>`, no whitespace after the colon
+        let (main_extension_id, query) = if *kind == QueryPrefixKind::Extension
+            && after_prefix.starts_with(|c: char| !c.is_whitespace())
+        {
+            let (extension_id, rest) = split_first_word(after_prefix);
+            (Some(extension_id.to_string()), rest)
+        } else {
+            (None, after_prefix.trim())
+        };
+
+        return Ok(Some(ScopedQuery {
+            query_source_id: query_source_id.to_string(),
+            datasource: None,
+            main_extension_id,
+            query: query.to_string(),
+        }));
+    }
+
+    Ok(None)
+}
+
+fn parse_server_prefix(
+    after_prefix: &str,
+    query_sources: &[QuerySource],
+) -> Result<Option<ScopedQuery>, SearchError> {
+    let (server, query) = split_first_word(after_prefix);
+    // A bare "@", users are still typing.
+    if server.is_empty() {
+        return Ok(None);
+    }
+
+    let (server, datasource) = match server.split_once(SERVER_DATASOURCE_SEPARATOR) {
+        Some((server, datasource)) if !datasource.is_empty() => {
+            (server, Some(datasource.to_string()))
+        }
+        Some((server, _empty_datasource)) => (server, None),
+        None => (server, None),
+    };
+
+    let opt_query_source = query_sources.iter().find(|qs| {
+        qs.r#type == COCO_SERVERS
+            && (qs.id == server || name_without_whitespace(&qs.name).eq_ignore_ascii_case(server))
+    });
+    // Not a server prefix, e.g., "@mention" or an email address, search it
+    // as is.
+    let Some(query_source) = opt_query_source else {
+        return Ok(None);
+    };
+
+    Ok(Some(ScopedQuery {
+        query_source_id: query_source.id.clone(),
+        datasource,
+        main_extension_id: None,
+        query: query.to_string(),
+    }))
+}
+
+fn strip_prefix_ignore_ascii_case<'s>(str: &'s str, prefix: &str) -> Option<&'s str> {
+    let head = str.get(..prefix.len())?;
+    if head.eq_ignore_ascii_case(prefix) {
+        Some(&str[prefix.len()..])
+    } else {
+        None
+    }
+}
+
+/// Split `str` into its first word and the rest (trimmed).
+fn split_first_word(str: &str) -> (&str, &str) {
+    match str.split_once(char::is_whitespace) {
+        Some((first_word, rest)) => (first_word, rest.trim()),
+        None => (str, ""),
+    }
+}
+
+fn name_without_whitespace(name: &str) -> String {
+    name.split_whitespace().collect()
+}
+
+/// Return the query prefixes that can be used with the currently enabled query
+/// sources.
+#[tauri::command]
+pub(crate) async fn get_query_prefixes(tauri_app_handle: AppHandle) -> Vec<QueryPrefix> {
+    let registry = tauri_app_handle.state::<SearchSourceRegistry>();
+    let query_sources: Vec<QuerySource> = registry
+        .get_sources()
+        .await
+        .iter()
+        .map(|search_source| search_source.get_type())
+        .collect();
+
+    available_query_prefixes(&query_sources)
+}
+
+fn available_query_prefixes(query_sources: &[QuerySource]) -> Vec<QueryPrefix> {
+    let mut prefixes = Vec::new();
+
+    for (keyword, kind, query_source_id) in KEYWORD_PREFIXES {
+        if let Some(query_source) = query_sources.iter().find(|qs| qs.id == *query_source_id) {
+            prefixes.push(QueryPrefix {
+                prefix: keyword.to_string(),
+                kind: *kind,
+                query_source: query_source.clone(),
+            });
+        }
+    }
+
+    for query_source in query_sources {
+        if query_source.r#type == COCO_SERVERS {
+            prefixes.push(QueryPrefix {
+                prefix: format!(
+                    "{}{}",
+                    SERVER_PREFIX,
+                    name_without_whitespace(&query_source.name)
+                ),
+                kind: QueryPrefixKind::CocoServer,
+                query_source: query_source.clone(),
+            });
+        }
+    }
+
+    prefixes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extension::LOCAL_QUERY_SOURCE_TYPE;
+
+    fn query_sources() -> Vec<QuerySource> {
+        vec![
+            QuerySource {
+                r#type: LOCAL_QUERY_SOURCE_TYPE.into(),
+                id: APPLICATION_QUERY_SOURCE_ID.into(),
+                name: "My Computer".into(),
+            },
+            QuerySource {
+                r#type: LOCAL_QUERY_SOURCE_TYPE.into(),
+                id: THIRD_PARTY_EXTENSIONS_QUERY_SOURCE_ID.into(),
+                name: "My Computer".into(),
+            },
+            QuerySource {
+                r#type: COCO_SERVERS.into(),
+                id: "d2a7e3f0".into(),
+                name: "Coco Cloud".into(),
+            },
+        ]
+    }
+
+    fn scoped(
+        query_source_id: &str,
+        datasource: Option<&str>,
+        main_extension_id: Option<&str>,
+        query: &str,
+    ) -> ScopedQuery {
+        ScopedQuery {
+            query_source_id: query_source_id.into(),
+            datasource: datasource.map(str::to_string),
+            main_extension_id: main_extension_id.map(str::to_string),
+            query: query.into(),
+        }
+    }
+
+    #[test]
+    fn test_no_prefix() {
+        let sources = query_sources();
+        assert_eq!(parse_query_prefix("slack", &sources).unwrap(), None);
+        assert_eq!(parse_query_prefix("", &sources).unwrap(), None);
+        assert_eq!(parse_query_prefix("@", &sources).unwrap(), None);
+        assert_eq!(parse_query_prefix("apps: x", &sources).unwrap(), None);
+    }
+
+    #[test]
+    fn test_keyword_prefixes() {
+        let sources = query_sources();
+
+        assert_eq!(
+            parse_query_prefix("app: slack", &sources).unwrap(),
+            Some(scoped(APPLICATION_QUERY_SOURCE_ID, None, None, "slack"))
+        );
+        assert_eq!(
+            parse_query_prefix("  APP:slack ", &sources).unwrap(),
+            Some(scoped(APPLICATION_QUERY_SOURCE_ID, None, None, "slack"))
+        );
+        assert_eq!(
+            parse_query_prefix("ext: translate", &sources).unwrap(),
+            Some(scoped(
+                THIRD_PARTY_EXTENSIONS_QUERY_SOURCE_ID,
+                None,
+                None,
+                "translate"
+            ))
+        );
+        assert_eq!(
+            parse_query_prefix("ext:deepl hello world", &sources).unwrap(),
+            Some(scoped(
+                THIRD_PARTY_EXTENSIONS_QUERY_SOURCE_ID,
+                None,
+                Some("deepl"),
+                "hello world"
+            ))
+        );
+    }
+
+    #[test]
+    fn test_prefix_of_disabled_query_source() {
+        let sources = query_sources();
+        assert!(matches!(
+            parse_query_prefix("file: report.pdf", &sources),
+            Err(SearchError::InvalidQuery { .. })
+        ));
+    }
+
+    #[test]
+    fn test_server_prefix() {
+        let sources = query_sources();
+
+        assert_eq!(
+            parse_query_prefix("@cococloud rust", &sources).unwrap(),
+            Some(scoped("d2a7e3f0", None, None, "rust"))
+        );
+        assert_eq!(
+            parse_query_prefix("@d2a7e3f0/github rust async", &sources).unwrap(),
+            Some(scoped("d2a7e3f0", Some("github"), None, "rust async"))
+        );
+        assert_eq!(
+            parse_query_prefix("@CocoCloud/", &sources).unwrap(),
+            Some(scoped("d2a7e3f0", None, None, ""))
+        );
+        assert_eq!(parse_query_prefix("@unknown rust", &sources).unwrap(), None);
+        assert_eq!(parse_query_prefix("@cococloudx", &sources).unwrap(), None);
+    }
+
+    #[test]
+    fn test_available_query_prefixes() {
+        let prefixes: Vec<String> = available_query_prefixes(&query_sources())
+            .into_iter()
+            .map(|prefix| prefix.prefix)
+            .collect();
+
+        assert_eq!(prefixes, vec!["app:", "ext:", "@CocoCloud"]);
+    }
+}
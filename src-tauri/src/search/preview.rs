@@ -0,0 +1,356 @@
+//! Rich preview of a search result, shown in the detail pane when it gets
+//! selected.
+//!
+//! Previews are generated by the search source that returns the document, see
+//! `SearchSource::preview()`.  They are size-limited and cached so that moving
+//! the selection around stays cheap.
+
+use crate::common::document::Document;
+use crate::common::error::SearchError;
+use crate::common::register::SearchSourceRegistry;
+#[cfg(feature = "use_pizza_engine")]
+use crate::extension::built_in::application::AppMetadata;
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+
+/// At most this many bytes of a text file or document content are previewed.
+const MAX_TEXT_PREVIEW_BYTES: usize = 16 * 1024;
+/// At most this many entries of a folder, in name order, are previewed.
+const MAX_FOLDER_PREVIEW_ENTRIES: usize = 100;
+
+const PREVIEW_CACHE_CAPACITY: usize = 64;
+/// Previews could be outdated, e.g., the file gets modified, so they expire.
+const PREVIEW_CACHE_TTL: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub(crate) enum DocumentPreview {
+    /// Content of a text file or a document.
+    Text {
+        content: String,
+        /// Set if `content` is only the first `MAX_TEXT_PREVIEW_BYTES` bytes.
+        truncated: bool,
+    },
+    Image {
+        /// Image format, e.g., "png", `None` if unknown.
+        format: Option<String>,
+        width: usize,
+        height: usize,
+        /// File size in bytes.
+        size: u64,
+    },
+    Folder {
+        /// Sorted by name.
+        entries: Vec<FolderEntry>,
+        /// Set if there are more than `MAX_FOLDER_PREVIEW_ENTRIES` entries.
+        truncated: bool,
+    },
+    /// Only the application search source backed by Pizza engine previews
+    /// applications.
+    #[cfg(feature = "use_pizza_engine")]
+    Application { metadata: AppMetadata },
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub(crate) struct FolderEntry {
+    name: String,
+    is_dir: bool,
+}
+
+/// (query source ID, document ID)
+type PreviewCacheKey = (String, String);
+
+/// A size-bounded cache of previews, the oldest entry is evicted when it is
+/// full.
+#[derive(Debug, Default)]
+struct PreviewCache {
+    entries: HashMap<PreviewCacheKey, (Instant, Option<DocumentPreview>)>,
+    /// Keys in insertion order, oldest first.
+    order: VecDeque<PreviewCacheKey>,
+}
+
+impl PreviewCache {
+    /// Return the cached preview, `Some(None)` means the document has no
+    /// preview.
+    fn get(&self, key: &PreviewCacheKey, now: Instant) -> Option<Option<DocumentPreview>> {
+        let (cached_at, preview) = self.entries.get(key)?;
+        if now.duration_since(*cached_at) > PREVIEW_CACHE_TTL {
+            return None;
+        }
+
+        Some(preview.clone())
+    }
+
+    fn insert(&mut self, key: PreviewCacheKey, preview: Option<DocumentPreview>, now: Instant) {
+        if self.entries.insert(key.clone(), (now, preview)).is_some() {
+            self.order.retain(|existing_key| existing_key != &key);
+        }
+        self.order.push_back(key);
+
+        while self.order.len() > PREVIEW_CACHE_CAPACITY {
+            let oldest = self.order.pop_front().expect("order should not be empty");
+            self.entries.remove(&oldest);
+        }
+    }
+}
+
+static PREVIEW_CACHE: LazyLock<Mutex<PreviewCache>> =
+    LazyLock::new(|| Mutex::new(PreviewCache::default()));
+
+/// Return the preview of `document`, which is returned by query source
+/// `source_id`, or `None` if it has nothing more to show than its summary.
+#[tauri::command]
+pub(crate) async fn preview_document(
+    tauri_app_handle: AppHandle,
+    source_id: String,
+    document: Document,
+) -> Result<Option<DocumentPreview>, SearchError> {
+    let cache_key = (source_id, document.id.clone());
+    let opt_cached = PREVIEW_CACHE
+        .lock()
+        .expect("preview cache lock poisoned")
+        .get(&cache_key, Instant::now());
+    if let Some(cached) = opt_cached {
+        return Ok(cached);
+    }
+
+    let registry = tauri_app_handle.state::<SearchSourceRegistry>();
+    let Some(search_source) = registry.get_source(&cache_key.0).await else {
+        // The query source has been removed since the search
+        return Ok(None);
+    };
+
+    let preview = search_source.preview(&tauri_app_handle, &document).await?;
+    PREVIEW_CACHE
+        .lock()
+        .expect("preview cache lock poisoned")
+        .insert(cache_key, preview.clone(), Instant::now());
+
+    Ok(preview)
+}
+
+/// Preview the file or folder at `path`.
+///
+/// Return `None` for files that are neither text nor images.
+pub(crate) async fn preview_file(path: &Path) -> Result<Option<DocumentPreview>, SearchError> {
+    let path: PathBuf = path.to_path_buf();
+
+    tokio::task::spawn_blocking(move || preview_file_blocking(&path))
+        .await
+        .map_err(|e| SearchError::InternalError {
+            error: e.to_string(),
+        })?
+        .map_err(|e| SearchError::InternalError {
+            error: e.to_string(),
+        })
+}
+
+fn preview_file_blocking(path: &Path) -> std::io::Result<Option<DocumentPreview>> {
+    let metadata = std::fs::metadata(path)?;
+
+    if metadata.is_dir() {
+        // Sort all the entries before truncating, so that the preview shows
+        // the first entries by name rather than the arbitrary readdir order.
+        let mut entries = std::fs::read_dir(path)?
+            .map(|dir_entry| {
+                let dir_entry = dir_entry?;
+                Ok(FolderEntry {
+                    name: dir_entry.file_name().to_string_lossy().into_owned(),
+                    is_dir: dir_entry.file_type()?.is_dir(),
+                })
+            })
+            .collect::<std::io::Result<Vec<_>>>()?;
+        entries.sort_by(|a, b| a.name.cmp(&b.name));
+        let truncated = entries.len() > MAX_FOLDER_PREVIEW_ENTRIES;
+        entries.truncate(MAX_FOLDER_PREVIEW_ENTRIES);
+
+        return Ok(Some(DocumentPreview::Folder { entries, truncated }));
+    }
+
+    if let Ok(image_size) = imagesize::size(path) {
+        let mut header = [0_u8; 16];
+        let n_read = std::fs::File::open(path)?.read(&mut header)?;
+        let format = imagesize::image_type(&header[..n_read])
+            .ok()
+            .map(|image_type| format!("{:?}", image_type).to_lowercase());
+
+        return Ok(Some(DocumentPreview::Image {
+            format,
+            width: image_size.width,
+            height: image_size.height,
+            size: metadata.len(),
+        }));
+    }
+
+    // Read one more byte to tell if the file is truncated
+    let mut bytes = Vec::with_capacity(MAX_TEXT_PREVIEW_BYTES + 1);
+    std::fs::File::open(path)?
+        .take(MAX_TEXT_PREVIEW_BYTES as u64 + 1)
+        .read_to_end(&mut bytes)?;
+    let truncated = bytes.len() > MAX_TEXT_PREVIEW_BYTES;
+    bytes.truncate(MAX_TEXT_PREVIEW_BYTES);
+
+    Ok(text_preview(&bytes, truncated))
+}
+
+/// Build a text preview from the first bytes of a file, `None` if the file is
+/// binary.
+fn text_preview(bytes: &[u8], truncated: bool) -> Option<DocumentPreview> {
+    if bytes.contains(&0) {
+        return None;
+    }
+
+    let content = match std::str::from_utf8(bytes) {
+        Ok(str) => str,
+        // The last char is cut off by truncation
+        Err(e) if truncated && e.error_len().is_none() => {
+            std::str::from_utf8(&bytes[..e.valid_up_to()]).expect("valid up to here")
+        }
+        Err(_) => return None,
+    };
+
+    Some(DocumentPreview::Text {
+        content: content.to_string(),
+        truncated,
+    })
+}
+
+/// Build a text preview of `content`, truncated to `MAX_TEXT_PREVIEW_BYTES`
+/// bytes on a char boundary.
+pub(crate) fn truncated_text_preview(content: &str) -> DocumentPreview {
+    if content.len() <= MAX_TEXT_PREVIEW_BYTES {
+        return DocumentPreview::Text {
+            content: content.to_string(),
+            truncated: false,
+        };
+    }
+
+    let mut end = MAX_TEXT_PREVIEW_BYTES;
+    while !content.is_char_boundary(end) {
+        end -= 1;
+    }
+
+    DocumentPreview::Text {
+        content: content[..end].to_string(),
+        truncated: true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(preview: Option<DocumentPreview>) -> Option<(String, bool)> {
+        match preview? {
+            DocumentPreview::Text { content, truncated } => Some((content, truncated)),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_text_preview() {
+        assert_eq!(
+            text(text_preview(b"hello", false)),
+            Some(("hello".into(), false))
+        );
+        // Binary
+        assert_eq!(text(text_preview(b"\x7fELF\x00\x01", false)), None);
+        assert_eq!(text(text_preview(b"\xff\xfe", false)), None);
+
+        // "中" is 3 bytes, the last byte is cut off
+        let bytes = "a中".as_bytes();
+        assert_eq!(
+            text(text_preview(&bytes[..3], true)),
+            Some(("a".into(), true))
+        );
+    }
+
+    #[test]
+    fn test_truncated_text_preview() {
+        let content = "中".repeat(MAX_TEXT_PREVIEW_BYTES);
+        let (preview, truncated) = text(Some(truncated_text_preview(&content))).unwrap();
+        assert!(truncated);
+        assert!(preview.len() <= MAX_TEXT_PREVIEW_BYTES);
+        assert!(preview.chars().all(|c| c == '中'));
+
+        assert_eq!(
+            text(Some(truncated_text_preview("short"))),
+            Some(("short".into(), false))
+        );
+    }
+
+    #[test]
+    fn test_preview_file() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "hello world").unwrap();
+        std::fs::create_dir(dir.path().join("sub")).unwrap();
+
+        let file_preview = preview_file_blocking(&dir.path().join("notes.txt")).unwrap();
+        assert_eq!(text(file_preview), Some(("hello world".into(), false)));
+
+        let Some(DocumentPreview::Folder { entries, truncated }) =
+            preview_file_blocking(dir.path()).unwrap()
+        else {
+            panic!("expect a folder preview");
+        };
+        assert!(!truncated);
+        assert_eq!(
+            entries,
+            vec![
+                FolderEntry {
+                    name: "notes.txt".into(),
+                    is_dir: false
+                },
+                FolderEntry {
+                    name: "sub".into(),
+                    is_dir: true
+                },
+            ]
+        );
+
+        // The first entries by name are kept
+        let big_dir = tempfile::tempdir().unwrap();
+        for i in (0..=MAX_FOLDER_PREVIEW_ENTRIES).rev() {
+            std::fs::write(big_dir.path().join(format!("{:03}", i)), "").unwrap();
+        }
+        let Some(DocumentPreview::Folder { entries, truncated }) =
+            preview_file_blocking(big_dir.path()).unwrap()
+        else {
+            panic!("expect a folder preview");
+        };
+        assert!(truncated);
+        assert_eq!(entries.len(), MAX_FOLDER_PREVIEW_ENTRIES);
+        assert_eq!(entries[0].name, "000");
+        assert_eq!(entries[MAX_FOLDER_PREVIEW_ENTRIES - 1].name, "099");
+    }
+
+    #[test]
+    fn test_preview_cache() {
+        let now = Instant::now();
+        let mut cache = PreviewCache::default();
+        let key = |id: &str| ("files".to_string(), id.to_string());
+
+        cache.insert(key("a"), None, now);
+        assert!(matches!(cache.get(&key("a"), now), Some(None)));
+        assert!(cache.get(&key("b"), now).is_none());
+
+        // Expired
+        assert!(
+            cache
+                .get(&key("a"), now + PREVIEW_CACHE_TTL + Duration::from_secs(1))
+                .is_none()
+        );
+
+        // The oldest entry is evicted
+        for i in 0..PREVIEW_CACHE_CAPACITY {
+            cache.insert(key(&i.to_string()), None, now);
+        }
+        assert!(cache.get(&key("a"), now).is_none());
+        assert_eq!(cache.entries.len(), PREVIEW_CACHE_CAPACITY);
+    }
+}
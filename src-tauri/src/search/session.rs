@@ -0,0 +1,172 @@
+//! Query sessions.
+//!
+//! The frontend issues a new search on every keystroke.  Without sessions, the
+//! older searches keep running until they complete, and with a slow Coco server,
+//! outstanding requests pile up.
+//!
+//! A query session is identified by a session ID (e.g., one per search input
+//! box), every search of this session carries a monotonically increasing
+//! sequence number.  When a search with a greater sequence number arrives, the
+//! older one gets cancelled.
+
+use std::collections::HashMap;
+use std::sync::LazyLock;
+use std::sync::Mutex;
+use tokio_util::sync::CancellationToken;
+
+/// The in-flight (latest) query of every session.
+static QUERY_SESSIONS: LazyLock<Mutex<QuerySessions>> =
+    LazyLock::new(|| Mutex::new(QuerySessions::default()));
+
+#[derive(Default)]
+struct QuerySessions {
+    /// Session ID => (sequence, token of that query)
+    in_flight: HashMap<String, (u64, CancellationToken)>,
+}
+
+impl QuerySessions {
+    /// Register query `sequence` as the latest query of session `session_id`,
+    /// the previous query of this session, if any, will be cancelled.
+    ///
+    /// Return `None` if `sequence` is outdated, i.e., a newer query of this
+    /// session has been registered.
+    fn begin(&mut self, session_id: &str, sequence: u64) -> Option<CancellationToken> {
+        if let Some((in_flight_sequence, in_flight_token)) = self.in_flight.get(session_id) {
+            if *in_flight_sequence > sequence {
+                return None;
+            }
+
+            if *in_flight_sequence < sequence {
+                in_flight_token.cancel();
+            }
+        }
+
+        let token = CancellationToken::new();
+        self.in_flight
+            .insert(session_id.to_string(), (sequence, token.clone()));
+
+        Some(token)
+    }
+
+    /// Remove the query if it is still the latest one of this session.
+    fn end(&mut self, session_id: &str, sequence: u64) {
+        if let Some((in_flight_sequence, _token)) = self.in_flight.get(session_id)
+            && *in_flight_sequence == sequence
+        {
+            self.in_flight.remove(session_id);
+        }
+    }
+
+    /// Cancel the in-flight query of this session, if any.
+    fn cancel(&mut self, session_id: &str) -> bool {
+        match self.in_flight.remove(session_id) {
+            Some((_sequence, token)) => {
+                token.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// A registered query, it un-registers itself on drop.
+pub(crate) struct QuerySessionGuard {
+    session_id: String,
+    sequence: u64,
+    token: CancellationToken,
+}
+
+impl QuerySessionGuard {
+    /// Start query `sequence` of session `session_id`, the older in-flight query
+    /// of this session will be cancelled.
+    ///
+    /// Return `None` if this query has already been superseded.
+    pub(crate) fn begin(session_id: String, sequence: u64) -> Option<Self> {
+        let token = QUERY_SESSIONS
+            .lock()
+            .expect("query session lock poisoned")
+            .begin(&session_id, sequence)?;
+
+        Some(Self {
+            session_id,
+            sequence,
+            token,
+        })
+    }
+
+    /// Resolves when this query gets cancelled.
+    pub(crate) async fn cancelled(&self) {
+        self.token.cancelled().await
+    }
+}
+
+impl Drop for QuerySessionGuard {
+    fn drop(&mut self) {
+        if let Ok(mut sessions) = QUERY_SESSIONS.lock() {
+            sessions.end(&self.session_id, self.sequence);
+        }
+    }
+}
+
+/// Cancel the in-flight query of the session specified by `session_id`.
+///
+/// The frontend calls this when the search input is cleared or the window gets
+/// hidden. Return `true` if there was an in-flight query.
+#[tauri::command]
+pub(crate) fn cancel_query_session(session_id: String) -> bool {
+    QUERY_SESSIONS
+        .lock()
+        .expect("query session lock poisoned")
+        .cancel(&session_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn newer_query_cancels_older_one() {
+        let mut sessions = QuerySessions::default();
+
+        let first = sessions.begin("main", 1).unwrap();
+        let second = sessions.begin("main", 2).unwrap();
+
+        assert!(first.is_cancelled());
+        assert!(!second.is_cancelled());
+    }
+
+    #[test]
+    fn outdated_query_is_rejected() {
+        let mut sessions = QuerySessions::default();
+
+        let latest = sessions.begin("main", 5).unwrap();
+        assert!(sessions.begin("main", 4).is_none());
+        assert!(!latest.is_cancelled());
+    }
+
+    #[test]
+    fn sessions_are_independent() {
+        let mut sessions = QuerySessions::default();
+
+        let main = sessions.begin("main", 1).unwrap();
+        let _chat = sessions.begin("chat", 2).unwrap();
+
+        assert!(!main.is_cancelled());
+    }
+
+    #[test]
+    fn end_only_removes_the_latest_query() {
+        let mut sessions = QuerySessions::default();
+
+        let _first = sessions.begin("main", 1).unwrap();
+        let second = sessions.begin("main", 2).unwrap();
+
+        // The first query finishes after being superseded, this should not
+        // remove the second one.
+        sessions.end("main", 1);
+        assert!(sessions.cancel("main"));
+        assert!(second.is_cancelled());
+
+        assert!(!sessions.cancel("main"));
+    }
+}
@@ -0,0 +1,118 @@
+//! Autocomplete of the query being typed, see `SearchSource::suggest()`.
+
+use crate::common::error::SearchError;
+use crate::common::register::SearchSourceRegistry;
+use futures::future::join_all;
+use std::collections::HashSet;
+use tauri::{AppHandle, Manager};
+use tokio::time::{Duration, timeout};
+
+/// Return at most `size` completions of `prefix`, collected from all the
+/// registered search sources.
+///
+/// Search sources that fail or do not respond within `query_timeout`
+/// milliseconds are skipped.
+#[tauri::command]
+pub(crate) async fn query_suggestions(
+    tauri_app_handle: AppHandle,
+    prefix: String,
+    size: usize,
+    query_timeout: u64,
+) -> Result<Vec<String>, SearchError> {
+    let registry = tauri_app_handle.state::<SearchSourceRegistry>();
+    let search_sources = registry.get_sources().await;
+    let timeout_duration = Duration::from_millis(query_timeout);
+
+    let tauri_app_handle = &tauri_app_handle;
+    let prefix = prefix.as_str();
+    let suggest_futures = search_sources.iter().map(|search_source| async move {
+        let timeout_result = timeout(
+            timeout_duration,
+            search_source.suggest(tauri_app_handle, prefix, size),
+        )
+        .await;
+
+        match timeout_result {
+            Err(_timeout) => {
+                log::warn!(
+                    "getting suggestions from search source [{}] timed out",
+                    search_source.get_type().id
+                );
+                Vec::new()
+            }
+            Ok(Err(e)) => {
+                log::warn!(
+                    "getting suggestions from search source [{}] failed, error [{}]",
+                    search_source.get_type().id,
+                    e
+                );
+                Vec::new()
+            }
+            Ok(Ok(suggestions)) => suggestions,
+        }
+    });
+
+    let suggestions_of_sources = join_all(suggest_futures).await;
+    Ok(merge_suggestions(suggestions_of_sources, size))
+}
+
+/// Interleave the suggestions of every search source so that every search
+/// source gets its top suggestions in, remove the duplicates (case-insensitive)
+/// and return at most `size` of them.
+fn merge_suggestions(suggestions_of_sources: Vec<Vec<String>>, size: usize) -> Vec<String> {
+    let mut merged = Vec::with_capacity(size);
+    let mut seen = HashSet::new();
+    let max_len = suggestions_of_sources
+        .iter()
+        .map(Vec::len)
+        .max()
+        .unwrap_or(0);
+
+    for rank in 0..max_len {
+        for suggestions in suggestions_of_sources.iter() {
+            if merged.len() >= size {
+                return merged;
+            }
+
+            let Some(suggestion) = suggestions.get(rank) else {
+                continue;
+            };
+            if seen.insert(suggestion.to_lowercase()) {
+                merged.push(suggestion.clone());
+            }
+        }
+    }
+
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(strs: &[&str]) -> Vec<String> {
+        strs.iter().map(|str| str.to_string()).collect()
+    }
+
+    #[test]
+    fn test_merge_suggestions() {
+        let merged = merge_suggestions(
+            vec![
+                strings(&["Slack", "Safari", "Steam"]),
+                strings(&["slack", "Sublime"]),
+                Vec::new(),
+            ],
+            10,
+        );
+        assert_eq!(merged, strings(&["Slack", "Safari", "Sublime", "Steam"]));
+    }
+
+    #[test]
+    fn test_merge_suggestions_respects_size() {
+        let merged = merge_suggestions(
+            vec![strings(&["a1", "a2", "a3"]), strings(&["b1", "b2"])],
+            3,
+        );
+        assert_eq!(merged, strings(&["a1", "b1", "a2"]));
+    }
+}
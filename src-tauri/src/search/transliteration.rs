@@ -0,0 +1,228 @@
+//! Pinyin transliteration of Chinese text.
+//!
+//! Chinese-speaking users type Latin letters to search, e.g., "weixin" or "wx"
+//! for "微信".  This module generates the full pinyin and the initial letters of
+//! a text, called transliteration keys, which are matched against the query.
+//!
+//! Polyphonic characters have multiple readings, e.g., "重" can be read as
+//! "zhong" or "chong", keys are generated for every combination of the
+//! readings, up to `MAX_READING_COMBINATIONS`.
+
+use pinyin::{ToPinyin, ToPinyinMulti};
+
+/// Generating keys for all the combinations could explode for texts with many
+/// polyphonic characters, readings of the subsequent characters are limited to
+/// the most common one once we have this many combinations.
+const MAX_READING_COMBINATIONS: usize = 16;
+
+/// A transliteration key of a text.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct TransliterationKey {
+    /// Full pinyin with every syllable capitalized, e.g., "WeiXin", or the
+    /// initial letters, e.g., "wx".
+    ///
+    /// Capitalized syllables make them look like camelCase to the fuzzy
+    /// matcher, so every syllable is a word.
+    pub(crate) key: String,
+    /// For every char of `key`, index of the char of the original text it comes
+    /// from.
+    pub(crate) char_indexes: Vec<usize>,
+}
+
+/// Does `text` contain any Chinese character?
+pub(crate) fn contains_han(text: &str) -> bool {
+    text.chars().any(|c| c.to_pinyin().is_some())
+}
+
+/// Plain (without tones) readings of `c`, the most common one comes first.
+/// Empty if `c` is not a Chinese character.
+fn readings(c: char) -> Vec<&'static str> {
+    let Some(pinyin_multi) = c.to_pinyin_multi() else {
+        return Vec::new();
+    };
+
+    let mut readings: Vec<&'static str> = Vec::new();
+    for pinyin in pinyin_multi {
+        let reading = pinyin.plain();
+        // Readings that only differ in tones are the same without tones
+        if !readings.contains(&reading) {
+            readings.push(reading);
+        }
+    }
+
+    readings
+}
+
+/// Generate the transliteration keys of `text`, both full pinyin and initials.
+///
+/// Return an empty vector if `text` contains no Chinese characters.
+pub(crate) fn transliteration_keys(text: &str) -> Vec<TransliterationKey> {
+    let chars: Vec<char> = text.chars().collect();
+    let readings_of_chars: Vec<Vec<&'static str>> = chars.iter().map(|c| readings(*c)).collect();
+    if readings_of_chars.iter().all(Vec::is_empty) {
+        return Vec::new();
+    }
+
+    // Every combination is a list of the chosen reading of every char, `None`
+    // for non-Chinese characters.
+    let mut combinations: Vec<Vec<Option<&'static str>>> = vec![Vec::with_capacity(chars.len())];
+    for readings in readings_of_chars.iter() {
+        if readings.is_empty() {
+            combinations
+                .iter_mut()
+                .for_each(|combination| combination.push(None));
+            continue;
+        }
+
+        let readings_to_use = if combinations.len() * readings.len() <= MAX_READING_COMBINATIONS {
+            readings.as_slice()
+        } else {
+            &readings[..1]
+        };
+
+        combinations = combinations
+            .into_iter()
+            .flat_map(|combination| {
+                readings_to_use.iter().map(move |reading| {
+                    let mut combination = combination.clone();
+                    combination.push(Some(*reading));
+                    combination
+                })
+            })
+            .collect();
+    }
+
+    let mut keys: Vec<TransliterationKey> = Vec::new();
+    for combination in combinations {
+        for key in [
+            full_pinyin_key(&chars, &combination),
+            initials_key(&chars, &combination),
+        ] {
+            if !keys.iter().any(|existing| existing.key == key.key) {
+                keys.push(key);
+            }
+        }
+    }
+
+    keys
+}
+
+fn full_pinyin_key(chars: &[char], combination: &[Option<&'static str>]) -> TransliterationKey {
+    let mut key = String::new();
+    let mut char_indexes = Vec::new();
+
+    for (index, (c, opt_reading)) in chars.iter().zip(combination).enumerate() {
+        match opt_reading {
+            Some(reading) => {
+                for (nth, reading_char) in reading.chars().enumerate() {
+                    if nth == 0 {
+                        key.extend(reading_char.to_uppercase());
+                    } else {
+                        key.push(reading_char);
+                    }
+                    char_indexes.push(index);
+                }
+            }
+            None => {
+                key.push(*c);
+                char_indexes.push(index);
+            }
+        }
+    }
+
+    TransliterationKey { key, char_indexes }
+}
+
+fn initials_key(chars: &[char], combination: &[Option<&'static str>]) -> TransliterationKey {
+    let mut key = String::new();
+    let mut char_indexes = Vec::new();
+
+    for (index, (c, opt_reading)) in chars.iter().zip(combination).enumerate() {
+        let initial = match opt_reading {
+            Some(reading) => reading.chars().next().unwrap_or(*c),
+            None => *c,
+        };
+        key.push(initial);
+        char_indexes.push(index);
+    }
+
+    TransliterationKey { key, char_indexes }
+}
+
+/// Return the keys of `text` joined by whitespace, or an empty string if `text`
+/// contains no Chinese characters.
+///
+/// This is used to index the keys in a full-text search engine, which
+/// tokenizes the keys into words.
+pub(crate) fn transliteration_keys_for_indexing(text: &str) -> String {
+    transliteration_keys(text)
+        .into_iter()
+        .map(|key| key.key.to_lowercase())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(text: &str) -> Vec<String> {
+        transliteration_keys(text)
+            .into_iter()
+            .map(|key| key.key)
+            .collect()
+    }
+
+    #[test]
+    fn test_no_chinese() {
+        assert!(!contains_han("WeChat"));
+        assert!(keys("WeChat").is_empty());
+        assert_eq!(transliteration_keys_for_indexing("WeChat"), "");
+    }
+
+    #[test]
+    fn test_full_pinyin_and_initials() {
+        assert!(contains_han("微信"));
+        // Keys of the most common readings come first
+        assert_eq!(keys("微信")[..2], ["WeiXin", "wx"]);
+    }
+
+    #[test]
+    fn test_mixed_text() {
+        let transliteration_keys = transliteration_keys("QQ浏览器");
+        assert_eq!(transliteration_keys[0].key, "QQLiuLanQi");
+        assert_eq!(
+            transliteration_keys[0].char_indexes,
+            vec![0, 1, 2, 2, 2, 3, 3, 3, 4, 4]
+        );
+        assert_eq!(transliteration_keys[1].key, "QQllq");
+        assert_eq!(transliteration_keys[1].char_indexes, vec![0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_polyphonic_characters() {
+        // 重: zhong/chong
+        let keys = keys("重庆");
+        assert!(keys.contains(&"ChongQing".to_string()));
+        assert!(keys.contains(&"ZhongQing".to_string()));
+        assert!(keys.contains(&"cq".to_string()));
+        assert!(keys.contains(&"zq".to_string()));
+
+        // 行: xing/hang
+        let keys = transliteration_keys_for_indexing("银行");
+        assert!(keys.split(' ').any(|key| key == "yinhang"));
+        assert!(keys.split(' ').any(|key| key == "yinxing"));
+        assert!(keys.split(' ').any(|key| key == "yh"));
+    }
+
+    #[test]
+    fn test_combinations_are_bounded() {
+        // Every char is polyphonic
+        let text = "重行长乐还重行长乐还";
+        let keys = transliteration_keys(text);
+
+        // Full pinyin + initials for every combination, initials may collide
+        assert!(keys.len() <= MAX_READING_COMBINATIONS * 2);
+        assert!(!keys.is_empty());
+    }
+}
@@ -1,13 +1,18 @@
 use crate::common::document::{Document, OnOpened};
+use crate::common::document_action::url_actions;
 use crate::common::error::{HttpSnafu, ResponseDecodeSnafu, SearchError};
+use crate::common::health::{SourceHealth, Status};
 use crate::common::http::get_response_body_text;
 use crate::common::search::{QueryHits, QueryResponse, QuerySource, SearchQuery, SearchResponse};
 use crate::common::server::Server;
 use crate::common::traits::SearchSource;
+use crate::search::preview::{DocumentPreview, truncated_text_preview};
 use crate::server::http_client::{HttpClient, HttpRequestError};
+use crate::server::servers::{get_server_by_id, get_server_token};
 use async_trait::async_trait;
 use ordered_float::OrderedFloat;
 use reqwest::StatusCode;
+use serde::Deserialize;
 use snafu::ResultExt;
 use std::collections::HashMap;
 use tauri::AppHandle;
@@ -62,6 +67,7 @@ impl DocumentsSizedCollector {
                 source,
                 score: score.into_inner(),
                 document: doc,
+                also_found_in: Vec::new(),
             });
         }
 
@@ -69,7 +75,15 @@ impl DocumentsSizedCollector {
     }
 }
 
-const COCO_SERVERS: &str = "coco-servers";
+/// Type of the query sources of Coco servers.
+pub(crate) const COCO_SERVERS: &str = "coco-servers";
+
+/// Response of Coco server API `GET /document/<id>`.
+#[derive(Debug, Deserialize)]
+struct GetDocumentResponse {
+    #[serde(rename = "_source")]
+    source: Document,
+}
 
 pub struct CocoSearchSource {
     server: Server,
@@ -130,6 +144,77 @@ impl SearchSource for CocoSearchSource {
         }
     }
 
+    /// A non-public server is searchable only if we have its token.
+    async fn init(&self, _tauri_app_handle: &AppHandle) -> Result<(), SearchError> {
+        if !self.server.public && get_server_token(&self.server.id).await.is_none() {
+            return Err(SearchError::InternalError {
+                error: format!(
+                    "server [{}] is not public and no token was found",
+                    self.server.id
+                ),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Report the server status refreshed by the heartbeat worker, this won't
+    /// send any request.
+    async fn health(&self, _tauri_app_handle: &AppHandle) -> SourceHealth {
+        let Some(server) = get_server_by_id(&self.server.id).await else {
+            return SourceHealth::unhealthy(Status::Red, "server has been removed");
+        };
+
+        if !server.available {
+            return SourceHealth::unhealthy(Status::Red, "server is unavailable");
+        }
+
+        match server.health.map(|health| health.status) {
+            Some(Status::Yellow) => SourceHealth::unhealthy(Status::Yellow, "server is degraded"),
+            Some(Status::Red) => SourceHealth::unhealthy(Status::Red, "server is unhealthy"),
+            Some(Status::Green) | None => SourceHealth::healthy(),
+        }
+    }
+
+    /// Fetch the full document, the search response may only contain its
+    /// summary.
+    async fn preview(
+        &self,
+        _tauri_app_handle: &AppHandle,
+        document: &Document,
+    ) -> Result<Option<DocumentPreview>, SearchError> {
+        let url = format!("/document/{}", urlencoding::encode(&document.id));
+        let response = HttpClient::get(&self.server.id, &url, None)
+            .await
+            .context(HttpSnafu)?;
+        let status_code = response.status();
+
+        if status_code == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        if status_code != StatusCode::OK {
+            let http_err = HttpRequestError::RequestFailed {
+                status: status_code.as_u16(),
+                error_response_body_str: None,
+                coco_server_api_error_response_body: None,
+            };
+            return Err(SearchError::HttpError { source: http_err });
+        }
+
+        let response_body = get_response_body_text(response).await.context(HttpSnafu)?;
+        let parsed: GetDocumentResponse =
+            serde_json::from_str(&response_body).context(ResponseDecodeSnafu)?;
+        let full_document = parsed.source;
+
+        let opt_preview = full_document
+            .content
+            .or(full_document.summary)
+            .filter(|content| !content.is_empty())
+            .map(|content| truncated_text_preview(&content));
+
+        Ok(opt_preview)
+    }
+
     async fn search(
         &self,
         _tauri_app_handle: AppHandle,
@@ -232,8 +317,13 @@ impl SearchSource for CocoSearchSource {
                         .url
                         .as_ref()
                         .map(|url| OnOpened::Document { url: url.clone() });
-                    // Set the `on_opened` field as it won't be returned from Coco server
+                    // Set the `on_opened` and `actions` fields as they won't be
+                    // returned from Coco server
                     document.on_opened = on_opened;
+                    document.actions = document
+                        .url
+                        .as_deref()
+                        .map(|url| url_actions(document.title.as_deref(), url));
 
                     hits.push((document, score));
                 }
@@ -367,7 +367,9 @@ pub(crate) fn start_bg_heartbeat_worker(tauri_app_handle: AppHandle) {
                     );
 
                     if !coco_server.available {
-                        let removed = search_sources.remove_source(&search_source_id).await;
+                        let removed = search_sources
+                            .remove_source(&tauri_app_handle, &search_source_id)
+                            .await;
                         if removed {
                             server_removed.push((search_source_id, search_source_name));
                         }
@@ -383,10 +385,16 @@ pub(crate) fn start_bg_heartbeat_worker(tauri_app_handle: AppHandle) {
                         && coco_server.available
                         && search_sources.get_source(&coco_server.id).await.is_none()
                     {
-                        server_added.push((coco_server.id.clone(), coco_server.name.clone()));
-
-                        let source = CocoSearchSource::new(coco_server);
-                        search_sources.register_source(source).await;
+                        let id = coco_server.id.clone();
+                        let name = coco_server.name.clone();
+                        try_register_server_to_search_source(
+                            tauri_app_handle.clone(),
+                            &coco_server,
+                        )
+                        .await;
+                        if search_sources.get_source(&id).await.is_some() {
+                            server_added.push((id, name));
+                        }
                     }
                 }
 
@@ -572,7 +580,7 @@ pub async fn add_coco_server(app_handle: AppHandle, endpoint: String) -> Result<
 #[function_name::named]
 pub async fn remove_coco_server(app_handle: AppHandle, id: String) -> Result<(), ()> {
     let registry = app_handle.state::<SearchSourceRegistry>();
-    registry.remove_source(id.as_str()).await;
+    registry.remove_source(&app_handle, id.as_str()).await;
 
     let opt_server = remove_server_by_id(id.as_str()).await;
     let Some(server) = opt_server else {
@@ -641,7 +649,7 @@ pub async fn disable_server(app_handle: AppHandle, id: String) -> Result<(), ()>
     server.enabled = false;
 
     let registry = app_handle.state::<SearchSourceRegistry>();
-    registry.remove_source(id.as_str()).await;
+    registry.remove_source(&app_handle, id.as_str()).await;
 
     save_server(&server).await;
     persist_servers(&app_handle)
@@ -651,10 +659,8 @@ pub async fn disable_server(app_handle: AppHandle, id: String) -> Result<(), ()>
     Ok(())
 }
 
-/// For non-public Coco servers, we add it to the search source as long as it is
-/// enabled.
-///
-/// For public Coco server, an extra token is required.
+/// Add `server` to the search source if it is enabled, see
+/// `CocoSearchSource::init()` for the other requirements.
 pub async fn try_register_server_to_search_source(app_handle: AppHandle, server: &Server) {
     if server.enabled {
         log::trace!(
@@ -665,18 +671,11 @@ pub async fn try_register_server_to_search_source(app_handle: AppHandle, server:
             &server.available
         );
 
-        if !server.public {
-            let opt_token = get_server_token(&server.id).await;
-
-            if opt_token.is_none() {
-                log::debug!("Server {} is not public and no token was found", &server.id);
-                return;
-            }
-        }
-
         let registry = app_handle.state::<SearchSourceRegistry>();
         let source = CocoSearchSource::new(server.clone());
-        registry.register_source(source).await;
+        if let Err(e) = registry.register_source(&app_handle, source).await {
+            log::debug!("Server {} won't be searched, error [{}]", &server.id, e);
+        }
     }
 }
 
@@ -708,7 +707,7 @@ pub(crate) async fn mark_server_as_offline(app_handle: AppHandle, id: &str) {
         save_server(&server).await;
 
         let registry = app_handle.state::<SearchSourceRegistry>();
-        registry.remove_source(id).await;
+        registry.remove_source(&app_handle, id).await;
     } else {
         log::warn!(
             "[{}()] invoked with a server [{}] that does not exist!",
@@ -769,7 +768,7 @@ pub async fn logout_coco_server(app_handle: AppHandle, id: String) -> Result<(),
     // Remove it from the search source if it becomes unavailable
     if !server.available {
         let registry = app_handle.state::<SearchSourceRegistry>();
-        registry.remove_source(id.as_str()).await;
+        registry.remove_source(&app_handle, id.as_str()).await;
     }
 
     log::debug!("Successfully logged out server with id: {}", &id);
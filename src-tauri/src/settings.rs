@@ -1,10 +1,13 @@
 use crate::COCO_TAURI_STORE;
+use crate::search::fusion::FusionStrategyKind;
 use serde_json::Value as Json;
 use tauri::AppHandle;
 use tauri_plugin_store::StoreExt;
 
 const SETTINGS_ALLOW_SELF_SIGNATURE: &str = "settings_allow_self_signature";
 const LOCAL_QUERY_SOURCE_WEIGHT: &str = "local_query_source_weight";
+const FUSION_STRATEGY: &str = "fusion_strategy";
+const FRECENCY_ENABLED: &str = "frecency_enabled";
 
 #[tauri::command]
 pub async fn set_allow_self_signature(tauri_app_handle: AppHandle, value: bool) {
@@ -116,3 +119,92 @@ pub fn get_local_query_source_weight(tauri_app_handle: AppHandle) -> f64 {
         _ => unreachable!("{} should be stored as a number", LOCAL_QUERY_SOURCE_WEIGHT),
     }
 }
+
+#[tauri::command]
+pub async fn set_fusion_strategy(tauri_app_handle: AppHandle, value: FusionStrategyKind) {
+    let store = tauri_app_handle
+        .store(COCO_TAURI_STORE)
+        .unwrap_or_else(|e| {
+            panic!(
+                "store [{}] not found/loaded, error [{}]",
+                COCO_TAURI_STORE, e
+            )
+        });
+
+    store.set(
+        FUSION_STRATEGY,
+        serde_json::to_value(value).expect("FusionStrategyKind should be serializable"),
+    );
+}
+
+/// Return the rank fusion strategy used by multi-source search, see
+/// `src-tauri/src/search/fusion.rs`.
+#[tauri::command]
+pub fn get_fusion_strategy(tauri_app_handle: AppHandle) -> FusionStrategyKind {
+    let store = tauri_app_handle
+        .store(COCO_TAURI_STORE)
+        .unwrap_or_else(|e| {
+            panic!(
+                "store [{}] not found/loaded, error [{}]",
+                COCO_TAURI_STORE, e
+            )
+        });
+    if !store.has(FUSION_STRATEGY) {
+        store.set(
+            FUSION_STRATEGY,
+            serde_json::to_value(FusionStrategyKind::default())
+                .expect("FusionStrategyKind should be serializable"),
+        );
+    }
+
+    let value = store.get(FUSION_STRATEGY).expect("should be Some");
+    serde_json::from_value(value).unwrap_or_else(|e| {
+        // Could happen if a strategy gets removed in a newer version, fall
+        // back to the default one.
+        log::warn!(
+            "setting [{}] is invalid, error [{}], fall back to the default strategy",
+            FUSION_STRATEGY,
+            e
+        );
+        FusionStrategyKind::default()
+    })
+}
+
+#[tauri::command]
+pub async fn set_frecency_enabled(tauri_app_handle: AppHandle, value: bool) {
+    let store = tauri_app_handle
+        .store(COCO_TAURI_STORE)
+        .unwrap_or_else(|e| {
+            panic!(
+                "store [{}] not found/loaded, error [{}]",
+                COCO_TAURI_STORE, e
+            )
+        });
+
+    store.set(FRECENCY_ENABLED, value);
+}
+
+/// Whether to record the opened search results and boost them in later
+/// searches, see `src-tauri/src/search/frecency.rs`.
+#[tauri::command]
+pub fn get_frecency_enabled(tauri_app_handle: AppHandle) -> bool {
+    // default to true
+    const DEFAULT: bool = true;
+
+    let store = tauri_app_handle
+        .store(COCO_TAURI_STORE)
+        .unwrap_or_else(|e| {
+            panic!(
+                "store [{}] not found/loaded, error [{}]",
+                COCO_TAURI_STORE, e
+            )
+        });
+    if !store.has(FRECENCY_ENABLED) {
+        store.set(FRECENCY_ENABLED, DEFAULT);
+    }
+
+    match store.get(FRECENCY_ENABLED).expect("should be Some") {
+        Json::Bool(b) => b,
+        _ => unreachable!("{} should be stored in a boolean", FRECENCY_ENABLED),
+    }
+}
@@ -0,0 +1,120 @@
+//! Simulate key presses in the frontmost application.
+//!
+//! On Linux, this requires `xdotool` (X11) or `wtype` (Wayland) to be
+//! installed.
+
+/// Send the paste shortcut.
+#[cfg(target_os = "macos")]
+pub(crate) async fn simulate_paste() -> Result<(), String> {
+    run(
+        "osascript",
+        &[
+            "-e",
+            r#"tell application "System Events" to keystroke "v" using command down"#,
+        ],
+    )
+    .await
+}
+
+#[cfg(target_os = "linux")]
+pub(crate) async fn simulate_paste() -> Result<(), String> {
+    if use_wtype()? {
+        run("wtype", &["-M", "ctrl", "v", "-m", "ctrl"]).await
+    } else {
+        run("xdotool", &["key", "--clearmodifiers", "ctrl+v"]).await
+    }
+}
+
+#[cfg(target_os = "windows")]
+pub(crate) async fn simulate_paste() -> Result<(), String> {
+    use enigo::{Direction, Enigo, Key, Keyboard, Settings};
+
+    let mut enigo = Enigo::new(&Settings::default()).map_err(|e| e.to_string())?;
+    enigo
+        .key(Key::Control, Direction::Press)
+        .map_err(|e| e.to_string())?;
+    let click_result = enigo.key(Key::Unicode('v'), Direction::Click);
+    enigo
+        .key(Key::Control, Direction::Release)
+        .map_err(|e| e.to_string())?;
+    click_result.map_err(|e| e.to_string())
+}
+
+/// Press the left arrow key `count` times, to move the text cursor back.
+#[cfg(target_os = "macos")]
+pub(crate) async fn press_left_arrow(count: usize) -> Result<(), String> {
+    if count == 0 {
+        return Ok(());
+    }
+    // 123 is the key code of the left arrow
+    let script = format!(
+        r#"tell application "System Events" to repeat {} times
+    key code 123
+end repeat"#,
+        count
+    );
+    run("osascript", &["-e", &script]).await
+}
+
+#[cfg(target_os = "linux")]
+pub(crate) async fn press_left_arrow(count: usize) -> Result<(), String> {
+    if count == 0 {
+        return Ok(());
+    }
+    if use_wtype()? {
+        let args: Vec<&str> = std::iter::repeat_n(["-k", "Left"], count)
+            .flatten()
+            .collect();
+        run("wtype", &args).await
+    } else {
+        let count = count.to_string();
+        run("xdotool", &["key", "--repeat", &count, "Left"]).await
+    }
+}
+
+#[cfg(target_os = "windows")]
+pub(crate) async fn press_left_arrow(count: usize) -> Result<(), String> {
+    use enigo::{Direction, Enigo, Key, Keyboard, Settings};
+
+    let mut enigo = Enigo::new(&Settings::default()).map_err(|e| e.to_string())?;
+    for _ in 0..count {
+        enigo
+            .key(Key::LeftArrow, Direction::Click)
+            .map_err(|e| e.to_string())?;
+    }
+
+    Ok(())
+}
+
+/// Return true if `wtype` should be used, false if `xdotool` should be used.
+#[cfg(target_os = "linux")]
+fn use_wtype() -> Result<bool, String> {
+    if std::env::var_os("WAYLAND_DISPLAY").is_some() && which::which("wtype").is_ok() {
+        Ok(true)
+    } else if which::which("xdotool").is_ok() {
+        Ok(false)
+    } else {
+        Err(
+            "simulating key presses requires xdotool (X11) or wtype (Wayland) to be installed"
+                .into(),
+        )
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+async fn run(program: &str, args: &[&str]) -> Result<(), String> {
+    let output = tokio::process::Command::new(program)
+        .args(args)
+        .output()
+        .await
+        .map_err(|e| format!("failed to spawn [{}] due to error [{}]", program, e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "[{}] failed, stderr [{}]",
+            program,
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    Ok(())
+}
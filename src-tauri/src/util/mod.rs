@@ -1,5 +1,6 @@
 pub(crate) mod app_lang;
 pub(crate) mod file;
+pub(crate) mod keyboard;
 // We need this in main.rs, so it has to be pub
 pub mod logging;
 pub(crate) mod path;
@@ -8,7 +8,11 @@ import platformAdapter from "@/utils/platformAdapter";
 import { Get } from "@/api/axiosRequest";
 import type { Assistant } from "@/types/chat";
 import { useAppStore } from "@/stores/appStore";
-import { canNavigateBack, navigateBack } from "@/utils";
+import {
+  canNavigateBack,
+  getOpenedSearchResult,
+  navigateBack,
+} from "@/utils";
 import { useShortcutsStore } from "@/stores/shortcutsStore";
 
 interface AssistantManagerProps {
@@ -188,6 +192,7 @@ export function useAssistantManager({
         return platformAdapter.invokeBackend("open", {
           onOpened: onOpened,
           extraArgs: null,
+          openedSearchResult: getOpenedSearchResult(selectedSearchContent),
         });
       }
     }
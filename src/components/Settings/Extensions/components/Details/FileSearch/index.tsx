@@ -15,6 +15,11 @@ interface Config {
   file_types: string[];
 }
 
+interface InvalidPattern {
+  pattern: string;
+  error: string;
+}
+
 const FileSearch = () => {
   const [config, setConfig] = useState<Config>({
     search_by: "Name",
@@ -33,17 +38,50 @@ const FileSearch = () => {
     setConfig(config);
   });
 
-  const changeConfig = (partialConfig: Partial<Config>) => {
+  const changeConfig = async (partialConfig: Partial<Config>) => {
     const nextConfig = {
       ...config,
       ...partialConfig,
     };
 
+    const invalidPatterns = await platformAdapter.invokeBackend<
+      InvalidPattern[]
+    >("validate_file_search_patterns", {
+      excludePaths: nextConfig.exclude_paths,
+      fileTypes: nextConfig.file_types,
+    });
+
+    if (invalidPatterns.length > 0) {
+      for (const { pattern, error } of invalidPatterns) {
+        addError(
+          t("settings.extensions.fileSearch.hints.invalidPattern", {
+            replace: [pattern, error],
+          })
+        );
+      }
+
+      return false;
+    }
+
     setConfig(nextConfig);
 
-    platformAdapter.invokeBackend("set_file_system_config", {
-      config: nextConfig,
-    });
+    try {
+      await platformAdapter.invokeBackend("set_file_system_config", {
+        config: nextConfig,
+      });
+    } catch (error) {
+      setConfig(config);
+
+      addError(
+        t("settings.extensions.fileSearch.hints.saveFailed", {
+          replace: [String(error)],
+        })
+      );
+
+      return false;
+    }
+
+    return true;
   };
 
   const searchByOptions = [
@@ -138,12 +176,13 @@ const FileSearch = () => {
         <SettingsInput
           placeholder=".*"
           className="h-6 w-24 px-2 border-0 outline-none focus-visible:ring-0 focus-visible:ring-offset-0"
-          onKeyDown={(event) => {
+          onKeyDown={async (event) => {
             if (event.code !== "Enter") return;
 
             event.preventDefault();
 
-            const type = event.currentTarget.value.trim();
+            const input = event.currentTarget;
+            const type = input.value.trim();
 
             if (type === "") return;
 
@@ -154,14 +193,11 @@ const FileSearch = () => {
               );
             }
 
-            const nextFileTypes = [
-              ...config.file_types,
-              event.currentTarget.value.trim(),
-            ];
-
-            changeConfig({ file_types: nextFileTypes });
+            const nextFileTypes = [...config.file_types, type];
 
-            event.currentTarget.value = "";
+            if (await changeConfig({ file_types: nextFileTypes })) {
+              input.value = "";
+            }
           }}
         />
       </div>
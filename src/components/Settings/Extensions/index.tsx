@@ -100,6 +100,7 @@ export interface Extension {
   scripts?: Extension[];
   quicklinks?: Extension[];
   views?: Extension[];
+  setting_entries?: Extension[];
   settings: Record<string, unknown>;
   developer?: string;
   page?: string;
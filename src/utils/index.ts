@@ -17,7 +17,7 @@ import { DEFAULT_COCO_SERVER_ID, HISTORY_PANEL_ID } from "@/constants";
 import { useChatStore } from "@/stores/chatStore";
 import { getCurrentWindowService } from "@/commands/windowService";
 import { useSearchStore } from "@/stores/searchStore";
-import { MultiSourceQueryResponse } from "@/types/search";
+import { MultiSourceQueryResponse, SearchDocument } from "@/types/search";
 import dayjs from "dayjs";
 
 export async function copyToClipboard(text: string, noTip = false) {
@@ -494,3 +494,19 @@ export const updateAggregations = (result?: MultiSourceQueryResponse) => {
     setAggregateFilter(void 0);
   }
 };
+
+/**
+ * Identifies a search result that gets opened, so that the backend can record
+ * it for frecency ranking.
+ */
+export const getOpenedSearchResult = (document?: SearchDocument) => {
+  const querySourceId = document?.querySource?.id;
+
+  if (!document?.id || !querySourceId) return null;
+
+  return {
+    query: useSearchStore.getState().searchValue,
+    query_source_id: querySourceId,
+    document_id: document.id,
+  };
+};
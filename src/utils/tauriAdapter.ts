@@ -13,7 +13,12 @@ import {
 import type { BasePlatformAdapter } from "@/types/platform";
 import type { AppTheme } from "@/types/index";
 import { useAppearanceStore } from "@/stores/appearanceStore";
-import { copyToClipboard, dispatchEvent, OpenURLWithBrowser } from ".";
+import {
+  copyToClipboard,
+  dispatchEvent,
+  getOpenedSearchResult,
+  OpenURLWithBrowser,
+} from ".";
 import { useAppStore } from "@/stores/appStore";
 import { unrequitable } from "@/utils";
 import {
@@ -358,6 +363,7 @@ export const createTauriAdapter = (): TauriPlatformAdapter => {
         await invoke("open", {
           onOpened: data.on_opened,
           extraArgs: null,
+          openedSearchResult: getOpenedSearchResult(data),
         });
 
         return hideCoco();